@@ -0,0 +1,86 @@
+// Builds Polyglot opening books from PGN files and looks positions up in them.
+// Run with `--help` for the options.
+
+use anyhow::{anyhow, bail, Context, Result};
+use chess_core::book::{Book, BookBuilder};
+use chess_core::position::Position;
+
+const USAGE: &str = "\
+usage: chess-book build -o BOOK [options] PGN...
+       chess-book probe BOOK [FEN]
+
+Build options:
+  --min-games N          leave out moves played in fewer games (default 1)
+  --min-score S          leave out moves scoring less than S, 0 to 1 (default 0)
+  --max-ply N            only look at the first N plies of a game (default 40)
+";
+
+fn main() -> Result<()> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match args.first().map(String::as_str) {
+        Some("build") => build(&args[1..]),
+        Some("probe") => probe(&args[1..]),
+        Some("--help" | "-h") => {
+            print!("{USAGE}");
+            Ok(())
+        }
+        _ => bail!("{USAGE}"),
+    }
+}
+
+fn build(args: &[String]) -> Result<()> {
+    let mut builder = BookBuilder::new();
+    let mut output = None;
+    let mut inputs = Vec::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or_else(|| anyhow!("{arg} needs a value"));
+        match arg.as_str() {
+            "-o" => output = Some(value()?),
+            "--min-games" => builder.min_games = number(value()?, arg)?,
+            "--min-score" => builder.min_score = number(value()?, arg)?,
+            "--max-ply" => builder.max_ply = number(value()?, arg)?,
+            _ if arg.starts_with('-') => bail!("Unknown option {arg}\n\n{USAGE}"),
+            _ => inputs.push(arg),
+        }
+    }
+    let output = output.ok_or_else(|| anyhow!("Give the book file with -o\n\n{USAGE}"))?;
+    for path in inputs {
+        let text =
+            std::fs::read_to_string(path).with_context(|| format!("Failed to read {path}"))?;
+        let (added, skipped) = builder.add_pgn(&text);
+        println!("{path}: {added} games used, {skipped} skipped");
+    }
+    let book = builder.build();
+    book.save(output)?;
+    println!("{output}: {} entries", book.len());
+    Ok(())
+}
+
+fn probe(args: &[String]) -> Result<()> {
+    let (path, fen) = match args {
+        [path] => (path, None),
+        [path, fen] => (path, Some(fen)),
+        _ => bail!("{USAGE}"),
+    };
+    let book = Book::open(path)?;
+    let pos = match fen {
+        Some(fen) => Position::from_fen(fen)?,
+        None => Position::startpos(),
+    };
+    let moves = book.moves(&pos);
+    let total: u32 = moves.iter().map(|m| u32::from(m.weight)).sum();
+    if moves.is_empty() {
+        println!("No book moves");
+    }
+    for m in moves {
+        let share = f64::from(m.weight) * 100.0 / f64::from(total.max(1));
+        println!("{:7} {:5} {share:5.1}%", pos.move_to_san(&m.mv), m.weight);
+    }
+    Ok(())
+}
+
+fn number<T: std::str::FromStr>(text: &str, flag: &str) -> Result<T> {
+    text.parse()
+        .map_err(|_| anyhow!("{flag} expects a number, got {text:?}"))
+}
@@ -0,0 +1,27 @@
+// Runs the chess-core engine as a CECP (xboard/WinBoard) engine over stdin/stdout.
+// `--book FILE` makes it play from a Polyglot opening book while it can,
+// `--tablebase DIR` plays endings from the tables `chess-tablebase` wrote there,
+// `--syzygy DIR` from Syzygy tables.
+use anyhow::{bail, Result};
+use chess_core::book::Book;
+use chess_core::engine::xboard::Xboard;
+use chess_core::tablebase::syzygy::Syzygy;
+use chess_core::tablebase::Tablebase;
+use std::io;
+use std::sync::Arc;
+
+fn main() -> Result<()> {
+    let stdin = io::stdin();
+    let stdout = io::stdout();
+    let mut session = Xboard::new(stdout.lock());
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match (arg.as_str(), args.next()) {
+            ("--book", Some(path)) => session.set_book(Book::open(path)?),
+            ("--tablebase", Some(dir)) => session.set_tablebase(Arc::new(Tablebase::open(dir)?)),
+            ("--syzygy", Some(dir)) => session.set_syzygy(Arc::new(Syzygy::open(dir)?)),
+            _ => bail!("usage: chess-engine [--book FILE] [--tablebase DIR] [--syzygy DIR]"),
+        }
+    }
+    session.run(stdin.lock())
+}
@@ -0,0 +1,186 @@
+// Plays an engine-vs-engine match and reports the score, the Elo difference
+// and, if asked for, the SPRT verdict. Run with `--help` for the options.
+
+use anyhow::{anyhow, bail, Context, Result};
+use chess_core::engine::SearchLimits;
+use chess_core::tablebase::syzygy::Syzygy;
+use chess_core::tournament::openings::load_suite;
+use chess_core::tournament::stats::{Sprt, SprtStatus};
+use chess_core::tournament::{parse_time_control, run_match, EngineConfig, MatchConfig};
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::sync::Arc;
+use std::time::Duration;
+
+const USAGE: &str = "\
+usage: chess-match --engine ENGINE --engine ENGINE [options]
+
+ENGINE is `internal` or `uci=PATH`. These apply to the engine named last:
+  --name NAME            name used in the PGN and the report
+  --arg ARG              command line argument for a UCI engine
+  --option NAME=VALUE    UCI option sent before the first game
+
+Match options:
+  --openings FILE        FEN or EPD suite, each opening played with both colors
+  --rounds N             times the suite is played (default 1)
+  --concurrency N        games played at the same time (default 1)
+  --tc [MOVES/]SECS[+INC]  clock, e.g. 40/5400+30 or 10+0.1
+  --depth N | --nodes N | --movetime MS   per-move limits
+  --max-plies N          adjudicate longer games as draws (default 400)
+  --syzygy DIR           adjudicate endings from the Syzygy tables in DIR
+  --sprt ELO0,ELO1[,ALPHA,BETA]  stop once the test has an answer
+  --pgn FILE             write every game to FILE
+";
+
+fn main() -> Result<()> {
+    let mut args = std::env::args().skip(1);
+    let mut engines: Vec<EngineConfig> = Vec::new();
+    let mut config = MatchConfig::default();
+    let mut pgn_path = None;
+
+    while let Some(flag) = args.next() {
+        match flag.as_str() {
+            "--help" | "-h" => {
+                print!("{USAGE}");
+                return Ok(());
+            }
+            "--engine" => {
+                let spec = value(&mut args, &flag)?;
+                let engine = match spec.strip_prefix("uci=") {
+                    Some(path) => EngineConfig::uci(path, path),
+                    None if spec == "internal" => EngineConfig::internal("chess-core"),
+                    None => bail!("Unknown engine {spec:?}"),
+                };
+                engines.push(engine);
+            }
+            "--name" | "--arg" | "--option" => {
+                let arg = value(&mut args, &flag)?;
+                let engine = engines
+                    .pop()
+                    .ok_or_else(|| anyhow!("{flag} must follow an --engine"))?;
+                let engine = match flag.as_str() {
+                    "--name" => EngineConfig {
+                        name: arg,
+                        ..engine
+                    },
+                    "--arg" => engine.with_arg(arg),
+                    _ => {
+                        let (name, value) = arg
+                            .split_once('=')
+                            .ok_or_else(|| anyhow!("--option expects NAME=VALUE"))?;
+                        engine.with_option(name, value)
+                    }
+                };
+                engines.push(engine);
+            }
+            "--openings" => config.openings = load_suite(value(&mut args, &flag)?)?,
+            "--rounds" => config.rounds = number(&value(&mut args, &flag)?, &flag)?,
+            "--concurrency" => config.concurrency = number(&value(&mut args, &flag)?, &flag)?,
+            "--tc" => {
+                let tc = value(&mut args, &flag)?;
+                config.level =
+                    Some(parse_time_control(&tc).ok_or_else(|| anyhow!("Bad time control {tc}"))?);
+            }
+            "--depth" => config.limits.depth = Some(number(&value(&mut args, &flag)?, &flag)?),
+            "--nodes" => config.limits.nodes = Some(number(&value(&mut args, &flag)?, &flag)?),
+            "--movetime" => {
+                let ms = number(&value(&mut args, &flag)?, &flag)?;
+                config.limits.movetime = Some(Duration::from_millis(ms));
+            }
+            "--max-plies" => config.max_plies = Some(number(&value(&mut args, &flag)?, &flag)?),
+            "--syzygy" => {
+                config.syzygy = Some(Arc::new(Syzygy::open(value(&mut args, &flag)?)?));
+            }
+            "--sprt" => config.sprt = Some(parse_sprt(&value(&mut args, &flag)?)?),
+            "--pgn" => pgn_path = Some(value(&mut args, &flag)?),
+            _ => bail!("Unknown option {flag}\n\n{USAGE}"),
+        }
+    }
+
+    let [first, second]: [EngineConfig; 2] = engines
+        .try_into()
+        .map_err(|_| anyhow!("Exactly two engines are needed\n\n{USAGE}"))?;
+    if config.level.is_none() && config.limits == SearchLimits::default() {
+        bail!("Give a time control or a per-move limit");
+    }
+    config.first = first;
+    config.second = second;
+
+    let mut pgn = match &pgn_path {
+        Some(path) => Some(BufWriter::new(
+            File::create(path).with_context(|| format!("Failed to create {path}"))?,
+        )),
+        None => None,
+    };
+    let total = config.total_games();
+    let report = run_match(&config, |record, tally| {
+        println!(
+            "Game {} of {total}: {} - {} {} {{{}}} | {tally}",
+            record.index + 1,
+            record.pgn.tag("White").unwrap_or("?"),
+            record.pgn.tag("Black").unwrap_or("?"),
+            record.pgn.result,
+            record.termination.comment(),
+        );
+        if let Some(out) = pgn.as_mut() {
+            let _ = writeln!(out, "{}", record.pgn);
+        }
+    })?;
+    if let Some(mut out) = pgn {
+        out.flush()?;
+    }
+
+    let tally = report.tally;
+    println!(
+        "Score of {} vs {}: {tally} [{:.3}] {}",
+        config.first.name,
+        config.second.name,
+        tally.score().unwrap_or(0.5),
+        tally.games()
+    );
+    match tally.elo() {
+        Some(elo) => println!("Elo difference: {elo}"),
+        None => println!("Elo difference: undefined"),
+    }
+    if let (Some(test), Some(status)) = (config.sprt, report.sprt) {
+        let (lower, upper) = test.bounds();
+        let verdict = match status {
+            SprtStatus::Continue => "inconclusive",
+            SprtStatus::AcceptH0 => "H0 accepted (fail)",
+            SprtStatus::AcceptH1 => "H1 accepted (pass)",
+        };
+        println!(
+            "SPRT: llr {:.2} ({lower:.2}, {upper:.2}) [{}, {}] {verdict}",
+            test.llr(&tally),
+            test.elo0,
+            test.elo1
+        );
+    }
+    Ok(())
+}
+
+fn value(args: &mut impl Iterator<Item = String>, flag: &str) -> Result<String> {
+    args.next().ok_or_else(|| anyhow!("{flag} needs a value"))
+}
+
+fn number<T: std::str::FromStr>(text: &str, flag: &str) -> Result<T> {
+    text.parse()
+        .map_err(|_| anyhow!("{flag} expects a number, got {text:?}"))
+}
+
+fn parse_sprt(text: &str) -> Result<Sprt> {
+    let values = text
+        .split(',')
+        .map(|v| number::<f64>(v, "--sprt"))
+        .collect::<Result<Vec<_>>>()?;
+    let mut sprt = match values[..] {
+        [elo0, elo1, ..] => Sprt::new(elo0, elo1),
+        _ => bail!("--sprt expects ELO0,ELO1[,ALPHA,BETA]"),
+    };
+    match values[2..] {
+        [] => {}
+        [alpha, beta] => (sprt.alpha, sprt.beta) = (alpha, beta),
+        _ => bail!("--sprt expects ELO0,ELO1[,ALPHA,BETA]"),
+    }
+    Ok(sprt)
+}
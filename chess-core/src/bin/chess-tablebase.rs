@@ -0,0 +1,63 @@
+// Generates distance-to-mate tables into a directory and looks positions up
+// in them. Run with `--help` for the options.
+
+use anyhow::{bail, Result};
+use chess_core::position::Position;
+use chess_core::tablebase::{Material, Tablebase};
+use std::path::Path;
+use std::time::Instant;
+
+const USAGE: &str = "\
+usage: chess-tablebase generate DIR MATERIAL...
+       chess-tablebase probe DIR FEN
+
+MATERIAL names the men of both sides, e.g. KQvK, KBNvK or KRvKP. Tables a
+capture or promotion leads to are generated as well; tables already in DIR
+are reused.
+";
+
+fn main() -> Result<()> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match args.first().map(String::as_str) {
+        Some("generate") if args.len() > 2 => generate(&args[1], &args[2..]),
+        Some("probe") if args.len() == 3 => probe(&args[1], &args[2]),
+        Some("--help" | "-h") => {
+            print!("{USAGE}");
+            Ok(())
+        }
+        _ => bail!("{USAGE}"),
+    }
+}
+
+fn generate(dir: &str, materials: &[String]) -> Result<()> {
+    let mut tb = match Path::new(dir).is_dir() {
+        true => Tablebase::open(dir)?,
+        false => Tablebase::new(),
+    };
+    for name in materials {
+        let material = Material::parse(name)?;
+        let started = Instant::now();
+        tb.generate(&material)?;
+        let longest = tb.table(&material).map_or(0, |table| table.longest());
+        println!(
+            "{}: longest win {} plies, {:.1}s",
+            material.canonical(),
+            longest,
+            started.elapsed().as_secs_f64()
+        );
+    }
+    tb.save(dir)
+}
+
+fn probe(dir: &str, fen: &str) -> Result<()> {
+    let tb = Tablebase::open(dir)?;
+    let pos = Position::from_fen(fen)?;
+    let Some(dtm) = tb.probe(&pos) else {
+        bail!("No table for {}", Material::from_position(&pos));
+    };
+    println!("{dtm}");
+    if let Some((mv, _)) = tb.best_move(&pos) {
+        println!("best move {}", pos.move_to_san(&mv));
+    }
+    Ok(())
+}
@@ -0,0 +1,518 @@
+//! chess_core::book
+//!
+//! Opening books in the Polyglot format. A book file is a flat array of
+//! 16-byte big-endian entries `(key: u64, move: u16, weight: u16, learn: u32)`
+//! sorted by key, where the key is the Polyglot Zobrist hash of a position
+//! (see [`polyglot_key`]) and the move is packed by [`encode_move`].
+//!
+//! [`Book`] reads and writes such files and picks moves out of them;
+//! [`BookBuilder`] makes one from a collection of PGN games.
+//!
+//! See <http://hgm.nubati.net/book_format.html> for the format.
+
+pub mod random;
+
+use crate::pgn::{parse_games, PgnGame};
+use crate::position::{file_of, rank_of, square_at, CastleSide, ChessMove, Position};
+use crate::types::{Color, Type};
+use anyhow::{bail, Context, Result};
+use random::{RANDOM64, RANDOM_CASTLE, RANDOM_EN_PASSANT, RANDOM_PIECE, RANDOM_TURN};
+use std::collections::hash_map::RandomState;
+use std::collections::BTreeMap;
+use std::hash::{BuildHasher, Hasher};
+use std::path::Path;
+
+pub const ENTRY_SIZE: usize = 16;
+
+// The Polyglot hash of `pos`. The en passant file only counts when a pawn of
+// the side to move stands next to the pawn that just made its double step.
+pub fn polyglot_key(pos: &Position) -> u64 {
+    let mut key = 0;
+    for (sq, man) in pos.board.iter().enumerate() {
+        let Some(man) = man else { continue };
+        let role = match man.ty {
+            Type::Pawn => 0,
+            Type::Knight => 1,
+            Type::Bishop => 2,
+            Type::Rook => 3,
+            Type::Queen => 4,
+            Type::King => 5,
+            Type::Fairy(_) => unreachable!("a position only holds orthodox pieces"),
+        };
+        let kind = 2 * role + usize::from(man.color == Color::White);
+        key ^= RANDOM64[RANDOM_PIECE + 64 * kind + sq];
+    }
+    let rights = [
+        (Color::White, CastleSide::King),
+        (Color::White, CastleSide::Queen),
+        (Color::Black, CastleSide::King),
+        (Color::Black, CastleSide::Queen),
+    ];
+    for (i, (color, side)) in rights.into_iter().enumerate() {
+        if pos.castling.rook(color, side).is_some() {
+            key ^= RANDOM64[RANDOM_CASTLE + i];
+        }
+    }
+    if let Some(ep) = pos.ep.filter(|&ep| pos.ep_capturable(ep)) {
+        key ^= RANDOM64[RANDOM_EN_PASSANT + ep.file().index()];
+    }
+    if pos.turn == Color::White {
+        key ^= RANDOM64[RANDOM_TURN];
+    }
+    key
+}
+
+// Packs `mv` the Polyglot way: destination file and row, origin file and row,
+// then the promotion piece, three bits each. Castling is the king taking its
+// own rook (`e1h1`), exactly as [`ChessMove`]() already encodes it.
+pub fn encode_move(mv: &ChessMove) -> u16 {
+    let promotion = match mv.promotion {
+        None => 0,
+        Some(Type::Knight) => 1,
+        Some(Type::Bishop) => 2,
+        Some(Type::Rook) => 3,
+        Some(_) => 4,
+    };
+    (file_of(mv.to) | rank_of(mv.to) << 3 | file_of(mv.from) << 6 | rank_of(mv.from) << 9) as u16
+        | promotion << 12
+}
+
+// The legal move in `pos` that `raw` stands for, if there is one.
+pub fn decode_move(pos: &Position, raw: u16) -> Option<ChessMove> {
+    let bits = |shift: u16| (raw >> shift & 7) as usize;
+    let to = square_at(bits(0), bits(3));
+    let from = square_at(bits(6), bits(9));
+    let promotion = match bits(12) {
+        0 => None,
+        1 => Some(Type::Knight),
+        2 => Some(Type::Bishop),
+        3 => Some(Type::Rook),
+        4 => Some(Type::Queen),
+        _ => return None,
+    };
+    let mv = ChessMove {
+        from,
+        to,
+        promotion,
+    };
+    pos.legal_moves().contains(&mv).then_some(mv)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BookEntry {
+    pub key: u64,
+    pub mv: u16,
+    pub weight: u16,
+    pub learn: u32,
+}
+
+impl BookEntry {
+    pub fn from_bytes(bytes: &[u8; ENTRY_SIZE]) -> Self {
+        Self {
+            key: u64::from_be_bytes(bytes[0..8].try_into().unwrap()),
+            mv: u16::from_be_bytes([bytes[8], bytes[9]]),
+            weight: u16::from_be_bytes([bytes[10], bytes[11]]),
+            learn: u32::from_be_bytes(bytes[12..16].try_into().unwrap()),
+        }
+    }
+
+    pub fn to_bytes(&self) -> [u8; ENTRY_SIZE] {
+        let mut bytes = [0; ENTRY_SIZE];
+        bytes[0..8].copy_from_slice(&self.key.to_be_bytes());
+        bytes[8..10].copy_from_slice(&self.mv.to_be_bytes());
+        bytes[10..12].copy_from_slice(&self.weight.to_be_bytes());
+        bytes[12..16].copy_from_slice(&self.learn.to_be_bytes());
+        bytes
+    }
+}
+
+// A decoded entry for one position.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BookMove {
+    pub mv: ChessMove,
+    pub weight: u16,
+    pub learn: u32,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Book {
+    // Sorted by key, and by descending weight within a key.
+    entries: Vec<BookEntry>,
+}
+
+impl Book {
+    pub fn from_entries(mut entries: Vec<BookEntry>) -> Self {
+        entries.sort_by(|a, b| a.key.cmp(&b.key).then(b.weight.cmp(&a.weight)));
+        Self { entries }
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        if !bytes.len().is_multiple_of(ENTRY_SIZE) {
+            bail!(
+                "Book size {} is not a multiple of {ENTRY_SIZE} bytes",
+                bytes.len()
+            );
+        }
+        let entries = bytes
+            .chunks_exact(ENTRY_SIZE)
+            .map(|chunk| BookEntry::from_bytes(chunk.try_into().unwrap()))
+            .collect();
+        Ok(Self::from_entries(entries))
+    }
+
+    // Reads a book from a local file.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let bytes = std::fs::read(path)
+            .with_context(|| format!("Failed to read book {}", path.display()))?;
+        Self::from_bytes(&bytes).with_context(|| format!("In book {}", path.display()))
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.entries.iter().flat_map(BookEntry::to_bytes).collect()
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        let path = path.as_ref();
+        std::fs::write(path, self.to_bytes())
+            .with_context(|| format!("Failed to write book {}", path.display()))
+    }
+
+    pub fn entries(&self) -> &[BookEntry] {
+        &self.entries
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    // Every book move for `pos`, heaviest first. Entries that do not decode to
+    // a legal move (a hash collision, or a broken book) are left out.
+    pub fn moves(&self, pos: &Position) -> Vec<BookMove> {
+        let key = polyglot_key(pos);
+        let start = self.entries.partition_point(|e| e.key < key);
+        self.entries[start..]
+            .iter()
+            .take_while(|e| e.key == key)
+            .filter_map(|e| {
+                Some(BookMove {
+                    mv: decode_move(pos, e.mv)?,
+                    weight: e.weight,
+                    learn: e.learn,
+                })
+            })
+            .collect()
+    }
+
+    // The heaviest book move for `pos`.
+    pub fn best_move(&self, pos: &Position) -> Option<ChessMove> {
+        self.moves(pos)
+            .into_iter()
+            .find(|m| m.weight > 0)
+            .map(|m| m.mv)
+    }
+
+    // Picks a book move with probability proportional to its weight; `roll` is
+    // any random number, e.g. from [`random_roll`](). Moves of weight zero are
+    // never picked.
+    pub fn weighted_move(&self, pos: &Position, roll: u64) -> Option<ChessMove> {
+        let moves = self.moves(pos);
+        let total: u64 = moves.iter().map(|m| u64::from(m.weight)).sum();
+        if total == 0 {
+            return None;
+        }
+        let mut pick = roll % total;
+        for m in moves {
+            let weight = u64::from(m.weight);
+            if pick < weight {
+                return Some(m.mv);
+            }
+            pick -= weight;
+        }
+        None
+    }
+}
+
+// A fresh random number for [`Book::weighted_move`](), good enough to vary
+// the opening from game to game.
+pub fn random_roll() -> u64 {
+    let mut hasher = RandomState::new().build_hasher();
+    hasher.write_u64(
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_or(0, |d| d.as_nanos() as u64),
+    );
+    hasher.finish()
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+struct MoveStats {
+    games: u32,
+    // Points scored by the side that played the move, counted in half points.
+    half_points: u32,
+}
+
+// Collects moves from finished games and turns them into a [`Book`](). A move
+// makes it into the book if it was played in at least `min_games` games and
+// scored at least `min_score` (0.0 to 1.0) for the side that played it. The
+// weight of a move is the number of half points it scored.
+#[derive(Debug, Clone)]
+pub struct BookBuilder {
+    pub min_games: u32,
+    pub min_score: f64,
+    // Only the first `max_ply` plies of every game are looked at.
+    pub max_ply: usize,
+    stats: BTreeMap<(u64, u16), MoveStats>,
+}
+
+impl Default for BookBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl BookBuilder {
+    pub fn new() -> Self {
+        Self {
+            min_games: 1,
+            min_score: 0.0,
+            max_ply: 40,
+            stats: BTreeMap::new(),
+        }
+    }
+
+    // Adds the moves of `game`. Returns `false` (and adds nothing) for games
+    // without a decisive or drawn result.
+    pub fn add_game(&mut self, game: &PgnGame) -> bool {
+        let white_half_points = match game.result.as_str() {
+            "1-0" => 2,
+            "1/2-1/2" => 1,
+            "0-1" => 0,
+            _ => return false,
+        };
+        let mut pos = game.start;
+        for mv in game.moves.iter().take(self.max_ply) {
+            let half_points = match pos.turn {
+                Color::White => white_half_points,
+                Color::Black => 2 - white_half_points,
+            };
+            let stats = self
+                .stats
+                .entry((polyglot_key(&pos), encode_move(mv)))
+                .or_default();
+            stats.games += 1;
+            stats.half_points += half_points;
+            pos.make_move(mv);
+        }
+        true
+    }
+
+    // Adds every game of a PGN collection. Returns how many games were used
+    // and how many were skipped, either unfinished or unreadable.
+    pub fn add_pgn(&mut self, text: &str) -> (usize, usize) {
+        let (mut added, mut skipped) = (0, 0);
+        for game in parse_games(text) {
+            match game {
+                Ok(game) if self.add_game(&game) => added += 1,
+                _ => skipped += 1,
+            }
+        }
+        (added, skipped)
+    }
+
+    pub fn build(&self) -> Book {
+        let mut entries: Vec<BookEntry> = self
+            .stats
+            .iter()
+            .filter(|(_, s)| {
+                s.games >= self.min_games
+                    && f64::from(s.half_points) >= self.min_score * 2.0 * f64::from(s.games)
+            })
+            .map(|(&(key, mv), s)| BookEntry {
+                key,
+                mv,
+                weight: s.half_points.min(u32::from(u16::MAX)) as u16,
+                learn: 0,
+            })
+            .collect();
+        // Keep the proportions between moves of one position when some weight
+        // does not fit in 16 bits.
+        for group in entries.chunk_by_mut(|a, b| a.key == b.key) {
+            let half_points = |e: &BookEntry| self.stats[&(e.key, e.mv)].half_points;
+            let heaviest = group.iter().map(half_points).max().unwrap_or(0);
+            if heaviest > u32::from(u16::MAX) {
+                for e in group.iter_mut() {
+                    let scaled =
+                        u64::from(half_points(e)) * u64::from(u16::MAX) / u64::from(heaviest);
+                    e.weight = scaled as u16;
+                }
+            }
+        }
+        Book::from_entries(entries)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(fen: &str) -> u64 {
+        polyglot_key(&Position::from_fen(fen).unwrap())
+    }
+
+    #[test]
+    fn matches_the_reference_keys() {
+        // The test positions from the Polyglot format description.
+        let cases = [
+            (
+                "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+                0x463b96181691fc9c,
+            ),
+            (
+                "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1",
+                0x823c9b50fd114196,
+            ),
+            (
+                "rnbqkbnr/ppp1pppp/8/3p4/4P3/8/PPPP1PPP/RNBQKBNR w KQkq d6 0 2",
+                0x0756b94461c50fb0,
+            ),
+            (
+                "rnbqkbnr/ppp1pppp/8/3pP3/8/8/PPPP1PPP/RNBQKBNR b KQkq - 0 2",
+                0x662fafb965db29d4,
+            ),
+            (
+                "rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3",
+                0x22a48b5a8e47ff78,
+            ),
+            (
+                "rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPPKPPP/RNBQ1BNR b kq - 0 3",
+                0x652a607ca3f242c1,
+            ),
+            (
+                "rnbq1bnr/ppp1pkpp/8/3pPp2/8/8/PPPPKPPP/RNBQ1BNR w - - 0 4",
+                0x00fdd303c946bdd9,
+            ),
+            (
+                "rnbqkbnr/p1pppppp/8/8/PpP4P/8/1P1PPPP1/RNBQKBNR b KQkq c3 0 3",
+                0x3c8123ea7b067637,
+            ),
+            (
+                "rnbqkbnr/p1pppppp/8/8/P6P/R1p5/1P1PPPP1/1NBQKBNR b Kkq - 0 4",
+                0x5c3f9b829b279560,
+            ),
+        ];
+        for (fen, expected) in cases {
+            assert_eq!(key(fen), expected, "{fen}");
+        }
+    }
+
+    #[test]
+    fn encodes_moves() {
+        let pos = Position::startpos();
+        let e4 = pos.parse_uci("e2e4").unwrap();
+        assert_eq!(encode_move(&e4), 0x031c);
+        assert_eq!(decode_move(&pos, 0x031c), Some(e4));
+        // Not a legal move from the start.
+        assert_eq!(decode_move(&pos, encode_move(&ChessMove::new(4, 36))), None);
+
+        let pos = Position::from_fen("r3k3/1P6/8/8/8/8/8/R3K2R w KQq - 0 1").unwrap();
+        for uci in ["e1g1", "e1c1", "b7a8n", "b7b8q"] {
+            let mv = pos.parse_uci(uci).unwrap();
+            assert_eq!(decode_move(&pos, encode_move(&mv)), Some(mv), "{uci}");
+        }
+        // Castling short is stored as the king taking the h1 rook.
+        assert_eq!(encode_move(&pos.parse_uci("e1g1").unwrap()), 0x0107);
+        assert_eq!(
+            encode_move(&pos.parse_uci("b7b8q").unwrap()),
+            0x4000 | 0x0039 | 1 << 6 | 6 << 9
+        );
+    }
+
+    fn sample_book() -> Book {
+        let pos = Position::startpos();
+        let entry = |uci: &str, weight| BookEntry {
+            key: polyglot_key(&pos),
+            mv: encode_move(&pos.parse_uci(uci).unwrap()),
+            weight,
+            learn: 0,
+        };
+        Book::from_entries(vec![
+            entry("d2d4", 10),
+            BookEntry {
+                key: 1,
+                mv: 0,
+                weight: 1,
+                learn: 0,
+            },
+            entry("e2e4", 30),
+            entry("a2a3", 0),
+        ])
+    }
+
+    #[test]
+    fn reads_back_what_it_writes() {
+        let book = sample_book();
+        let bytes = book.to_bytes();
+        assert_eq!(bytes.len(), 4 * ENTRY_SIZE);
+        assert_eq!(Book::from_bytes(&bytes).unwrap(), book);
+        assert!(Book::from_bytes(&bytes[1..]).is_err());
+        assert!(book.entries().windows(2).all(|w| w[0].key <= w[1].key));
+    }
+
+    #[test]
+    fn picks_moves_by_weight() {
+        let book = sample_book();
+        let pos = Position::startpos();
+        let uci = |mv: Option<ChessMove>| mv.map(|mv| pos.move_to_uci(&mv));
+        assert_eq!(book.moves(&pos).len(), 3);
+        assert_eq!(uci(book.best_move(&pos)).as_deref(), Some("e2e4"));
+        // Rolls 0..30 land on e2e4, 30..40 on d2d4, and a2a3 never comes up.
+        assert_eq!(uci(book.weighted_move(&pos, 29)).as_deref(), Some("e2e4"));
+        assert_eq!(uci(book.weighted_move(&pos, 35)).as_deref(), Some("d2d4"));
+        assert_eq!(uci(book.weighted_move(&pos, 40)).as_deref(), Some("e2e4"));
+        let after = Position::from_fen("8/8/8/8/8/8/8/K6k w - - 0 1").unwrap();
+        assert_eq!(book.best_move(&after), None);
+        assert_eq!(book.weighted_move(&after, 7), None);
+    }
+
+    #[test]
+    fn builds_from_games() {
+        let pgn = "\
+[Result \"1-0\"]\n\n1. e4 e5 2. Nf3 1-0\n\n\
+[Result \"1/2-1/2\"]\n\n1. e4 c5 1/2-1/2\n\n\
+[Result \"0-1\"]\n\n1. d4 d5 0-1\n\n\
+[Result \"*\"]\n\n1. c4 *\n";
+        let mut builder = BookBuilder::new();
+        assert_eq!(builder.add_pgn(pgn), (3, 1));
+        let start = Position::startpos();
+        let weights = |book: &Book, pos: &Position| -> Vec<(String, u16)> {
+            book.moves(pos)
+                .iter()
+                .map(|m| (pos.move_to_uci(&m.mv), m.weight))
+                .collect()
+        };
+
+        let book = builder.build();
+        assert_eq!(
+            weights(&book, &start),
+            [("e2e4".to_string(), 3), ("d2d4".to_string(), 0)]
+        );
+
+        builder.min_games = 2;
+        let book = builder.build();
+        assert_eq!(weights(&book, &start), [("e2e4".to_string(), 3)]);
+        assert_eq!(book.len(), 1);
+
+        builder.min_games = 1;
+        builder.min_score = 0.5;
+        let book = builder.build();
+        let mut after_d4 = start;
+        after_d4.play(&start.parse_san("d4").unwrap()).unwrap();
+        assert_eq!(weights(&book, &start), [("e2e4".to_string(), 3)]);
+        assert_eq!(weights(&book, &after_d4), [("d7d5".to_string(), 2)]);
+    }
+}
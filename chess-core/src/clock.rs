@@ -0,0 +1,414 @@
+//! chess_core::clock
+//!
+//! Game clocks. A [`TimeControl`] says how much time the players get: sudden
+//! death, a Fischer increment, a Bronstein or simple delay, or several
+//! periods such as 40 moves in 90 minutes followed by 30 minutes for the rest
+//! of the game. A [`ChessClock`] runs it for the player to move and reads the
+//! time from a [`TimeSource`]; tests use a [`ManualTime`] they advance by hand.
+//! Both fail with a [`ChessError`].
+
+use crate::engine::xboard::Level;
+use crate::error::ChessError;
+use crate::types::Color;
+use std::fmt;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+// What a move does to the time of the player who made it.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Bonus {
+    #[default]
+    None,
+    // Fischer: added after every move.
+    Increment(Duration),
+    // Bronstein: the time a move took is given back, up to this much.
+    Bronstein(Duration),
+    // Simple or US delay: the clock waits this long before it starts running.
+    Delay(Duration),
+}
+
+// `moves` moves to be made in `time`. Without a move count the period lasts
+// for the rest of the game.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Period {
+    pub moves: Option<u32>,
+    pub time: Duration,
+    pub bonus: Bonus,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TimeControl {
+    periods: Vec<Period>,
+}
+
+impl TimeControl {
+    // Players who get through every period start the last one over again,
+    // so `40/5400` is 90 minutes for every 40 moves.
+    pub fn new(periods: Vec<Period>) -> Result<Self, ChessError> {
+        if periods.is_empty() {
+            return Err(ChessError::time_control("it needs at least one period"));
+        }
+        if periods.iter().any(|period| period.moves == Some(0)) {
+            return Err(ChessError::time_control("a period needs at least one move"));
+        }
+        Ok(Self { periods })
+    }
+
+    pub fn sudden_death(time: Duration) -> Self {
+        Self::single(time, Bonus::None)
+    }
+
+    pub fn fischer(time: Duration, increment: Duration) -> Self {
+        Self::single(time, Bonus::Increment(increment))
+    }
+
+    pub fn bronstein(time: Duration, delay: Duration) -> Self {
+        Self::single(time, Bonus::Bronstein(delay))
+    }
+
+    pub fn delay(time: Duration, delay: Duration) -> Self {
+        Self::single(time, Bonus::Delay(delay))
+    }
+
+    fn single(time: Duration, bonus: Bonus) -> Self {
+        Self {
+            periods: vec![Period {
+                moves: None,
+                time,
+                bonus,
+            }],
+        }
+    }
+
+    pub fn periods(&self) -> &[Period] {
+        &self.periods
+    }
+
+    fn period(&self, index: usize) -> &Period {
+        &self.periods[index.min(self.periods.len() - 1)]
+    }
+}
+
+// The PGN `TimeControl` tag: periods like `40/5400+30` or `300+2`, in
+// seconds, separated by colons.
+impl FromStr for TimeControl {
+    type Err = ChessError;
+
+    fn from_str(tag: &str) -> Result<Self, ChessError> {
+        let bad_time = |s: &str| ChessError::time_control(format!("bad time {s:?} in {tag:?}"));
+        let seconds = |s: &str| -> Result<Duration, ChessError> {
+            let secs: f64 = s.parse().map_err(|_| bad_time(s))?;
+            if !secs.is_finite() || secs < 0.0 {
+                return Err(bad_time(s));
+            }
+            Ok(Duration::from_secs_f64(secs))
+        };
+        let periods = tag
+            .split(':')
+            .map(|period| {
+                let (moves, rest) = match period.split_once('/') {
+                    Some((moves, rest)) => (
+                        Some(moves.parse().map_err(|_| {
+                            ChessError::time_control(format!("bad move count in {tag:?}"))
+                        })?),
+                        rest,
+                    ),
+                    None => (None, period),
+                };
+                let (time, bonus) = match rest.split_once('+') {
+                    Some((time, inc)) => (seconds(time)?, Bonus::Increment(seconds(inc)?)),
+                    None => (seconds(rest)?, Bonus::None),
+                };
+                Ok(Period { moves, time, bonus })
+            })
+            .collect::<Result<Vec<_>, ChessError>>()?;
+        Self::new(periods)
+    }
+}
+
+// The clock `level MPS BASE INC` asks an xboard engine to play with.
+impl From<Level> for TimeControl {
+    fn from(level: Level) -> Self {
+        let moves = (level.moves_per_session > 0).then_some(level.moves_per_session);
+        Self {
+            periods: vec![Period {
+                moves,
+                time: level.base,
+                bonus: Bonus::Increment(level.increment),
+            }],
+        }
+    }
+}
+
+// Where a [`ChessClock`]() gets the time from.
+pub trait TimeSource: fmt::Debug + Send + Sync {
+    // Time since a fixed point in the past; it never goes backwards.
+    fn now(&self) -> Duration;
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct WallClock {
+    epoch: Instant,
+}
+
+impl Default for WallClock {
+    fn default() -> Self {
+        Self {
+            epoch: Instant::now(),
+        }
+    }
+}
+
+impl TimeSource for WallClock {
+    fn now(&self) -> Duration {
+        self.epoch.elapsed()
+    }
+}
+
+// Time that only passes when told to. Clones share the same time.
+#[derive(Debug, Clone, Default)]
+pub struct ManualTime(Arc<AtomicU64>);
+
+impl ManualTime {
+    pub fn advance(&self, by: Duration) {
+        self.0.fetch_add(by.as_nanos() as u64, Ordering::Relaxed);
+    }
+}
+
+impl TimeSource for ManualTime {
+    fn now(&self) -> Duration {
+        Duration::from_nanos(self.0.load(Ordering::Relaxed))
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ChessClock {
+    control: TimeControl,
+    source: Arc<dyn TimeSource>,
+    // Time left when the current turn of each player began.
+    left: [Duration; 2],
+    // The period each player is in and the moves they made in it.
+    period: [usize; 2],
+    moves: [u32; 2],
+    // Whose clock runs, and since when.
+    running: Option<(Color, Duration)>,
+}
+
+impl ChessClock {
+    pub fn new(control: TimeControl) -> Self {
+        Self::with_source(control, Arc::new(WallClock::default()))
+    }
+
+    pub fn with_source(control: TimeControl, source: Arc<dyn TimeSource>) -> Self {
+        let time = control.period(0).time;
+        Self {
+            control,
+            source,
+            left: [time; 2],
+            period: [0; 2],
+            moves: [0; 2],
+            running: None,
+        }
+    }
+
+    pub fn control(&self) -> &TimeControl {
+        &self.control
+    }
+
+    pub fn running(&self) -> Option<Color> {
+        self.running.map(|(color, _)| color)
+    }
+
+    // Starts `color`'s clock, stopping the other one.
+    pub fn start(&mut self, color: Color) {
+        self.stop();
+        self.running = Some((color, self.source.now()));
+    }
+
+    // Stops both clocks, charging the running one for the time it ran.
+    pub fn stop(&mut self) {
+        if let Some((color, _)) = self.running {
+            self.left[color.index()] = self.remaining(color);
+            self.running = None;
+        }
+    }
+
+    // The time `color` has left right now.
+    pub fn remaining(&self, color: Color) -> Duration {
+        let left = self.left[color.index()];
+        match self.running {
+            Some((running, since)) if running == color => {
+                left.saturating_sub(self.used(color, since))
+            }
+            _ => left,
+        }
+    }
+
+    // Adjusts the time `color` has left, e.g. when a game is set up again.
+    pub fn set_remaining(&mut self, color: Color, left: Duration) {
+        if self.running() == Some(color) {
+            self.running = Some((color, self.source.now()));
+        }
+        self.left[color.index()] = left;
+    }
+
+    // The player whose time ran out, if any.
+    pub fn flagged(&self) -> Option<Color> {
+        [Color::White, Color::Black]
+            .into_iter()
+            .find(|&color| self.remaining(color).is_zero())
+    }
+
+    // Ends the turn of the player whose clock runs and starts the opponent's.
+    // Fails if their flag fell first, which ends the game.
+    pub fn press(&mut self) -> Result<(), ChessError> {
+        let Some((color, since)) = self.running else {
+            return Err(ChessError::ClockStopped);
+        };
+        if self.flagged() == Some(color) {
+            return Err(ChessError::GameFinished);
+        }
+        let side = color.index();
+        let period = *self.control.period(self.period[side]);
+        let took = self.source.now().saturating_sub(since);
+        self.left[side] = self.remaining(color);
+        self.left[side] += match period.bonus {
+            Bonus::Increment(increment) => increment,
+            Bonus::Bronstein(delay) => took.min(delay),
+            Bonus::None | Bonus::Delay(_) => Duration::ZERO,
+        };
+        self.moves[side] += 1;
+        if period.moves == Some(self.moves[side]) {
+            self.period[side] += 1;
+            self.moves[side] = 0;
+            self.left[side] += self.control.period(self.period[side]).time;
+        }
+        self.running = Some((color.opposite(), self.source.now()));
+        Ok(())
+    }
+
+    // Moves `color` has to make before the next period's time is added.
+    pub fn moves_to_go(&self, color: Color) -> Option<u32> {
+        let period = self.control.period(self.period[color.index()]);
+        period
+            .moves
+            .map(|moves| moves.saturating_sub(self.moves[color.index()]))
+    }
+
+    // The Fischer increment `color` gets for their next move.
+    pub fn increment(&self, color: Color) -> Duration {
+        match self.control.period(self.period[color.index()]).bonus {
+            Bonus::Increment(increment) => increment,
+            _ => Duration::ZERO,
+        }
+    }
+
+    // How much of `color`'s time the turn that began at `since` has used.
+    fn used(&self, color: Color, since: Duration) -> Duration {
+        let took = self.source.now().saturating_sub(since);
+        match self.control.period(self.period[color.index()]).bonus {
+            Bonus::Delay(delay) => took.saturating_sub(delay),
+            _ => took,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn secs(s: u64) -> Duration {
+        Duration::from_secs(s)
+    }
+
+    fn clock(control: TimeControl) -> (ChessClock, ManualTime) {
+        let time = ManualTime::default();
+        let mut clock = ChessClock::with_source(control, Arc::new(time.clone()));
+        clock.start(Color::White);
+        (clock, time)
+    }
+
+    #[test]
+    fn adds_fischer_increments() {
+        let (mut clock, time) = clock(TimeControl::fischer(secs(60), secs(2)));
+        time.advance(secs(10));
+        assert_eq!(clock.remaining(Color::White), secs(50));
+        assert_eq!(clock.remaining(Color::Black), secs(60));
+        clock.press().unwrap();
+        assert_eq!(clock.remaining(Color::White), secs(52));
+        assert_eq!(clock.running(), Some(Color::Black));
+        time.advance(secs(1));
+        clock.press().unwrap();
+        assert_eq!(clock.remaining(Color::Black), secs(61));
+    }
+
+    #[test]
+    fn delays_count_down_differently() {
+        let (mut bronstein, time) = clock(TimeControl::bronstein(secs(60), secs(5)));
+        let (mut delay, delay_time) = clock(TimeControl::delay(secs(60), secs(5)));
+        for (clock, time) in [(&mut bronstein, &time), (&mut delay, &delay_time)] {
+            time.advance(secs(3));
+            clock.press().unwrap();
+            assert_eq!(clock.remaining(Color::White), secs(60));
+            time.advance(secs(8));
+            clock.press().unwrap();
+            assert_eq!(clock.remaining(Color::Black), secs(57));
+        }
+        // Only the simple delay holds the clock still while it lasts.
+        time.advance(secs(4));
+        delay_time.advance(secs(4));
+        assert_eq!(bronstein.remaining(Color::White), secs(56));
+        assert_eq!(delay.remaining(Color::White), secs(60));
+    }
+
+    #[test]
+    fn adds_time_for_the_next_period() {
+        let control: TimeControl = "2/60+1:30".parse().unwrap();
+        assert_eq!(control.periods().len(), 2);
+        let (mut clock, time) = clock(control);
+        for _ in 0..2 {
+            assert_eq!(clock.increment(Color::White), secs(1));
+            time.advance(secs(10));
+            clock.press().unwrap();
+            clock.press().unwrap();
+        }
+        assert_eq!(clock.remaining(Color::White), secs(60 - 20 + 2 + 30));
+        assert_eq!(clock.moves_to_go(Color::White), None);
+        assert_eq!(clock.increment(Color::White), Duration::ZERO);
+
+        let (clock, _) = self::clock("40/5400".parse().unwrap());
+        assert_eq!(clock.moves_to_go(Color::White), Some(40));
+        assert!("40/".parse::<TimeControl>().is_err());
+        assert!("".parse::<TimeControl>().is_err());
+    }
+
+    #[test]
+    fn periods_need_moves() {
+        assert_eq!(
+            "0/60".parse::<TimeControl>().map_err(|err| err.code()),
+            Err("invalid_time_control")
+        );
+        let period = Period {
+            moves: Some(0),
+            time: secs(60),
+            bonus: Bonus::None,
+        };
+        assert!(TimeControl::new(vec![period]).is_err());
+    }
+
+    #[test]
+    fn flags_fall_at_zero() {
+        let (mut clock, time) = clock(TimeControl::sudden_death(secs(5)));
+        time.advance(secs(4));
+        clock.press().unwrap();
+        time.advance(secs(5));
+        assert_eq!(clock.flagged(), Some(Color::Black));
+        assert_eq!(clock.press(), Err(ChessError::GameFinished));
+        clock.stop();
+        assert_eq!(clock.flagged(), Some(Color::Black));
+        time.advance(secs(5));
+        assert_eq!(clock.remaining(Color::White), secs(1));
+    }
+}
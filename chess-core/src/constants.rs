@@ -1,65 +1,70 @@
+// Board indices of the squares of the standard board, as [`crate::square::Square`]()
+// numbers them, for indexing a [`crate::types::RawBoard`]() or a position.
+use crate::msg::TileId;
+use crate::square::Square;
+
 pub const TILECOUNT: usize = 64;
-pub const A1: usize = 0;
-pub const B1: usize = 1;
-pub const C1: usize = 2;
-pub const D1: usize = 3;
-pub const E1: usize = 4;
-pub const F1: usize = 5;
-pub const G1: usize = 6;
-pub const H1: usize = 7;
-pub const A2: usize = 8;
-pub const B2: usize = 9;
-pub const C2: usize = 10;
-pub const D2: usize = 11;
-pub const E2: usize = 12;
-pub const F2: usize = 13;
-pub const G2: usize = 14;
-pub const H2: usize = 15;
-pub const A3: usize = 16;
-pub const B3: usize = 17;
-pub const C3: usize = 18;
-pub const D3: usize = 19;
-pub const E3: usize = 20;
-pub const F3: usize = 21;
-pub const G3: usize = 22;
-pub const H3: usize = 23;
-pub const A4: usize = 24;
-pub const B4: usize = 25;
-pub const C4: usize = 26;
-pub const D4: usize = 27;
-pub const E4: usize = 28;
-pub const F4: usize = 29;
-pub const G4: usize = 30;
-pub const H4: usize = 31;
-pub const A5: usize = 32;
-pub const B5: usize = 33;
-pub const C5: usize = 34;
-pub const D5: usize = 35;
-pub const E5: usize = 36;
-pub const F5: usize = 37;
-pub const G5: usize = 38;
-pub const H5: usize = 39;
-pub const A6: usize = 40;
-pub const B6: usize = 41;
-pub const C6: usize = 42;
-pub const D6: usize = 43;
-pub const E6: usize = 44;
-pub const F6: usize = 45;
-pub const G6: usize = 46;
-pub const H6: usize = 47;
-pub const A7: usize = 48;
-pub const B7: usize = 49;
-pub const C7: usize = 50;
-pub const D7: usize = 51;
-pub const E7: usize = 52;
-pub const F7: usize = 53;
-pub const G7: usize = 54;
-pub const H7: usize = 55;
-pub const A8: usize = 56;
-pub const B8: usize = 57;
-pub const C8: usize = 58;
-pub const D8: usize = 59;
-pub const E8: usize = 60;
-pub const F8: usize = 61;
-pub const G8: usize = 62;
-pub const H8: usize = 63;
+pub const A1: TileId = Square::A1.index();
+pub const B1: TileId = Square::B1.index();
+pub const C1: TileId = Square::C1.index();
+pub const D1: TileId = Square::D1.index();
+pub const E1: TileId = Square::E1.index();
+pub const F1: TileId = Square::F1.index();
+pub const G1: TileId = Square::G1.index();
+pub const H1: TileId = Square::H1.index();
+pub const A2: TileId = Square::A2.index();
+pub const B2: TileId = Square::B2.index();
+pub const C2: TileId = Square::C2.index();
+pub const D2: TileId = Square::D2.index();
+pub const E2: TileId = Square::E2.index();
+pub const F2: TileId = Square::F2.index();
+pub const G2: TileId = Square::G2.index();
+pub const H2: TileId = Square::H2.index();
+pub const A3: TileId = Square::A3.index();
+pub const B3: TileId = Square::B3.index();
+pub const C3: TileId = Square::C3.index();
+pub const D3: TileId = Square::D3.index();
+pub const E3: TileId = Square::E3.index();
+pub const F3: TileId = Square::F3.index();
+pub const G3: TileId = Square::G3.index();
+pub const H3: TileId = Square::H3.index();
+pub const A4: TileId = Square::A4.index();
+pub const B4: TileId = Square::B4.index();
+pub const C4: TileId = Square::C4.index();
+pub const D4: TileId = Square::D4.index();
+pub const E4: TileId = Square::E4.index();
+pub const F4: TileId = Square::F4.index();
+pub const G4: TileId = Square::G4.index();
+pub const H4: TileId = Square::H4.index();
+pub const A5: TileId = Square::A5.index();
+pub const B5: TileId = Square::B5.index();
+pub const C5: TileId = Square::C5.index();
+pub const D5: TileId = Square::D5.index();
+pub const E5: TileId = Square::E5.index();
+pub const F5: TileId = Square::F5.index();
+pub const G5: TileId = Square::G5.index();
+pub const H5: TileId = Square::H5.index();
+pub const A6: TileId = Square::A6.index();
+pub const B6: TileId = Square::B6.index();
+pub const C6: TileId = Square::C6.index();
+pub const D6: TileId = Square::D6.index();
+pub const E6: TileId = Square::E6.index();
+pub const F6: TileId = Square::F6.index();
+pub const G6: TileId = Square::G6.index();
+pub const H6: TileId = Square::H6.index();
+pub const A7: TileId = Square::A7.index();
+pub const B7: TileId = Square::B7.index();
+pub const C7: TileId = Square::C7.index();
+pub const D7: TileId = Square::D7.index();
+pub const E7: TileId = Square::E7.index();
+pub const F7: TileId = Square::F7.index();
+pub const G7: TileId = Square::G7.index();
+pub const H7: TileId = Square::H7.index();
+pub const A8: TileId = Square::A8.index();
+pub const B8: TileId = Square::B8.index();
+pub const C8: TileId = Square::C8.index();
+pub const D8: TileId = Square::D8.index();
+pub const E8: TileId = Square::E8.index();
+pub const F8: TileId = Square::F8.index();
+pub const G8: TileId = Square::G8.index();
+pub const H8: TileId = Square::H8.index();
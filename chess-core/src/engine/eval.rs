@@ -0,0 +1,172 @@
+//! chess_core::engine::eval
+//!
+//! Static evaluation: material plus piece-square tables, with a small
+//! "mop-up" term that drives a lone king to the edge once the game is won.
+
+use crate::position::{file_of, rank_of, Man, Position};
+use crate::types::{Color, Type};
+
+pub const fn value(ty: Type) -> i32 {
+    match ty {
+        Type::Pawn => 100,
+        Type::Knight => 320,
+        Type::Bishop => 330,
+        Type::Rook => 500,
+        Type::Queen => 900,
+        Type::King => 0,
+        Type::Fairy(_) => panic!("a position only holds orthodox pieces"),
+    }
+}
+
+// The tables are written the way a board diagram reads, rank 8 first,
+// from white's point of view.
+#[rustfmt::skip]
+const PAWN: [i32; 64] = [
+     0,  0,  0,  0,  0,  0,  0,  0,
+    50, 50, 50, 50, 50, 50, 50, 50,
+    10, 10, 20, 30, 30, 20, 10, 10,
+     5,  5, 10, 25, 25, 10,  5,  5,
+     0,  0,  0, 20, 20,  0,  0,  0,
+     5, -5,-10,  0,  0,-10, -5,  5,
+     5, 10, 10,-20,-20, 10, 10,  5,
+     0,  0,  0,  0,  0,  0,  0,  0,
+];
+
+#[rustfmt::skip]
+const KNIGHT: [i32; 64] = [
+   -50,-40,-30,-30,-30,-30,-40,-50,
+   -40,-20,  0,  0,  0,  0,-20,-40,
+   -30,  0, 10, 15, 15, 10,  0,-30,
+   -30,  5, 15, 20, 20, 15,  5,-30,
+   -30,  0, 15, 20, 20, 15,  0,-30,
+   -30,  5, 10, 15, 15, 10,  5,-30,
+   -40,-20,  0,  5,  5,  0,-20,-40,
+   -50,-40,-30,-30,-30,-30,-40,-50,
+];
+
+#[rustfmt::skip]
+const BISHOP: [i32; 64] = [
+   -20,-10,-10,-10,-10,-10,-10,-20,
+   -10,  0,  0,  0,  0,  0,  0,-10,
+   -10,  0,  5, 10, 10,  5,  0,-10,
+   -10,  5,  5, 10, 10,  5,  5,-10,
+   -10,  0, 10, 10, 10, 10,  0,-10,
+   -10, 10, 10, 10, 10, 10, 10,-10,
+   -10,  5,  0,  0,  0,  0,  5,-10,
+   -20,-10,-10,-10,-10,-10,-10,-20,
+];
+
+#[rustfmt::skip]
+const ROOK: [i32; 64] = [
+     0,  0,  0,  0,  0,  0,  0,  0,
+     5, 10, 10, 10, 10, 10, 10,  5,
+    -5,  0,  0,  0,  0,  0,  0, -5,
+    -5,  0,  0,  0,  0,  0,  0, -5,
+    -5,  0,  0,  0,  0,  0,  0, -5,
+    -5,  0,  0,  0,  0,  0,  0, -5,
+    -5,  0,  0,  0,  0,  0,  0, -5,
+     0,  0,  0,  5,  5,  0,  0,  0,
+];
+
+#[rustfmt::skip]
+const QUEEN: [i32; 64] = [
+   -20,-10,-10, -5, -5,-10,-10,-20,
+   -10,  0,  0,  0,  0,  0,  0,-10,
+   -10,  0,  5,  5,  5,  5,  0,-10,
+    -5,  0,  5,  5,  5,  5,  0, -5,
+     0,  0,  5,  5,  5,  5,  0, -5,
+   -10,  5,  5,  5,  5,  5,  0,-10,
+   -10,  0,  5,  0,  0,  0,  0,-10,
+   -20,-10,-10, -5, -5,-10,-10,-20,
+];
+
+#[rustfmt::skip]
+const KING_MIDDLEGAME: [i32; 64] = [
+   -30,-40,-40,-50,-50,-40,-40,-30,
+   -30,-40,-40,-50,-50,-40,-40,-30,
+   -30,-40,-40,-50,-50,-40,-40,-30,
+   -30,-40,-40,-50,-50,-40,-40,-30,
+   -20,-30,-30,-40,-40,-30,-30,-20,
+   -10,-20,-20,-20,-20,-20,-20,-10,
+    20, 20,  0,  0,  0,  0, 20, 20,
+    20, 30, 10,  0,  0, 10, 30, 20,
+];
+
+#[rustfmt::skip]
+const KING_ENDGAME: [i32; 64] = [
+   -50,-40,-30,-20,-20,-30,-40,-50,
+   -30,-20,-10,  0,  0,-10,-20,-30,
+   -30,-10, 20, 30, 30, 20,-10,-30,
+   -30,-10, 30, 40, 40, 30,-10,-30,
+   -30,-10, 30, 40, 40, 30,-10,-30,
+   -30,-10, 20, 30, 30, 20,-10,-30,
+   -30,-30,  0,  0,  0,  0,-30,-30,
+   -50,-30,-30,-30,-30,-30,-30,-50,
+];
+
+// Non-pawn material at or below which kings should head for the center.
+const ENDGAME_MATERIAL: i32 = 2 * (value(Type::Rook) + value(Type::Bishop));
+
+fn table_index(sq: usize, color: Color) -> usize {
+    let (file, rank) = (file_of(sq), rank_of(sq));
+    match color {
+        Color::White => (7 - rank) * 8 + file,
+        Color::Black => rank * 8 + file,
+    }
+}
+
+fn square_bonus(man: Man, sq: usize, endgame: bool) -> i32 {
+    let idx = table_index(sq, man.color);
+    match man.ty {
+        Type::Pawn => PAWN[idx],
+        Type::Knight => KNIGHT[idx],
+        Type::Bishop => BISHOP[idx],
+        Type::Rook => ROOK[idx],
+        Type::Queen => QUEEN[idx],
+        Type::King if endgame => KING_ENDGAME[idx],
+        Type::King => KING_MIDDLEGAME[idx],
+        Type::Fairy(_) => unreachable!("a position only holds orthodox pieces"),
+    }
+}
+
+fn center_distance(sq: usize) -> i32 {
+    let f = file_of(sq) as i32;
+    let r = rank_of(sq) as i32;
+    (2 * f - 7).abs().max((2 * r - 7).abs()) / 2
+}
+
+// Evaluates `pos` in centipawns from the point of view of the side to move.
+pub fn evaluate(pos: &Position) -> i32 {
+    let mut material = [0; 2];
+    let mut officers = [0; 2];
+    for man in pos.board.iter().flatten() {
+        material[man.color.index()] += value(man.ty);
+        if man.ty != Type::Pawn && man.ty != Type::King {
+            officers[man.color.index()] += value(man.ty);
+        }
+    }
+    let endgame = officers[0] + officers[1] <= ENDGAME_MATERIAL;
+
+    let mut score = [material[0], material[1]];
+    for (sq, man) in pos.board.iter().enumerate() {
+        if let Some(man) = man {
+            score[man.color.index()] += square_bonus(*man, sq, endgame);
+        }
+    }
+
+    for winner in [Color::White, Color::Black] {
+        let loser = winner.opposite();
+        if material[loser.index()] == 0 && officers[winner.index()] >= value(Type::Rook) {
+            if let (Some(wk), Some(lk)) = (pos.king(winner), pos.king(loser)) {
+                score[winner.index()] +=
+                    10 * center_distance(lk.index()) + 4 * (7 - wk.distance(lk) as i32);
+            }
+        }
+    }
+
+    let white = score[0] - score[1];
+    match pos.turn {
+        Color::White => white,
+        Color::Black => -white,
+    }
+}
@@ -0,0 +1,480 @@
+//! chess_core::engine
+//!
+//! A small alpha-beta searcher over [`Position`]. It is deliberately simple:
+//! iterative deepening, quiescence on captures, MVV-LVA and killer move
+//! ordering. Front ends such as [`xboard`] only need [`Engine::search`].
+//! With a [`Tablebase`] attached, endings it covers are played perfectly;
+//! [`Syzygy`] tables keep it on a winning path within the fifty-move rule.
+
+pub mod eval;
+pub mod xboard;
+
+use crate::position::{ChessMove, Position};
+use crate::tablebase::syzygy::{Syzygy, Wdl};
+use crate::tablebase::{Dtm, Tablebase};
+use crate::types::Color;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+pub const MATE: i32 = 32_000;
+const INFINITY: i32 = MATE + 1;
+const MAX_PLY: usize = 96;
+
+// Syzygy wins score [`TB_WIN`]() less the plies to the next zeroing move,
+// below every mate score.
+pub const TB_WIN: i32 = MATE - 2 * MAX_PLY as i32;
+
+// Mate scores count down from [`MATE`]() by the number of plies to the mate.
+pub fn is_mate_score(score: i32) -> bool {
+    score.abs() >= MATE - MAX_PLY as i32
+}
+
+// Full moves until mate, positive when the side to move is mating.
+pub fn mate_in(score: i32) -> Option<i32> {
+    if !is_mate_score(score) {
+        return None;
+    }
+    let plies = MATE - score.abs();
+    let moves = (plies + 1) / 2;
+    Some(if score > 0 { moves } else { -moves })
+}
+
+// How long to search. The clock fields mirror UCI's `go wtime ... movestogo`;
+// they only matter when no fixed `movetime` is given.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SearchLimits {
+    pub depth: Option<u32>,
+    pub movetime: Option<Duration>,
+    pub nodes: Option<u64>,
+    pub wtime: Option<Duration>,
+    pub btime: Option<Duration>,
+    pub winc: Option<Duration>,
+    pub binc: Option<Duration>,
+    pub movestogo: Option<u32>,
+}
+
+impl SearchLimits {
+    pub fn depth(depth: u32) -> Self {
+        Self {
+            depth: Some(depth),
+            ..Default::default()
+        }
+    }
+    pub fn movetime(movetime: Duration) -> Self {
+        Self {
+            movetime: Some(movetime),
+            ..Default::default()
+        }
+    }
+
+    pub fn clock(&self, side: Color) -> Option<Duration> {
+        match side {
+            Color::White => self.wtime,
+            Color::Black => self.btime,
+        }
+    }
+
+    pub fn increment(&self, side: Color) -> Duration {
+        match side {
+            Color::White => self.winc,
+            Color::Black => self.binc,
+        }
+        .unwrap_or_default()
+    }
+
+    // Time to spend on the next move of `side`: the fixed `movetime` if there
+    // is one, otherwise an even share of the clock plus most of the increment.
+    pub fn budget(&self, side: Color) -> Option<Duration> {
+        if self.movetime.is_some() {
+            return self.movetime;
+        }
+        let left = self.clock(side)?;
+        let moves_to_go = self.movestogo.unwrap_or(30).max(1);
+        let budget = left / moves_to_go + self.increment(side) * 3 / 4;
+        // Never plan to spend more than half of what is left on one move.
+        Some(budget.min(left / 2).max(Duration::from_millis(10)))
+    }
+}
+
+// What the engine knows after each completed iteration.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SearchInfo {
+    pub depth: u32,
+    pub score: i32,
+    pub nodes: u64,
+    pub elapsed: Duration,
+    pub pv: Vec<ChessMove>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SearchResult {
+    pub best: Option<ChessMove>,
+    pub info: SearchInfo,
+}
+
+pub struct Engine {
+    nodes: u64,
+    started: Instant,
+    deadline: Option<Instant>,
+    node_limit: Option<u64>,
+    stopped: bool,
+    killers: [[Option<ChessMove>; 2]; MAX_PLY],
+    // Hashes of every position from the start of the game up to the node being searched.
+    path: Vec<u64>,
+    root_pv: Vec<ChessMove>,
+    tablebase: Option<Arc<Tablebase>>,
+    syzygy: Option<Arc<Syzygy>>,
+}
+
+impl Default for Engine {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Engine {
+    pub fn new() -> Self {
+        Self {
+            nodes: 0,
+            started: Instant::now(),
+            deadline: None,
+            node_limit: None,
+            stopped: false,
+            killers: [[None; 2]; MAX_PLY],
+            path: Vec::new(),
+            root_pv: Vec::new(),
+            tablebase: None,
+            syzygy: None,
+        }
+    }
+
+    // Answers positions covered by `tablebase` from it instead of searching.
+    pub fn set_tablebase(&mut self, tablebase: Arc<Tablebase>) {
+        self.tablebase = Some(tablebase);
+    }
+
+    // Picks the root move from `syzygy` when it covers the position and our
+    // own tables don't.
+    pub fn set_syzygy(&mut self, syzygy: Arc<Syzygy>) {
+        self.syzygy = Some(syzygy);
+    }
+
+    // The move and score our tables give for the root.
+    fn probe_root(&self, pos: &Position) -> Option<(ChessMove, i32)> {
+        if let Some((best, dtm)) = self.tablebase.as_ref().and_then(|tb| tb.best_move(pos)) {
+            let score = match dtm {
+                Dtm::Win(plies) => MATE - plies as i32,
+                Dtm::Draw => 0,
+                Dtm::Loss(plies) => -MATE + plies as i32,
+            };
+            return Some((best, score));
+        }
+        let (best, probe) = self.syzygy.as_ref()?.best_move(pos)?;
+        let plies = probe.dtz.map_or(0, i32::abs);
+        let score = match probe.wdl {
+            Wdl::Win => TB_WIN - plies,
+            Wdl::Loss => -TB_WIN + plies,
+            _ => 0,
+        };
+        Some((best, score))
+    }
+
+    // Searches `pos` within `limits`. `game_hashes` are the [`Position::hash`]()es of
+    // the positions that preceded `pos` in the game so that repetitions score as draws.
+    // `report` is called after every completed iteration.
+    pub fn search(
+        &mut self,
+        pos: &Position,
+        game_hashes: &[u64],
+        limits: &SearchLimits,
+        mut report: impl FnMut(&SearchInfo),
+    ) -> SearchResult {
+        self.nodes = 0;
+        self.started = Instant::now();
+        let budget = limits.budget(pos.turn);
+        self.deadline = budget.map(|t| self.started + t);
+        self.node_limit = limits.nodes;
+        self.stopped = false;
+        self.killers = [[None; 2]; MAX_PLY];
+        self.root_pv.clear();
+        self.path.clear();
+        self.path.extend_from_slice(game_hashes);
+
+        let legal = pos.legal_moves();
+        let mut result = SearchResult {
+            best: legal.first().copied(),
+            info: SearchInfo::default(),
+        };
+        if legal.len() <= 1 {
+            return result;
+        }
+        if let Some((best, score)) = self.probe_root(pos) {
+            result.best = Some(best);
+            result.info = SearchInfo {
+                depth: 1,
+                score,
+                nodes: 1,
+                elapsed: self.started.elapsed(),
+                pv: vec![best],
+            };
+            report(&result.info);
+            return result;
+        }
+
+        let max_depth = limits.depth.unwrap_or(MAX_PLY as u32 - 1).max(1);
+        for depth in 1..=max_depth {
+            let mut pv = Vec::new();
+            let score = self.negamax(pos, depth as i32, -INFINITY, INFINITY, 0, &mut pv);
+            if self.stopped && depth > 1 {
+                break;
+            }
+            self.root_pv = pv.clone();
+            result.best = pv.first().copied().or(result.best);
+            result.info = SearchInfo {
+                depth,
+                score,
+                nodes: self.nodes,
+                elapsed: self.started.elapsed(),
+                pv,
+            };
+            report(&result.info);
+            if self.stopped || is_mate_score(score) && mate_in(score).is_some_and(|m| m > 0) {
+                break;
+            }
+            // Another iteration will very likely not finish in the remaining time.
+            if let (Some(deadline), Some(total)) = (self.deadline, budget) {
+                if deadline.saturating_duration_since(Instant::now()) < total / 2 {
+                    break;
+                }
+            }
+        }
+        result
+    }
+
+    fn should_stop(&mut self) -> bool {
+        if self.stopped {
+            return true;
+        }
+        if self.nodes.is_multiple_of(1024) {
+            if let Some(deadline) = self.deadline {
+                self.stopped |= Instant::now() >= deadline;
+            }
+        }
+        if let Some(limit) = self.node_limit {
+            self.stopped |= self.nodes >= limit;
+        }
+        self.stopped
+    }
+
+    fn is_repetition(&self, pos: &Position, hash: u64) -> bool {
+        // Only positions since the last irreversible move can repeat.
+        let window = pos.halfmoves as usize;
+        self.path
+            .iter()
+            .rev()
+            .take(window)
+            .skip(1)
+            .step_by(2)
+            .any(|&h| h == hash)
+    }
+
+    fn negamax(
+        &mut self,
+        pos: &Position,
+        depth: i32,
+        mut alpha: i32,
+        beta: i32,
+        ply: usize,
+        pv: &mut Vec<ChessMove>,
+    ) -> i32 {
+        self.nodes += 1;
+        if ply > 0 && self.should_stop() {
+            return 0;
+        }
+        let hash = pos.hash();
+        if ply > 0 && (pos.halfmoves >= 100 || self.is_repetition(pos, hash)) {
+            return 0;
+        }
+        let in_check = pos.in_check();
+        let depth = if in_check { depth + 1 } else { depth };
+        if depth <= 0 || ply >= MAX_PLY - 1 {
+            return self.quiesce(pos, alpha, beta, ply);
+        }
+
+        let mut moves = pos.legal_moves();
+        if moves.is_empty() {
+            return if in_check { -MATE + ply as i32 } else { 0 };
+        }
+        self.order(pos, &mut moves, ply);
+
+        self.path.push(hash);
+        let mut child_pv = Vec::new();
+        for mv in moves {
+            let mut next = *pos;
+            next.make_move(&mv);
+            child_pv.clear();
+            let score = -self.negamax(&next, depth - 1, -beta, -alpha, ply + 1, &mut child_pv);
+            if self.stopped {
+                break;
+            }
+            if score >= beta {
+                if !pos.is_capture(&mv) && ply < MAX_PLY {
+                    let killers = &mut self.killers[ply];
+                    if killers[0] != Some(mv) {
+                        killers[1] = killers[0];
+                        killers[0] = Some(mv);
+                    }
+                }
+                self.path.pop();
+                return beta;
+            }
+            if score > alpha {
+                alpha = score;
+                pv.clear();
+                pv.push(mv);
+                pv.extend_from_slice(&child_pv);
+            }
+        }
+        self.path.pop();
+        alpha
+    }
+
+    fn quiesce(&mut self, pos: &Position, mut alpha: i32, beta: i32, ply: usize) -> i32 {
+        self.nodes += 1;
+        if self.should_stop() {
+            return 0;
+        }
+        let in_check = pos.in_check();
+        if !in_check {
+            let stand_pat = eval::evaluate(pos);
+            if stand_pat >= beta {
+                return beta;
+            }
+            alpha = alpha.max(stand_pat);
+        }
+        if ply >= MAX_PLY - 1 {
+            return alpha;
+        }
+
+        let mut moves = pos.legal_moves();
+        if moves.is_empty() {
+            return if in_check { -MATE + ply as i32 } else { 0 };
+        }
+        if !in_check {
+            moves.retain(|mv| pos.is_capture(mv) || mv.promotion.is_some());
+        }
+        self.order(pos, &mut moves, ply);
+        for mv in moves {
+            let mut next = *pos;
+            next.make_move(&mv);
+            let score = -self.quiesce(&next, -beta, -alpha, ply + 1);
+            if self.stopped {
+                return 0;
+            }
+            if score >= beta {
+                return beta;
+            }
+            alpha = alpha.max(score);
+        }
+        alpha
+    }
+
+    // Principal variation first, then captures by MVV-LVA, then killers.
+    fn order(&self, pos: &Position, moves: &mut [ChessMove], ply: usize) {
+        let pv_move = self.root_pv.get(ply).copied();
+        let killers = self.killers.get(ply).copied().unwrap_or_default();
+        moves.sort_by_cached_key(|mv| {
+            if Some(*mv) == pv_move {
+                return i32::MIN;
+            }
+            let mut key = 0;
+            if let Some(victim) = pos.captured(mv) {
+                let attacker = pos.at(mv.from_square()).map_or(0, |m| eval::value(m.ty));
+                key -= 10_000 + 10 * eval::value(victim.ty) - attacker / 10;
+            }
+            if let Some(ty) = mv.promotion {
+                key -= 9_000 + eval::value(ty);
+            }
+            if key == 0 && killers.contains(&Some(*mv)) {
+                key -= 5_000;
+            }
+            key
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tablebase::Material;
+
+    fn best(fen: &str, depth: u32) -> (String, SearchInfo) {
+        let pos = Position::from_fen(fen).unwrap();
+        let mut engine = Engine::new();
+        let res = engine.search(&pos, &[], &SearchLimits::depth(depth), |_| {});
+        (pos.move_to_uci(&res.best.unwrap()), res.info)
+    }
+
+    #[test]
+    fn finds_mate_in_one() {
+        let (mv, info) = best("6k1/5ppp/8/8/8/8/5PPP/R5K1 w - - 0 1", 3);
+        assert_eq!(mv, "a1a8");
+        assert_eq!(mate_in(info.score), Some(1));
+    }
+
+    #[test]
+    fn takes_a_hanging_queen() {
+        let (mv, _) = best(
+            "rnb1kbnr/pppp1ppp/8/4p1q1/3PP3/8/PPP2PPP/RNBQKBNR w KQkq - 1 3",
+            3,
+        );
+        assert_eq!(mv, "c1g5");
+    }
+
+    #[test]
+    fn respects_movetime() {
+        let pos = Position::startpos();
+        let mut engine = Engine::new();
+        let limit = Duration::from_millis(200);
+        let res = engine.search(&pos, &[], &SearchLimits::movetime(limit), |_| {});
+        assert!(res.best.is_some());
+        assert!(res.info.elapsed < limit * 3);
+    }
+
+    #[test]
+    fn budgets_the_clock() {
+        let limits = SearchLimits {
+            wtime: Some(Duration::from_secs(60)),
+            binc: Some(Duration::from_secs(2)),
+            btime: Some(Duration::from_secs(1)),
+            ..Default::default()
+        };
+        assert_eq!(limits.budget(Color::White), Some(Duration::from_secs(2)));
+        // Half a second left: the increment cannot be banked on.
+        assert_eq!(
+            limits.budget(Color::Black),
+            Some(Duration::from_millis(500))
+        );
+        let fixed = SearchLimits {
+            movetime: Some(Duration::from_millis(300)),
+            ..limits
+        };
+        assert_eq!(fixed.budget(Color::Black), Some(Duration::from_millis(300)));
+        assert_eq!(SearchLimits::depth(3).budget(Color::White), None);
+    }
+
+    #[test]
+    fn plays_tablebase_moves() {
+        let mut tb = Tablebase::new();
+        tb.generate(&Material::parse("KRvK").unwrap()).unwrap();
+        let pos = Position::from_fen("8/8/8/3k4/8/8/8/R3K3 w - - 0 1").unwrap();
+        let Some(Dtm::Win(plies)) = tb.probe(&pos) else {
+            panic!("KRK is a win");
+        };
+        let mut engine = Engine::new();
+        engine.set_tablebase(Arc::new(tb));
+        let res = engine.search(&pos, &[], &SearchLimits::depth(1), |_| {});
+        assert_eq!(mate_in(res.info.score), Some((plies as i32 + 1) / 2));
+        assert!(pos.legal_moves().contains(&res.best.unwrap()));
+    }
+}
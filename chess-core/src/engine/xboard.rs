@@ -0,0 +1,576 @@
+//! chess_core::engine::xboard
+//!
+//! The Chess Engine Communication Protocol (CECP), as spoken by xboard and
+//! WinBoard. [`run`] reads one command per line and writes the engine's
+//! replies; [`Xboard::handle`] processes a single command, which is handy
+//! when the transport is not a pair of pipes.
+//!
+//! See <https://www.gnu.org/software/xboard/engine-intf.html> for the protocol.
+
+use super::{mate_in, Engine, SearchInfo, SearchLimits};
+use crate::book::{random_roll, Book};
+use crate::position::{ChessMove, Outcome, Position};
+use crate::tablebase::syzygy::Syzygy;
+use crate::tablebase::Tablebase;
+use crate::types::Color;
+use anyhow::Result;
+use std::io::{BufRead, Write};
+use std::sync::Arc;
+use std::time::Duration;
+
+const MY_NAME: &str = "chess-core";
+
+// Time control set by `level MPS BASE INC`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Level {
+    pub moves_per_session: u32,
+    pub base: Duration,
+    pub increment: Duration,
+}
+
+impl Level {
+    // `BASE` is either whole minutes (`5`) or minutes and seconds (`0:30`).
+    pub fn parse(mps: &str, base: &str, inc: &str) -> Option<Self> {
+        let moves_per_session = mps.parse().ok()?;
+        let base = match base.split_once(':') {
+            Some((min, sec)) => {
+                Duration::from_secs(min.parse::<u64>().ok()? * 60 + sec.parse::<u64>().ok()?)
+            }
+            None => Duration::from_secs(base.parse::<u64>().ok()? * 60),
+        };
+        let increment = Duration::from_secs_f64(inc.parse::<f64>().ok()?.max(0.0));
+        Some(Self {
+            moves_per_session,
+            base,
+            increment,
+        })
+    }
+}
+
+pub struct Xboard<W: Write> {
+    out: W,
+    engine: Engine,
+    start: Position,
+    pos: Position,
+    moves: Vec<ChessMove>,
+    // Hashes of every position reached since `start`, including the current one.
+    hashes: Vec<u64>,
+    force: bool,
+    engine_color: Color,
+    post: bool,
+    game_over: bool,
+    depth_limit: Option<u32>,
+    move_time: Option<Duration>,
+    level: Option<Level>,
+    time_left: Option<Duration>,
+    opponent_time: Option<Duration>,
+    book: Option<Book>,
+}
+
+impl<W: Write> Xboard<W> {
+    pub fn new(out: W) -> Self {
+        let start = Position::startpos();
+        Self {
+            out,
+            engine: Engine::new(),
+            start,
+            pos: start,
+            moves: Vec::new(),
+            hashes: vec![start.hash()],
+            force: false,
+            engine_color: Color::Black,
+            post: false,
+            game_over: false,
+            depth_limit: None,
+            move_time: None,
+            level: None,
+            time_left: None,
+            opponent_time: None,
+            book: None,
+        }
+    }
+
+    // Plays from `book` for as long as it has moves for the position.
+    pub fn set_book(&mut self, book: Book) {
+        self.book = Some(book);
+    }
+
+    // Plays endings covered by `tablebase` perfectly.
+    pub fn set_tablebase(&mut self, tablebase: Arc<Tablebase>) {
+        self.engine.set_tablebase(tablebase);
+    }
+
+    // Plays on from `syzygy` in the endings it covers. `egtpath syzygy` does
+    // the same for tables the GUI points us at.
+    pub fn set_syzygy(&mut self, syzygy: Arc<Syzygy>) {
+        self.engine.set_syzygy(syzygy);
+    }
+
+    pub fn position(&self) -> &Position {
+        &self.pos
+    }
+
+    pub fn into_inner(self) -> W {
+        self.out
+    }
+
+    // Handles commands from `input` until `quit` or the end of `input`.
+    pub fn run(&mut self, input: impl BufRead) -> Result<()> {
+        for line in input.lines() {
+            if !self.handle(&line?)? {
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    // Processes a single command line. Returns `false` once the GUI asked us to quit.
+    pub fn handle(&mut self, line: &str) -> Result<bool> {
+        let line = line.trim();
+        let (cmd, args) = line.split_once(' ').unwrap_or((line, ""));
+        let args = args.trim();
+        match cmd {
+            "" => {}
+            "xboard" | "accepted" | "rejected" | "hard" | "easy" | "random" | "computer"
+            | "name" | "rating" | "ics" | "draw" | "." | "?" => {}
+            "protover" => self.features()?,
+            "quit" => return Ok(false),
+            "new" => {
+                self.reset(Position::startpos());
+                self.force = false;
+                self.engine_color = Color::Black;
+                self.depth_limit = None;
+                self.time_left = None;
+                self.opponent_time = None;
+            }
+            "variant" => {
+                if args != "normal" {
+                    writeln!(self.out, "Error (unsupported variant): {args}")?;
+                }
+            }
+            "force" => self.force = true,
+            "go" => {
+                self.force = false;
+                self.engine_color = self.pos.turn;
+                self.think()?;
+            }
+            "playother" => {
+                self.force = false;
+                self.engine_color = self.pos.turn.opposite();
+            }
+            "white" | "black" => {
+                // Protocol version 1: set the side to move, engine plays the other one.
+                let mut pos = self.pos;
+                pos.turn = if cmd == "white" {
+                    Color::White
+                } else {
+                    Color::Black
+                };
+                self.reset(pos);
+                self.engine_color = pos.turn.opposite();
+                self.force = false;
+            }
+            "usermove" => self.user_move(args)?,
+            "setboard" => match Position::from_fen(args) {
+                Ok(pos) => self.reset(pos),
+                Err(_) => writeln!(self.out, "tellusererror Illegal position")?,
+            },
+            "level" => {
+                let parts: Vec<&str> = args.split_whitespace().collect();
+                match parts.as_slice() {
+                    [mps, base, inc] => match Level::parse(mps, base, inc) {
+                        Some(level) => {
+                            self.level = Some(level);
+                            self.move_time = None;
+                        }
+                        None => writeln!(self.out, "Error (bad arguments): level {args}")?,
+                    },
+                    _ => writeln!(self.out, "Error (bad arguments): level {args}")?,
+                }
+            }
+            "st" => match args.parse::<f64>() {
+                Ok(secs) if secs > 0.0 => self.move_time = Some(Duration::from_secs_f64(secs)),
+                _ => writeln!(self.out, "Error (bad arguments): st {args}")?,
+            },
+            "sd" => match args.parse::<u32>() {
+                Ok(depth) if depth > 0 => self.depth_limit = Some(depth),
+                _ => writeln!(self.out, "Error (bad arguments): sd {args}")?,
+            },
+            "time" | "otim" => match args.parse::<u64>() {
+                Ok(cs) => {
+                    let t = Some(Duration::from_millis(cs * 10));
+                    if cmd == "time" {
+                        self.time_left = t;
+                    } else {
+                        self.opponent_time = t;
+                    }
+                }
+                Err(_) => writeln!(self.out, "Error (bad arguments): {cmd} {args}")?,
+            },
+            "undo" => self.take_back(1)?,
+            "remove" => self.take_back(2)?,
+            "post" => self.post = true,
+            "nopost" => self.post = false,
+            "result" => {
+                self.game_over = true;
+                self.force = true;
+            }
+            "ping" => writeln!(self.out, "pong {args}")?,
+            "hint" => self.hint()?,
+            "bk" => self.book_moves()?,
+            "egtpath" => match args.split_once(' ') {
+                Some(("syzygy", path)) => match Syzygy::open(path.trim()) {
+                    Ok(syzygy) => self.set_syzygy(Arc::new(syzygy)),
+                    Err(err) => writeln!(self.out, "tellusererror {err:#}")?,
+                },
+                _ => writeln!(self.out, "Error (bad arguments): egtpath {args}")?,
+            },
+            _ => {
+                // Older interfaces send bare moves without the `usermove` prefix.
+                if is_coordinate_move(cmd) && args.is_empty() {
+                    self.user_move(cmd)?;
+                } else {
+                    writeln!(self.out, "Error (unknown command): {cmd}")?;
+                }
+            }
+        }
+        self.out.flush()?;
+        Ok(true)
+    }
+
+    fn features(&mut self) -> Result<()> {
+        writeln!(self.out, "feature done=0")?;
+        writeln!(
+            self.out,
+            "feature myname=\"{MY_NAME}\" ping=1 setboard=1 playother=1 usermove=1 time=1 \
+             draw=0 sigint=0 sigterm=0 reuse=1 analyze=0 colors=0 san=0 debug=1 \
+             variants=\"normal\" egt=\"syzygy\""
+        )?;
+        writeln!(self.out, "feature done=1")?;
+        Ok(())
+    }
+
+    fn reset(&mut self, pos: Position) {
+        self.start = pos;
+        self.pos = pos;
+        self.moves.clear();
+        self.hashes = vec![pos.hash()];
+        self.game_over = false;
+    }
+
+    fn push(&mut self, mv: ChessMove) {
+        self.pos.make_move(&mv);
+        self.moves.push(mv);
+        self.hashes.push(self.pos.hash());
+    }
+
+    fn take_back(&mut self, plies: usize) -> Result<()> {
+        if plies > self.moves.len() {
+            writeln!(self.out, "Error (command not legal now): nothing to undo")?;
+            return Ok(());
+        }
+        let keep = self.moves.len() - plies;
+        let moves: Vec<ChessMove> = self.moves[..keep].to_vec();
+        let start = self.start;
+        self.reset(start);
+        for mv in moves {
+            self.push(mv);
+        }
+        Ok(())
+    }
+
+    fn user_move(&mut self, text: &str) -> Result<()> {
+        if self.game_over {
+            writeln!(self.out, "Illegal move (game is over): {text}")?;
+            return Ok(());
+        }
+        let mv = match self.pos.parse_uci(text) {
+            Ok(mv) => mv,
+            Err(_) => {
+                writeln!(self.out, "Illegal move: {text}")?;
+                return Ok(());
+            }
+        };
+        self.push(mv);
+        if self.announce_outcome()? {
+            return Ok(());
+        }
+        if !self.force && self.pos.turn == self.engine_color {
+            self.think()?;
+        }
+        Ok(())
+    }
+
+    fn outcome(&self) -> Option<Outcome> {
+        if let Some(outcome) = self.pos.outcome() {
+            return Some(outcome);
+        }
+        let current = self.hashes.last().copied()?;
+        let seen = self.hashes.iter().filter(|&&h| h == current).count();
+        (seen >= 3).then_some(Outcome::Repetition)
+    }
+
+    // Claims the result if the game just ended. Returns whether it did.
+    fn announce_outcome(&mut self) -> Result<bool> {
+        let Some(outcome) = self.outcome() else {
+            return Ok(false);
+        };
+        writeln!(self.out, "{} {{{}}}", outcome.result(), outcome.reason())?;
+        self.game_over = true;
+        Ok(true)
+    }
+
+    fn limits(&self) -> SearchLimits {
+        let mut limits = SearchLimits {
+            depth: self.depth_limit,
+            movetime: self.move_time,
+            ..Default::default()
+        };
+        let left = self.time_left.or(self.level.map(|level| level.base));
+        let increment = self.level.map(|level| level.increment);
+        match self.pos.turn {
+            Color::White => (limits.wtime, limits.winc) = (left, increment),
+            Color::Black => (limits.btime, limits.binc) = (left, increment),
+        }
+        if let Some(level) = self.level.filter(|level| level.moves_per_session > 0) {
+            let played = (self.pos.fullmoves.saturating_sub(1)) % level.moves_per_session;
+            limits.movestogo = Some(level.moves_per_session - played);
+        }
+        limits
+    }
+
+    // Suggests a move for the user: the best book move, or else whatever a
+    // shallow search likes.
+    fn hint(&mut self) -> Result<()> {
+        if self.game_over {
+            return Ok(());
+        }
+        let book_move = self
+            .book
+            .as_ref()
+            .and_then(|book| book.best_move(&self.pos));
+        let mv = book_move.or_else(|| {
+            let limits = SearchLimits {
+                depth: Some(4),
+                ..Default::default()
+            };
+            let history = &self.hashes[..self.hashes.len() - 1];
+            self.engine.search(&self.pos, history, &limits, |_| {}).best
+        });
+        if let Some(mv) = mv {
+            writeln!(self.out, "Hint: {}", self.pos.move_to_uci(&mv))?;
+        }
+        Ok(())
+    }
+
+    // Lists the book moves for the position with their share of the weight,
+    // one per line, followed by an empty line.
+    fn book_moves(&mut self) -> Result<()> {
+        let moves = match &self.book {
+            Some(book) => book.moves(&self.pos),
+            None => Vec::new(),
+        };
+        let total: u32 = moves.iter().map(|m| u32::from(m.weight)).sum();
+        if total == 0 {
+            writeln!(self.out, " No book moves")?;
+        }
+        for m in moves.iter().filter(|_| total > 0) {
+            let share = u32::from(m.weight) * 100 / total;
+            writeln!(self.out, " {} {share}%", self.pos.move_to_uci(&m.mv))?;
+        }
+        writeln!(self.out)?;
+        Ok(())
+    }
+
+    fn think(&mut self) -> Result<()> {
+        if self.game_over || self.announce_outcome()? {
+            return Ok(());
+        }
+        let book_move = self
+            .book
+            .as_ref()
+            .and_then(|book| book.weighted_move(&self.pos, random_roll()));
+        if let Some(mv) = book_move {
+            writeln!(self.out, "move {}", self.pos.move_to_uci(&mv))?;
+            self.push(mv);
+            self.announce_outcome()?;
+            return Ok(());
+        }
+        let limits = self.limits();
+        let history = &self.hashes[..self.hashes.len() - 1];
+        let post = self.post;
+        let pos = self.pos;
+        let out = &mut self.out;
+        let result = self.engine.search(&pos, history, &limits, |info| {
+            if post {
+                let _ = writeln!(out, "{}", thinking_line(&pos, info));
+            }
+        });
+        let Some(mv) = result.best else {
+            return Ok(());
+        };
+        writeln!(self.out, "move {}", self.pos.move_to_uci(&mv))?;
+        self.push(mv);
+        self.announce_outcome()?;
+        Ok(())
+    }
+}
+
+// Whether `cmd` has the shape of a move like `e2e4` or `a7a8q`, legal or not.
+fn is_coordinate_move(cmd: &str) -> bool {
+    match cmd.as_bytes() {
+        [f1, r1, f2, r2, promotion @ ..] => {
+            [f1, f2].iter().all(|f| (b'a'..=b'h').contains(f))
+                && [r1, r2].iter().all(|r| (b'1'..=b'8').contains(r))
+                && matches!(promotion, [] | [b'q' | b'r' | b'b' | b'n'])
+        }
+        _ => false,
+    }
+}
+
+// `ply score time nodes pv`, with the time in centiseconds and mates
+// reported as 100000 + moves.
+fn thinking_line(pos: &Position, info: &SearchInfo) -> String {
+    let score = match mate_in(info.score) {
+        Some(m) if m > 0 => 100_000 + m,
+        Some(m) => -100_000 + m,
+        None => info.score,
+    };
+    let mut line = format!(
+        "{} {} {} {}",
+        info.depth,
+        score,
+        info.elapsed.as_millis() / 10,
+        info.nodes
+    );
+    let mut cur = *pos;
+    for mv in &info.pv {
+        line.push(' ');
+        line.push_str(&cur.move_to_uci(mv));
+        cur.make_move(mv);
+    }
+    line
+}
+
+// Runs a CECP session until `quit` or the end of `input`.
+pub fn run(input: impl BufRead, output: impl Write) -> Result<()> {
+    Xboard::new(output).run(input)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn session() -> Xboard<Vec<u8>> {
+        Xboard::new(Vec::new())
+    }
+
+    fn output(x: &mut Xboard<Vec<u8>>) -> String {
+        String::from_utf8(std::mem::take(&mut x.out)).unwrap()
+    }
+
+    #[test]
+    fn parses_levels() {
+        let l = Level::parse("40", "0:30", "0").unwrap();
+        assert_eq!(l.base, Duration::from_secs(30));
+        let l = Level::parse("0", "5", "2.5").unwrap();
+        assert_eq!(l.base, Duration::from_secs(300));
+        assert_eq!(l.increment, Duration::from_millis(2500));
+        assert!(Level::parse("x", "5", "0").is_none());
+    }
+
+    #[test]
+    fn undo_and_remove_take_back_plies() {
+        let mut x = session();
+        for cmd in [
+            "new",
+            "force",
+            "usermove e2e4",
+            "usermove e7e5",
+            "usermove g1f3",
+        ] {
+            x.handle(cmd).unwrap();
+        }
+        x.handle("undo").unwrap();
+        assert_eq!(
+            x.position().to_fen(),
+            "rnbqkbnr/pppp1ppp/8/4p3/4P3/8/PPPP1PPP/RNBQKBNR w KQkq e6 0 2"
+        );
+        x.handle("remove").unwrap();
+        assert_eq!(x.position(), &Position::startpos());
+        x.handle("undo").unwrap();
+        assert!(output(&mut x).starts_with("Error (command not legal now)"));
+    }
+
+    #[test]
+    fn rejects_illegal_moves_and_positions() {
+        let mut x = session();
+        x.handle("force").unwrap();
+        x.handle("usermove e2e5").unwrap();
+        assert_eq!(output(&mut x), "Illegal move: e2e5\n");
+        // The same without the prefix, as older interfaces send it.
+        x.handle("e2e5").unwrap();
+        assert_eq!(output(&mut x), "Illegal move: e2e5\n");
+        x.handle("e2e9").unwrap();
+        assert_eq!(output(&mut x), "Error (unknown command): e2e9\n");
+        x.handle("setboard 8/8/8/8/8/8/8/8 w - - 0 1").unwrap();
+        assert_eq!(output(&mut x), "tellusererror Illegal position\n");
+    }
+
+    #[test]
+    fn loads_syzygy_tables() {
+        let mut x = session();
+        x.handle("protover 2").unwrap();
+        assert!(output(&mut x).contains(" egt=\"syzygy\""));
+        x.handle("egtpath gaviota /tb").unwrap();
+        assert_eq!(
+            output(&mut x),
+            "Error (bad arguments): egtpath gaviota /tb\n"
+        );
+        let missing = std::env::temp_dir().join("chess-core-no-such-tables");
+        x.handle(&format!("egtpath syzygy {}", missing.display()))
+            .unwrap();
+        assert!(output(&mut x).starts_with("tellusererror Failed to read"));
+        x.handle(&format!(
+            "egtpath syzygy {}",
+            std::env::temp_dir().display()
+        ))
+        .unwrap();
+        assert_eq!(output(&mut x), "");
+    }
+
+    #[test]
+    fn plays_and_hints_book_moves() {
+        let start = Position::startpos();
+        let entry = |uci: &str, weight| crate::book::BookEntry {
+            key: crate::book::polyglot_key(&start),
+            mv: crate::book::encode_move(&start.parse_uci(uci).unwrap()),
+            weight,
+            learn: 0,
+        };
+        let mut x = session();
+        x.set_book(Book::from_entries(vec![entry("g1f3", 3), entry("c2c4", 1)]));
+        x.handle("hint").unwrap();
+        assert_eq!(output(&mut x), "Hint: g1f3\n");
+        x.handle("bk").unwrap();
+        assert_eq!(output(&mut x), " g1f3 75%\n c2c4 25%\n\n");
+        x.handle("white").unwrap();
+        x.handle("go").unwrap();
+        let reply = output(&mut x);
+        assert!(reply == "move g1f3\n" || reply == "move c2c4\n", "{reply}");
+        x.handle("bk").unwrap();
+        assert_eq!(output(&mut x), " No book moves\n\n");
+    }
+
+    #[test]
+    fn claims_threefold_repetition() {
+        let mut x = session();
+        x.handle("force").unwrap();
+        for mv in [
+            "g1f3", "g8f6", "f3g1", "f6g8", "g1f3", "g8f6", "f3g1", "f6g8",
+        ] {
+            x.handle(&format!("usermove {mv}")).unwrap();
+        }
+        assert_eq!(output(&mut x), "1/2-1/2 {Draw by repetition}\n");
+    }
+}
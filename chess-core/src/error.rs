@@ -0,0 +1,134 @@
+//! chess_core::error
+//!
+//! [`ChessError`] is what the game-facing API fails with: [`GameMaster`],
+//! [`ChessGame`], FEN parsing and the board helpers. A server matches on it,
+//! or on [`ChessError::code`], to tell its clients what went wrong. Tools such
+//! as the engine and the match runner keep reporting through `anyhow`, which
+//! takes a `ChessError` with `?`.
+//!
+//! [`GameMaster`]: crate::GameMaster
+//! [`ChessGame`]: crate::ChessGame
+
+use crate::msg::{GameId, PieceId, TileId};
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ChessError {
+    GameNotFound(GameId),
+    // A game is being set up under an id that is taken.
+    GameExists(GameId),
+    PieceNotFound(PieceId),
+    // The tile should have had a piece on it.
+    EmptyTile(TileId),
+    TileOccupied(TileId),
+    // Text or an index that names no square of the board.
+    InvalidSquare(String),
+    IllegalMove { reason: String },
+    NotYourTurn,
+    GameFinished,
+    // A draw was accepted that the opponent had not offered.
+    NoDrawOffer,
+    InvalidFen { field: FenField, reason: String },
+    // The pieces, clocks or history handed over do not make a game.
+    InvalidSetup { reason: String },
+    InvalidTimeControl { reason: String },
+    // A clock was pressed while neither side's was running.
+    ClockStopped,
+    // Asked for something chess-core cannot do yet.
+    Unsupported(String),
+    // An external engine failed to answer.
+    Engine(String),
+}
+
+// The parts of a FEN record, in order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FenField {
+    Placement,
+    SideToMove,
+    Castling,
+    EnPassant,
+    // The remaining checks of Three-check.
+    Checks,
+    HalfmoveClock,
+    FullmoveNumber,
+}
+
+impl ChessError {
+    // A stable name for clients to switch on; the message may change.
+    pub fn code(&self) -> &'static str {
+        match self {
+            ChessError::GameNotFound(_) => "game_not_found",
+            ChessError::GameExists(_) => "game_exists",
+            ChessError::PieceNotFound(_) => "piece_not_found",
+            ChessError::EmptyTile(_) => "empty_tile",
+            ChessError::TileOccupied(_) => "tile_occupied",
+            ChessError::InvalidSquare(_) => "invalid_square",
+            ChessError::IllegalMove { .. } => "illegal_move",
+            ChessError::NotYourTurn => "not_your_turn",
+            ChessError::GameFinished => "game_finished",
+            ChessError::NoDrawOffer => "no_draw_offer",
+            ChessError::InvalidFen { .. } => "invalid_fen",
+            ChessError::InvalidSetup { .. } => "invalid_setup",
+            ChessError::InvalidTimeControl { .. } => "invalid_time_control",
+            ChessError::ClockStopped => "clock_stopped",
+            ChessError::Unsupported(_) => "unsupported",
+            ChessError::Engine(_) => "engine_failed",
+        }
+    }
+
+    pub(crate) fn time_control(reason: impl Into<String>) -> Self {
+        ChessError::InvalidTimeControl {
+            reason: reason.into(),
+        }
+    }
+
+    pub(crate) fn fen(field: FenField, reason: impl Into<String>) -> Self {
+        ChessError::InvalidFen {
+            field,
+            reason: reason.into(),
+        }
+    }
+}
+
+impl fmt::Display for ChessError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ChessError::GameNotFound(id) => write!(f, "Game with {id} not found"),
+            ChessError::GameExists(id) => {
+                write!(f, "Game with {id} already exists. Not overriding it.")
+            }
+            ChessError::PieceNotFound(id) => write!(f, "Piece not found: {id}"),
+            ChessError::EmptyTile(idx) => write!(f, "No piece on tile {idx}"),
+            ChessError::TileOccupied(idx) => write!(f, "Tile {idx} is already occupied"),
+            ChessError::InvalidSquare(name) => write!(f, "Not a square: {name:?}"),
+            ChessError::IllegalMove { reason } => write!(f, "Illegal move: {reason}"),
+            ChessError::NotYourTurn => write!(f, "It is not your turn"),
+            ChessError::GameFinished => write!(f, "The game is already over"),
+            ChessError::NoDrawOffer => write!(f, "There is no draw offer to accept"),
+            ChessError::InvalidFen { field, reason } => write!(f, "Invalid FEN {field}: {reason}"),
+            ChessError::InvalidSetup { reason } => write!(f, "Invalid game setup: {reason}"),
+            ChessError::InvalidTimeControl { reason } => {
+                write!(f, "Invalid time control: {reason}")
+            }
+            ChessError::ClockStopped => write!(f, "Neither clock is running"),
+            ChessError::Unsupported(what) => write!(f, "{what} not available yet"),
+            ChessError::Engine(reason) => write!(f, "Engine failed: {reason}"),
+        }
+    }
+}
+
+impl std::error::Error for ChessError {}
+
+impl fmt::Display for FenField {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            FenField::Placement => "piece placement",
+            FenField::SideToMove => "side to move",
+            FenField::Castling => "castling rights",
+            FenField::EnPassant => "en passant square",
+            FenField::Checks => "remaining checks",
+            FenField::HalfmoveClock => "halfmove clock",
+            FenField::FullmoveNumber => "fullmove number",
+        })
+    }
+}
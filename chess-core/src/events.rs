@@ -0,0 +1,336 @@
+//! chess_core::events
+//!
+//! What happens in the games of a [`GameMaster`], as it happens. Front ends,
+//! spectators, loggers and bots call [`GameMaster::subscribe`] for one game or
+//! all of them and read [`GameEvent`]s off the channel instead of polling
+//! snapshots. Receivers may live on other threads; dropping one unsubscribes it.
+//!
+//! [`GameMaster`]: crate::GameMaster
+//! [`GameMaster::subscribe`]: crate::GameMaster::subscribe
+
+use crate::msg::GameId;
+use crate::position::{ChessMove, Man, Outcome, Position};
+use crate::square::Square;
+use crate::types::{Color, Type};
+use crate::variant::Variant;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::time::Duration;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GameEvent {
+    pub game_id: GameId,
+    pub kind: EventKind,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EventKind {
+    MoveMade {
+        by: Color,
+        mv: ChessMove,
+        san: String,
+    },
+    // `man` was taken off `square`, which differs from the move's target
+    // square for en passant.
+    Capture {
+        man: Man,
+        square: Square,
+    },
+    // `color`'s king is attacked after the move.
+    Check {
+        color: Color,
+    },
+    Promotion {
+        square: Square,
+        to: Type,
+    },
+    // In Crazyhouse and Bughouse, `man` came out of the reserve onto `square`.
+    Drop {
+        man: Man,
+        square: Square,
+    },
+    // Time left on both clocks, after a move or whenever the clocks are read.
+    ClockTick {
+        white: Duration,
+        black: Duration,
+    },
+    DrawOffered {
+        by: Color,
+    },
+    // In Bughouse, `color` got a `ty` for their reserve from the partner board.
+    PieceReceived {
+        color: Color,
+        ty: Type,
+    },
+    GameOver {
+        outcome: Outcome,
+    },
+}
+
+// Who gets which events. Subscribers whose receiver is gone are dropped on
+// the next event.
+#[derive(Default)]
+pub(crate) struct Subscribers {
+    senders: Vec<(Option<GameId>, Sender<GameEvent>)>,
+}
+
+impl Subscribers {
+    pub fn subscribe(&mut self, game_id: Option<GameId>) -> Receiver<GameEvent> {
+        let (tx, rx) = mpsc::channel();
+        self.senders.push((game_id, tx));
+        rx
+    }
+
+    pub fn emit(&mut self, game_id: GameId, kind: EventKind) {
+        let event = GameEvent { game_id, kind };
+        self.senders.retain(|(filter, tx)| {
+            filter.is_some_and(|id| id != game_id) || tx.send(event.clone()).is_ok()
+        });
+    }
+}
+
+// The events `mv`, played from `before` under `rules`, gives rise to apart
+// from clock and game end.
+pub(crate) fn move_events(
+    rules: &dyn Variant,
+    before: &Position,
+    mv: &ChessMove,
+) -> Vec<EventKind> {
+    let mut after = *before;
+    rules.make_move(&mut after, mv);
+    let mut events = vec![EventKind::MoveMade {
+        by: before.turn,
+        mv: *mv,
+        san: before.move_to_san(mv),
+    }];
+    let (from, to) = (mv.from_square(), mv.to_square());
+    if let Some(man) = before.captured(mv) {
+        let square = match before.is_en_passant(mv) {
+            true => to
+                .forward(before.turn, -1)
+                .expect("en passant lands past a pawn"),
+            false => to,
+        };
+        events.push(EventKind::Capture { man, square });
+        // In Atomic the capture blows up the capturer and the men around it
+        // as well; whatever else left the board went with them.
+        if let (Some(man), None) = (before.at(from), after.at(to)) {
+            events.push(EventKind::Capture { man, square: to });
+        }
+        for sq in Square::all() {
+            if let (Some(man), None) = (before.at(sq), after.at(sq)) {
+                if ![from, to, square].contains(&sq) {
+                    events.push(EventKind::Capture { man, square: sq });
+                }
+            }
+        }
+    }
+    match mv.promotion {
+        Some(ty) if mv.is_drop() => events.push(EventKind::Drop {
+            man: Man::new(before.turn, ty),
+            square: to,
+        }),
+        Some(ty) => events.push(EventKind::Promotion { square: to, to: ty }),
+        None => {}
+    }
+    if rules.in_check(&after) {
+        events.push(EventKind::Check { color: after.turn });
+    }
+    events
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::{ChessClock, ManualTime, TimeControl};
+    use crate::game::GameMode;
+    use crate::spawn_game_master;
+    use std::sync::Arc;
+
+    #[test]
+    fn reports_captures_checks_and_promotions() {
+        let mut gm = spawn_game_master();
+        let start = Position::from_fen("4k3/1P6/8/3pP3/8/8/8/4K3 w - d6 0 1").unwrap();
+        let game_id = gm.create_game_from(start).unwrap();
+        let other = gm.create_game().unwrap();
+        let events = gm.subscribe(Some(game_id));
+        let all = gm.subscribe(None);
+
+        gm.make_move(other, Position::startpos().parse_uci("e2e4").unwrap())
+            .unwrap();
+        let ep = start.parse_uci("e5d6").unwrap();
+        gm.make_move(game_id, ep).unwrap();
+        let kinds: Vec<_> = events.try_iter().map(|e| e.kind).collect();
+        assert_eq!(
+            kinds,
+            [
+                EventKind::MoveMade {
+                    by: Color::White,
+                    mv: ep,
+                    san: "exd6".to_string(),
+                },
+                EventKind::Capture {
+                    man: Man::new(Color::Black, Type::Pawn),
+                    square: Square::D5,
+                },
+            ]
+        );
+        let games: Vec<_> = all.try_iter().map(|e| e.game_id).collect();
+        // A new game is timed, so its move comes with a clock tick.
+        assert_eq!(games, [other, other, game_id, game_id]);
+
+        let pos = gm.request_position(game_id).unwrap();
+        gm.make_move(game_id, pos.parse_uci("e8d8").unwrap())
+            .unwrap();
+        let pos = gm.request_position(game_id).unwrap();
+        let _ = events.try_iter().count();
+        gm.make_move(game_id, pos.parse_uci("b7b8q").unwrap())
+            .unwrap();
+        let kinds: Vec<_> = events.try_iter().skip(1).map(|e| e.kind).collect();
+        assert_eq!(
+            kinds,
+            [
+                EventKind::Promotion {
+                    square: Square::B8,
+                    to: Type::Queen,
+                },
+                EventKind::Check {
+                    color: Color::Black
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn reports_every_man_an_explosion_takes() {
+        let mut gm = spawn_game_master();
+        let start =
+            Position::from_fen("rnb1kbnr/ppp2ppp/8/3p4/4P2q/P7/1PPP1PPP/RNBQKB1R b KQkq - 0 4")
+                .unwrap();
+        let game_id = gm.create_game_from(start).unwrap();
+        gm.set_mode(game_id, GameMode::Atomic).unwrap();
+        let events = gm.subscribe(Some(game_id));
+        gm.make_move(game_id, start.parse_uci("h4f2").unwrap())
+            .unwrap();
+        let captures: Vec<_> = events
+            .try_iter()
+            .map(|e| e.kind)
+            .filter(|kind| matches!(kind, EventKind::Capture { .. }))
+            .collect();
+        // The pawn taken, the queen that took it, then king and bishop next
+        // door; the pawn on g2 survives.
+        assert_eq!(
+            captures,
+            [
+                EventKind::Capture {
+                    man: Man::new(Color::White, Type::Pawn),
+                    square: Square::F2,
+                },
+                EventKind::Capture {
+                    man: Man::new(Color::Black, Type::Queen),
+                    square: Square::F2,
+                },
+                EventKind::Capture {
+                    man: Man::new(Color::White, Type::King),
+                    square: Square::E1,
+                },
+                EventKind::Capture {
+                    man: Man::new(Color::White, Type::Bishop),
+                    square: Square::F1,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn reports_drops_apart_from_promotions() {
+        let mut gm = spawn_game_master();
+        let start = Position::from_fen("4k3/8/8/8/8/8/8/4K3[N] w - - 0 1").unwrap();
+        let game_id = gm.create_game_from(start).unwrap();
+        let events = gm.subscribe(Some(game_id));
+        let drop = start.parse_uci("N@f3").unwrap();
+        gm.make_move(game_id, drop).unwrap();
+        let kinds: Vec<_> = events.try_iter().map(|e| e.kind).collect();
+        assert_eq!(
+            kinds,
+            [
+                EventKind::MoveMade {
+                    by: Color::White,
+                    mv: drop,
+                    san: "N@f3".to_string(),
+                },
+                EventKind::Drop {
+                    man: Man::new(Color::White, Type::Knight),
+                    square: Square::F3,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn reports_clocks_and_game_end() {
+        let time = ManualTime::default();
+        let mut gm = spawn_game_master();
+        let game_id = gm.create_game().unwrap();
+        let control = TimeControl::sudden_death(Duration::from_secs(60));
+        let clock = ChessClock::with_source(control, Arc::new(time.clone()));
+        gm.set_clock(game_id, Some(clock)).unwrap();
+        let events = gm.subscribe(Some(game_id));
+
+        gm.make_move(game_id, Position::startpos().parse_uci("e2e4").unwrap())
+            .unwrap();
+        time.advance(Duration::from_secs(20));
+        gm.tick();
+        time.advance(Duration::from_secs(45));
+        gm.tick();
+        gm.tick();
+        let kinds: Vec<_> = events
+            .try_iter()
+            .map(|e| e.kind)
+            .filter(|kind| !matches!(kind, EventKind::MoveMade { .. }))
+            .collect();
+        let white = Duration::from_secs(60);
+        assert_eq!(
+            kinds,
+            [
+                EventKind::ClockTick {
+                    white,
+                    black: Duration::from_secs(60),
+                },
+                EventKind::ClockTick {
+                    white,
+                    black: Duration::from_secs(40),
+                },
+                EventKind::ClockTick {
+                    white,
+                    black: Duration::ZERO,
+                },
+                EventKind::GameOver {
+                    outcome: Outcome::TimeForfeit {
+                        winner: Color::White
+                    },
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn reports_draw_offers_and_drops_closed_channels() {
+        let mut gm = spawn_game_master();
+        let game_id = gm.create_game().unwrap();
+        let events = gm.subscribe(Some(game_id));
+        drop(gm.subscribe(None));
+        gm.offer_draw(game_id, true).unwrap();
+        gm.accept_draw(game_id, false).unwrap();
+        assert_eq!(gm.subscribers.senders.len(), 1);
+        let kinds: Vec<_> = events.try_iter().map(|e| e.kind).collect();
+        assert_eq!(
+            kinds,
+            [
+                EventKind::DrawOffered { by: Color::Black },
+                EventKind::GameOver {
+                    outcome: Outcome::Agreement,
+                },
+            ]
+        );
+    }
+}
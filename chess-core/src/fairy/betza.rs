@@ -0,0 +1,328 @@
+//! chess_core::fairy::betza
+//!
+//! Piece movement written in Betza's notation. A description is a run of
+//! atoms, each an uppercase letter standing for a leap, optionally led by
+//! lowercase modifiers: `N` is the knight, `BN` the archbishop, `fmW` a step
+//! straight forward that cannot capture. Doubling an atom or following it
+//! with a number turns the leap into a ride, so `WW` is the rook and `F2` a
+//! bishop that goes at most two squares.
+//!
+//! | atom | leap | | atom | shorthand for |
+//! |------|------|-|------|---------------|
+//! | `W`  | (1, 0) | | `K` | `WF` |
+//! | `F`  | (1, 1) | | `R` | `WW` |
+//! | `D`  | (2, 0) | | `B` | `FF` |
+//! | `N`  | (2, 1) | | `Q` | `WWFF` |
+//! | `A`  | (2, 2) | | | |
+//! | `H`  | (3, 0) | | | |
+//! | `C`  | (3, 1) | | | |
+//! | `Z`  | (3, 2) | | | |
+//! | `G`  | (3, 3) | | | |
+//!
+//! The modifiers are `m` (moves only) and `c` (captures only); `f`, `b`,
+//! `l`, `r`, `v` and `s` for forward, backward, left, right, vertical and
+//! sideways, paired as in `fl` for one quadrant or doubled as in `ff` for the
+//! narrow moves of an oblique leaper; `n` for a lame leap whose path must be
+//! empty; `g` for a grasshopper, which rides up to the first piece in its way
+//! and lands just beyond it; and `i` for a move only made from the piece's
+//! own second rank, like the pawn's double step.
+//!
+//! Directions are seen from White's side, so `f` points up the board for
+//! White and down it for Black.
+
+use crate::error::ChessError;
+use std::fmt;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Mode {
+    #[default]
+    Both,
+    // Only onto an empty square.
+    Move,
+    // Only onto an enemy piece.
+    Capture,
+}
+
+impl Mode {
+    pub fn moves(self) -> bool {
+        self != Mode::Capture
+    }
+    pub fn captures(self) -> bool {
+        self != Mode::Move
+    }
+}
+
+// One way a piece may move: a set of steps, each taken up to `range` times.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Leg {
+    // (files, ranks) for White; Black mirrors the ranks.
+    pub steps: Vec<(isize, isize)>,
+    // How many steps the piece may take in a line; None rides to the edge.
+    pub range: Option<usize>,
+    pub mode: Mode,
+    pub hop: bool,
+    pub lame: bool,
+    pub initial: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Betza {
+    text: String,
+    legs: Vec<Leg>,
+}
+
+impl Betza {
+    pub fn legs(&self) -> &[Leg] {
+        &self.legs
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.text
+    }
+}
+
+impl fmt::Display for Betza {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.text)
+    }
+}
+
+// The basic leaps, by their (longer, shorter) offsets.
+fn leap(atom: char) -> Option<(isize, isize)> {
+    Some(match atom {
+        'W' => (1, 0),
+        'F' => (1, 1),
+        'D' => (2, 0),
+        'N' => (2, 1),
+        'A' => (2, 2),
+        'H' => (3, 0),
+        'C' => (3, 1),
+        'Z' => (3, 2),
+        'G' => (3, 3),
+        _ => return None,
+    })
+}
+
+// The compound atoms as (leap, ride) pairs.
+fn shorthand(atom: char) -> Option<&'static [(char, bool)]> {
+    Some(match atom {
+        'K' => &[('W', false), ('F', false)],
+        'R' => &[('W', true)],
+        'B' => &[('F', true)],
+        'Q' => &[('W', true), ('F', true)],
+        _ => return None,
+    })
+}
+
+// Every way of turning the leap (a, b) around the board.
+fn symmetric((a, b): (isize, isize)) -> Vec<(isize, isize)> {
+    let mut steps = Vec::with_capacity(8);
+    for (x, y) in [(a, b), (b, a)] {
+        for (sx, sy) in [(1, 1), (1, -1), (-1, 1), (-1, -1)] {
+            let step = (x * sx, y * sy);
+            if !steps.contains(&step) {
+                steps.push(step);
+            }
+        }
+    }
+    steps
+}
+
+// Whether the step (dx, dy) points the way of one direction term, such as
+// `f`, `fl` or `ff`.
+fn points(term: &str, (dx, dy): (isize, isize)) -> bool {
+    let vertical = dy.abs() > dx.abs();
+    let sideways = dx.abs() > dy.abs();
+    let half = |c| match c {
+        'f' => dy > 0,
+        'b' => dy < 0,
+        'l' => dx < 0,
+        'r' => dx > 0,
+        'v' => vertical || dx == 0,
+        's' => sideways || dy == 0,
+        _ => false,
+    };
+    let mut chars = term.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) => half(c),
+        (Some(c), Some(d)) if c == d && matches!(c, 'f' | 'b') => half(c) && vertical,
+        (Some(c), Some(d)) if c == d => half(c) && sideways,
+        (Some(c), Some(d)) => half(c) && half(d),
+        _ => true,
+    }
+}
+
+// Splits the direction modifiers into terms: `fl` and `ff` stay together,
+// anything else stands alone.
+fn direction_terms(dirs: &str) -> Vec<String> {
+    let chars: Vec<char> = dirs.chars().collect();
+    let mut terms = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let pairs = match (chars[i], chars.get(i + 1)) {
+            ('f' | 'b', Some('l' | 'r')) => true,
+            (c, Some(&d)) => c == d,
+            _ => false,
+        };
+        let len = if pairs { 2 } else { 1 };
+        terms.push(chars[i..i + len].iter().collect());
+        i += len;
+    }
+    terms
+}
+
+impl FromStr for Betza {
+    type Err = ChessError;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let bad = |reason: String| ChessError::InvalidSetup {
+            reason: format!("{text:?} is not a movement in Betza notation: {reason}"),
+        };
+        let chars: Vec<char> = text.chars().collect();
+        let mut legs = Vec::new();
+        let mut i = 0;
+        while i < chars.len() {
+            let mut leg = Leg {
+                steps: Vec::new(),
+                range: Some(1),
+                mode: Mode::Both,
+                hop: false,
+                lame: false,
+                initial: false,
+            };
+            let mut dirs = String::new();
+            while let Some(&c) = chars.get(i).filter(|c| c.is_ascii_lowercase()) {
+                match c {
+                    'm' => leg.mode = Mode::Move,
+                    'c' => leg.mode = Mode::Capture,
+                    'g' => leg.hop = true,
+                    'n' => leg.lame = true,
+                    'i' => leg.initial = true,
+                    'f' | 'b' | 'l' | 'r' | 'v' | 's' => dirs.push(c),
+                    other => return Err(bad(format!("unknown modifier {other:?}"))),
+                }
+                i += 1;
+            }
+            let Some(&atom) = chars.get(i) else {
+                return Err(bad("modifiers without an atom".to_string()));
+            };
+            i += 1;
+            // A doubled atom rides; a number limits the ride.
+            let doubled = chars.get(i) == Some(&atom);
+            if doubled {
+                i += 1;
+            }
+            let digits: String = chars[i..]
+                .iter()
+                .take_while(|c| c.is_ascii_digit())
+                .collect();
+            i += digits.len();
+            let limit = match digits.parse::<usize>() {
+                Ok(0) => return Err(bad(format!("{atom} cannot go zero squares"))),
+                Ok(n) => Some(Some(n)),
+                Err(_) if doubled => Some(None),
+                Err(_) => None,
+            };
+
+            let parts: Vec<(char, bool)> = match (leap(atom), shorthand(atom)) {
+                (Some(_), _) => vec![(atom, false)],
+                (None, Some(parts)) => parts.to_vec(),
+                (None, None) => return Err(bad(format!("unknown atom {atom:?}"))),
+            };
+            let terms = direction_terms(&dirs);
+            for (part, rides) in parts {
+                let steps: Vec<_> = symmetric(leap(part).expect("shorthands are made of leaps"))
+                    .into_iter()
+                    .filter(|&step| terms.is_empty() || terms.iter().any(|t| points(t, step)))
+                    .collect();
+                if steps.is_empty() {
+                    return Err(bad(format!("{dirs}{atom} has no direction left")));
+                }
+                let range = match limit {
+                    Some(range) => range,
+                    None if rides => None,
+                    None => Some(1),
+                };
+                legs.push(Leg {
+                    steps,
+                    range,
+                    ..leg.clone()
+                });
+            }
+        }
+        if legs.is_empty() {
+            return Err(bad("no atoms".to_string()));
+        }
+        Ok(Self {
+            text: text.to_string(),
+            legs,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn steps(text: &str) -> Vec<(isize, isize)> {
+        let betza: Betza = text.parse().unwrap();
+        let mut steps: Vec<_> = betza
+            .legs()
+            .iter()
+            .flat_map(|leg| leg.steps.clone())
+            .collect();
+        steps.sort();
+        steps
+    }
+
+    #[test]
+    fn atoms_combine_into_the_familiar_pieces() {
+        assert_eq!(steps("N").len(), 8);
+        assert_eq!(steps("W"), vec![(-1, 0), (0, -1), (0, 1), (1, 0)]);
+        assert_eq!(steps("K").len(), 8);
+        assert_eq!(steps("C").len(), 8);
+        assert!(steps("C").contains(&(-1, -3)));
+
+        let rook: Betza = "R".parse().unwrap();
+        assert_eq!(rook.legs()[0].range, None);
+        assert_eq!("WW".parse::<Betza>().unwrap().legs(), rook.legs());
+        let short: Betza = "F2".parse().unwrap();
+        assert_eq!(short.legs()[0].range, Some(2));
+
+        let archbishop: Betza = "BN".parse().unwrap();
+        assert_eq!(archbishop.legs().len(), 2);
+        assert_eq!(archbishop.to_string(), "BN");
+    }
+
+    #[test]
+    fn modifiers_pick_directions_and_modes() {
+        let pawn: Betza = "fmWfcFifmnD".parse().unwrap();
+        let [push, take, double] = pawn.legs() else {
+            panic!("a pawn has three legs");
+        };
+        assert_eq!((push.steps.clone(), push.mode), (vec![(0, 1)], Mode::Move));
+        assert_eq!(take.mode, Mode::Capture);
+        assert_eq!(take.steps.len(), 2);
+        assert!(take.steps.iter().all(|&(_, dy)| dy == 1));
+        assert!(double.initial && double.lame);
+        assert_eq!(double.steps, vec![(0, 2)]);
+
+        assert_eq!(steps("ffN"), vec![(-1, 2), (1, 2)]);
+        assert_eq!(steps("fN").len(), 4);
+        assert_eq!(steps("flF"), vec![(-1, 1)]);
+        assert_eq!(steps("sW"), vec![(-1, 0), (1, 0)]);
+        assert!("gQ"
+            .parse::<Betza>()
+            .unwrap()
+            .legs()
+            .iter()
+            .all(|leg| leg.hop));
+
+        assert!("X".parse::<Betza>().is_err());
+        assert!("fm".parse::<Betza>().is_err());
+        assert!("xN".parse::<Betza>().is_err());
+        assert!("W0".parse::<Betza>().is_err());
+        assert!("".parse::<Betza>().is_err());
+    }
+}
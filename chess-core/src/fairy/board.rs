@@ -0,0 +1,726 @@
+//! chess_core::fairy::board
+//!
+//! A position of fairy chess. Unlike [`crate::position::Position`], a
+//! [`FairyPosition`] can be any [`Geometry`], and every piece on it moves the
+//! way its [`PieceDef`] says, orthodox pieces included. On top of the Betza
+//! movement the board knows the rules that are not about a single piece:
+//! kings are royal, pawns promote on the far rank to anything in the
+//! [`PieceSet`]'s promotions and take en passant after an initial double
+//! step, and a king castles with the outermost rook of its back rank,
+//! landing on the second file from the edge with the rook beside it, as on
+//! g1 and c1 in orthodox chess. Castling moves are written king-takes-rook.
+//!
+//! [`PieceDef`]: super::PieceDef
+
+use super::betza::Leg;
+use super::PieceSet;
+use crate::error::{ChessError, FenField};
+use crate::game::math::XyPair;
+use crate::geometry::Geometry;
+use crate::msg::TileId;
+use crate::position::{CastleSide, Castling, ChessMove, Man, Outcome};
+use crate::types::{Color, Type};
+use std::sync::Arc;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FairyPosition {
+    pub geometry: Geometry,
+    pub pieces: Arc<PieceSet>,
+    pub board: Vec<Option<Man>>,
+    pub turn: Color,
+    pub castling: Castling,
+    pub ep: Option<TileId>,
+    pub halfmoves: u32,
+    pub fullmoves: u32,
+}
+
+impl FairyPosition {
+    pub fn empty(geometry: Geometry, pieces: Arc<PieceSet>) -> Self {
+        Self {
+            geometry,
+            pieces,
+            board: vec![None; geometry.tile_count()],
+            turn: Color::White,
+            castling: Castling::default(),
+            ep: None,
+            halfmoves: 0,
+            fullmoves: 1,
+        }
+    }
+
+    pub fn from_fen(
+        geometry: Geometry,
+        pieces: Arc<PieceSet>,
+        fen: &str,
+    ) -> Result<Self, ChessError> {
+        let mut fields = fen.split_whitespace();
+        let mut pos = Self::empty(geometry, pieces);
+
+        let placement = fields
+            .next()
+            .ok_or_else(|| ChessError::fen(FenField::Placement, "missing"))?;
+        let rows: Vec<&str> = placement.split('/').collect();
+        if rows.len() != geometry.ranks {
+            return Err(ChessError::fen(
+                FenField::Placement,
+                format!("expected {} ranks in {placement:?}", geometry.ranks),
+            ));
+        }
+        for (i, row) in rows.iter().enumerate() {
+            let rank = geometry.ranks - 1 - i;
+            let mut file = 0;
+            let mut chars = row.chars().peekable();
+            while let Some(c) = chars.next() {
+                if let Some(digit) = c.to_digit(10) {
+                    // Wide boards need skips of ten or more.
+                    let mut skip = digit as usize;
+                    while let Some(digit) = chars.peek().and_then(|c| c.to_digit(10)) {
+                        skip = skip * 10 + digit as usize;
+                        chars.next();
+                    }
+                    file += skip;
+                    continue;
+                }
+                let ty = pos.pieces.from_letter(c).ok_or_else(|| {
+                    ChessError::fen(FenField::Placement, format!("unknown piece {c:?}"))
+                })?;
+                let xy = XyPair::from((file, rank));
+                let sq = geometry.xy_to_index(xy).ok_or_else(|| {
+                    ChessError::fen(
+                        FenField::Placement,
+                        format!("rank {} has too many files", rank + 1),
+                    )
+                })?;
+                let color = match c.is_ascii_uppercase() {
+                    true => Color::White,
+                    false => Color::Black,
+                };
+                pos.board[sq] = Some(Man::new(color, ty));
+                file += 1;
+            }
+            if file != geometry.files {
+                return Err(ChessError::fen(
+                    FenField::Placement,
+                    format!("rank {} does not have {} files", rank + 1, geometry.files),
+                ));
+            }
+        }
+
+        pos.turn = match fields.next() {
+            Some("w") | None => Color::White,
+            Some("b") => Color::Black,
+            Some(other) => {
+                return Err(ChessError::fen(
+                    FenField::SideToMove,
+                    format!("{other:?} is neither w nor b"),
+                ))
+            }
+        };
+        for color in [Color::White, Color::Black] {
+            let kings = pos
+                .men(color)
+                .filter(|(_, man)| man.ty == Type::King)
+                .count();
+            if kings != 1 {
+                return Err(ChessError::fen(
+                    FenField::Placement,
+                    format!("{color:?} needs one king, not {kings}"),
+                ));
+            }
+        }
+
+        match fields.next() {
+            Some("-") | None => {}
+            Some(rights) => {
+                for c in rights.chars() {
+                    let color = match c.is_ascii_uppercase() {
+                        true => Color::White,
+                        false => Color::Black,
+                    };
+                    let side = match c.to_ascii_lowercase() {
+                        'k' => CastleSide::King,
+                        'q' => CastleSide::Queen,
+                        _ => {
+                            return Err(ChessError::fen(
+                                FenField::Castling,
+                                format!("unknown castling right {c:?}"),
+                            ))
+                        }
+                    };
+                    let rook = pos.castling_rook(color, side).ok_or_else(|| {
+                        ChessError::fen(FenField::Castling, format!("no rook to castle with {c:?}"))
+                    })?;
+                    pos.castling.set(color, side, Some(rook));
+                }
+            }
+        }
+        pos.ep = match fields.next() {
+            Some("-") | None => None,
+            Some(name) => Some(geometry.parse_square(name).ok_or_else(|| {
+                ChessError::fen(FenField::EnPassant, format!("{name:?} is not a square"))
+            })?),
+        };
+        let mut counter = |field: FenField, default: u32| match fields.next() {
+            None => Ok(default),
+            Some(n) => n
+                .parse()
+                .map_err(|_| ChessError::fen(field, format!("{n:?} is not a number"))),
+        };
+        pos.halfmoves = counter(FenField::HalfmoveClock, 0)?;
+        pos.fullmoves = counter(FenField::FullmoveNumber, 1)?;
+        Ok(pos)
+    }
+
+    pub fn to_fen(&self) -> String {
+        let mut fen = String::new();
+        for rank in (0..self.geometry.ranks).rev() {
+            let mut empty = 0;
+            for file in 0..self.geometry.files {
+                match self.board[rank * self.geometry.files + file] {
+                    Some(man) => {
+                        if empty > 0 {
+                            fen += &empty.to_string();
+                            empty = 0;
+                        }
+                        fen.push(man.to_char());
+                    }
+                    None => empty += 1,
+                }
+            }
+            if empty > 0 {
+                fen += &empty.to_string();
+            }
+            if rank > 0 {
+                fen.push('/');
+            }
+        }
+        fen.push_str(match self.turn {
+            Color::White => " w ",
+            Color::Black => " b ",
+        });
+        let mut rights = String::new();
+        for (color, side, c) in [
+            (Color::White, CastleSide::King, 'K'),
+            (Color::White, CastleSide::Queen, 'Q'),
+            (Color::Black, CastleSide::King, 'k'),
+            (Color::Black, CastleSide::Queen, 'q'),
+        ] {
+            if self.castling.rook(color, side).is_some() {
+                rights.push(c);
+            }
+        }
+        if rights.is_empty() {
+            rights.push('-');
+        }
+        let ep = self
+            .ep
+            .map_or("-".to_string(), |sq| self.geometry.square_name(sq));
+        format!("{fen}{rights} {ep} {} {}", self.halfmoves, self.fullmoves)
+    }
+
+    pub fn men(&self, color: Color) -> impl Iterator<Item = (TileId, Man)> + '_ {
+        self.board
+            .iter()
+            .enumerate()
+            .filter_map(move |(sq, man)| man.filter(|man| man.color == color).map(|man| (sq, man)))
+    }
+
+    pub fn king(&self, color: Color) -> Option<TileId> {
+        self.men(color)
+            .find(|(_, man)| man.ty == Type::King)
+            .map(|(sq, _)| sq)
+    }
+
+    // The square `(dx, dy)` away from `sq` as `color` sees the board.
+    fn step(&self, sq: TileId, (dx, dy): (isize, isize), color: Color) -> Option<TileId> {
+        let dy = match color {
+            Color::White => dy,
+            Color::Black => -dy,
+        };
+        let XyPair { x, y } = self.geometry.index_to_xy(sq);
+        self.geometry.xy_to_index(XyPair {
+            x: x + dx,
+            y: y + dy,
+        })
+    }
+
+    fn rank(&self, sq: TileId) -> usize {
+        sq / self.geometry.files
+    }
+
+    fn file(&self, sq: TileId) -> usize {
+        sq % self.geometry.files
+    }
+
+    fn back_rank(&self, color: Color) -> usize {
+        match color {
+            Color::White => 0,
+            Color::Black => self.geometry.ranks - 1,
+        }
+    }
+
+    // The outermost rook on `side` of the king, on the king's back rank.
+    fn castling_rook(&self, color: Color, side: CastleSide) -> Option<TileId> {
+        let king = self.king(color)?;
+        if self.rank(king) != self.back_rank(color) {
+            return None;
+        }
+        let first = king - self.file(king);
+        let rank = first..first + self.geometry.files;
+        let rook = |&sq: &TileId| self.board[sq] == Some(Man::new(color, Type::Rook));
+        match side {
+            CastleSide::King => rank.rev().take_while(|&sq| sq > king).find(rook),
+            CastleSide::Queen => rank.take_while(|&sq| sq < king).find(rook),
+        }
+    }
+
+    // Where the king and the rook land when castling on `side`.
+    fn castling_squares(&self, king: TileId, side: CastleSide) -> (TileId, TileId) {
+        let first = king - self.file(king);
+        let files = self.geometry.files;
+        match side {
+            CastleSide::King => (first + files - 2, first + files - 3),
+            CastleSide::Queen => (first + 2, first + 3),
+        }
+    }
+
+    // Where a leg takes the piece on `from`, as (square, captures) pairs.
+    fn leg_targets(&self, from: TileId, man: Man, leg: &Leg, out: &mut Vec<(TileId, bool)>) {
+        if leg.initial && self.rank(from) != self.back_rank(man.color).abs_diff(1) {
+            return;
+        }
+        for &step in &leg.steps {
+            if leg.hop {
+                let mut sq = from;
+                let hurdle = loop {
+                    match self.step(sq, step, man.color) {
+                        Some(next) if self.board[next].is_none() => sq = next,
+                        other => break other,
+                    }
+                };
+                let Some(landing) = hurdle.and_then(|h| self.step(h, step, man.color)) else {
+                    continue;
+                };
+                match self.board[landing] {
+                    None if leg.mode.moves() => out.push((landing, false)),
+                    Some(other) if other.color != man.color && leg.mode.captures() => {
+                        out.push((landing, true))
+                    }
+                    _ => {}
+                }
+                continue;
+            }
+            if leg.lame && !self.path_is_clear(from, step, man.color) {
+                continue;
+            }
+            let mut sq = from;
+            let mut taken = 0;
+            while leg.range.is_none_or(|range| taken < range) {
+                let Some(next) = self.step(sq, step, man.color) else {
+                    break;
+                };
+                taken += 1;
+                sq = next;
+                match self.board[sq] {
+                    None => {
+                        if leg.mode.moves() {
+                            out.push((sq, false));
+                        } else if leg.mode.captures() && Some(sq) == self.ep && man.ty == Type::Pawn
+                        {
+                            out.push((sq, true));
+                        }
+                    }
+                    Some(other) => {
+                        if other.color != man.color && leg.mode.captures() {
+                            out.push((sq, true));
+                        }
+                        break;
+                    }
+                }
+            }
+        }
+    }
+
+    // Whether the squares a straight leap passes over are empty.
+    fn path_is_clear(&self, from: TileId, (dx, dy): (isize, isize), color: Color) -> bool {
+        let n = dx.abs().max(dy.abs());
+        let (ux, uy) = (dx / n, dy / n);
+        if (ux * n, uy * n) != (dx, dy) {
+            return true;
+        }
+        let mut sq = from;
+        for _ in 1..n {
+            match self.step(sq, (ux, uy), color) {
+                Some(next) if self.board[next].is_none() => sq = next,
+                _ => return false,
+            }
+        }
+        true
+    }
+
+    fn targets(&self, from: TileId, man: Man, out: &mut Vec<(TileId, bool)>) {
+        let def = self
+            .pieces
+            .get(man.ty)
+            .unwrap_or_else(|| panic!("{:?} is not in this piece set", man.ty));
+        for leg in def.betza.legs() {
+            self.leg_targets(from, man, leg, out);
+        }
+    }
+
+    // Whether a piece of `by` could take something standing on `sq`.
+    pub fn is_attacked(&self, sq: TileId, by: Color) -> bool {
+        match self.board[sq] {
+            Some(man) if man.color != by => self.attacks(sq, by),
+            _ => {
+                let mut probe = self.clone();
+                probe.board[sq] = Some(Man::new(by.opposite(), Type::King));
+                probe.ep = None;
+                probe.attacks(sq, by)
+            }
+        }
+    }
+
+    fn attacks(&self, sq: TileId, by: Color) -> bool {
+        let mut targets = Vec::new();
+        self.men(by).any(|(from, man)| {
+            targets.clear();
+            self.targets(from, man, &mut targets);
+            targets.contains(&(sq, true))
+        })
+    }
+
+    pub fn in_check(&self) -> bool {
+        self.king(self.turn)
+            .is_some_and(|king| self.is_attacked(king, self.turn.opposite()))
+    }
+
+    pub fn pseudo_legal_moves(&self) -> Vec<ChessMove> {
+        let us = self.turn;
+        let mut moves = Vec::new();
+        let mut targets = Vec::new();
+        for (from, man) in self.men(us) {
+            targets.clear();
+            self.targets(from, man, &mut targets);
+            let far_rank = self.back_rank(us.opposite());
+            for &(to, _) in &targets {
+                if man.ty == Type::Pawn && self.rank(to) == far_rank {
+                    for &ty in self.pieces.promotions() {
+                        moves.push(ChessMove::promoting(from, to, ty));
+                    }
+                } else {
+                    moves.push(ChessMove::new(from, to));
+                }
+            }
+            if man.ty == Type::King {
+                self.castling_moves(from, &mut moves);
+            }
+        }
+        moves
+    }
+
+    fn castling_moves(&self, king: TileId, out: &mut Vec<ChessMove>) {
+        let us = self.turn;
+        for side in [CastleSide::King, CastleSide::Queen] {
+            let Some(rook) = self.castling.rook(us, side) else {
+                continue;
+            };
+            if self.board[rook] != Some(Man::new(us, Type::Rook)) {
+                continue;
+            }
+            let (king_to, rook_to) = self.castling_squares(king, side);
+            let span = |a: TileId, b: TileId| a.min(b)..=a.max(b);
+            let blocked = span(king, king_to)
+                .chain(span(rook, rook_to))
+                .any(|sq| sq != king && sq != rook && self.board[sq].is_some());
+            // The landing square is left to the legality check.
+            let attacked = || {
+                span(king, king_to)
+                    .filter(|&sq| sq != king_to)
+                    .any(|sq| self.is_attacked(sq, us.opposite()))
+            };
+            if !blocked && !attacked() {
+                out.push(ChessMove::new(king, rook));
+            }
+        }
+    }
+
+    pub fn legal_moves(&self) -> Vec<ChessMove> {
+        let us = self.turn;
+        self.pseudo_legal_moves()
+            .into_iter()
+            .filter(|mv| {
+                let mut after = self.clone();
+                after.make_move(mv);
+                after
+                    .king(us)
+                    .is_none_or(|king| !after.is_attacked(king, us.opposite()))
+            })
+            .collect()
+    }
+
+    // Plays `mv`, which should be one of [`Self::legal_moves`]().
+    pub fn make_move(&mut self, mv: &ChessMove) {
+        let us = self.turn;
+        let man = self.board[mv.from].expect("a move starts from a piece");
+        let target = self.board[mv.to];
+        let castles = man.ty == Type::King && target == Some(Man::new(us, Type::Rook));
+        let mut ep = None;
+        self.halfmoves += 1;
+
+        if castles {
+            let side = match mv.to > mv.from {
+                true => CastleSide::King,
+                false => CastleSide::Queen,
+            };
+            let (king_to, rook_to) = self.castling_squares(mv.from, side);
+            self.board[mv.from] = None;
+            self.board[mv.to] = None;
+            self.board[king_to] = Some(man);
+            self.board[rook_to] = Some(Man::new(us, Type::Rook));
+        } else {
+            if man.ty == Type::Pawn || target.is_some() {
+                self.halfmoves = 0;
+            }
+            if man.ty == Type::Pawn && Some(mv.to) == self.ep && target.is_none() {
+                // The pawn taken en passant stands beside the one taking it.
+                let passed = self.rank(mv.from) * self.geometry.files + self.file(mv.to);
+                self.board[passed] = None;
+            }
+            let (from, to) = (self.rank(mv.from), self.rank(mv.to));
+            if man.ty == Type::Pawn
+                && self.file(mv.from) == self.file(mv.to)
+                && from.abs_diff(to) == 2
+            {
+                ep = Some((mv.from + mv.to) / 2);
+            }
+            self.board[mv.from] = None;
+            self.board[mv.to] = Some(Man::new(us, mv.promotion.unwrap_or(man.ty)));
+        }
+
+        if man.ty == Type::King {
+            self.castling.clear_color(us);
+        }
+        for color in [Color::White, Color::Black] {
+            for side in [CastleSide::King, CastleSide::Queen] {
+                if self
+                    .castling
+                    .rook(color, side)
+                    .is_some_and(|rook| rook == mv.from || rook == mv.to)
+                {
+                    self.castling.set(color, side, None);
+                }
+            }
+        }
+        self.ep = ep;
+        if us == Color::Black {
+            self.fullmoves += 1;
+        }
+        self.turn = us.opposite();
+    }
+
+    pub fn outcome(&self) -> Option<Outcome> {
+        if self.legal_moves().is_empty() {
+            return Some(match self.in_check() {
+                true => Outcome::Checkmate {
+                    winner: self.turn.opposite(),
+                },
+                false => Outcome::Stalemate,
+            });
+        }
+        (self.halfmoves >= 100).then_some(Outcome::FiftyMoves)
+    }
+
+    // A move in coordinates such as `a7a8c` or `j1j10`.
+    pub fn move_name(&self, mv: &ChessMove) -> String {
+        let mut name = self.geometry.square_name(mv.from) + &self.geometry.square_name(mv.to);
+        if let Some(ty) = mv.promotion {
+            name.push(Man::new(Color::Black, ty).to_char());
+        }
+        name
+    }
+
+    // Reads a move written like [`Self::move_name`]() and checks that it is legal.
+    pub fn parse_move(&self, text: &str) -> Result<ChessMove, ChessError> {
+        let illegal = || ChessError::IllegalMove {
+            reason: format!("{text:?} is not a legal move"),
+        };
+        // Each square is a letter followed by digits.
+        let second = text
+            .char_indices()
+            .skip(1)
+            .find(|(_, c)| c.is_ascii_lowercase())
+            .map(|(i, _)| i)
+            .ok_or_else(illegal)?;
+        let rest = &text[second..];
+        let end = rest
+            .char_indices()
+            .skip(1)
+            .find(|(_, c)| !c.is_ascii_digit())
+            .map_or(rest.len(), |(i, _)| i);
+        let from = self.geometry.parse_square(&text[..second]);
+        let to = self.geometry.parse_square(&rest[..end]);
+        let promotion = match rest[end..].chars().next() {
+            None => None,
+            Some(c) => Some(self.pieces.from_letter(c).ok_or_else(illegal)?),
+        };
+        let (Some(from), Some(to)) = (from, to) else {
+            return Err(illegal());
+        };
+        let mv = ChessMove {
+            from,
+            to,
+            promotion,
+        };
+        self.legal_moves()
+            .into_iter()
+            .find(|legal| *legal == mv)
+            .ok_or_else(illegal)
+    }
+
+    // The number of move sequences `depth` plies long.
+    pub fn perft(&self, depth: usize) -> u64 {
+        if depth == 0 {
+            return 1;
+        }
+        let moves = self.legal_moves();
+        if depth == 1 {
+            return moves.len() as u64;
+        }
+        moves
+            .iter()
+            .map(|mv| {
+                let mut next = self.clone();
+                next.make_move(mv);
+                next.perft(depth - 1)
+            })
+            .sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{FairyVariant, PieceDef, PieceSet};
+    use super::*;
+
+    fn position(pieces: PieceSet, geometry: Geometry, fen: &str) -> FairyPosition {
+        FairyPosition::from_fen(geometry, Arc::new(pieces), fen).unwrap()
+    }
+
+    #[test]
+    fn capablanca_counts_like_everyone_else() {
+        let capablanca = FairyVariant::capablanca();
+        let start = capablanca.start();
+        assert_eq!(
+            start.to_fen(),
+            "rnabqkbcnr/pppppppppp/10/10/10/10/PPPPPPPPPP/RNABQKBCNR w KQkq - 0 1"
+        );
+        assert_eq!(start.perft(1), 28);
+        assert_eq!(start.perft(2), 784);
+        assert_eq!(start.perft(3), 25_228);
+
+        let mut pos = start.clone();
+        for mv in ["e2e4", "f7f5", "e4f5"] {
+            let mv = pos.parse_move(mv).unwrap();
+            pos.make_move(&mv);
+        }
+        assert_eq!(
+            pos.board[pos.geometry.parse_square("f5").unwrap()],
+            Some(Man::new(Color::White, Type::Pawn))
+        );
+        assert!(pos.parse_move("j1j3").is_err());
+    }
+
+    #[test]
+    fn the_orthodox_pieces_keep_their_perft() {
+        // Kiwipete exercises castling, en passant and promotion.
+        let pos = position(
+            PieceSet::orthodox(),
+            Geometry::STANDARD,
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        );
+        assert_eq!(pos.perft(1), 48);
+        assert_eq!(pos.perft(2), 2039);
+    }
+
+    #[test]
+    fn fairy_pieces_move_by_their_betza() {
+        let pieces = PieceSet::orthodox()
+            .with(PieceDef::amazon())
+            .with(PieceDef::camel())
+            .with(PieceDef::grasshopper())
+            .with(PieceDef::archbishop());
+        // Lone pieces in the corner of an empty 10x8 board.
+        let moves = |letter: char| {
+            let fen = format!("9k/10/10/10/10/10/10/{letter}8K w - - 0 1");
+            let pos = position(pieces.clone(), Geometry::CAPABLANCA, &fen);
+            let mut moves: Vec<String> = pos
+                .legal_moves()
+                .iter()
+                .filter(|mv| mv.from == 0)
+                .map(|mv| pos.move_name(mv))
+                .collect();
+            moves.sort();
+            moves
+        };
+        assert_eq!(moves('L'), ["a1b4", "a1d2"]);
+        assert_eq!(moves('M').len(), 8 + 7 + 7 + 2);
+        assert_eq!(moves('A').len(), 7 + 2);
+        // With nothing to jump, a grasshopper cannot move.
+        assert!(moves('G').is_empty());
+
+        let pos = position(
+            pieces,
+            Geometry::CAPABLANCA,
+            "9k/10/10/10/10/10/1p8/G1P6K w - - 0 1",
+        );
+        let mut hops: Vec<String> = pos
+            .legal_moves()
+            .iter()
+            .filter(|mv| mv.from == 0)
+            .map(|mv| pos.move_name(mv))
+            .collect();
+        hops.sort();
+        // Over c1 to d1, and over b2 to c3; the hurdle is not taken.
+        assert_eq!(hops, ["a1c3", "a1d1"]);
+        assert!(pos.board[pos.geometry.parse_square("b2").unwrap()].is_some());
+    }
+
+    #[test]
+    fn pawns_promote_to_the_pieces_of_the_set() {
+        let capablanca = FairyVariant::capablanca();
+        let pos = capablanca
+            .parse_fen("9k/P9/10/10/10/10/10/K9 w - - 0 1")
+            .unwrap();
+        let mut promotions: Vec<String> = pos
+            .legal_moves()
+            .iter()
+            .map(|mv| pos.move_name(mv))
+            .filter(|mv| mv.starts_with("a7"))
+            .collect();
+        promotions.sort();
+        assert_eq!(
+            promotions,
+            ["a7a8a", "a7a8b", "a7a8c", "a7a8n", "a7a8q", "a7a8r"]
+        );
+        let mut pos = pos;
+        pos.make_move(&pos.parse_move("a7a8c").unwrap());
+        assert_eq!(pos.to_fen(), "C8k/10/10/10/10/10/10/K9 b - - 0 1");
+        // The chancellor checks like a knight and covers like a rook.
+        let mate = capablanca
+            .parse_fen("9k/10/8CK/10/10/10/10/10 b - - 0 1")
+            .unwrap();
+        assert_eq!(
+            mate.outcome(),
+            Some(Outcome::Checkmate {
+                winner: Color::White
+            })
+        );
+        assert!(capablanca
+            .parse_fen("10/10/10/10/10/10/10/K9 w - - 0 1")
+            .is_err());
+        assert!(capablanca
+            .parse_fen("k9/10/10/10/10/10/10/K8 w - - 0 1")
+            .is_err());
+    }
+}
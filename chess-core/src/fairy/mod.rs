@@ -0,0 +1,261 @@
+//! chess_core::fairy
+//!
+//! Pieces beyond the orthodox six, and boards of any [`Geometry`] to play them
+//! on. A [`PieceDef`] gives a piece its name, its FEN letter and its movement
+//! in Betza's notation (see [`betza`]); a [`PieceSet`] collects the pieces of
+//! a game along with what pawns may promote to; and a [`FairyVariant`] puts a
+//! set on a board with a starting position, which is all it takes to describe
+//! Capablanca chess:
+//!
+//! ```
+//! use chess_core::fairy::{FairyVariant, PieceDef, PieceSet};
+//! use chess_core::geometry::Geometry;
+//!
+//! let pieces = PieceSet::orthodox()
+//!     .with(PieceDef::archbishop())
+//!     .with(PieceDef::chancellor());
+//! let capablanca = FairyVariant::new(
+//!     "Capablanca",
+//!     Geometry::CAPABLANCA,
+//!     pieces,
+//!     "rnabqkbcnr/pppppppppp/10/10/10/10/PPPPPPPPPP/RNABQKBCNR w KQkq - 0 1",
+//! )
+//! .unwrap();
+//! assert_eq!(capablanca.start().legal_moves().len(), 28);
+//! ```
+//!
+//! Fairy pieces are [`Type::Fairy`] with their letter, and are set up on a
+//! [`crate::ChessGame`] through [`crate::traits::FairyChess`]. The rules of
+//! [`crate::position`] stay those of orthodox chess; moves among fairy pieces
+//! are made on a [`FairyPosition`].
+
+pub mod betza;
+pub mod board;
+
+pub use self::betza::Betza;
+pub use self::board::FairyPosition;
+
+use crate::error::ChessError;
+use crate::geometry::Geometry;
+use crate::types::Type;
+use std::sync::Arc;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct PieceDef {
+    pub ty: Type,
+    pub name: String,
+    pub betza: Betza,
+}
+
+impl PieceDef {
+    // A fairy piece known by its uppercase FEN `letter`. The letters of the
+    // orthodox pieces are taken.
+    pub fn new(name: &str, letter: char, betza: &str) -> Result<Self, ChessError> {
+        if !letter.is_ascii_uppercase() || "PNBRQK".contains(letter) {
+            return Err(ChessError::InvalidSetup {
+                reason: format!("{name} cannot use the letter {letter:?}"),
+            });
+        }
+        Ok(Self {
+            ty: Type::Fairy(letter),
+            name: name.to_string(),
+            betza: betza.parse()?,
+        })
+    }
+
+    // One of the orthodox pieces, described in Betza's notation. Castling
+    // and en passant are still up to the board.
+    pub fn orthodox(ty: Type) -> Self {
+        let (name, betza) = match ty {
+            Type::Pawn => ("Pawn", "fmWfcFifmnD"),
+            Type::Knight => ("Knight", "N"),
+            Type::Bishop => ("Bishop", "B"),
+            Type::Rook => ("Rook", "R"),
+            Type::Queen => ("Queen", "Q"),
+            Type::King => ("King", "K"),
+            Type::Fairy(letter) => panic!("{letter} is not an orthodox piece"),
+        };
+        Self {
+            ty,
+            name: name.to_string(),
+            betza: betza.parse().expect("the orthodox pieces are valid Betza"),
+        }
+    }
+
+    // Bishop and knight.
+    pub fn archbishop() -> Self {
+        Self::new("Archbishop", 'A', "BN").expect("a valid piece")
+    }
+
+    // Rook and knight.
+    pub fn chancellor() -> Self {
+        Self::new("Chancellor", 'C', "RN").expect("a valid piece")
+    }
+
+    // Queen and knight.
+    pub fn amazon() -> Self {
+        Self::new("Amazon", 'M', "QN").expect("a valid piece")
+    }
+
+    // Leaps three squares one way and one the other, so it never leaves the
+    // color it started on.
+    pub fn camel() -> Self {
+        Self::new("Camel", 'L', "C").expect("a valid piece")
+    }
+
+    // Moves along queen lines, but only by jumping the first piece in its way
+    // to land just behind it.
+    pub fn grasshopper() -> Self {
+        Self::new("Grasshopper", 'G', "gQ").expect("a valid piece")
+    }
+
+    // The FEN letter for White.
+    pub fn letter(&self) -> char {
+        match self.ty {
+            Type::Pawn => 'P',
+            Type::Knight => 'N',
+            Type::Bishop => 'B',
+            Type::Rook => 'R',
+            Type::Queen => 'Q',
+            Type::King => 'K',
+            Type::Fairy(letter) => letter,
+        }
+    }
+}
+
+// The pieces of a game. Kings are royal: leaving one in check is illegal.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct PieceSet {
+    defs: Vec<PieceDef>,
+    promotions: Vec<Type>,
+}
+
+impl PieceSet {
+    pub fn orthodox() -> Self {
+        Self {
+            defs: Type::ORTHODOX.into_iter().map(PieceDef::orthodox).collect(),
+            promotions: vec![Type::Queen, Type::Rook, Type::Bishop, Type::Knight],
+        }
+    }
+
+    // Adds `def`, or puts it in place of the piece with the same letter. Pawns
+    // may promote to it unless it is royal.
+    pub fn with(mut self, def: PieceDef) -> Self {
+        let ty = def.ty;
+        match self.defs.iter_mut().find(|known| known.ty == ty) {
+            Some(known) => *known = def,
+            None => self.defs.push(def),
+        }
+        if !matches!(ty, Type::Pawn | Type::King) && !self.promotions.contains(&ty) {
+            self.promotions.push(ty);
+        }
+        self
+    }
+
+    // Takes `ty` out of the set, e.g. to play without queens.
+    pub fn without(mut self, ty: Type) -> Self {
+        self.defs.retain(|def| def.ty != ty);
+        self.promotions.retain(|&promotion| promotion != ty);
+        self
+    }
+
+    pub fn get(&self, ty: Type) -> Option<&PieceDef> {
+        self.defs.iter().find(|def| def.ty == ty)
+    }
+
+    pub fn defs(&self) -> &[PieceDef] {
+        &self.defs
+    }
+
+    pub fn promotions(&self) -> &[Type] {
+        &self.promotions
+    }
+
+    // The piece a FEN letter stands for, in either case.
+    pub fn from_letter(&self, letter: char) -> Option<Type> {
+        let letter = letter.to_ascii_uppercase();
+        self.defs
+            .iter()
+            .find(|def| def.letter() == letter)
+            .map(|def| def.ty)
+    }
+}
+
+// A game of fairy chess: its pieces, its board and where they start.
+#[derive(Debug, Clone)]
+pub struct FairyVariant {
+    pub name: String,
+    pub geometry: Geometry,
+    pub pieces: Arc<PieceSet>,
+    start: FairyPosition,
+}
+
+impl FairyVariant {
+    pub fn new(
+        name: &str,
+        geometry: Geometry,
+        pieces: PieceSet,
+        start_fen: &str,
+    ) -> Result<Self, ChessError> {
+        let pieces = Arc::new(pieces);
+        let start = FairyPosition::from_fen(geometry, Arc::clone(&pieces), start_fen)?;
+        Ok(Self {
+            name: name.to_string(),
+            geometry,
+            pieces,
+            start,
+        })
+    }
+
+    // Orthodox chess on a 10x8 board with an archbishop and a chancellor on
+    // each side.
+    pub fn capablanca() -> Self {
+        let pieces = PieceSet::orthodox()
+            .with(PieceDef::archbishop())
+            .with(PieceDef::chancellor());
+        Self::new(
+            "Capablanca",
+            Geometry::CAPABLANCA,
+            pieces,
+            "rnabqkbcnr/pppppppppp/10/10/10/10/PPPPPPPPPP/RNABQKBCNR w KQkq - 0 1",
+        )
+        .expect("a valid variant")
+    }
+
+    pub fn start(&self) -> &FairyPosition {
+        &self.start
+    }
+
+    // Reads a position of this variant from FEN.
+    pub fn parse_fen(&self, fen: &str) -> Result<FairyPosition, ChessError> {
+        FairyPosition::from_fen(self.geometry, Arc::clone(&self.pieces), fen)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn piece_sets_are_configured_by_letter() {
+        let set = PieceSet::orthodox()
+            .with(PieceDef::amazon())
+            .without(Type::Queen);
+        assert_eq!(set.from_letter('m'), Some(Type::Fairy('M')));
+        assert_eq!(set.from_letter('Q'), None);
+        assert_eq!(
+            set.promotions(),
+            &[Type::Rook, Type::Bishop, Type::Knight, Type::Fairy('M')]
+        );
+        assert_eq!(set.get(Type::Fairy('M')).unwrap().betza.as_str(), "QN");
+
+        // A new movement replaces the old one under the same letter.
+        let set = set.with(PieceDef::new("Fers-Amazon", 'M', "QF").unwrap());
+        assert_eq!(set.defs().len(), 6);
+        assert_eq!(set.get(Type::Fairy('M')).unwrap().name, "Fers-Amazon");
+
+        assert!(PieceDef::new("Queen", 'Q', "Q").is_err());
+        assert!(PieceDef::new("Odd", 'o', "W").is_err());
+        assert!(PieceDef::new("Odd", 'O', "Y").is_err());
+    }
+}
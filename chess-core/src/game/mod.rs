@@ -1,25 +1,78 @@
 pub mod math;
 
+use crate::clock::ChessClock;
+use crate::error::ChessError;
+use crate::geometry::Geometry;
 use crate::msg::{PieceId, PlayerId, TileId};
-use crate::types::{Color, Move, Piece, RawBoard, Tile, Type, VisionPiece};
+use crate::position::crazyhouse::{Reserves, DROPPABLE};
+use crate::position::{rank_of, square_at, CastleSide, ChessMove, Outcome, Position};
+use crate::types::{Color, Move, Piece, RawBoard, Type, VisionPiece};
+use crate::variant::{self, Variant};
 use crate::{constants, types};
-use anyhow::{anyhow, bail, Result};
 // use serde::{Deserialize, Serialize};
 // use serde_with::serde_as;
+use std::collections::HashMap;
 use std::{cell::RefCell, rc::Rc};
 
 use self::math::XyPair;
 
 // #[derive(Debug, Serialize, Deserialize)]
 // #[serde_as]
+// The rules a game is played by.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum GameMode {
+    #[default]
+    Standard,
+    // Each side only sees part of the board and wins by taking the king;
+    // see [`crate::position::fog`]().
+    FogOfWar,
+    // Captured pieces change sides and may be dropped back onto the board;
+    // see [`crate::position::crazyhouse`]().
+    Crazyhouse,
+    // See [`crate::variant::three_check`]().
+    ThreeCheck,
+    // See [`crate::variant::king_of_the_hill`]().
+    KingOfTheHill,
+    // See [`crate::variant::atomic`]().
+    Atomic,
+    // See [`crate::variant::antichess`]().
+    Antichess,
+    // See [`crate::variant::racing_kings`]().
+    RacingKings,
+    // See [`crate::variant::horde`]().
+    Horde,
+    // One board of a Bughouse match; see [`crate::variant::bughouse`]().
+    Bughouse,
+}
+
+impl GameMode {
+    // The rules behind this mode.
+    pub fn variant(self) -> &'static dyn Variant {
+        match self {
+            GameMode::Standard => &variant::Standard,
+            GameMode::FogOfWar => &variant::FogOfWar,
+            GameMode::Crazyhouse => &variant::Crazyhouse,
+            GameMode::ThreeCheck => &variant::ThreeCheck,
+            GameMode::KingOfTheHill => &variant::KingOfTheHill,
+            GameMode::Atomic => &variant::Atomic,
+            GameMode::Antichess => &variant::Antichess,
+            GameMode::RacingKings => &variant::RacingKings,
+            GameMode::Horde => &variant::Horde,
+            GameMode::Bughouse => &variant::Bughouse,
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct GameState {
     pub started: bool,
     pub finished: bool,
-    pub p1_clock: Option<u32>,
-    pub p2_clock: Option<u32>,
+    // Untimed games have no clock.
+    pub clock: Option<ChessClock>,
     pub p1: PlayerData,
     pub p2: PlayerData,
+    // The shape of [`Self::board`]().
+    pub geometry: Geometry,
     // #[serde_as(as = "[_; constants::TILECOUNT]")]
     pub board: RawBoard,
     pub hist: History,
@@ -27,14 +80,14 @@ pub struct GameState {
 
 impl GameState {
     pub fn new() -> Self {
-        let board: [Tile; constants::TILECOUNT] = crate::helper::chess_board();
+        let board: RawBoard = crate::helper::chess_board();
         Self {
             started: false,
             finished: false,
-            p1_clock: None,
-            p2_clock: None,
+            clock: None,
             p1: PlayerData::default(),
             p2: PlayerData::default(),
+            geometry: Geometry::STANDARD,
             board,
             hist: History::default(),
         }
@@ -42,8 +95,7 @@ impl GameState {
     pub fn init(
         started: bool,
         finished: bool,
-        p1_clock: Option<u32>,
-        p2_clock: Option<u32>,
+        clock: Option<ChessClock>,
         p1: PlayerData,
         p2: PlayerData,
         board: RawBoard,
@@ -52,17 +104,46 @@ impl GameState {
         Self {
             started,
             finished,
-            p1_clock,
-            p2_clock,
+            clock,
             p1,
             p2,
+            geometry: Geometry::STANDARD,
             board,
             hist,
         }
     }
+    // How the game ended, if it did: by the rules or on the clock.
+    pub fn outcome(&self) -> Option<Outcome> {
+        self.rules_outcome().or_else(|| self.time_outcome())
+    }
+    // The rules the game is played by.
+    pub fn mode(&self) -> GameMode {
+        self.hist.mode()
+    }
+    // How the rules of [`Self::mode`]() ended the game, clocks aside.
+    pub fn rules_outcome(&self) -> Option<Outcome> {
+        self.hist.outcome()
+    }
+    // The moves [`Self::mode`]() allows in the current position.
+    pub fn legal_moves(&self) -> Vec<ChessMove> {
+        self.hist
+            .mode()
+            .variant()
+            .legal_moves(&self.hist.position())
+    }
+    // A fallen flag loses, unless the opponent could never mate.
+    pub fn time_outcome(&self) -> Option<Outcome> {
+        let loser = self.clock.as_ref()?.flagged()?;
+        let winner = loser.opposite();
+        Some(if self.hist.position().has_mating_material(winner) {
+            Outcome::TimeForfeit { winner }
+        } else {
+            Outcome::TimeoutVsInsufficientMaterial
+        })
+    }
     pub fn piece_by_id(&self, piece_id: &PieceId) -> Option<Rc<RefCell<Piece>>> {
         match *piece_id {
-            pid @ 1..=16 => {
+            pid @ 1.. => {
                 for rc in &self.p1.pieces {
                     if rc.borrow().id == pid {
                         return Some(Rc::clone(&rc));
@@ -70,7 +151,7 @@ impl GameState {
                 }
                 None
             }
-            pid @ -16..=-1 => {
+            pid @ ..=-1 => {
                 for rc in &self.p2.pieces {
                     if rc.borrow().id == pid {
                         return Some(Rc::clone(&rc));
@@ -81,51 +162,352 @@ impl GameState {
             _ => None,
         }
     }
+    pub fn player(&self, color: Color) -> &PlayerData {
+        match color {
+            Color::White => &self.p1,
+            Color::Black => &self.p2,
+        }
+    }
+    // Gives both players the off-board pieces `reserves` holds for them.
+    pub fn fill_reserves(&mut self, reserves: &Reserves) {
+        for color in [Color::White, Color::Black] {
+            self.player_mut(color).reserve.clear();
+            for ty in DROPPABLE {
+                for _ in 0..reserves.count(color, ty) {
+                    self.add_to_reserve(color, ty);
+                }
+            }
+        }
+    }
+    // Hands `color` a new off-board `ty` to drop later.
+    pub fn add_to_reserve(&mut self, color: Color, ty: Type) {
+        let piece = Piece {
+            id: self.fresh_id(color),
+            color,
+            ty,
+            loc: self.board.len(),
+        };
+        let rc = Rc::new(RefCell::new(piece));
+        self.player_mut(color).reserve.push(rc);
+    }
+    // An id for a piece joining `color`'s side, past those of all its pieces.
+    pub fn fresh_id(&self, color: Color) -> PieceId {
+        let player = self.player(color);
+        let most = player
+            .pieces
+            .iter()
+            .chain(&player.reserve)
+            .map(|p| p.borrow().id.abs())
+            .max()
+            .unwrap_or(0);
+        match color {
+            Color::White => most + 1,
+            Color::Black => -(most + 1),
+        }
+    }
+    pub fn player_mut(&mut self, color: Color) -> &mut PlayerData {
+        match color {
+            Color::White => &mut self.p1,
+            Color::Black => &mut self.p2,
+        }
+    }
+    fn piece_at(&self, idx: TileId) -> Result<Rc<RefCell<Piece>>, ChessError> {
+        self.board[idx]
+            .pz
+            .as_ref()
+            .and_then(std::rc::Weak::upgrade)
+            .ok_or(ChessError::EmptyTile(idx))
+    }
+    fn place(&mut self, rc: &Rc<RefCell<Piece>>, idx: TileId) {
+        rc.borrow_mut().update_loc(idx);
+        self.board[idx].pz = Some(Rc::downgrade(rc));
+    }
+    // Takes whatever stands on `idx` off the board and away from its owner.
+    // The piece's location is moved past the last tile so it no longer shows up.
+    fn remove(&mut self, idx: TileId) -> Option<Rc<RefCell<Piece>>> {
+        let rc = self.piece_at(idx).ok()?;
+        self.board[idx].pz = None;
+        let color = rc.borrow().color;
+        let owner = self.player_mut(color);
+        owner.pieces.retain(|p| !Rc::ptr_eq(p, &rc));
+        rc.borrow_mut().update_loc(self.board.len());
+        Some(rc)
+    }
+    // Mirrors `mv`, played from `pos`, onto the tiles and pieces of this game state.
+    // The caller is responsible for `mv` being legal in `pos`.
+    pub fn apply_move(&mut self, pos: &Position, mv: &ChessMove) -> Result<(), ChessError> {
+        self.mirror_move(pos, mv)?;
+        // Whatever else the variant swept off the board, e.g. in an explosion.
+        let mut after = *pos;
+        self.hist.mode().variant().make_move(&mut after, mv);
+        for idx in (0..constants::TILECOUNT).filter(|&idx| after.board[idx].is_none()) {
+            self.remove(idx);
+        }
+        Ok(())
+    }
+    fn mirror_move(&mut self, pos: &Position, mv: &ChessMove) -> Result<(), ChessError> {
+        if let (true, Some(ty)) = (mv.is_drop(), mv.promotion) {
+            let owner = self.player_mut(pos.turn);
+            let idx = owner
+                .reserve
+                .iter()
+                .position(|p| p.borrow().ty == ty)
+                .ok_or_else(|| ChessError::IllegalMove {
+                    reason: format!("no {ty:?} in reserve"),
+                })?;
+            let dropped = owner.reserve.remove(idx);
+            owner.pieces.push(Rc::clone(&dropped));
+            self.place(&dropped, mv.to);
+            return Ok(());
+        }
+        let mover = self.piece_at(mv.from)?;
+        if pos.is_castling(mv) {
+            let rook = self.piece_at(mv.to)?;
+            let side = if mv.to > mv.from {
+                CastleSide::King
+            } else {
+                CastleSide::Queen
+            };
+            let rank = rank_of(mv.from);
+            self.board[mv.from].pz = None;
+            self.board[mv.to].pz = None;
+            self.place(&mover, square_at(side.king_dest_file(), rank));
+            self.place(&rook, square_at(side.rook_dest_file(), rank));
+            return Ok(());
+        }
+        let victim = pos.victim_square(mv);
+        let taken = self.remove(victim.index());
+        // In Bughouse the captured piece goes to the partner on the other
+        // board instead.
+        let keep = self.hist.mode() != GameMode::Bughouse;
+        if let (Some(reserves), Some(rc), true) = (pos.reserves, taken, keep) {
+            let id = self.fresh_id(pos.turn);
+            let mut piece = rc.borrow_mut();
+            piece.id = id;
+            piece.color = pos.turn;
+            if reserves.is_promoted(victim) {
+                piece.ty = Type::Pawn;
+            }
+            drop(piece);
+            self.player_mut(pos.turn).reserve.push(rc);
+        }
+        self.board[mv.from].pz = None;
+        self.place(&mover, mv.to);
+        if let Some(ty) = mv.promotion {
+            mover.borrow_mut().ty = ty;
+        }
+        Ok(())
+    }
     pub fn calculate_vision(
         &self,
         piece: Rc<RefCell<Piece>>,
         board: &types::RawBoard,
-    ) -> Result<VisionPiece> {
+    ) -> Result<VisionPiece, ChessError> {
         let p = piece.borrow();
-        let invert: bool = if &p.color == &Color::Black {
-            true
-        } else {
-            false
-        };
-        if invert {
-            bail!("not yet implemented: handling simple cases first");
-        }
-        // let XyPair { x, y } = crate::game::math::index_to_xy(p.loc);
+        // Moves are given as directions from the mover's side of the board, so
+        // black pieces need nothing special here; see [`crate::types::Direction::delta`]().
         match p.ty {
-            Type::Bishop => Err(anyhow!("Bishop movement not available yet")),
-            Type::King => Err(anyhow!("King movement not yet available")),
-            Type::Knight => Err(anyhow!("Knight movement not available")),
+            Type::Bishop => Err(ChessError::Unsupported("Bishop movement".to_string())),
+            Type::King => Err(ChessError::Unsupported("King movement".to_string())),
+            Type::Knight => Err(ChessError::Unsupported("Knight movement".to_string())),
             Type::Pawn => {
                 let nil = Move::new_nil(&piece);
                 let f1 = Move::forward(&piece, 1);
                 let f2 = Move::forward(&piece, 2);
-                let moves = [nil, f1, f2]; // Ok(VisionPiece::new_with_moves(p.id, &[nil, f1, f2]))
+                let moves = [nil, f1, f2].iter().map(Move::sight).collect::<Vec<_>>();
 
                 let piece_id: PieceId = ((*piece.clone()).borrow()).id;
                 Ok(VisionPiece::new_with_moves(piece_id, moves))
-                // Needs to be a Result<VisionPiece>
             }
-            Type::Queen => Err(anyhow!("Queen movement not yet available")),
-            Type::Rook => Err(anyhow!("Rook movemvent not available")),
+            Type::Queen => Err(ChessError::Unsupported("Queen movement".to_string())),
+            Type::Rook => Err(ChessError::Unsupported("Rook movement".to_string())),
+            Type::Fairy(_) => Err(ChessError::Unsupported("Fairy movement".to_string())),
         }
     }
 }
 
-#[derive(Default, Clone, PartialEq, Debug)]
+// [`History`]() is the record of everything that happened in a game, starting
+// from [`Self::start`](). Replaying the [`Action::Move`]()s on top of the start
+// under the rules of [`Self::mode`]() gives the current [`Position`]().
+//
+// Each action is played once, when it is pushed: the history keeps the
+// position after every action and counts how often each position came up, so
+// that neither the current position nor a repetition needs a replay.
+#[derive(Clone, PartialEq, Debug)]
 pub struct History {
     id: String,
-    pub actions: Vec<Action>,
+    start: Position,
+    mode: GameMode,
+    actions: Vec<Action>,
+    // The position after each action.
+    after: Vec<Position>,
+    // The hash of each of [`Self::positions`]().
+    hashes: Vec<u64>,
+    // How many of [`Self::hashes`]() are equal to each hash.
+    seen: HashMap<u64, usize>,
+}
+impl Default for History {
+    fn default() -> Self {
+        Self::init_from(String::new(), Position::default())
+    }
 }
 impl History {
     pub fn init(id: impl Into<String>) -> Self {
-        let id = id.into();
-        let actions: Vec<Action> = vec![];
-        Self { id, actions }
+        Self::init_from(id, Position::startpos())
+    }
+    pub fn init_from(id: impl Into<String>, start: Position) -> Self {
+        let mut hist = Self {
+            id: id.into(),
+            start,
+            mode: GameMode::Standard,
+            actions: Vec::new(),
+            after: Vec::new(),
+            hashes: Vec::new(),
+            seen: HashMap::new(),
+        };
+        hist.remember(start.hash());
+        hist
+    }
+    pub fn start(&self) -> Position {
+        self.start
+    }
+    pub fn mode(&self) -> GameMode {
+        self.mode
+    }
+    pub fn actions(&self) -> &[Action] {
+        &self.actions
+    }
+    // Plays the game again from `start`, e.g. once a variant has set it up.
+    pub fn set_start(&mut self, start: Position) {
+        self.start = start;
+        self.rebuild();
+    }
+    // Plays the game again under the rules of `mode`.
+    pub fn set_mode(&mut self, mode: GameMode) {
+        self.mode = mode;
+        self.rebuild();
+    }
+    fn rebuild(&mut self) {
+        let actions = std::mem::take(&mut self.actions);
+        let mut fresh = Self::init_from(std::mem::take(&mut self.id), self.start);
+        fresh.mode = self.mode;
+        for action in actions {
+            fresh.push(action);
+        }
+        *self = fresh;
+    }
+    fn remember(&mut self, hash: u64) {
+        self.hashes.push(hash);
+        *self.seen.entry(hash).or_default() += 1;
+    }
+    fn forget_last(&mut self) {
+        let hash = self.hashes.pop().expect("the start is never forgotten");
+        if let Some(count) = self.seen.get_mut(&hash) {
+            *count -= 1;
+            if *count == 0 {
+                self.seen.remove(&hash);
+            }
+        }
+    }
+    // Records `action`, carrying it out on the current position.
+    pub fn push(&mut self, action: Action) {
+        let mut pos = self.position();
+        if !self.replay(&mut pos, &action) {
+            // Not a move: whatever it changed belongs to the position it
+            // was taken in.
+            self.forget_last();
+        }
+        self.remember(pos.hash());
+        self.actions.push(action);
+        self.after.push(pos);
+    }
+    // Takes back the last action, if there was one.
+    pub fn pop(&mut self) -> Option<Action> {
+        let action = self.actions.pop()?;
+        self.after.pop();
+        self.forget_last();
+        if !matches!(action, Action::Move(_)) {
+            self.remember(self.position().hash());
+        }
+        Some(action)
+    }
+    pub fn moves(&self) -> impl Iterator<Item = &ChessMove> + '_ {
+        self.actions.iter().filter_map(|action| match action {
+            Action::Move(mv) => Some(mv),
+            _ => None,
+        })
+    }
+    // Every position of the game, from the start up to and including the current one.
+    // Pieces received between two moves count towards the position before the later one.
+    pub fn positions(&self) -> Vec<Position> {
+        let mut all = vec![self.start];
+        for (action, &pos) in self.actions.iter().zip(&self.after) {
+            match action {
+                Action::Move(_) => all.push(pos),
+                _ => *all.last_mut().unwrap() = pos,
+            }
+        }
+        all
+    }
+    // The [`Position::hash`]() of each of [`Self::positions`]().
+    pub fn hashes(&self) -> &[u64] {
+        &self.hashes
+    }
+    pub fn position(&self) -> Position {
+        self.after.last().copied().unwrap_or(self.start)
+    }
+    // Carries out `action` on `pos` under the rules of [`Self::mode`](). True if
+    // it was a move.
+    pub(crate) fn replay(&self, pos: &mut Position, action: &Action) -> bool {
+        match action {
+            Action::Move(mv) => {
+                self.mode.variant().make_move(pos, mv);
+                true
+            }
+            Action::Receive(color, ty) => {
+                let reserves = pos.reserves.get_or_insert_with(Reserves::default);
+                reserves.add(*color, *ty);
+                false
+            }
+            _ => false,
+        }
+    }
+    // The player whose draw offer stands. An offer lapses once the opponent
+    // moves instead of accepting it.
+    pub fn draw_offer(&self) -> Option<PlayerId> {
+        let mut turn = self.start.turn;
+        let mut offer = None;
+        for action in &self.actions {
+            match action {
+                Action::OfferDraw(player) => offer = Some(*player),
+                Action::Move(_) => {
+                    if offer == Some(turn == Color::White) {
+                        offer = None;
+                    }
+                    turn = turn.opposite();
+                }
+                _ => {}
+            }
+        }
+        offer
+    }
+    fn agreed_draw(&self) -> bool {
+        self.actions
+            .iter()
+            .any(|a| matches!(a, Action::AcceptDraw(_)))
+    }
+    // Like [`Variant::outcome`](), but also aware of threefold repetitions
+    // and agreed draws.
+    pub fn outcome(&self) -> Option<Outcome> {
+        if self.agreed_draw() {
+            return Some(Outcome::Agreement);
+        }
+        let current = self.position();
+        if let Some(outcome) = self.mode.variant().outcome(&current) {
+            return Some(outcome);
+        }
+        let seen = self.seen.get(&current.hash()).copied().unwrap_or(0);
+        (seen >= 3).then_some(Outcome::Repetition)
     }
 }
 #[derive(Default, Debug, Clone, PartialEq)]
@@ -134,7 +516,12 @@ pub enum Action {
     Nil,
     FixPlayerData,
     SetActivePlayer(PlayerId),
-    Move(*mut u8),
+    Move(ChessMove),
+    OfferDraw(PlayerId),
+    AcceptDraw(PlayerId),
+    // In Bughouse, a piece captured on the partner board joins the
+    // reserve of `Color`.
+    Receive(Color, Type),
 }
 
 #[derive(Default, Debug, Clone)]
@@ -142,6 +529,8 @@ pub struct PlayerData {
     pub color: Color,
     pub name: String,
     pub pieces: Vec<Rc<RefCell<Piece>>>,
+    // In Crazyhouse and Bughouse, the pieces this player may drop, all off the board.
+    pub reserve: Vec<Rc<RefCell<Piece>>>,
 }
 
 impl PlayerData {
@@ -167,22 +556,26 @@ impl PlayerData {
             color,
             name,
             pieces,
+            reserve: Vec::new(),
         }
     }
     pub fn new_white_player() -> Self {
-        let pieces: Vec<Rc<RefCell<Piece>>> = Vec::with_capacity(16);
-        PlayerData {
-            color: Color::White,
-            name: "player_1".to_string(),
-            pieces,
-        }
+        Self::with_capacity(Color::White, 16)
     }
     pub fn new_black_player() -> Self {
-        let pieces: Vec<Rc<RefCell<Piece>>> = Vec::with_capacity(16);
+        Self::with_capacity(Color::Black, 16)
+    }
+    // A player with room for an army of `count` pieces, which need not be 16.
+    pub fn with_capacity(color: Color, count: usize) -> Self {
+        let name = match color {
+            Color::White => "player_1",
+            Color::Black => "player_2",
+        };
         PlayerData {
-            color: Color::Black,
-            name: "player_2".to_string(),
-            pieces,
+            color,
+            name: name.to_string(),
+            pieces: Vec::with_capacity(count),
+            reserve: Vec::new(),
         }
     }
     fn add_piece(&mut self, pz: Rc<RefCell<Piece>>) {
@@ -195,13 +588,11 @@ pub fn add_piece(
     idx: usize,
     player: &mut PlayerData,
     mut pz: Piece,
-) -> Result<()> {
-    assert_eq!(board.as_ref().len(), 64);
-    use crate::constants::TILECOUNT;
+) -> Result<(), ChessError> {
     assert_eq!(pz.color, player.color, "Forbidden enemy piece assignment");
-    assert!(idx < TILECOUNT, "Out of bounds tile");
-    if let Some(found) = &board[idx].pz {
-        anyhow::bail!("Tried to overwrite an existing piece: {found:?}");
+    assert!(idx < board.len(), "Out of bounds tile");
+    if board[idx].pz.is_some() {
+        return Err(ChessError::TileOccupied(idx));
     }
     pz.update_loc(idx);
     let owned = Rc::new(RefCell::new(pz));
@@ -246,7 +637,7 @@ mod tests {
     #[ignore = "Skipping new game until prerequisites are done"]
     #[test]
     fn create_new_local_game() {
-        fn new_local_game() -> Result<()> {
+        fn new_local_game() -> anyhow::Result<()> {
             anyhow::bail!("Not yet implemented");
         }
         let _game = new_local_game().unwrap();
@@ -259,6 +650,22 @@ mod tests {
         let mut p2 = PlayerData::new_black_player();
     }
 
+    #[test]
+    fn pieces_cannot_share_a_tile() {
+        let mut board = crate::helper::chess_board();
+        let mut white = PlayerData::new_white_player();
+        let pawn = |id| Piece {
+            id,
+            color: Color::White,
+            ty: Type::Pawn,
+            loc: 12,
+        };
+        add_piece(&mut board, 12, &mut white, pawn(1)).unwrap();
+        let err = add_piece(&mut board, 12, &mut white, pawn(2)).unwrap_err();
+        assert_eq!(err, ChessError::TileOccupied(12));
+        assert_eq!(white.pieces.len(), 1);
+    }
+
     #[test]
     fn queen_color_matches_background() {
         // ChessWorld.com says that on the initial board setup
@@ -286,12 +693,12 @@ mod tests {
         // save the owned one to the player
         for white_pz in w.into_iter() {
             let pos: TileId = white_pz.loc.clone();
-            let res: Result<()> = add_piece(&mut board, pos, &mut player_w, white_pz);
+            let res: Result<(), ChessError> = add_piece(&mut board, pos, &mut player_w, white_pz);
             assert!(res.is_ok());
         }
         for black_pz in b.into_iter() {
             let pos: TileId = black_pz.loc.clone();
-            let res: Result<()> = add_piece(&mut board, pos, &mut player_b, black_pz);
+            let res: Result<(), ChessError> = add_piece(&mut board, pos, &mut player_b, black_pz);
             assert!(res.is_ok());
         }
 
@@ -320,4 +727,141 @@ mod tests {
         assert!(gt.pz.is_none());
         assert!(gt.update_piece(gaga_shared, true).is_ok());
     }
+
+    #[test]
+    fn queens_start_on_their_own_color() {
+        let game = crate::fen!("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
+        for color in [Color::White, Color::Black] {
+            let queens: Vec<_> = game
+                .player(color)
+                .pieces
+                .iter()
+                .map(|pz| pz.borrow().clone())
+                .filter(|pz| pz.ty == Type::Queen)
+                .collect();
+            assert_eq!(queens.len(), 1);
+            assert_eq!(queens[0].color, game.board[queens[0].loc].color);
+        }
+    }
+
+    #[test]
+    fn diagrams_match_their_fen() {
+        let diagram = crate::board! {
+            r . . . k . . r /
+            8 /
+            8 /
+            . . . p P . . . /
+            8 /
+            8 /
+            8 /
+            R . . . K . . R ; w KQkq d6 3+2 4 20
+        };
+        let fen = crate::fen!("r3k2r/8/8/3pP3/8/8/8/R3K2R w KQkq d6 3+2 4 20");
+        assert_eq!(diagram.hist.position(), fen.hist.position());
+        assert_eq!(diagram.mode(), GameMode::ThreeCheck);
+
+        let short = crate::board! { 4k3 / 8 / 8 / 8 / 8 / 8 / 8 / 4K3 };
+        assert_eq!(
+            short.hist.position().to_fen(),
+            "4k3/8/8/8/8/8/8/4K3 w - - 0 1"
+        );
+    }
+
+    #[test]
+    fn history_counts_repetitions_as_it_goes() {
+        let mut hist = History::init("knights");
+        let shuffle = ["g1f3", "g8f6", "f3g1", "f6g8"];
+        for uci in shuffle.iter().chain(&shuffle) {
+            assert_eq!(hist.outcome(), None);
+            let mv = hist.position().parse_uci(uci).unwrap();
+            hist.push(Action::Move(mv));
+        }
+        // The start has come up three times.
+        assert_eq!(hist.outcome(), Some(Outcome::Repetition));
+        assert_eq!(hist.hashes().len(), 9);
+        assert_eq!(hist.positions().len(), 9);
+
+        let last = hist.pop();
+        assert!(matches!(last, Some(Action::Move(_))));
+        assert_eq!(hist.outcome(), None);
+        assert_eq!(
+            hist.position().to_fen(),
+            "rnbqkb1r/pppppppp/5n2/8/8/8/PPPPPPPP/RNBQKBNR b KQkq - 7 4"
+        );
+        hist.push(Action::OfferDraw(true));
+        assert_eq!(hist.hashes().len(), 8);
+        assert_eq!(hist.pop(), Some(Action::OfferDraw(true)));
+        assert_eq!(hist.hashes().len(), 8);
+        while hist.pop().is_some() {}
+        assert_eq!(hist, History::init("knights"));
+    }
+
+    #[test]
+    fn derived_setups_come_from_their_attributes() {
+        use chess_derive::{ChessFactory, StandardChess};
+        use constants::*;
+
+        #[derive(StandardChess, ChessFactory)]
+        struct Classical;
+        #[derive(StandardChess, ChessFactory)]
+        #[chess(fen = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1")]
+        struct FromFen;
+        #[derive(StandardChess, ChessFactory)]
+        #[chess(variant = "chess960", seed = 42)]
+        struct Shuffled;
+        #[derive(StandardChess, ChessFactory)]
+        #[chess(variant = "chess960", seed = 42)]
+        struct ShuffledAgain;
+        // The queens have swapped places with the kings.
+        #[derive(StandardChess, ChessFactory)]
+        #[chess(layout = "
+            Ra1 Nb1 Bc1 Kd1 Qe1 Bf1 Ng1 Rh1 Pa2 Pb2 Pc2 Pd2 Pe2 Pf2 Pg2 Ph2
+            pa7 pb7 pc7 pd7 pe7 pf7 pg7 ph7 ra8 nb8 bc8 kd8 qe8 bf8 ng8 rh8
+        ")]
+        struct Swapped;
+
+        let squares = |pieces: [Piece; 16]| pieces.map(|p| (p.loc, p.ty, p.id));
+        assert_eq!(
+            squares(FromFen::gen_std_white()),
+            squares(Classical::gen_std_white())
+        );
+        assert_eq!(
+            squares(FromFen::gen_std_black()),
+            squares(Classical::gen_std_black())
+        );
+
+        let white = Shuffled::gen_std_white();
+        assert_eq!(
+            squares(white.clone()),
+            squares(ShuffledAgain::gen_std_white())
+        );
+        let back: Vec<Type> = white[..8].iter().map(|p| p.ty).collect();
+        let files = |ty| {
+            back.iter()
+                .enumerate()
+                .filter(move |(_, &t)| t == ty)
+                .map(|(f, _)| f)
+        };
+        let (rooks, king): (Vec<_>, Vec<_>) =
+            (files(Type::Rook).collect(), files(Type::King).collect());
+        assert!(rooks[0] < king[0] && king[0] < rooks[1]);
+        let bishops: Vec<_> = files(Type::Bishop).collect();
+        assert_ne!(bishops[0] % 2, bishops[1] % 2);
+        let black = Shuffled::gen_std_black();
+        for (w, b) in white[..8].iter().zip(&black[8..]) {
+            assert_eq!((w.ty, w.loc + 56), (b.ty, b.loc));
+        }
+
+        let white = Swapped::gen_std_white();
+        assert_eq!(
+            (white[3].ty, white[3].loc, white[3].id),
+            (Type::King, D1, 4)
+        );
+        let black = Swapped::gen_std_black();
+        assert_eq!(
+            (black[12].ty, black[12].loc, black[12].id),
+            (Type::Queen, E8, -4)
+        );
+        assert!(black.iter().all(|p| p.color == Color::Black));
+    }
 }
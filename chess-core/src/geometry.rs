@@ -0,0 +1,281 @@
+//! chess_core::geometry
+//!
+//! The shape of the board. A [`Geometry`] is a number of files and ranks, and
+//! everything else about the board follows from those two: tiles are numbered
+//! rank by rank starting from a1, the a1 corner is dark and the shading
+//! alternates from there, and pawns promote on the opponent's back rank.
+//! Besides the standard 8x8 board there are presets for Capablanca (10x8),
+//! Grand Chess (10x10) and Los Alamos (6x6). The rules in
+//! [`crate::position`] are still those of the 8x8 board; a
+//! [`crate::fairy::FairyPosition`] plays on any of them. An [`Orientation`]
+//! says which side of the board is drawn at the bottom.
+
+use crate::error::ChessError;
+use crate::game::math::XyPair;
+use crate::msg::TileId;
+use crate::types::{Background, Color, RawBoard, Tile};
+use std::fmt;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Geometry {
+    pub files: usize,
+    pub ranks: usize,
+}
+
+impl Default for Geometry {
+    fn default() -> Self {
+        Self::STANDARD
+    }
+}
+
+impl Geometry {
+    pub const STANDARD: Geometry = Geometry { files: 8, ranks: 8 };
+    pub const CAPABLANCA: Geometry = Geometry {
+        files: 10,
+        ranks: 8,
+    };
+    pub const GRAND: Geometry = Geometry {
+        files: 10,
+        ranks: 10,
+    };
+    pub const LOS_ALAMOS: Geometry = Geometry { files: 6, ranks: 6 };
+
+    // Files are lettered, so there can be at most 26 of them. A board needs
+    // at least two ranks for both sides to have a back rank.
+    pub fn new(files: usize, ranks: usize) -> Result<Self, ChessError> {
+        if !(1..=26).contains(&files) || !(2..=26).contains(&ranks) {
+            return Err(ChessError::InvalidSetup {
+                reason: format!("a board cannot be {files}x{ranks}"),
+            });
+        }
+        Ok(Self { files, ranks })
+    }
+
+    pub fn tile_count(&self) -> usize {
+        self.files * self.ranks
+    }
+
+    pub fn contains(&self, xy: XyPair) -> bool {
+        (0..self.files as isize).contains(&xy.x) && (0..self.ranks as isize).contains(&xy.y)
+    }
+
+    // The coordinates of tile `index`, with a1 at (0, 0).
+    pub fn index_to_xy(&self, index: TileId) -> XyPair {
+        assert!(
+            index < self.tile_count(),
+            "Semantic error giving an out of bounds index: {index}"
+        );
+        (index % self.files, index / self.files).into()
+    }
+
+    // The tile at `xy`, or None off the board.
+    pub fn xy_to_index(&self, xy: XyPair) -> Option<TileId> {
+        self.contains(xy)
+            .then(|| xy.y as usize * self.files + xy.x as usize)
+    }
+
+    // The name of tile `index`, such as `j1` or `a10`.
+    pub fn square_name(&self, index: TileId) -> String {
+        let XyPair { x, y } = self.index_to_xy(index);
+        format!("{}{}", (b'a' + x as u8) as char, y + 1)
+    }
+
+    // The tile named `name`, or None if there is no such square on this board.
+    pub fn parse_square(&self, name: &str) -> Option<TileId> {
+        let mut chars = name.chars();
+        let file = chars.next().filter(char::is_ascii_lowercase)?;
+        let rank: isize = chars.as_str().parse().ok()?;
+        let xy = XyPair {
+            x: (file as u8 - b'a') as isize,
+            y: rank - 1,
+        };
+        self.xy_to_index(xy)
+    }
+
+    // Half a turn about the middle of the board: where a tile is drawn when
+    // the board is shown from Black's side. Both the files and the ranks come
+    // in the other order; [`Self::flip_ranks`]() keeps the files.
+    pub fn rot_index(&self, index: TileId) -> TileId {
+        self.tile_count() - 1 - index
+    }
+
+    // The tile on the same file with the ranks counted from the other side.
+    pub fn flip_ranks(&self, index: TileId) -> TileId {
+        let XyPair { x, y } = self.index_to_xy(index);
+        (self.ranks - 1 - y as usize) * self.files + x as usize
+    }
+
+    // The tile on the same rank with the files in the other order.
+    pub fn flip_files(&self, index: TileId) -> TileId {
+        let XyPair { x, y } = self.index_to_xy(index);
+        y as usize * self.files + self.files - 1 - x as usize
+    }
+
+    pub fn background(&self, index: TileId) -> Background {
+        let XyPair { x, y } = self.index_to_xy(index);
+        match (x + y) % 2 {
+            0 => Background::Dark,
+            _ => Background::Light,
+        }
+    }
+
+    // White promotes on the last rank and Black on the first.
+    pub fn is_w_endzone(&self, index: TileId) -> bool {
+        index / self.files == self.ranks - 1
+    }
+
+    pub fn is_b_endzone(&self, index: TileId) -> bool {
+        index / self.files == 0
+    }
+
+    pub fn tile(&self, index: TileId) -> Tile {
+        let (w, b) = (self.is_w_endzone(index), self.is_b_endzone(index));
+        match self.background(index) {
+            Background::Dark => Tile::dark(index, w, b),
+            Background::Light => Tile::light(index, w, b),
+        }
+    }
+
+    // An empty board of this shape.
+    pub fn board(&self) -> RawBoard {
+        (0..self.tile_count()).map(|idx| self.tile(idx)).collect()
+    }
+}
+
+// The side of the board drawn nearest the viewer. From White's side a1 is at
+// the bottom left; from Black's the board is turned half way round and h8 is.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Orientation {
+    #[default]
+    White,
+    Black,
+}
+
+impl Orientation {
+    pub fn of(color: Color) -> Self {
+        match color {
+            Color::White => Orientation::White,
+            Color::Black => Orientation::Black,
+        }
+    }
+
+    pub fn flipped(self) -> Self {
+        match self {
+            Orientation::White => Orientation::Black,
+            Orientation::Black => Orientation::White,
+        }
+    }
+
+    // Where the tile at `xy` is drawn: its column from the left and its row
+    // from the top.
+    pub fn to_screen(self, geometry: &Geometry, xy: XyPair) -> (usize, usize) {
+        let (x, y) = (xy.x as usize, xy.y as usize);
+        match self {
+            Orientation::White => (x, geometry.ranks - 1 - y),
+            Orientation::Black => (geometry.files - 1 - x, y),
+        }
+    }
+
+    // The tile drawn at `column` and `row`, or None outside the board.
+    pub fn from_screen(self, geometry: &Geometry, column: usize, row: usize) -> Option<XyPair> {
+        if column >= geometry.files || row >= geometry.ranks {
+            return None;
+        }
+        let (x, y) = match self {
+            Orientation::White => (column, geometry.ranks - 1 - row),
+            Orientation::Black => (geometry.files - 1 - column, row),
+        };
+        Some((x, y).into())
+    }
+}
+
+// Written like `10x8`, files first.
+impl fmt::Display for Geometry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}x{}", self.files, self.ranks)
+    }
+}
+
+impl FromStr for Geometry {
+    type Err = ChessError;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let bad = || ChessError::InvalidSetup {
+            reason: format!("{text:?} is not a board size like 10x8"),
+        };
+        let (files, ranks) = text.split_once('x').ok_or_else(bad)?;
+        let files = files.parse().map_err(|_| bad())?;
+        let ranks = ranks.parse().map_err(|_| bad())?;
+        Self::new(files, ranks)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constants::*;
+    use crate::helper::chess_board;
+
+    #[test]
+    fn the_standard_board_keeps_its_tiles() {
+        let board = chess_board();
+        assert_eq!(board.len(), TILECOUNT);
+        let tile = |idx: TileId| (board[idx].color, board[idx].w_endzone, board[idx].b_endzone);
+        assert_eq!(tile(A1), (Background::Dark, false, true));
+        assert_eq!(tile(H1), (Background::Light, false, true));
+        assert_eq!(tile(A2), (Background::Light, false, false));
+        assert_eq!(tile(D8), (Background::Dark, true, false));
+        assert_eq!(tile(H8), (Background::Dark, true, false));
+        assert_eq!(Geometry::STANDARD.index_to_xy(E2), (4, 1).into());
+        assert_eq!(Geometry::STANDARD.xy_to_index((7, 7).into()), Some(H8));
+        assert_eq!(Geometry::STANDARD.rot_index(A1), H8);
+        assert_eq!(Geometry::STANDARD.rot_index(E2), D7);
+        assert_eq!(Geometry::STANDARD.flip_ranks(E2), E7);
+        assert_eq!(Geometry::STANDARD.flip_files(E2), D2);
+    }
+
+    #[test]
+    fn either_side_can_sit_at_the_bottom() {
+        let geometry = Geometry::CAPABLANCA;
+        let a1: XyPair = (0, 0).into();
+        let j8: XyPair = (9, 7).into();
+        assert_eq!(Orientation::White.to_screen(&geometry, a1), (0, 7));
+        assert_eq!(Orientation::Black.to_screen(&geometry, a1), (9, 0));
+        assert_eq!(Orientation::Black.to_screen(&geometry, j8), (0, 7));
+        for orientation in [Orientation::White, Orientation::Black] {
+            for idx in 0..geometry.tile_count() {
+                let xy = geometry.index_to_xy(idx);
+                let (column, row) = orientation.to_screen(&geometry, xy);
+                assert_eq!(orientation.from_screen(&geometry, column, row), Some(xy));
+            }
+        }
+        assert_eq!(Orientation::White.from_screen(&geometry, 10, 0), None);
+        assert_eq!(Orientation::of(Color::Black).flipped(), Orientation::White);
+    }
+
+    #[test]
+    fn other_sizes_follow_from_files_and_ranks() {
+        let capablanca: Geometry = "10x8".parse().unwrap();
+        assert_eq!(capablanca, Geometry::CAPABLANCA);
+        assert_eq!(capablanca.tile_count(), 80);
+        // j1, the far corner of the first rank, is light like h1.
+        assert_eq!(capablanca.background(9), Background::Light);
+        assert_eq!(capablanca.index_to_xy(79), (9, 7).into());
+        assert!(capablanca.is_w_endzone(70) && !capablanca.is_w_endzone(69));
+        assert_eq!(capablanca.xy_to_index((10, 0).into()), None);
+        assert_eq!(capablanca.xy_to_index((9, 1).into()), Some(19));
+        assert_eq!(capablanca.square_name(79), "j8");
+        assert_eq!(capablanca.parse_square("j2"), Some(19));
+        assert_eq!(capablanca.parse_square("j9"), None);
+        assert_eq!(Geometry::GRAND.parse_square("a10"), Some(90));
+        assert_eq!(Geometry::GRAND.square_name(90), "a10");
+
+        let grand = Geometry::GRAND.board();
+        assert_eq!(grand.len(), 100);
+        assert!(grand[99].w_endzone && grand[0].b_endzone);
+        assert_eq!(Geometry::LOS_ALAMOS.to_string(), "6x6");
+        assert!("27x8".parse::<Geometry>().is_err());
+        assert!("8by8".parse::<Geometry>().is_err());
+    }
+}
@@ -1,23 +1,55 @@
+// The derives of chess-derive name this crate as `::chess_core`, which
+// has to resolve inside it too.
+extern crate self as chess_core;
+
+pub mod book;
+pub mod clock;
 pub mod constants;
+pub mod engine;
+pub mod error;
+pub mod events;
+pub mod fairy;
 pub mod game;
+pub mod geometry;
 pub mod helper;
-pub mod layout;
 pub mod msg;
+pub mod pgn;
+pub mod position;
+pub mod snapshot;
+pub mod square;
+pub mod tablebase;
+pub mod tournament;
 pub mod traits;
 pub mod types;
-
-use crate::layout::Layout;
+pub mod uci;
+pub mod variant;
+
+// Positions for tests, checked while compiling; see [`position::fen`]().
+pub use chess_derive::{board, fen};
+
+use crate::clock::{ChessClock, TimeControl};
+use crate::engine::SearchLimits;
+use crate::error::ChessError;
+use crate::events::{move_events, EventKind, GameEvent, Subscribers};
+use crate::fairy::FairyVariant;
+use crate::geometry::Geometry;
 use crate::msg::{GameId, PieceId};
-use crate::traits::{ChessFactory, StandardChess};
+use crate::position::attacks::AttackMap;
+use crate::position::{ChessMove, Outcome, Position};
+use crate::snapshot::BoardSnapshot;
+use crate::traits::{ChessFactory, FairyChess, StandardChess};
 use crate::types::VisionPiece;
-use anyhow::{anyhow, Result};
 use chess_derive::ChessFactory;
+use chess_derive::FairyChess;
 use chess_derive::StandardChess;
-use game::{Action, GameState, History, PlayerData};
+use game::{Action, GameMode, GameState, History, PlayerData};
 use msg::PlayerId;
 use std::marker::PhantomData;
+use std::sync::mpsc::Receiver;
+use std::time::Duration;
 use std::{collections::BTreeMap, sync::atomic::AtomicU64};
-use types::{Direction, RawBoard};
+use types::{Color, Direction, RawBoard};
+use uci::{BestMove, UciEngine};
 
 pub fn spawn_game_master<'parent, 'child>() -> GameMaster<'parent, 'child>
 where
@@ -32,6 +64,7 @@ where
 {
     indexer: Box<dyn Fn() -> GameId>,
     sessions: BTreeMap<GameId, ChessGame>,
+    subscribers: Subscribers,
     _phantone: PhantomData<&'gm ()>,
     _phantwo: PhantomData<&'game ()>,
 }
@@ -52,34 +85,288 @@ impl<'gm, 'game> GameMaster<'gm, 'game> {
         Self {
             indexer,
             sessions,
+            subscribers: Subscribers::default(),
             _phantone: PhantomData,
             _phantwo: PhantomData,
         }
     }
 
-    pub fn create_game(&mut self) -> Result<GameId> {
+    pub fn create_game(&mut self) -> Result<GameId, ChessError> {
         let game_id = (self.indexer)();
         let new_game = ChessGame::new(game_id)?;
         let _ = self.sessions.insert(game_id, new_game);
         Ok(game_id)
     }
 
-    pub fn request_game_state(&'gm self, game_id: GameId) -> Result<&'game ChessGame> {
+    // Like [`Self::create_game`](), but the game begins from `start`, e.g. an opening
+    // position from a FEN or EPD suite.
+    pub fn create_game_from(&mut self, start: Position) -> Result<GameId, ChessError> {
+        let game_id = (self.indexer)();
+        let new_game = ChessGame::from_position(game_id, start)?;
+        let _ = self.sessions.insert(game_id, new_game);
+        Ok(game_id)
+    }
+
+    // An empty board of any size, e.g. for a fairy variant to set up. The rules
+    // only know the 8x8 board so far, so moves on other sizes are refused.
+    pub fn create_empty_game(&mut self, geometry: Geometry) -> Result<GameId, ChessError> {
+        let game_id = (self.indexer)();
+        let new_game = ChessGame::empty(game_id, geometry);
+        let _ = self.sessions.insert(game_id, new_game);
+        Ok(game_id)
+    }
+
+    // The starting position of a fairy variant, set up for display. Its moves
+    // are played on a [`fairy::FairyPosition`](); this game refuses them.
+    pub fn create_fairy_game(&mut self, variant: &FairyVariant) -> Result<GameId, ChessError> {
+        let game_id = (self.indexer)();
+        let new_game = ChessGame::fairy(game_id, variant)?;
+        let _ = self.sessions.insert(game_id, new_game);
+        Ok(game_id)
+    }
+
+    // Switches `game_id` to the rules of `mode` before its first move.
+    pub fn set_mode(&mut self, game_id: GameId, mode: GameMode) -> Result<(), ChessError> {
+        let chess = self.game_mut(game_id)?;
+        chess.check_geometry()?;
+        if chess.game.hist.moves().next().is_some() {
+            return Err(ChessError::InvalidSetup {
+                reason: "the rules cannot change once the game is under way".to_string(),
+            });
+        }
+        let mut start = chess.game.hist.start();
+        start.reserves = None;
+        start.checks = None;
+        let start = mode.variant().setup(start);
+        mode.variant().validate(&start)?;
+        // Some variants start from a position of their own, so set the
+        // pieces up again.
+        let fresh = ChessGame::from_position(game_id, start)?.game;
+        let game = &mut chess.game;
+        game.board = fresh.board;
+        game.p1.pieces = fresh.p1.pieces;
+        game.p2.pieces = fresh.p2.pieces;
+        game.hist.set_start(start);
+        game.hist.set_mode(mode);
+        game.fill_reserves(&start.reserves.unwrap_or_default());
+        Ok(())
+    }
+
+    // A Chess960 game from starting position `index`, 518 being the classical one.
+    pub fn create_chess960_game(&mut self, index: u16) -> Result<GameId, ChessError> {
+        self.create_game_from(Position::chess960(index)?)
+    }
+
+    // Takes a game out of the master, e.g. to archive it once it is over.
+    pub fn remove_game(&mut self, game_id: GameId) -> Result<ChessGame, ChessError> {
+        self.sessions
+            .remove(&game_id)
+            .ok_or(ChessError::GameNotFound(game_id))
+    }
+
+    // Puts `game_id` on `clock`, or makes it untimed.
+    pub fn set_clock(
+        &mut self,
+        game_id: GameId,
+        clock: Option<ChessClock>,
+    ) -> Result<(), ChessError> {
+        self.game_mut(game_id)?.game.clock = clock;
+        Ok(())
+    }
+
+    // Events of `game_id`, or of every game if it is None, from now on.
+    pub fn subscribe(&mut self, game_id: Option<GameId>) -> Receiver<GameEvent> {
+        self.subscribers.subscribe(game_id)
+    }
+
+    pub fn request_game_state(&'gm self, game_id: GameId) -> Result<&'game ChessGame, ChessError> {
         if let Some(ref_game) = self.sessions.get(&game_id) {
             Ok(&ref_game)
         } else {
-            anyhow::bail!("Game with {game_id} not found");
+            Err(ChessError::GameNotFound(game_id))
         }
     }
 
-    pub fn request_vision(&self, game_id: GameId, piece_id: PieceId) -> Result<VisionPiece> {
+    pub fn request_vision(
+        &self,
+        game_id: GameId,
+        piece_id: PieceId,
+    ) -> Result<VisionPiece, ChessError> {
         let chess = self.request_game_state(game_id)?;
         chess.request_vision(piece_id)
     }
 
-    pub fn request_game_layout(&self, game_id: GameId) -> Result<Layout> {
+    // The whole board, as spectators see it.
+    pub fn request_snapshot(&self, game_id: GameId) -> Result<BoardSnapshot, ChessError> {
+        let chess = self.request_game_state(game_id)?;
+        Ok(BoardSnapshot::of(&chess.game))
+    }
+
+    // The board as `player` sees it, which in fog of war hides what their
+    // pieces cannot see.
+    pub fn request_player_snapshot(
+        &self,
+        game_id: GameId,
+        player: PlayerId,
+    ) -> Result<BoardSnapshot, ChessError> {
+        let chess = self.request_game_state(game_id)?;
+        Ok(match chess.game.mode() {
+            GameMode::FogOfWar => BoardSnapshot::seen_by(&chess.game, Color::of_player(player)),
+            _ => BoardSnapshot::of(&chess.game),
+        })
+    }
+
+    // Which pieces of `color` attack each square, by the rules of the game's
+    // mode, e.g. to shade the squares a player's pieces are threatened on.
+    pub fn request_attack_map(
+        &self,
+        game_id: GameId,
+        color: Color,
+    ) -> Result<AttackMap, ChessError> {
+        let chess = self.request_game_state(game_id)?;
+        let pos = chess.position();
+        Ok(chess.game.hist.mode().variant().attack_map(&pos, color))
+    }
+
+    fn game_mut(&mut self, game_id: GameId) -> Result<&mut ChessGame, ChessError> {
+        self.sessions
+            .get_mut(&game_id)
+            .ok_or(ChessError::GameNotFound(game_id))
+    }
+
+    pub fn request_position(&self, game_id: GameId) -> Result<Position, ChessError> {
         let chess = self.request_game_state(game_id)?;
-        Ok(chess.request_game_layout())
+        Ok(chess.position())
+    }
+
+    pub fn make_move(&mut self, game_id: GameId, mv: ChessMove) -> Result<(), ChessError> {
+        let chess = self.game_mut(game_id)?;
+        let before = chess.position();
+        let was_finished = chess.game.finished;
+        let played = chess.make_move(mv);
+        let mut events = Vec::new();
+        if played.is_ok() {
+            events = move_events(chess.game.mode().variant(), &before, &mv);
+            events.extend(clock_tick(&chess.game));
+        }
+        if chess.game.finished && !was_finished {
+            events.extend(
+                chess
+                    .game
+                    .outcome()
+                    .map(|outcome| EventKind::GameOver { outcome }),
+            );
+        }
+        for kind in events {
+            self.subscribers.emit(game_id, kind);
+        }
+        played
+    }
+
+    // Like [`Self::make_move`](), on behalf of `player`, whose turn it has to be.
+    pub fn make_player_move(
+        &mut self,
+        game_id: GameId,
+        player: PlayerId,
+        mv: ChessMove,
+    ) -> Result<(), ChessError> {
+        if Color::of_player(player) != self.request_position(game_id)?.turn {
+            return Err(ChessError::NotYourTurn);
+        }
+        self.make_move(game_id, mv)
+    }
+
+    // `player` offers their opponent a draw.
+    pub fn offer_draw(&mut self, game_id: GameId, player: PlayerId) -> Result<(), ChessError> {
+        self.game_mut(game_id)?.offer_draw(player)?;
+        let by = Color::of_player(player);
+        self.subscribers
+            .emit(game_id, EventKind::DrawOffered { by });
+        Ok(())
+    }
+
+    // `player` takes the draw their opponent offered, which ends the game.
+    pub fn accept_draw(&mut self, game_id: GameId, player: PlayerId) -> Result<(), ChessError> {
+        self.game_mut(game_id)?.accept_draw(player)?;
+        let outcome = Outcome::Agreement;
+        self.subscribers
+            .emit(game_id, EventKind::GameOver { outcome });
+        Ok(())
+    }
+
+    // Hands `color` a piece captured on the partner board of a Bughouse match.
+    pub fn receive(
+        &mut self,
+        game_id: GameId,
+        color: Color,
+        ty: types::Type,
+    ) -> Result<(), ChessError> {
+        self.game_mut(game_id)?.receive(color, ty)?;
+        self.subscribers
+            .emit(game_id, EventKind::PieceReceived { color, ty });
+        Ok(())
+    }
+
+    // Stops `game_id` without a result of its own, e.g. because the other
+    // board of its Bughouse match has finished. Its clock stops and further
+    // moves are refused.
+    pub fn halt_game(&mut self, game_id: GameId) -> Result<(), ChessError> {
+        self.game_mut(game_id)?.halt();
+        Ok(())
+    }
+
+    // Reads the running clocks of all games, telling subscribers the time left
+    // and ending the games whose flag fell. Call it as often as the clocks
+    // should be shown.
+    pub fn tick(&mut self) {
+        for (&game_id, chess) in self.sessions.iter_mut() {
+            let game = &mut chess.game;
+            if game.finished || game.clock.as_ref().and_then(ChessClock::running).is_none() {
+                continue;
+            }
+            if let Some(kind) = clock_tick(game) {
+                self.subscribers.emit(game_id, kind);
+            }
+            if let Some(outcome) = game.time_outcome() {
+                game.finished = true;
+                if let Some(clock) = game.clock.as_mut() {
+                    clock.stop();
+                }
+                self.subscribers
+                    .emit(game_id, EventKind::GameOver { outcome });
+            }
+        }
+    }
+
+    // Lets an external UCI engine choose and play the next move of `game_id`.
+    pub fn play_engine_move(
+        &mut self,
+        game_id: GameId,
+        engine: &mut UciEngine,
+        limits: &SearchLimits,
+    ) -> Result<ChessMove, ChessError> {
+        let chess = self.request_game_state(game_id)?;
+        let reply = engine
+            .go(&chess.game.hist, limits)
+            .map_err(|err| ChessError::Engine(format!("{err:#}")))?;
+        let mv = reply
+            .best
+            .ok_or_else(|| ChessError::Engine(format!("no move in game {game_id}")))?;
+        self.make_move(game_id, mv)?;
+        Ok(mv)
+    }
+
+    // Asks an external UCI engine for its opinion of the current position of `game_id`.
+    pub fn analyse(
+        &self,
+        game_id: GameId,
+        engine: &mut UciEngine,
+        limits: &SearchLimits,
+    ) -> Result<BestMove, ChessError> {
+        let chess = self.request_game_state(game_id)?;
+        engine
+            .go(&chess.game.hist, limits)
+            .map_err(|err| ChessError::Engine(format!("{err:#}")))
     }
 
     // A hack for reconstructing arbitrary game states; is useful in testing scenarios, or
@@ -95,50 +382,27 @@ impl<'gm, 'game> GameMaster<'gm, 'game> {
     //   on pawns or movement history relating to kings/rooks for castling;
     // - knows that [`History`]() will be replayed on top of the [`RawBoard`]() and will mutate it;
     // - knows that existing [`GameId`]'s will not be overridden;
-    // - knows to start timing threads elsewhere if p1_clock or p2_clock are not None;
-    // - has passed `Some(player_id)` to the active_player argument if started is `true` or either
-    //   of p1_clock or p2_clock are not None
+    // - has set the time left on `clock`; the active player's clock starts running;
+    // - has passed `Some(player_id)` to the active_player argument if started is `true` or
+    //   `clock` is not None
     pub fn try_init_arbitrary_game(
         &mut self,
         game_id: GameId,
         started: bool,
         active_player: Option<PlayerId>,
-        p1_clock: Option<u32>,
-        p2_clock: Option<u32>,
+        clock: Option<ChessClock>,
         board: RawBoard,
         hist: History,
-    ) -> Result<GameId> {
+    ) -> Result<GameId, ChessError> {
         if self.request_game_state(game_id).is_ok() {
-            anyhow::bail!("Game with {game_id} already exists. Not overriding it.");
+            return Err(ChessError::GameExists(game_id));
         }
-        match (active_player, p1_clock, p2_clock) {
-            (Some(_), None, None) | (Some(_), Some(_), Some(_)) | (None, None, None) => {
-                dbg!("Seems fine; I think this is the ideal happy path");
-            }
-            (Some(_), Some(_), None) => {
-                dbg!("Seems fine; need to take care upstream to set the None to default time");
-            }
-            (Some(_), None, Some(_)) => {
-                dbg!("Seems fine; need to take care upstream to set the None to default time");
-            }
-            (None, Some(_), Some(_)) => {
-                anyhow::bail!(
-                    "Cannot spawn game with time information without specifying active player"
-                );
-            }
-            (None, Some(_), None) | (None, None, Some(_)) => {
-                unreachable!("This is a bug. Please report it on Github.")
-            }
+        if active_player.is_none() && clock.is_some() {
+            return Err(ChessError::InvalidSetup {
+                reason: "a clock needs an active player".to_string(),
+            });
         }
-        let pregame = ChessGame::internal_new(
-            game_id,
-            started,
-            active_player,
-            p1_clock,
-            p2_clock,
-            board,
-            hist,
-        );
+        let pregame = ChessGame::internal_new(game_id, started, active_player, clock, board, hist);
         let game_id = loop {
             let game_id = (self.indexer)();
             if game_id == pregame.game_id + 1 {
@@ -152,7 +416,16 @@ impl<'gm, 'game> GameMaster<'gm, 'game> {
     }
 }
 
-#[derive(StandardChess, ChessFactory)]
+// The time left on a timed game's clocks.
+fn clock_tick(game: &GameState) -> Option<EventKind> {
+    let clock = game.clock.as_ref()?;
+    Some(EventKind::ClockTick {
+        white: clock.remaining(Color::White),
+        black: clock.remaining(Color::Black),
+    })
+}
+
+#[derive(StandardChess, FairyChess, ChessFactory)]
 pub struct ChessGame {
     pub game_id: u64,
     pub game: GameState,
@@ -160,19 +433,22 @@ pub struct ChessGame {
 
 // impl<'a, 'b> ChessGame<'a, 'b> {
 impl ChessGame {
-    pub fn try_apply_history(&mut self) -> Result<GameId> {
-        for action in self.game.hist.actions.clone() {
-            self.apply_action(action)?;
+    pub fn try_apply_history(&mut self) -> Result<GameId, ChessError> {
+        // Moves are replayed on top of the position they were played in.
+        let mut pos = self.game.hist.start();
+        for action in self.game.hist.actions().to_vec() {
+            self.apply_action(&pos, action.clone())?;
+            self.game.hist.replay(&mut pos, &action);
         }
         Ok(self.game_id)
     }
-    fn apply_action(&mut self, action: Action) -> Result<()> {
+    fn apply_action(&mut self, pos: &Position, action: Action) -> Result<(), ChessError> {
         match action {
             Action::Nil => Ok(()),
             Action::SetActivePlayer(pid) => {
-                // TODO:
-                // Start deducting from the remaining time
-                // on the associated player's clock
+                if let Some(clock) = self.game.clock.as_mut() {
+                    clock.start(Color::of_player(pid));
+                }
                 Ok(())
             }
             Action::FixPlayerData => {
@@ -183,42 +459,150 @@ impl ChessGame {
                 //   into Rc's,
                 Ok(())
             }
-            Action::Move(r#move) => {
-                // TODO:
-                // - apply the move to self.game.board
+            Action::Move(r#move) => self.game.apply_move(pos, &r#move),
+            Action::OfferDraw(_) | Action::AcceptDraw(_) => Ok(()),
+            Action::Receive(color, ty) => {
+                self.game.add_to_reserve(color, ty);
                 Ok(())
             }
         }
     }
+
+    // The rules-level view of the game, as its [`History`]() left it.
+    pub fn position(&self) -> Position {
+        self.game.hist.position()
+    }
+
+    // Plays `mv` if it is legal, updating the board, the pieces and the history.
+    // On a timed game the mover's clock stops and the opponent's starts; the
+    // first move starts the clock if nothing else did. A move made after the
+    // flag fell is refused and ends the game.
+    pub fn make_move(&mut self, mv: ChessMove) -> Result<(), ChessError> {
+        if self.game.finished {
+            return Err(ChessError::GameFinished);
+        }
+        self.check_geometry()?;
+        let pos = self.position();
+        if !self.game.legal_moves().contains(&mv) {
+            return Err(ChessError::IllegalMove {
+                reason: format!("{} in game {}", pos.move_to_uci(&mv), self.game_id),
+            });
+        }
+        if self.game.time_outcome().is_some() {
+            self.game.finished = true;
+            if let Some(clock) = self.game.clock.as_mut() {
+                clock.stop();
+            }
+            return Err(ChessError::GameFinished);
+        }
+        if let Some(clock) = self.game.clock.as_mut() {
+            if clock.running() != Some(pos.turn) {
+                clock.start(pos.turn);
+            }
+            clock.press()?;
+        }
+        self.game.apply_move(&pos, &mv)?;
+        self.game.hist.push(Action::Move(mv));
+        self.game.started = true;
+        self.game.finished = self.game.rules_outcome().is_some();
+        if self.game.finished {
+            if let Some(clock) = self.game.clock.as_mut() {
+                clock.stop();
+            }
+        }
+        Ok(())
+    }
+
+    // Offers the opponent of `player` a draw, which stands until they accept
+    // it or make a move.
+    pub fn offer_draw(&mut self, player: PlayerId) -> Result<(), ChessError> {
+        if self.game.finished {
+            return Err(ChessError::GameFinished);
+        }
+        self.game.hist.push(Action::OfferDraw(player));
+        Ok(())
+    }
+
+    // Puts a piece captured on the partner board of a Bughouse match into
+    // `color`'s reserve.
+    pub fn receive(&mut self, color: Color, ty: types::Type) -> Result<(), ChessError> {
+        if self.game.finished {
+            return Err(ChessError::GameFinished);
+        }
+        if self.game.mode() != GameMode::Bughouse {
+            return Err(ChessError::InvalidSetup {
+                reason: "only Bughouse games receive pieces".to_string(),
+            });
+        }
+        self.game.hist.push(Action::Receive(color, ty));
+        self.game.add_to_reserve(color, ty);
+        Ok(())
+    }
+
+    // The rules only know the orthodox pieces on the standard board.
+    fn check_geometry(&self) -> Result<(), ChessError> {
+        let fairy = [&self.game.p1, &self.game.p2]
+            .into_iter()
+            .flat_map(|player| &player.pieces)
+            .any(|piece| matches!(piece.borrow().ty, types::Type::Fairy(_)));
+        match self.game.geometry {
+            _ if fairy => Err(ChessError::Unsupported(
+                "Rules for fairy pieces".to_string(),
+            )),
+            Geometry::STANDARD => Ok(()),
+            other => Err(ChessError::Unsupported(format!("Rules on a {other} board"))),
+        }
+    }
+
+    // Ends the game where it stands, without an outcome of its own.
+    pub fn halt(&mut self) {
+        self.game.finished = true;
+        if let Some(clock) = self.game.clock.as_mut() {
+            clock.stop();
+        }
+    }
+
+    pub fn accept_draw(&mut self, player: PlayerId) -> Result<(), ChessError> {
+        if self.game.finished {
+            return Err(ChessError::GameFinished);
+        }
+        if self.game.hist.draw_offer() != Some(!player) {
+            return Err(ChessError::NoDrawOffer);
+        }
+        self.game.hist.push(Action::AcceptDraw(player));
+        self.game.finished = true;
+        if let Some(clock) = self.game.clock.as_mut() {
+            clock.stop();
+        }
+        Ok(())
+    }
     pub fn internal_new(
         game_id: GameId,
         started: bool,
         active_player: Option<PlayerId>,
-        p1_clock: Option<u32>,
-        p2_clock: Option<u32>,
+        clock: Option<ChessClock>,
         board: RawBoard,
         hist: History,
     ) -> Self {
-        let mut new_hist = if hist.actions[0] != Action::FixPlayerData {
+        let mut new_hist = if hist.actions()[0] != Action::FixPlayerData {
             let mut new_hist = History::init("ChessGame::internal_new->new_history");
-            let _ = &mut new_hist.actions.push(Action::FixPlayerData);
+            new_hist.push(Action::FixPlayerData);
             new_hist
         } else {
             History::init("ChessGame::internal_new->new_history_without_fix_player_data")
         };
-        for action in hist.actions {
-            let _ = &mut new_hist.actions.push(action);
+        for action in hist.actions() {
+            new_hist.push(action.clone());
         }
         if let Some(player_id) = active_player {
-            let _ = &mut new_hist.actions.push(Action::SetActivePlayer(player_id));
+            new_hist.push(Action::SetActivePlayer(player_id));
         }
         Self {
             game_id,
             game: GameState::init(
                 started,
                 /* finished: */ false,
-                p1_clock,
-                p2_clock,
+                clock,
                 /* p1: */ PlayerData::incomplete_init(types::Color::White, None, None),
                 /* p2: */ PlayerData::incomplete_init(types::Color::Black, None, None),
                 board,
@@ -227,11 +611,11 @@ impl ChessGame {
         }
     }
     #[allow(non_upper_case_globals)]
-    pub fn new(game_id: u64) -> Result<Self> {
+    pub fn new(game_id: u64) -> Result<Self, ChessError> {
         use crate::{game::add_piece, helper::chess_board};
 
         let (started, finished): (bool, bool) = (false, false);
-        let (p1_clock, p2_clock): (Option<u32>, Option<u32>) = (Some(5400000), Some(5400000));
+        let clock = ChessClock::new(TimeControl::sudden_death(Duration::from_secs(90 * 60)));
 
         let mut p1 = PlayerData::new_white_player();
         let mut p2 = PlayerData::new_black_player();
@@ -257,12 +641,86 @@ impl ChessGame {
         }
 
         let hist = History::init(format!("History of Game {}", &stringify!(&game_id)));
-        let game = GameState::init(started, finished, p1_clock, p2_clock, p1, p2, board, hist);
+        let game = GameState::init(started, finished, Some(clock), p1, p2, board, hist);
 
         Ok(Self { game_id, game })
     }
 
-    fn request_vision(&self, piece_id: PieceId) -> Result<VisionPiece> {
+    // A board of `geometry` without any pieces on it, as yet untimed.
+    pub fn empty(game_id: GameId, geometry: Geometry) -> Self {
+        let hist = History::init_from(format!("History of Game {game_id}"), Position::empty());
+        let p1 = PlayerData::with_capacity(Color::White, 0);
+        let p2 = PlayerData::with_capacity(Color::Black, 0);
+        let mut game = GameState::init(false, false, None, p1, p2, geometry.board(), hist);
+        game.geometry = geometry;
+        Self { game_id, game }
+    }
+
+    // The start of `variant`, numbered like [`Self::from_position`]().
+    pub fn fairy(game_id: GameId, variant: &FairyVariant) -> Result<Self, ChessError> {
+        use crate::game::add_piece;
+
+        let start = variant.start();
+        let white = <Self as FairyChess>::gen_fairy_white(start);
+        let black = <Self as FairyChess>::gen_fairy_black(start);
+        let mut p1 = PlayerData::with_capacity(Color::White, white.len());
+        let mut p2 = PlayerData::with_capacity(Color::Black, black.len());
+        let mut board = variant.geometry.board();
+        for piece in white {
+            add_piece(&mut board, piece.loc, &mut p1, piece)?;
+        }
+        for piece in black {
+            add_piece(&mut board, piece.loc, &mut p2, piece)?;
+        }
+        let hist = History::init_from(format!("History of Game {game_id}"), Position::empty());
+        let mut game = GameState::init(false, false, None, p1, p2, board, hist);
+        game.geometry = variant.geometry;
+        Ok(Self { game_id, game })
+    }
+
+    // Sets up the pieces of `start` on a fresh board. White's pieces are numbered
+    // from 1 upwards and black's from -1 downwards, in board order.
+    pub fn from_position(game_id: u64, start: Position) -> Result<Self, ChessError> {
+        use crate::{game::add_piece, helper::chess_board};
+
+        let mut p1 = PlayerData::with_capacity(Color::White, start.pieces(Color::White).count());
+        let mut p2 = PlayerData::with_capacity(Color::Black, start.pieces(Color::Black).count());
+        let mut board = chess_board();
+        let (mut white_id, mut black_id): (PieceId, PieceId) = (0, 0);
+        for (loc, man) in start.board.iter().enumerate() {
+            let Some(man) = man else { continue };
+            let (player, id) = match man.color {
+                types::Color::White => {
+                    white_id += 1;
+                    (&mut p1, white_id)
+                }
+                types::Color::Black => {
+                    black_id -= 1;
+                    (&mut p2, black_id)
+                }
+            };
+            let piece = types::Piece {
+                id,
+                color: man.color,
+                ty: man.ty,
+                loc,
+            };
+            add_piece(&mut board, loc, player, piece)?;
+        }
+
+        let hist = History::init_from(format!("History of Game {game_id}"), start);
+        let mut game = GameState::init(false, false, None, p1, p2, board, hist);
+        if let Some(reserves) = start.reserves {
+            game.hist.set_mode(GameMode::Crazyhouse);
+            game.fill_reserves(&reserves);
+        }
+        if start.checks.is_some() {
+            game.hist.set_mode(GameMode::ThreeCheck);
+        }
+        Ok(Self { game_id, game })
+    }
+
+    fn request_vision(&self, piece_id: PieceId) -> Result<VisionPiece, ChessError> {
         // First thing we're going to do is ask our GameState for a
         // reference to the piece corresponding to the PieceId we specify
         use crate::types::Piece;
@@ -271,22 +729,16 @@ impl ChessGame {
         if let Some(rc) = piece {
             self.game.calculate_vision(rc, &self.game.board)
         } else {
-            Err(anyhow::anyhow!("Piece not found: {piece_id}"))
+            Err(ChessError::PieceNotFound(piece_id))
         }
     }
-
-    fn request_game_layout(&self) -> Layout {
-        let layout = Layout::generate(&self.game);
-        dbg!("{layout:?}");
-        layout
-    }
 }
 
 #[test]
 fn new_game_has_32_pieces() {
     let mut gm = spawn_game_master();
     let game_id = gm.create_game().unwrap();
-    let game: Result<&'_ ChessGame> = gm.request_game_state(game_id);
+    let game: Result<&'_ ChessGame, ChessError> = gm.request_game_state(game_id);
     assert!(&game.is_ok(), "Failed to create a game");
     {
         let mut count: usize = 0;
@@ -302,10 +754,10 @@ fn new_game_has_32_pieces() {
 fn new_standard_game_has_64_tiles() {
     let mut gm = spawn_game_master();
     let game_id = gm.create_game().unwrap();
-    let res: Result<Layout> = gm.request_game_layout(game_id);
+    let res: Result<BoardSnapshot, ChessError> = gm.request_snapshot(game_id);
     match res {
-        Ok(layout) => {
-            assert_eq!(layout.data.len(), 64);
+        Ok(snapshot) => {
+            assert_eq!(snapshot.tile_count(), 64);
         }
         Err(error) => {
             eprintln!("{error:?}");
@@ -313,9 +765,198 @@ fn new_standard_game_has_64_tiles() {
     }
 }
 
+#[test]
+fn empty_games_come_in_any_size() {
+    let mut gm = spawn_game_master();
+    let game_id = gm.create_empty_game(Geometry::CAPABLANCA).unwrap();
+    let snapshot = gm.request_snapshot(game_id).unwrap();
+    assert_eq!(snapshot.geometry, Geometry::CAPABLANCA);
+    assert_eq!(snapshot.tile_count(), 80);
+    assert_eq!(snapshot.pieces().count(), 0);
+    let (j8, xy, color, _) = snapshot.tiles().last().unwrap();
+    assert_eq!((j8, xy), (79, (9, 7).into()));
+    assert!(snapshot.geometry.is_w_endzone(j8));
+    assert_eq!(color, types::Background::Dark);
+    let mv = ChessMove::new(constants::E2, constants::E4);
+    assert!(matches!(
+        gm.make_move(game_id, mv),
+        Err(ChessError::Unsupported(_))
+    ));
+}
+
+#[test]
+fn fairy_games_lay_out_their_pieces() {
+    use crate::fairy::FairyVariant;
+    let mut gm = spawn_game_master();
+    let game_id = gm.create_fairy_game(&FairyVariant::capablanca()).unwrap();
+    let snapshot = gm.request_snapshot(game_id).unwrap();
+    assert_eq!(snapshot.geometry, Geometry::CAPABLANCA);
+    let piece_at = |x: usize, y: usize| {
+        let idx = snapshot.geometry.xy_to_index((x, y).into()).unwrap();
+        let piece = snapshot.at(idx).unwrap();
+        (piece.color, piece.ty, piece.id)
+    };
+    assert_eq!(piece_at(2, 0), (Color::White, types::Type::Fairy('A'), 3));
+    assert_eq!(piece_at(7, 7), (Color::Black, types::Type::Fairy('C'), -18));
+    assert_eq!(piece_at(9, 7), (Color::Black, types::Type::Rook, -20));
+    let mv = ChessMove::new(constants::E2, constants::E4);
+    assert!(matches!(
+        gm.make_move(game_id, mv),
+        Err(ChessError::Unsupported(_))
+    ));
+}
+
+#[test]
+fn fog_of_war_hides_the_enemy_until_the_king_falls() {
+    use crate::position::Outcome;
+    let mut gm = spawn_game_master();
+    let game_id = gm.create_game().unwrap();
+    gm.set_mode(game_id, GameMode::FogOfWar).unwrap();
+
+    let white = gm.request_player_snapshot(game_id, false).unwrap();
+    assert_eq!(white.tile_count(), 64);
+    assert_eq!(white.hidden().count(), 32);
+    assert_eq!(white.pieces().count(), 16);
+    let e7 = constants::E7;
+    assert!(white.is_hidden(e7) && white.at(e7).is_none());
+    let spectator = gm.request_snapshot(game_id).unwrap();
+    assert!(spectator.hidden().next().is_none() && spectator.at(e7).is_some());
+
+    // Fool's mate, except that nothing stops white from ignoring the check.
+    for mv in ["f2f3", "e7e5", "g2g4", "d8h4", "a2a3", "h4e1"] {
+        let pos = gm.request_position(game_id).unwrap();
+        let mv = pos
+            .fog_moves()
+            .into_iter()
+            .find(|m| m.to_string() == mv)
+            .unwrap();
+        gm.make_move(game_id, mv).unwrap();
+    }
+    let state = gm.request_game_state(game_id).unwrap();
+    assert!(state.game.finished);
+    assert_eq!(
+        state.game.outcome(),
+        Some(Outcome::KingCaptured {
+            winner: Color::Black
+        })
+    );
+    assert!(gm.set_mode(game_id, GameMode::Standard).is_err());
+}
+
+#[test]
+fn crazyhouse_captures_change_sides_and_drop_back_in() {
+    let mut gm = spawn_game_master();
+    let game_id = gm.create_game().unwrap();
+    gm.set_mode(game_id, GameMode::Crazyhouse).unwrap();
+    for mv in ["e2e4", "d7d5", "e4d5", "d8d5", "P@d3"] {
+        let pos = gm.request_position(game_id).unwrap();
+        gm.make_move(game_id, pos.parse_uci(mv).unwrap()).unwrap();
+    }
+    let state = gm.request_game_state(game_id).unwrap();
+    let (white, black) = (&state.game.p1, &state.game.p2);
+    assert!(white.reserve.is_empty());
+    assert_eq!(white.pieces.len(), 16);
+    let dropped = state.game.board[constants::D3].pz.as_ref().unwrap();
+    let dropped = dropped.upgrade().unwrap();
+    assert_eq!(dropped.borrow().color, Color::White);
+    assert_eq!(dropped.borrow().id, 17);
+    assert_eq!(black.reserve.len(), 1);
+    let pawn = black.reserve[0].borrow();
+    assert_eq!(
+        (pawn.color, pawn.ty, pawn.id),
+        (Color::Black, types::Type::Pawn, -17)
+    );
+    assert_eq!(
+        gm.request_position(game_id).unwrap().to_fen(),
+        "rnb1kbnr/ppp1pppp/8/3q4/8/3P4/PPPP1PPP/RNBQKBNR[p] b KQkq - 0 3"
+    );
+}
+
+#[test]
+fn atomic_explosions_take_pieces_off_the_board() {
+    let mut gm = spawn_game_master();
+    let game_id = gm.create_game().unwrap();
+    gm.set_mode(game_id, GameMode::Atomic).unwrap();
+    for mv in ["g1f3", "d7d5", "f3e5", "h7h6", "e5f7"] {
+        let pos = gm.request_position(game_id).unwrap();
+        gm.make_move(game_id, pos.parse_uci(mv).unwrap()).unwrap();
+    }
+    // Nxf7 blows up the knight, the pawn, and the king, bishop and knight
+    // behind it.
+    let state = gm.request_game_state(game_id).unwrap();
+    assert!(state.game.finished);
+    assert_eq!(
+        state.game.outcome(),
+        Some(Outcome::KingExploded {
+            winner: Color::White
+        })
+    );
+    assert_eq!(state.game.p1.pieces.len(), 15);
+    assert_eq!(state.game.p2.pieces.len(), 12);
+    for idx in [constants::E8, constants::F7, constants::F8, constants::G8] {
+        assert!(state.game.board[idx].pz.is_none());
+    }
+    assert_eq!(
+        gm.request_position(game_id).unwrap().to_fen(),
+        "rnbq3r/ppp1p1p1/7p/3p4/8/8/PPPPPPPP/RNBQKB1R b KQ - 0 3"
+    );
+}
+
+#[test]
+fn attack_maps_follow_the_game() {
+    let mut gm = spawn_game_master();
+    let game_id = gm.create_game().unwrap();
+    let pos = gm.request_position(game_id).unwrap();
+    gm.make_move(game_id, pos.parse_uci("e2e4").unwrap())
+        .unwrap();
+    // The pawn has opened the queen's diagonal.
+    let white = gm.request_attack_map(game_id, Color::White).unwrap();
+    assert!(white.attackers(square::Square::H5).eq([square::Square::D1]));
+    let black = gm.request_attack_map(game_id, Color::Black).unwrap();
+    assert!(!black.is_attacked(square::Square::E4));
+    assert!(gm.request_attack_map(game_id + 1, Color::White).is_err());
+}
+
+#[test]
+fn racing_kings_sets_up_its_own_start() {
+    let mut gm = spawn_game_master();
+    let game_id = gm.create_game().unwrap();
+    gm.set_mode(game_id, GameMode::RacingKings).unwrap();
+    let state = gm.request_game_state(game_id).unwrap();
+    assert_eq!(state.game.p1.pieces.len(), 8);
+    assert_eq!(state.game.p2.pieces.len(), 8);
+    let king = state.game.board[constants::H2].pz.as_ref().unwrap();
+    assert_eq!(king.upgrade().unwrap().borrow().ty, types::Type::King);
+    assert!(state.game.board[constants::E4].pz.is_none());
+    // Checking the black king with Nc3 is not allowed.
+    let pos = gm.request_position(game_id).unwrap();
+    let check = ChessMove::new(constants::E2, constants::C3);
+    assert!(gm.make_move(game_id, check).is_err());
+    assert_eq!(pos.to_fen(), crate::variant::racing_kings::RACING_KINGS_FEN);
+}
+
+#[test]
+fn horde_gives_every_white_pawn_its_own_id() {
+    let mut gm = spawn_game_master();
+    let game_id = gm.create_game().unwrap();
+    gm.set_mode(game_id, GameMode::Horde).unwrap();
+    let state = gm.request_game_state(game_id).unwrap();
+    assert_eq!(state.game.p1.pieces.len(), 36);
+    assert_eq!(state.game.p2.pieces.len(), 16);
+    for piece_id in 1..=36 {
+        let pawn = state.game.piece_by_id(&piece_id).unwrap();
+        assert_eq!(pawn.borrow().ty, types::Type::Pawn);
+    }
+    assert!(state.game.piece_by_id(&37).is_none());
+    assert!(state.game.piece_by_id(&-16).is_some());
+    let pos = gm.request_position(game_id).unwrap();
+    gm.make_move(game_id, pos.parse_uci("b5b6").unwrap())
+        .unwrap();
+}
+
 #[test]
 fn opening_white_pawn_mvmt() {
-    use crate::game::math::{self, XyPair};
+    use crate::game::math::XyPair;
     use crate::types::Piece;
     use std::cell::RefCell;
     use std::collections::HashSet;
@@ -326,7 +967,7 @@ fn opening_white_pawn_mvmt() {
     let state = gm.request_game_state(game_id).unwrap();
     // From left to right, the white pawns have the IDs 9 to 16 inclusive
     for piece_id in 9..=16 {
-        let vision_options: Result<VisionPiece> = gm.request_vision(game_id, piece_id);
+        let vision_options: Result<VisionPiece, ChessError> = gm.request_vision(game_id, piece_id);
         assert!(
             vision_options.is_ok(),
             "Test uses PieceId's which do not exist"
@@ -337,7 +978,7 @@ fn opening_white_pawn_mvmt() {
         // moving a single space forward, and moving two spaces forward
         let ops = vision_options.unwrap();
         let pz: Rc<RefCell<Piece>> = state.game.piece_by_id(&ops.piece_id).unwrap();
-        let now: XyPair = math::index_to_xy(((*pz.clone()).borrow()).loc);
+        let now: XyPair = (*pz).borrow().square().unwrap().xy();
         let viable: HashSet<XyPair> = HashSet::from([
             now.clone(),
             XyPair {
@@ -349,21 +990,56 @@ fn opening_white_pawn_mvmt() {
                 y: now.clone().y + 2,
             },
         ]);
-        // We expect that two non-staying movements should result in a `.dest()` call
-        // that matches Y + 1 and Y + 2 from the current coordinate.
-        //
-        // Note we only check the first three moves because the remaining 22 are None
-        for mvmt in &ops.moves[0..3] {
-            let xy = mvmt.as_ref().unwrap().dest();
-            assert!(viable.contains(&xy), "Impossible movement option found");
+        // We expect that two non-staying movements should look at Y + 1 and
+        // Y + 2 from the current coordinate.
+        assert_eq!(ops.moves.len(), 3);
+        for mvmt in &ops.moves {
+            assert_eq!(mvmt.from, now);
+            assert!(
+                viable.contains(&mvmt.to),
+                "Impossible movement option found"
+            );
+            assert!(!mvmt.capture);
         }
     }
 }
 
-#[ignore = "until pawn movement stabilized"]
 #[test]
 fn opening_black_pawn_mvmt() {
-    todo!("Black pawns should relatively move forwards, but their destination tile's index must be less than their starting index.");
+    use crate::game::math::XyPair;
+    use crate::types::Type;
+
+    let mut gm = spawn_game_master();
+    let game_id = gm.create_game().unwrap();
+    let state = gm.request_game_state(game_id).unwrap();
+    let pawns: Vec<PieceId> = state
+        .game
+        .player(Color::Black)
+        .pieces
+        .iter()
+        .map(|pz| (*pz).borrow().clone())
+        .filter(|pz| pz.ty == Type::Pawn)
+        .map(|pz| pz.id)
+        .collect();
+    assert_eq!(pawns.len(), 8);
+    // Black pawns move forwards as Black sees the board, which is down it:
+    // their destination tile's index is less than their starting index.
+    for piece_id in pawns {
+        let ops = gm.request_vision(game_id, piece_id).unwrap();
+        let from = state.game.piece_by_id(&piece_id).unwrap();
+        let from = (*from).borrow().square().unwrap();
+        let dests: Vec<XyPair> = ops.moves.iter().map(|mvmt| mvmt.to).collect();
+        let expected: Vec<XyPair> = [
+            Some(from),
+            from.forward(Color::Black, 1),
+            from.forward(Color::Black, 2),
+        ]
+        .into_iter()
+        .map(|sq| sq.unwrap().xy())
+        .collect();
+        assert_eq!(dests, expected);
+        assert!(dests[1].y < dests[0].y && dests[2].y < dests[1].y);
+    }
 }
 
 #[ignore = "until pawn movement stabilized"]
@@ -612,13 +1288,27 @@ fn allow_forfeit_before_checkmate() {
     todo!("Players can concede the game");
 }
 
-#[ignore = "Future"]
 #[test]
 fn request_tie_from_opponent() {
-    todo!(
-        "Either player can request a draw from the opponent \
-    such that agreeing ends the game."
-    );
+    let mut gm = spawn_game_master();
+    let game_id = gm.create_game().unwrap();
+    let start = gm.request_position(game_id).unwrap();
+    gm.offer_draw(game_id, false).unwrap();
+    // White may move after offering; the offer stands for black.
+    gm.make_move(game_id, start.parse_uci("e2e4").unwrap())
+        .unwrap();
+    assert_eq!(gm.accept_draw(game_id, false), Err(ChessError::NoDrawOffer));
+    gm.accept_draw(game_id, true).unwrap();
+    let state = gm.request_game_state(game_id).unwrap();
+    assert!(state.game.finished);
+    assert_eq!(state.game.outcome(), Some(Outcome::Agreement));
+
+    // Replying with a move declines the offer.
+    let game_id = gm.create_game().unwrap();
+    gm.offer_draw(game_id, true).unwrap();
+    gm.make_move(game_id, start.parse_uci("d2d4").unwrap())
+        .unwrap();
+    assert_eq!(gm.accept_draw(game_id, false), Err(ChessError::NoDrawOffer));
 }
 
 #[ignore = "Future"]
@@ -639,22 +1329,112 @@ fn checkmate_concessions_and_drawing_disable_further_movement() {
     todo!("... as well as freeze the clocks");
 }
 
-#[ignore = "Future"]
+#[test]
+fn moves_fail_with_typed_errors() {
+    let mut gm = spawn_game_master();
+    let game_id = gm.create_game().unwrap();
+    let start = gm.request_position(game_id).unwrap();
+    let e4 = start.parse_uci("e2e4").unwrap();
+    let e5 = ChessMove {
+        from: 12,
+        to: 36,
+        promotion: None,
+    };
+    let err = gm.make_move(game_id, e5).unwrap_err();
+    assert_eq!(err.code(), "illegal_move");
+    assert_eq!(
+        gm.make_player_move(game_id, true, e4),
+        Err(ChessError::NotYourTurn)
+    );
+    gm.make_player_move(game_id, false, e4).unwrap();
+    assert_eq!(
+        gm.make_move(game_id + 1000, e4),
+        Err(ChessError::GameNotFound(game_id + 1000))
+    );
+    assert!(matches!(
+        gm.request_vision(game_id, 99),
+        Err(ChessError::PieceNotFound(99))
+    ));
+}
+
+#[cfg(test)]
+fn timed_game(control: TimeControl) -> (GameMaster<'static, 'static>, GameId, clock::ManualTime) {
+    let time = clock::ManualTime::default();
+    let mut gm = spawn_game_master();
+    let game_id = gm.create_game().unwrap();
+    let mut clock = ChessClock::with_source(control, std::sync::Arc::new(time.clone()));
+    clock.start(types::Color::White);
+    gm.set_clock(game_id, Some(clock)).unwrap();
+    (gm, game_id, time)
+}
+
 #[test]
 fn time_elapsing_mutates_active_players_clock_only() {
-    todo!("Use concurrent synchronization to accurately decrement a player's chess clock");
+    use types::Color::{Black, White};
+    let (mut gm, game_id, time) = timed_game(TimeControl::fischer(
+        Duration::from_secs(300),
+        Duration::from_secs(2),
+    ));
+    let start = gm.request_position(game_id).unwrap();
+    time.advance(Duration::from_secs(10));
+    gm.make_move(game_id, start.parse_uci("e2e4").unwrap())
+        .unwrap();
+    time.advance(Duration::from_secs(4));
+    let state = gm.request_game_state(game_id).unwrap();
+    let clock = state.game.clock.as_ref().unwrap();
+    assert_eq!(clock.running(), Some(Black));
+    assert_eq!(clock.remaining(White), Duration::from_secs(292));
+    assert_eq!(clock.remaining(Black), Duration::from_secs(296));
 }
 
-#[ignore = "Future"]
 #[test]
 fn pausing_game_suspends_all_clocks() {
-    todo!("Verify that time remaining on clocks remains the same at the start and end of pausing");
+    use types::Color::{Black, White};
+    let (mut gm, game_id, time) = timed_game(TimeControl::sudden_death(Duration::from_secs(60)));
+    time.advance(Duration::from_secs(5));
+    let clock = gm.game_mut(game_id).unwrap().game.clock.as_mut().unwrap();
+    clock.stop();
+    time.advance(Duration::from_secs(30));
+    assert_eq!(clock.remaining(White), Duration::from_secs(55));
+    assert_eq!(clock.remaining(Black), Duration::from_secs(60));
+    // Handing the turn back to white resumes the clock where it stopped.
+    let chess = gm.game_mut(game_id).unwrap();
+    chess
+        .apply_action(&Position::startpos(), Action::SetActivePlayer(false))
+        .unwrap();
+    time.advance(Duration::from_secs(5));
+    let clock = chess.game.clock.as_ref().unwrap();
+    assert_eq!(clock.remaining(White), Duration::from_secs(50));
 }
 
-#[ignore = "Future"]
 #[test]
 fn running_out_of_time_triggers_forfeit() {
-    todo!("When a player's clock reaches zero, submit a forfeit action and do not accept more movement submissions");
+    let (mut gm, game_id, time) = timed_game(TimeControl::sudden_death(Duration::from_secs(60)));
+    let start = gm.request_position(game_id).unwrap();
+    let e4 = start.parse_uci("e2e4").unwrap();
+    time.advance(Duration::from_secs(61));
+    let state = gm.request_game_state(game_id).unwrap();
+    assert_eq!(
+        state.game.outcome(),
+        Some(Outcome::TimeForfeit {
+            winner: types::Color::Black
+        })
+    );
+    assert_eq!(gm.make_move(game_id, e4), Err(ChessError::GameFinished));
+    let state = gm.request_game_state(game_id).unwrap();
+    assert!(state.game.finished);
+    assert!(state.game.hist.moves().next().is_none());
+
+    // Against a bare king the flag only costs half a point.
+    let (mut gm, game_id, time) = timed_game(TimeControl::sudden_death(Duration::from_secs(60)));
+    let bare = Position::from_fen("8/8/4k3/8/8/8/3QK3/8 w - - 0 1").unwrap();
+    let chess = gm.game_mut(game_id).unwrap();
+    chess.game.hist = History::init_from("bare king", bare);
+    time.advance(Duration::from_secs(60));
+    assert_eq!(
+        chess.game.outcome(),
+        Some(Outcome::TimeoutVsInsufficientMaterial)
+    );
 }
 
 #[ignore = "Future"]
@@ -1,12 +1,16 @@
 #[allow(unused_imports)]
-use crate::{constants, game::GameState, helper, traits, types};
+use crate::{constants, error::ChessError, game::GameState, helper, traits, types};
 // use serde::{Deserialize, Serialize};
 
 pub type GameId = u64;
 
-// Positively valued integers are black (like the stock market).
-// Negatively valued ones are white.
+// White pieces are numbered from 1 upwards and black ones from -1 downwards;
+// 0 is never used. There is no upper bound, since variants such as Horde field
+// more than 16 pieces and Crazyhouse keeps handing out new ones.
 pub type PieceId = i16;
+// A tile numbered rank by rank from a1 on a board of any [`Geometry`](),
+// beyond h8 on boards larger than 8x8. Whatever only ever sees the 8x8 board
+// takes a [`Square`](crate::square::Square) instead.
 pub type TileId = usize;
 pub type MoveOp = usize;
 pub type Class = usize;
@@ -56,4 +60,11 @@ pub(crate) enum Response {
     RenderUpdate,
     GameCreated(GameId),
     GameState(GameState),
+    Error(ChessError),
+}
+
+impl From<ChessError> for Response {
+    fn from(err: ChessError) -> Self {
+        Response::Error(err)
+    }
 }
@@ -0,0 +1,470 @@
+//! chess_core::pgn
+//!
+//! Portable Game Notation. A [`PgnGame`] is a tag section plus the moves of
+//! one game from a given start position; its [`std::fmt::Display`]
+//! implementation writes the export format, with the Seven Tag Roster first
+//! and the movetext wrapped at 80 columns. [`parse_games`] reads collections
+//! back, keeping the main line and skipping comments, NAGs and variations.
+
+use crate::game::History;
+use crate::position::{ChessMove, Position};
+use crate::types::Color;
+use anyhow::{bail, Context, Result};
+use std::fmt;
+
+// The tags every PGN game carries, in the order they have to appear.
+pub const SEVEN_TAG_ROSTER: [&str; 7] =
+    ["Event", "Site", "Date", "Round", "White", "Black", "Result"];
+
+const LINE_WIDTH: usize = 80;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PgnGame {
+    pub tags: Vec<(String, String)>,
+    pub start: Position,
+    pub moves: Vec<ChessMove>,
+    // `1-0`, `0-1`, `1/2-1/2` or `*` for a game still in progress.
+    pub result: String,
+    // Written as a comment right before the result, e.g. `{White loses on time}`.
+    pub comment: Option<String>,
+}
+
+impl Default for PgnGame {
+    fn default() -> Self {
+        Self::new(Position::startpos())
+    }
+}
+
+impl PgnGame {
+    pub fn new(start: Position) -> Self {
+        Self {
+            tags: Vec::new(),
+            start,
+            moves: Vec::new(),
+            result: "*".to_string(),
+            comment: None,
+        }
+    }
+
+    // The moves of `hist`, with the result filled in if the game is over.
+    pub fn from_history(hist: &History) -> Self {
+        let mut game = Self::new(hist.start());
+        game.moves = hist.moves().copied().collect();
+        if let Some(outcome) = hist.outcome() {
+            game.result = outcome.result().to_string();
+        }
+        game
+    }
+
+    pub fn tag(&self, name: &str) -> Option<&str> {
+        self.tags
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    // Sets `name` to `value`, replacing an earlier value of the same tag.
+    pub fn set_tag(&mut self, name: impl Into<String>, value: impl Into<String>) {
+        let (name, value) = (name.into(), value.into());
+        match self.tags.iter_mut().find(|(key, _)| *key == name) {
+            Some(tag) => tag.1 = value,
+            None => self.tags.push((name, value)),
+        }
+    }
+
+    // The position after the last move.
+    pub fn position(&self) -> Position {
+        let mut pos = self.start;
+        for mv in &self.moves {
+            pos.make_move(mv);
+        }
+        pos
+    }
+
+    // Reads exactly one game.
+    pub fn parse(text: &str) -> Result<Self> {
+        let mut games = parse_games(text).into_iter();
+        match (games.next(), games.next()) {
+            (Some(game), None) => game,
+            (None, _) => bail!("No game found"),
+            (Some(_), Some(_)) => bail!("More than one game found"),
+        }
+    }
+
+    fn movetext(&self) -> Vec<String> {
+        let mut tokens = Vec::with_capacity(self.moves.len() * 3 / 2 + 2);
+        let mut pos = self.start;
+        for (ply, mv) in self.moves.iter().enumerate() {
+            match pos.turn {
+                Color::White => tokens.push(format!("{}.", pos.fullmoves)),
+                Color::Black if ply == 0 => tokens.push(format!("{}...", pos.fullmoves)),
+                Color::Black => {}
+            }
+            tokens.push(pos.move_to_san(mv));
+            pos.make_move(mv);
+        }
+        if let Some(comment) = &self.comment {
+            tokens.push(format!("{{{comment}}}"));
+        }
+        tokens.push(self.result.clone());
+        tokens
+    }
+}
+
+// Reads every game of a PGN collection. A game that does not parse comes
+// back as an error without affecting the games around it.
+pub fn parse_games(text: &str) -> Vec<Result<PgnGame>> {
+    let mut reader = Reader::default();
+    let mut games = Vec::new();
+    for token in Tokens::new(text) {
+        if let Some(game) = reader.feed(token) {
+            games.push(game);
+        }
+    }
+    games.extend(reader.finish());
+    games
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token<'a> {
+    Tag(&'a str, String),
+    Comment(&'a str),
+    VariationStart,
+    VariationEnd,
+    // A SAN move, a move number, a NAG or a result; sorted out by [`Reader`]().
+    Word(&'a str),
+}
+
+struct Tokens<'a> {
+    text: &'a str,
+    at_line_start: bool,
+}
+
+impl<'a> Tokens<'a> {
+    fn new(text: &'a str) -> Self {
+        Self {
+            text,
+            at_line_start: true,
+        }
+    }
+}
+
+impl<'a> Iterator for Tokens<'a> {
+    type Item = Token<'a>;
+
+    fn next(&mut self) -> Option<Token<'a>> {
+        loop {
+            let trimmed = self.text.trim_start_matches([' ', '\t', '\r']);
+            let skipped_newline = trimmed.starts_with('\n');
+            self.text = trimmed;
+            if skipped_newline {
+                self.text = &self.text[1..];
+                self.at_line_start = true;
+                continue;
+            }
+            let mut chars = self.text.chars();
+            let first = chars.next()?;
+            let line_start = std::mem::replace(&mut self.at_line_start, false);
+            // Escaped lines and rest-of-line comments.
+            if first == ';' || (first == '%' && line_start) {
+                let end = self.text.find('\n').unwrap_or(self.text.len());
+                self.text = &self.text[end..];
+                continue;
+            }
+            let (token, len) = match first {
+                '{' => {
+                    let end = self.text.find('}').unwrap_or(self.text.len());
+                    (Token::Comment(self.text[1..end].trim()), end + 1)
+                }
+                '(' => (Token::VariationStart, 1),
+                ')' => (Token::VariationEnd, 1),
+                '[' => {
+                    let end = self.text.find(']').unwrap_or(self.text.len());
+                    let inner = self.text[1..end].trim();
+                    let (name, value) =
+                        inner.split_once(char::is_whitespace).unwrap_or((inner, ""));
+                    (Token::Tag(name, unescape(value.trim())), end + 1)
+                }
+                _ => {
+                    let end = self
+                        .text
+                        .find(|c: char| c.is_whitespace() || "{}()[];".contains(c))
+                        .unwrap_or(self.text.len());
+                    (Token::Word(&self.text[..end]), end)
+                }
+            };
+            self.text = &self.text[len.min(self.text.len())..];
+            return Some(token);
+        }
+    }
+}
+
+// `"value"` with `\"` and `\\` escapes, as found in tags.
+fn unescape(quoted: &str) -> String {
+    let inner = quoted.strip_prefix('"').unwrap_or(quoted);
+    let inner = inner.strip_suffix('"').unwrap_or(inner);
+    let mut out = String::with_capacity(inner.len());
+    let mut chars = inner.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => out.extend(chars.next()),
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+const RESULTS: [&str; 4] = ["1-0", "0-1", "1/2-1/2", "*"];
+
+// Builds games out of tokens. The first problem in a game is remembered and
+// the rest of that game is skipped.
+#[derive(Default)]
+struct Reader {
+    game: Option<PgnGame>,
+    pos: Position,
+    depth: usize,
+    in_movetext: bool,
+    last_comment: Option<String>,
+    error: Option<anyhow::Error>,
+    count: usize,
+}
+
+impl Reader {
+    fn game(&mut self) -> &mut PgnGame {
+        self.game.get_or_insert_with(PgnGame::default)
+    }
+
+    fn feed(&mut self, token: Token) -> Option<Result<PgnGame>> {
+        // Tags after the movetext begin the next game.
+        let finished = match token {
+            Token::Tag(..) if self.in_movetext => self.finish(),
+            _ => None,
+        };
+        match token {
+            Token::Tag(name, value) => {
+                let game = self.game();
+                if name == "FEN" {
+                    match Position::from_fen(&value) {
+                        Ok(start) => game.start = start,
+                        Err(err) => self.error = self.error.take().or(Some(err.into())),
+                    }
+                    self.pos = self.game().start;
+                }
+                self.game().set_tag(name, value);
+            }
+            Token::Comment(text) if self.depth == 0 => {
+                self.last_comment = Some(text.to_string());
+            }
+            Token::Comment(_) => {}
+            Token::VariationStart => self.depth += 1,
+            Token::VariationEnd => self.depth = self.depth.saturating_sub(1),
+            Token::Word(word) => {
+                self.in_movetext = true;
+                if self.game.is_none() {
+                    self.game();
+                }
+                if self.depth > 0 || word.starts_with('$') {
+                    return finished;
+                }
+                if RESULTS.contains(&word) {
+                    let comment = self.last_comment.take();
+                    let game = self.game();
+                    game.result = word.to_string();
+                    game.comment = comment;
+                    return finished.or_else(|| self.finish());
+                }
+                // Move numbers, possibly glued to the move as in `1.e4`.
+                let san = word.trim_start_matches(|c: char| c.is_ascii_digit() || c == '.');
+                if san.is_empty() || self.error.is_some() {
+                    return finished;
+                }
+                match self.pos.parse_san(san) {
+                    Ok(mv) => {
+                        self.pos.make_move(&mv);
+                        self.game().moves.push(mv);
+                        self.last_comment = None;
+                    }
+                    Err(err) => {
+                        let ply = self.game().moves.len() + 1;
+                        self.error = Some(err.context(format!("At ply {ply}")));
+                    }
+                }
+            }
+        }
+        finished
+    }
+
+    fn finish(&mut self) -> Option<Result<PgnGame>> {
+        let mut game = self.game.take()?;
+        self.count += 1;
+        self.pos = Position::startpos();
+        self.depth = 0;
+        self.in_movetext = false;
+        self.last_comment = None;
+        if game.result == "*" {
+            if let Some(result) = game.tag("Result").filter(|r| RESULTS.contains(r)) {
+                game.result = result.to_string();
+            }
+        }
+        let count = self.count;
+        Some(match self.error.take() {
+            Some(err) => Err(err).with_context(|| format!("Game {count}")),
+            None => Ok(game),
+        })
+    }
+}
+
+fn escape(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+impl fmt::Display for PgnGame {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for name in SEVEN_TAG_ROSTER {
+            let value = match name {
+                "Result" => self.result.as_str(),
+                _ => self.tag(name).unwrap_or("?"),
+            };
+            writeln!(f, "[{name} \"{}\"]", escape(value))?;
+        }
+        if self.start != Position::startpos() {
+            writeln!(f, "[SetUp \"1\"]")?;
+            writeln!(f, "[FEN \"{}\"]", self.start.to_fen())?;
+        }
+        for (name, value) in &self.tags {
+            if SEVEN_TAG_ROSTER.contains(&name.as_str()) || name == "SetUp" || name == "FEN" {
+                continue;
+            }
+            writeln!(f, "[{name} \"{}\"]", escape(value))?;
+        }
+        writeln!(f)?;
+
+        let mut line = String::new();
+        for token in self.movetext() {
+            if !line.is_empty() && line.len() + 1 + token.len() > LINE_WIDTH {
+                writeln!(f, "{line}")?;
+                line.clear();
+            }
+            if !line.is_empty() {
+                line.push(' ');
+            }
+            line.push_str(&token);
+        }
+        writeln!(f, "{line}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn writes_export_format() {
+        let mut game = PgnGame::default();
+        let mut pos = game.start;
+        for text in ["f2f3", "e7e5", "g2g4", "d8h4"] {
+            let mv = pos.parse_uci(text).unwrap();
+            pos.make_move(&mv);
+            game.moves.push(mv);
+        }
+        game.result = "0-1".to_string();
+        game.set_tag("White", "Fool");
+        game.set_tag("Black", "Opponent \"the quick\"");
+        game.set_tag("Termination", "normal");
+        assert_eq!(
+            game.to_string(),
+            "[Event \"?\"]\n[Site \"?\"]\n[Date \"?\"]\n[Round \"?\"]\n[White \"Fool\"]\n\
+             [Black \"Opponent \\\"the quick\\\"\"]\n[Result \"0-1\"]\n[Termination \"normal\"]\n\
+             \n1. f3 e5 2. g4 Qh4# 0-1\n"
+        );
+    }
+
+    #[test]
+    fn records_setup_and_black_to_move() {
+        let start = Position::from_fen("4k3/8/8/8/8/8/4P3/4K3 b - - 3 30").unwrap();
+        let mut game = PgnGame::new(start);
+        let mut pos = start;
+        for text in ["e8d7", "e2e4"] {
+            let mv = pos.parse_uci(text).unwrap();
+            pos.make_move(&mv);
+            game.moves.push(mv);
+        }
+        game.comment = Some("Adjudicated".to_string());
+        let text = game.to_string();
+        assert!(text.contains("[SetUp \"1\"]\n[FEN \"4k3/8/8/8/8/8/4P3/4K3 b - - 3 30\"]\n"));
+        assert!(text.ends_with("\n30... Kd7 31. e4 {Adjudicated} *\n"));
+        assert_eq!(game.position(), pos);
+    }
+
+    #[test]
+    fn wraps_long_games() {
+        let mut game = PgnGame::default();
+        let mut pos = game.start;
+        // Knights shuffling back and forth.
+        for text in ["g1f3", "g8f6", "f3g1", "f6g8"].repeat(10) {
+            let mv = pos.parse_uci(text).unwrap();
+            pos.make_move(&mv);
+            game.moves.push(mv);
+        }
+        let text = game.to_string();
+        let movetext = text.split("\n\n").nth(1).unwrap();
+        assert!(movetext.lines().count() > 1);
+        assert!(movetext.lines().all(|l| l.len() <= LINE_WIDTH));
+    }
+
+    #[test]
+    fn reads_back_what_it_writes() {
+        let mut game =
+            PgnGame::new(Position::from_fen("4k3/8/8/8/8/8/4P3/4K3 b - - 3 30").unwrap());
+        let mut pos = game.start;
+        for text in ["e8d7", "e2e4", "d7e6"] {
+            let mv = pos.parse_uci(text).unwrap();
+            pos.make_move(&mv);
+            game.moves.push(mv);
+        }
+        game.set_tag("White", "A \"quoted\" name");
+        game.result = "1/2-1/2".to_string();
+        game.comment = Some("Agreed".to_string());
+        let back = PgnGame::parse(&game.to_string()).unwrap();
+        assert_eq!(back.tag("White"), Some("A \"quoted\" name"));
+        assert_eq!(back.tag("Site"), Some("?"));
+        assert_eq!(
+            (back.start, back.moves, back.result, back.comment),
+            (game.start, game.moves, game.result, game.comment)
+        );
+    }
+
+    #[test]
+    fn skips_comments_variations_and_nags() {
+        let text = "\
+% exported by hand
+[Event \"First\"]
+[Result \"1-0\"]
+
+1. e4 {best by test} e5 (1... c5 2. Nf3 (2. c3) d6) 2.Nf3 $1 Nc6 ; rest of line
+3. Bb5 a6?! 1-0
+
+[Event \"Second\"]
+
+1. d4 d5 *
+[Event \"Third\"]
+
+1. e4 e4 2. d4 0-1
+";
+        let games = parse_games(text);
+        assert_eq!(games.len(), 3);
+        let first = games[0].as_ref().unwrap();
+        assert_eq!(first.tag("Event"), Some("First"));
+        assert_eq!(first.result, "1-0");
+        let moves: Vec<String> = first.moves.iter().map(ToString::to_string).collect();
+        assert_eq!(moves, ["e2e4", "e7e5", "g1f3", "b8c6", "f1b5", "a7a6"]);
+        let second = games[1].as_ref().unwrap();
+        assert_eq!((second.moves.len(), second.result.as_str()), (2, "*"));
+        let err = games[2].as_ref().unwrap_err();
+        assert_eq!(
+            format!("{err:#}").split(':').take(2).collect::<Vec<_>>(),
+            ["Game 3", " At ply 2"]
+        );
+        assert!(PgnGame::parse(text).is_err());
+    }
+}
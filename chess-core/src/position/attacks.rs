@@ -0,0 +1,123 @@
+//! chess_core::position::attacks
+//!
+//! Who attacks what, over the whole board at once. An [`AttackMap`] lists,
+//! for every square, the squares holding a piece of one color that attacks
+//! it, whether the square is empty, friendly or hostile. A front end can
+//! shade threatened squares with one, and a [`crate::variant::Variant`] can
+//! build its own from [`Position::attack_map`] when its pieces attack
+//! differently.
+//!
+//! ```
+//! use chess_core::position::Position;
+//! use chess_core::square::Square;
+//! use chess_core::types::Color;
+//!
+//! let pos = Position::startpos();
+//! let white = pos.attack_map(Color::White);
+//! let f3: Vec<_> = white.attackers(Square::F3).collect();
+//! assert_eq!(f3, [Square::G1, Square::E2, Square::G2]);
+//! assert!(!white.is_attacked(Square::E4));
+//! ```
+
+use super::Position;
+use crate::constants::TILECOUNT;
+use crate::square::Square;
+use crate::types::Color;
+
+// The attackers of each square as a set of squares, one bit per square.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct AttackMap {
+    pub color: Color,
+    attackers: [u64; TILECOUNT],
+}
+
+fn squares(set: u64) -> impl Iterator<Item = Square> {
+    Square::all().filter(move |sq| set & (1 << sq.index()) != 0)
+}
+
+impl AttackMap {
+    // A map in which `color` attacks nothing.
+    pub const fn empty(color: Color) -> Self {
+        Self {
+            color,
+            attackers: [0; TILECOUNT],
+        }
+    }
+
+    // Records that the piece on `from` attacks `sq`.
+    pub fn add(&mut self, from: Square, sq: Square) {
+        self.attackers[sq.index()] |= 1 << from.index();
+    }
+
+    // Forgets every attack made by the piece on `from`, e.g. one that may
+    // not capture.
+    pub fn remove_attacker(&mut self, from: Square) {
+        for set in self.attackers.iter_mut() {
+            *set &= !(1 << from.index());
+        }
+    }
+
+    // The squares holding an attacker of `sq`, in board order.
+    pub fn attackers(&self, sq: Square) -> impl Iterator<Item = Square> {
+        squares(self.attackers[sq.index()])
+    }
+
+    pub fn count(&self, sq: Square) -> u32 {
+        self.attackers[sq.index()].count_ones()
+    }
+
+    pub fn is_attacked(&self, sq: Square) -> bool {
+        self.count(sq) > 0
+    }
+
+    // Every square with at least one attacker.
+    pub fn attacked(&self) -> impl Iterator<Item = Square> + '_ {
+        Square::all().filter(|&sq| self.is_attacked(sq))
+    }
+
+    // The squares the piece on `from` attacks.
+    pub fn targets(&self, from: Square) -> impl Iterator<Item = Square> + '_ {
+        Square::all().filter(move |sq| self.attackers[sq.index()] & (1 << from.index()) != 0)
+    }
+}
+
+impl Position {
+    // Which pieces of `by` attack each square, by the standard rules.
+    pub fn attack_map(&self, by: Color) -> AttackMap {
+        let mut map = AttackMap::empty(by);
+        for sq in Square::all() {
+            for from in self.attackers(sq, by) {
+                map.add(from, sq);
+            }
+        }
+        map
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn attack_maps_agree_with_attackers() {
+        let pos = Position::from_fen("4k3/8/8/8/3q4/2N5/8/4K2R w K - 0 1").unwrap();
+        for color in [Color::White, Color::Black] {
+            let map = pos.attack_map(color);
+            for sq in Square::all() {
+                let mut expected: Vec<Square> = pos.attackers(sq, color).collect();
+                expected.sort();
+                assert_eq!(map.attackers(sq).collect::<Vec<_>>(), expected);
+            }
+        }
+        let black = pos.attack_map(Color::Black);
+        // The knight blocks the queen's diagonal towards a1.
+        assert!(black.is_attacked(Square::C3) && !black.is_attacked(Square::B2));
+        assert_eq!(black.count(Square::D1), 1);
+        assert!(black.targets(Square::D4).any(|sq| sq == Square::H8));
+        let white = pos.attack_map(Color::White);
+        assert_eq!(
+            white.attackers(Square::D5).collect::<Vec<_>>(),
+            [Square::C3]
+        );
+    }
+}
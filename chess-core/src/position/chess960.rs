@@ -0,0 +1,204 @@
+//! chess_core::position::chess960
+//!
+//! Fischer Random starting positions. The 960 legal back ranks are numbered
+//! the way Scharnagl did, so that 518 is the classical `RNBQKBNR`; both sides
+//! mirror each other and keep all four castling rights. Castling itself needs
+//! nothing special: [`Castling`](super::Castling) remembers where the rooks
+//! started and the king and rook always end up on the g/f or c/d files.
+
+use super::{square_at, CastleSide, Man, Position};
+use crate::error::ChessError;
+use crate::types::{Color, Type};
+
+pub const CHESS960_POSITIONS: u16 = 960;
+// The number of the classical starting position.
+pub const CLASSICAL: u16 = 518;
+
+// Where the two knights go among the five files left over once the bishops
+// and the queen are placed.
+const KNIGHTS: [(usize, usize); 10] = [
+    (0, 1),
+    (0, 2),
+    (0, 3),
+    (0, 4),
+    (1, 2),
+    (1, 3),
+    (1, 4),
+    (2, 3),
+    (2, 4),
+    (3, 4),
+];
+
+// The back rank of position `index`, from the a-file to the h-file.
+pub fn back_rank(index: u16) -> Result<[Type; 8], ChessError> {
+    if index >= CHESS960_POSITIONS {
+        return Err(ChessError::InvalidSetup {
+            reason: format!("there is no Chess960 position {index}"),
+        });
+    }
+    let mut rank: [Option<Type>; 8] = [None; 8];
+    let n = index as usize;
+    rank[2 * (n % 4) + 1] = Some(Type::Bishop);
+    rank[2 * (n / 4 % 4)] = Some(Type::Bishop);
+    let n = n / 16;
+    let free = |rank: &[Option<Type>; 8]| -> Vec<usize> {
+        (0..8).filter(|&file| rank[file].is_none()).collect()
+    };
+    rank[free(&rank)[n % 6]] = Some(Type::Queen);
+    let (a, b) = KNIGHTS[n / 6];
+    let empty = free(&rank);
+    rank[empty[a]] = Some(Type::Knight);
+    rank[empty[b]] = Some(Type::Knight);
+    for (file, ty) in free(&rank)
+        .into_iter()
+        .zip([Type::Rook, Type::King, Type::Rook])
+    {
+        rank[file] = Some(ty);
+    }
+    Ok(rank.map(|ty| ty.expect("every file is filled")))
+}
+
+// The number of `rank`, after checking that it is a Chess960 back rank: two
+// rooks with the king between them, bishops on opposite colors, two knights
+// and a queen.
+pub fn index_of(rank: &[Type; 8]) -> Result<u16, ChessError> {
+    let invalid = |reason: &str| ChessError::InvalidSetup {
+        reason: reason.to_string(),
+    };
+    let files = |ty: Type| -> Vec<usize> { (0..8).filter(|&file| rank[file] == ty).collect() };
+    let (bishops, knights, rooks) = (files(Type::Bishop), files(Type::Knight), files(Type::Rook));
+    let (queens, kings) = (files(Type::Queen), files(Type::King));
+    if bishops.len() != 2
+        || knights.len() != 2
+        || rooks.len() != 2
+        || queens.len() != 1
+        || kings.len() != 1
+    {
+        return Err(invalid(
+            "a back rank needs two rooks, knights and bishops, a queen and a king",
+        ));
+    }
+    if bishops[0] % 2 == bishops[1] % 2 {
+        return Err(invalid("the bishops must stand on opposite colors"));
+    }
+    if !(rooks[0] < kings[0] && kings[0] < rooks[1]) {
+        return Err(invalid("the king must stand between the rooks"));
+    }
+    let (dark, light) = match bishops[0] % 2 {
+        0 => (bishops[0], bishops[1]),
+        _ => (bishops[1], bishops[0]),
+    };
+    let without =
+        |taken: &[usize]| -> Vec<usize> { (0..8).filter(|file| !taken.contains(file)).collect() };
+    let queen = without(&bishops)
+        .iter()
+        .position(|&file| file == queens[0])
+        .expect("the queen is not on a bishop's file");
+    let rest = without(&[dark, light, queens[0]]);
+    let slot = |file: usize| rest.iter().position(|&f| f == file).unwrap();
+    let pair = (slot(knights[0]), slot(knights[1]));
+    let n = KNIGHTS.iter().position(|&k| k == pair).unwrap();
+    Ok((((n * 6 + queen) * 4 + dark / 2) * 4 + (light - 1) / 2) as u16)
+}
+
+impl Position {
+    // Starting position `index` of Chess960, white to move.
+    pub fn chess960(index: u16) -> Result<Self, ChessError> {
+        let rank = back_rank(index)?;
+        let mut pos = Position::empty();
+        for (file, &ty) in rank.iter().enumerate() {
+            for (color, back, pawns) in [(Color::White, 0, 1), (Color::Black, 7, 6)] {
+                pos.board[square_at(file, back)] = Some(Man::new(color, ty));
+                pos.board[square_at(file, pawns)] = Some(Man::new(color, Type::Pawn));
+            }
+        }
+        let rooks: Vec<usize> = (0..8).filter(|&f| rank[f] == Type::Rook).collect();
+        for (color, back) in [(Color::White, 0), (Color::Black, 7)] {
+            let queenside = Some(square_at(rooks[0], back));
+            let kingside = Some(square_at(rooks[1], back));
+            pos.castling.set(color, CastleSide::Queen, queenside);
+            pos.castling.set(color, CastleSide::King, kingside);
+        }
+        Ok(pos)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constants::*;
+    use crate::position::{perft, ChessMove};
+    use crate::square::Square;
+
+    fn letters(index: u16) -> String {
+        back_rank(index)
+            .unwrap()
+            .iter()
+            .map(|&ty| Man::new(Color::White, ty).to_char())
+            .collect()
+    }
+
+    #[test]
+    fn matches_the_published_numbering() {
+        for (index, rank) in [
+            (0, "BBQNNRKR"),
+            (1, "BQNBNRKR"),
+            (2, "BQNNRBKR"),
+            (3, "BQNNRKRB"),
+            (4, "QBBNNRKR"),
+            (518, "RNBQKBNR"),
+            (959, "RKRNNQBB"),
+        ] {
+            assert_eq!(letters(index), rank, "position {index}");
+        }
+        assert_eq!(Position::chess960(CLASSICAL).unwrap(), Position::startpos());
+        assert!(back_rank(960).is_err());
+    }
+
+    #[test]
+    fn numbers_every_back_rank_once() {
+        let mut seen = std::collections::HashSet::new();
+        for index in 0..CHESS960_POSITIONS {
+            let rank = back_rank(index).unwrap();
+            assert_eq!(index_of(&rank), Ok(index));
+            assert!(seen.insert(rank));
+        }
+        use Type::*;
+        let same_shade = [Rook, Bishop, Knight, Bishop, King, Queen, Knight, Rook];
+        assert!(index_of(&same_shade).is_err());
+        let king_outside = [King, Rook, Bishop, Queen, Bishop, Knight, Knight, Rook];
+        assert!(index_of(&king_outside).is_err());
+    }
+
+    #[test]
+    fn castles_onto_the_classical_squares() {
+        // The king already stands on its destination and only the rook moves.
+        let mut pos = Position::from_fen("1r4kr/8/8/8/8/8/8/1R4KR w HBhb - 0 1").unwrap();
+        let short = pos.parse_uci("g1h1").unwrap();
+        assert_eq!(short, ChessMove::new(G1, H1));
+        assert_eq!(pos.move_to_uci(&short), "g1h1");
+        assert_eq!(pos.move_to_san(&short), "O-O");
+        let long = ChessMove::new(G1, B1);
+        assert!(pos.legal_moves().contains(&long));
+        pos.play(&long).unwrap();
+        assert_eq!(pos.at(Square::C1), Some(Man::new(Color::White, Type::King)));
+        assert_eq!(pos.at(Square::D1), Some(Man::new(Color::White, Type::Rook)));
+        assert_eq!(pos.at(Square::B1), None);
+        assert_eq!(pos.at(Square::G1), None);
+        assert_eq!(pos.to_fen(), "1r4kr/8/8/8/8/8/8/2KR3R b kq - 1 1");
+
+        // Every square the king and rook cross, other than their own, must be empty.
+        let blocked = Position::from_fen("4k3/8/8/8/8/8/8/RK1n4 w A - 0 1").unwrap();
+        assert!(!blocked.legal_moves().contains(&ChessMove::new(B1, A1)));
+    }
+
+    #[test]
+    fn perft_from_a_shuffled_start() {
+        let pos =
+            Position::from_fen("bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w HFhf - 2 9")
+                .unwrap();
+        assert_eq!(perft(&pos, 1), 21);
+        assert_eq!(perft(&pos, 2), 528);
+        assert_eq!(perft(&pos, 3), 12189);
+    }
+}
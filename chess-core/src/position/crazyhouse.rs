@@ -0,0 +1,211 @@
+//! chess_core::position::crazyhouse
+//!
+//! Crazyhouse keeps what it captures: a taken piece changes color and goes
+//! into the capturer's reserve, from where it can later be dropped onto any
+//! empty square as a move of its own. Pawns are never dropped onto the first
+//! or last rank, and a piece that was promoted goes back into the reserve as
+//! a pawn. A [`Position`] with [`Reserves`] follows these rules.
+
+use super::{rank_of, ChessMove, Man, Position};
+use crate::constants::TILECOUNT;
+use crate::square::Square;
+use crate::types::{Color, Type};
+
+// The kinds of piece a reserve can hold, in FEN order.
+pub const DROPPABLE: [Type; 5] = [
+    Type::Queen,
+    Type::Rook,
+    Type::Bishop,
+    Type::Knight,
+    Type::Pawn,
+];
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct Reserves {
+    counts: [[u8; 5]; 2],
+    // One bit per square holding a piece that was promoted from a pawn.
+    promoted: u64,
+}
+
+fn slot(ty: Type) -> usize {
+    match ty {
+        Type::Queen => 0,
+        Type::Rook => 1,
+        Type::Bishop => 2,
+        Type::Knight => 3,
+        Type::Pawn => 4,
+        Type::King => panic!("kings never go into the reserve"),
+        Type::Fairy(_) => unreachable!("a position only holds orthodox pieces"),
+    }
+}
+
+impl Reserves {
+    pub fn count(&self, color: Color, ty: Type) -> u8 {
+        match ty {
+            Type::King => 0,
+            _ => self.counts[color.index()][slot(ty)],
+        }
+    }
+    pub fn add(&mut self, color: Color, ty: Type) {
+        self.counts[color.index()][slot(ty)] += 1;
+    }
+    // Takes one `ty` out of `color`'s reserve, if there is one.
+    pub fn take(&mut self, color: Color, ty: Type) -> bool {
+        if self.count(color, ty) == 0 {
+            return false;
+        }
+        self.counts[color.index()][slot(ty)] -= 1;
+        true
+    }
+    pub fn is_empty(&self, color: Color) -> bool {
+        self.counts[color.index()].iter().all(|&n| n == 0)
+    }
+    pub fn is_promoted(&self, sq: Square) -> bool {
+        self.promoted & (1 << sq.index()) != 0
+    }
+    pub fn set_promoted(&mut self, sq: Square, promoted: bool) {
+        match promoted {
+            true => self.promoted |= 1 << sq.index(),
+            false => self.promoted &= !(1 << sq.index()),
+        }
+    }
+}
+
+impl Position {
+    // This position with empty reserves, i.e. under Crazyhouse rules.
+    pub fn with_reserves(mut self) -> Self {
+        self.reserves.get_or_insert_with(Reserves::default);
+        self
+    }
+
+    pub(super) fn drop_moves(&self, out: &mut Vec<ChessMove>) {
+        let Some(reserves) = self.reserves else {
+            return;
+        };
+        for ty in DROPPABLE {
+            if reserves.count(self.turn, ty) == 0 {
+                continue;
+            }
+            for to in (0..TILECOUNT).filter(|&sq| self.board[sq].is_none()) {
+                if ty == Type::Pawn && (rank_of(to) == 0 || rank_of(to) == 7) {
+                    continue;
+                }
+                out.push(ChessMove::drop(ty, to));
+            }
+        }
+    }
+
+    pub(super) fn make_drop(&mut self, mv: &ChessMove) {
+        let us = self.turn;
+        let ty = mv.promotion.expect("a drop names its piece");
+        if let Some(reserves) = self.reserves.as_mut() {
+            reserves.take(us, ty);
+        }
+        self.board[mv.to] = Some(Man::new(us, ty));
+        self.ep = None;
+        // Like a pawn move, a pawn drop cannot be undone.
+        self.halfmoves = match ty {
+            Type::Pawn => 0,
+            _ => self.halfmoves + 1,
+        };
+        if us == Color::Black {
+            self.fullmoves += 1;
+        }
+        self.turn = us.opposite();
+    }
+
+    // The square of the piece `mv` captures, which differs from the target
+    // square for en passant.
+    pub(crate) fn victim_square(&self, mv: &ChessMove) -> Square {
+        match self.is_en_passant(mv) {
+            true => Square::new(mv.to_square().file(), mv.from_square().rank()),
+            false => mv.to_square(),
+        }
+    }
+
+    // The piece that capturing with `mv` puts into a reserve: the one taken,
+    // or a pawn if it had been promoted. None for a move that captures
+    // nothing or a position without reserves.
+    pub fn reserve_gain(&self, mv: &ChessMove) -> Option<Type> {
+        let reserves = self.reserves?;
+        let man = self.captured(mv)?;
+        Some(match reserves.is_promoted(self.victim_square(mv)) {
+            true => Type::Pawn,
+            false => man.ty,
+        })
+    }
+
+    // Puts whatever `mv` captures into the mover's reserve and keeps track of
+    // promoted pieces. Called before `mv` is made.
+    pub(super) fn update_reserves(&mut self, mv: &ChessMove) {
+        let Some(mut reserves) = self.reserves else {
+            return;
+        };
+        if let Some(ty) = self.reserve_gain(mv) {
+            reserves.add(self.turn, ty);
+            reserves.set_promoted(self.victim_square(mv), false);
+        }
+        if !self.is_castling(mv) {
+            let promoted = reserves.is_promoted(mv.from_square()) || mv.promotion.is_some();
+            reserves.set_promoted(mv.from_square(), false);
+            reserves.set_promoted(mv.to_square(), promoted);
+        }
+        self.reserves = Some(reserves);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constants::*;
+    use crate::position::perft;
+
+    #[test]
+    fn captures_fill_the_reserve() {
+        let mut pos = Position::startpos().with_reserves();
+        for mv in ["e2e4", "d7d5", "e4d5"] {
+            let mv = pos.parse_uci(mv).unwrap();
+            pos.play(&mv).unwrap();
+        }
+        let reserves = pos.reserves.unwrap();
+        assert_eq!(reserves.count(Color::White, Type::Pawn), 1);
+        assert!(reserves.is_empty(Color::Black));
+        assert_eq!(
+            pos.to_fen(),
+            "rnbqkbnr/ppp1pppp/8/3P4/8/8/PPPP1PPP/RNBQKBNR[P] b KQkq - 0 2"
+        );
+        pos.play(&pos.parse_uci("d8d5").unwrap()).unwrap();
+        let drop = pos.parse_uci("P@d3").unwrap();
+        assert_eq!(drop, ChessMove::drop(Type::Pawn, D3));
+        assert_eq!(pos.move_to_san(&drop), "P@d3");
+        pos.play(&drop).unwrap();
+        assert_eq!(pos.at(Square::D3), Some(Man::new(Color::White, Type::Pawn)));
+        assert_eq!(pos.reserves.unwrap().count(Color::White, Type::Pawn), 0);
+        assert_eq!(pos.reserves.unwrap().count(Color::Black, Type::Pawn), 1);
+    }
+
+    #[test]
+    fn promoted_pieces_return_as_pawns() {
+        let mut pos = Position::from_fen("3qk3/2P5/8/8/8/8/8/4K3[] w - - 0 1").unwrap();
+        pos.play(&pos.parse_uci("c7d8q").unwrap()).unwrap();
+        assert_eq!(pos.to_fen(), "3Q~k3/8/8/8/8/8/8/4K3[Q] b - - 0 1");
+        pos.play(&pos.parse_uci("e8d8").unwrap()).unwrap();
+        assert_eq!(pos.to_fen(), "3k4/8/8/8/8/8/8/4K3[Qp] w - - 0 2");
+        assert_eq!(Position::from_fen(&pos.to_fen()).unwrap(), pos);
+    }
+
+    #[test]
+    fn drops_go_on_empty_squares_only() {
+        let knight = Position::from_fen("4k3/8/8/8/8/8/8/4K3[N] w - - 0 1").unwrap();
+        // Five king moves and 62 knight drops.
+        assert_eq!(perft(&knight, 1), 67);
+        let pawn = Position::from_fen("4k3/8/8/8/8/8/8/4K3[P] w - - 0 1").unwrap();
+        assert_eq!(perft(&pawn, 1), 5 + 48);
+        // A drop can block a check, and is the only way out of this one.
+        let check = Position::from_fen("1r5k/8/4b3/8/8/8/8/K1r5[B] w - - 0 1").unwrap();
+        assert_eq!(check.legal_moves(), [ChessMove::drop(Type::Bishop, B1)]);
+        // Without captures the first plies match standard chess.
+        let start = Position::startpos().with_reserves();
+        assert_eq!(perft(&start, 3), 8902);
+    }
+}
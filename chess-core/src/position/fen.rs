@@ -0,0 +1,412 @@
+//! chess_core::position::fen
+//!
+//! Forsyth-Edwards Notation for [`Position`]. Castling rights are read as
+//! `KQkq` or as the files of the castling rooks (Shredder-FEN `HAha`), or any
+//! mix of the two (X-FEN). [`Position::to_fen`] writes X-FEN, which is plain
+//! FEN for classical setups, and [`Position::to_shredder_fen`] writes files.
+//! Crazyhouse positions carry their reserves after the placement, as in
+//! `.../RNBQKBNR[Qp]` or as a ninth rank, and mark promoted pieces with `~`.
+//! Three-check positions add the checks each side still needs after the en
+//! passant square, as lichess does: `... KQkq - 3+2 0 1`.
+//!
+//! Tests can write their positions with [`crate::fen!`] or, as a diagram,
+//! with [`crate::board!`]. Both check the position while compiling and give a
+//! ready [`crate::game::GameState`]; `.` stands for an empty square.
+//!
+//! ```
+//! use chess_core::{board, fen};
+//!
+//! let game = board! {
+//!     r n b q k b n r /
+//!     p p p p p p p p /
+//!     . . . . . . . . /
+//!     . . . . . . . . /
+//!     . . . . P . . . /
+//!     . . . . . . . . /
+//!     P P P P . P P P /
+//!     R N B Q K B N R ; b KQkq e3 0 1
+//! };
+//! let same = fen!("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1");
+//! assert_eq!(game.hist.position(), same.hist.position());
+//! ```
+//!
+//! Mistakes are compile errors, reported at the square or field at fault:
+//!
+//! ```compile_fail
+//! // The white pawn cannot stand on a8.
+//! let game = chess_core::board! {
+//!     P . . . k . . . /
+//!     8 / 8 / 8 / 8 / 8 / 8 /
+//!     . . . . K . . .
+//! };
+//! ```
+//!
+//! ```compile_fail
+//! // There is no rook for White to castle with.
+//! let game = chess_core::fen!("4k3/8/8/8/8/8/8/4K3 w K - 0 1");
+//! ```
+
+use super::crazyhouse::{Reserves, DROPPABLE};
+use super::{file_of, rank_of, square_at, CastleSide, Man, Position};
+use crate::error::{ChessError, FenField};
+use crate::msg::TileId;
+use crate::square::Square;
+use crate::types::{Color, Type};
+
+impl Position {
+    pub fn from_fen(fen: &str) -> Result<Self, ChessError> {
+        let pos = Self::from_fen_unchecked(fen)?;
+        pos.check_kings()?;
+        Ok(pos)
+    }
+
+    // Like [`Self::from_fen`](), but with any number of kings, as variants
+    // such as Antichess have; see [`crate::variant::Variant::parse_fen`]().
+    pub fn from_fen_unchecked(fen: &str) -> Result<Self, ChessError> {
+        let mut fields = fen.split_whitespace().peekable();
+        let mut pos = Position::empty();
+
+        let placement = fields
+            .next()
+            .ok_or_else(|| ChessError::fen(FenField::Placement, "missing"))?;
+        let (placement, pocket) = match placement.split_once('[') {
+            Some((placement, pocket)) => {
+                let pocket = pocket
+                    .strip_suffix(']')
+                    .ok_or_else(|| ChessError::fen(FenField::Placement, "unterminated reserves"))?;
+                (placement, Some(pocket))
+            }
+            None => match placement.matches('/').count() {
+                8 => placement
+                    .rsplit_once('/')
+                    .map_or((placement, None), |(p, r)| (p, Some(r))),
+                _ => (placement, None),
+            },
+        };
+        if let Some(pocket) = pocket {
+            let mut reserves = Reserves::default();
+            for c in pocket.chars() {
+                match Man::from_char(c) {
+                    Some(man) if man.ty != Type::King => reserves.add(man.color, man.ty),
+                    _ => {
+                        return Err(ChessError::fen(
+                            FenField::Placement,
+                            format!("{c:?} cannot be in reserve"),
+                        ))
+                    }
+                }
+            }
+            pos.reserves = Some(reserves);
+        }
+        let ranks: Vec<&str> = placement.split('/').collect();
+        if ranks.len() != 8 {
+            return Err(ChessError::fen(
+                FenField::Placement,
+                format!("expected 8 ranks in {placement:?}"),
+            ));
+        }
+        for (i, row) in ranks.iter().enumerate() {
+            let rank = 7 - i;
+            let mut file = 0;
+            for c in row.chars() {
+                if c == '~' && file > 0 {
+                    let reserves = pos.reserves.get_or_insert_with(Reserves::default);
+                    let sq = Square::from_index(square_at(file - 1, rank))
+                        .filter(|_| file <= 8)
+                        .ok_or_else(|| {
+                            ChessError::fen(
+                                FenField::Placement,
+                                format!("rank {} has too many files", rank + 1),
+                            )
+                        })?;
+                    reserves.set_promoted(sq, true);
+                } else if let Some(skip) = c.to_digit(10) {
+                    file += skip as usize;
+                } else {
+                    let man = Man::from_char(c).ok_or_else(|| {
+                        ChessError::fen(FenField::Placement, format!("unknown piece {c:?}"))
+                    })?;
+                    if file >= 8 {
+                        return Err(ChessError::fen(
+                            FenField::Placement,
+                            format!("rank {} has too many files", rank + 1),
+                        ));
+                    }
+                    pos.board[square_at(file, rank)] = Some(man);
+                    file += 1;
+                }
+            }
+            if file != 8 {
+                return Err(ChessError::fen(
+                    FenField::Placement,
+                    format!("rank {} does not have 8 files", rank + 1),
+                ));
+            }
+        }
+
+        pos.turn = match fields.next().unwrap_or("w") {
+            "w" => Color::White,
+            "b" => Color::Black,
+            other => return Err(ChessError::fen(FenField::SideToMove, format!("{other:?}"))),
+        };
+
+        let castling = fields.next().unwrap_or("-");
+        if castling != "-" {
+            for c in castling.chars() {
+                pos.parse_castling_char(c)?;
+            }
+        }
+
+        pos.ep = match fields.next().unwrap_or("-") {
+            "-" => None,
+            sq => Some(
+                sq.parse::<Square>()
+                    .map_err(|_| ChessError::fen(FenField::EnPassant, format!("{sq:?}")))?,
+            ),
+        };
+
+        if let Some(checks) = fields.next_if(|field| field.contains('+')) {
+            let parsed = checks
+                .split_once('+')
+                .and_then(|(w, b)| Some([w.parse().ok()?, b.parse().ok()?]));
+            pos.checks = Some(
+                parsed.ok_or_else(|| ChessError::fen(FenField::Checks, format!("{checks:?}")))?,
+            );
+        }
+
+        pos.halfmoves = match fields.next() {
+            Some(n) => n
+                .parse()
+                .map_err(|_| ChessError::fen(FenField::HalfmoveClock, format!("{n:?}")))?,
+            None => 0,
+        };
+        pos.fullmoves = match fields.next() {
+            Some(n) => n
+                .parse::<u32>()
+                .map_err(|_| ChessError::fen(FenField::FullmoveNumber, format!("{n:?}")))?
+                .max(1),
+            None => 1,
+        };
+        Ok(pos)
+    }
+
+    // Each side must have exactly one king.
+    pub fn check_kings(&self) -> Result<(), ChessError> {
+        for color in [Color::White, Color::Black] {
+            if self
+                .pieces(color)
+                .filter(|(_, m)| m.ty == Type::King)
+                .count()
+                != 1
+            {
+                return Err(ChessError::fen(
+                    FenField::Placement,
+                    format!("{color:?} needs exactly one king"),
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    fn parse_castling_char(&mut self, c: char) -> Result<(), ChessError> {
+        let color = if c.is_ascii_uppercase() {
+            Color::White
+        } else {
+            Color::Black
+        };
+        let back_rank = match color {
+            Color::White => 0,
+            Color::Black => 7,
+        };
+        let king = self
+            .king(color)
+            .map(Square::index)
+            .filter(|&k| rank_of(k) == back_rank)
+            .ok_or_else(|| {
+                ChessError::fen(
+                    FenField::Castling,
+                    format!("no {color:?} king on its back rank"),
+                )
+            })?;
+        let rook = Man::new(color, Type::Rook);
+        let outermost = |files: &mut dyn Iterator<Item = usize>| -> Option<TileId> {
+            files
+                .map(|f| square_at(f, back_rank))
+                .find(|&sq| self.board[sq] == Some(rook))
+        };
+        let (side, sq) = match c.to_ascii_lowercase() {
+            'k' => (
+                CastleSide::King,
+                outermost(&mut (file_of(king) + 1..8).rev()),
+            ),
+            'q' => (CastleSide::Queen, outermost(&mut (0..file_of(king)))),
+            file @ 'a'..='h' => {
+                let file = (file as u8 - b'a') as usize;
+                let side = match file > file_of(king) {
+                    true => CastleSide::King,
+                    false => CastleSide::Queen,
+                };
+                (side, outermost(&mut std::iter::once(file)))
+            }
+            _ => {
+                return Err(ChessError::fen(
+                    FenField::Castling,
+                    format!("unexpected {c:?}"),
+                ))
+            }
+        };
+        let sq =
+            sq.ok_or_else(|| ChessError::fen(FenField::Castling, format!("no rook for {c:?}")))?;
+        self.castling.set(color, side, Some(sq));
+        Ok(())
+    }
+
+    pub fn to_fen(&self) -> String {
+        self.write_fen(false)
+    }
+
+    // Like [`Self::to_fen`](), naming every castling right by its rook's file.
+    pub fn to_shredder_fen(&self) -> String {
+        self.write_fen(true)
+    }
+
+    fn write_fen(&self, shredder: bool) -> String {
+        let mut out = String::with_capacity(90);
+        for rank in (0..8).rev() {
+            let mut empty = 0;
+            for file in 0..8 {
+                match self.board[square_at(file, rank)] {
+                    Some(man) => {
+                        if empty > 0 {
+                            out.push_str(&empty.to_string());
+                            empty = 0;
+                        }
+                        out.push(man.to_char());
+                        let sq = Square::from_index(square_at(file, rank)).expect("on the board");
+                        if self.reserves.is_some_and(|r| r.is_promoted(sq)) {
+                            out.push('~');
+                        }
+                    }
+                    None => empty += 1,
+                }
+            }
+            if empty > 0 {
+                out.push_str(&empty.to_string());
+            }
+            if rank > 0 {
+                out.push('/');
+            }
+        }
+        if let Some(reserves) = self.reserves {
+            out.push('[');
+            for color in [Color::White, Color::Black] {
+                for ty in DROPPABLE {
+                    for _ in 0..reserves.count(color, ty) {
+                        out.push(Man::new(color, ty).to_char());
+                    }
+                }
+            }
+            out.push(']');
+        }
+        out.push(' ');
+        out.push(match self.turn {
+            Color::White => 'w',
+            Color::Black => 'b',
+        });
+        out.push(' ');
+        let mut rights = String::new();
+        for (color, side, c) in [
+            (Color::White, CastleSide::King, 'K'),
+            (Color::White, CastleSide::Queen, 'Q'),
+            (Color::Black, CastleSide::King, 'k'),
+            (Color::Black, CastleSide::Queen, 'q'),
+        ] {
+            let Some(rook) = self.castling.rook(color, side) else {
+                continue;
+            };
+            // X-FEN only names the file when another rook stands further out.
+            if shredder || !self.is_outermost_rook(rook, side) {
+                let file = (b'a' + file_of(rook) as u8) as char;
+                rights.push(match color {
+                    Color::White => file.to_ascii_uppercase(),
+                    Color::Black => file,
+                });
+            } else {
+                rights.push(c);
+            }
+        }
+        if rights.is_empty() {
+            rights.push('-');
+        }
+        out.push_str(&rights);
+        out.push(' ');
+        match self.ep {
+            Some(sq) => out.push_str(&sq.to_string()),
+            None => out.push('-'),
+        }
+        if let Some([white, black]) = self.checks {
+            out.push_str(&format!(" {white}+{black}"));
+        }
+        out.push_str(&format!(" {} {}", self.halfmoves, self.fullmoves));
+        out
+    }
+
+    fn is_outermost_rook(&self, rook: TileId, side: CastleSide) -> bool {
+        let rank = rank_of(rook);
+        let outside = match side {
+            CastleSide::King => file_of(rook) + 1..8,
+            CastleSide::Queen => 0..file_of(rook),
+        };
+        !outside
+            .map(|file| square_at(file, rank))
+            .any(|sq| self.board[sq] == self.board[rook])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::position::STARTING_FEN;
+
+    #[test]
+    fn round_trips() {
+        for fen in [
+            STARTING_FEN,
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "rnbqkbnr/pp1ppppp/8/2p5/4P3/8/PPPP1PPP/RNBQKBNR w KQkq c6 0 2",
+            "8/8/4k3/8/8/3K4/8/8 b - - 12 40",
+        ] {
+            assert_eq!(Position::from_fen(fen).unwrap().to_fen(), fen);
+        }
+    }
+
+    #[test]
+    fn rejects_garbage() {
+        assert!(Position::from_fen("").is_err());
+        assert!(Position::from_fen("8/8/8/8/8/8/8/8 w - - 0 1").is_err());
+        assert!(
+            Position::from_fen("rnbqkbnr/pppppppp/9/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").is_err()
+        );
+        let err = Position::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR x KQkq - 0 1")
+            .unwrap_err();
+        assert_eq!(err, ChessError::fen(FenField::SideToMove, "\"x\""));
+        assert_eq!(err.to_string(), "Invalid FEN side to move: \"x\"");
+    }
+
+    #[test]
+    fn reads_and_writes_rook_files() {
+        let shredder = "rn2k1r1/ppp1pppp/3p4/8/8/8/PPPPPPPP/RN1K2RR w GAga - 0 1";
+        let pos = Position::from_fen(shredder).unwrap();
+        assert_eq!(pos.castling.rook(Color::White, CastleSide::King), Some(6));
+        assert_eq!(pos.to_shredder_fen(), shredder);
+        // The g-rook has the h-rook outside it, so X-FEN has to name it.
+        let xfen = "rn2k1r1/ppp1pppp/3p4/8/8/8/PPPPPPPP/RN1K2RR w GQkq - 0 1";
+        assert_eq!(pos.to_fen(), xfen);
+        assert_eq!(Position::from_fen(xfen).unwrap(), pos);
+        assert_eq!(
+            Position::from_fen(STARTING_FEN).unwrap().to_shredder_fen(),
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w HAha - 0 1"
+        );
+        assert!(Position::from_fen("4k3/8/8/8/8/8/8/R3K3 w B - 0 1").is_err());
+    }
+}
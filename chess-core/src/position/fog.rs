@@ -0,0 +1,115 @@
+//! chess_core::position::fog
+//!
+//! Fog of war, also known as dark chess. Each side only sees the squares its
+//! pieces stand on or could move to, plus the squares its pawns attack. Since
+//! nobody can be sure where the enemy is, there is no check: any move that
+//! obeys how the pieces travel may be played, and the game is won by taking
+//! the king.
+
+use super::{ChessMove, Outcome, Position};
+use crate::constants::TILECOUNT;
+use crate::types::{Color, Type};
+
+impl Position {
+    // The moves of the side to move. The king may walk into an attack, and
+    // castle out of or through one.
+    pub fn fog_moves(&self) -> Vec<ChessMove> {
+        let mut moves = Vec::with_capacity(48);
+        self.pseudo_legal_moves(&mut moves);
+        moves.retain(|mv| !self.is_castling(mv));
+        if let Some(king) = self.king(self.turn) {
+            self.castling_moves(king.index(), false, &mut moves);
+        }
+        moves
+    }
+
+    // The squares `color` can see, whoever is to move.
+    pub fn visible_squares(&self, color: Color) -> [bool; TILECOUNT] {
+        let mut seen = [false; TILECOUNT];
+        let mut view = *self;
+        view.turn = color;
+        if color != self.turn {
+            // Only the side to move may take en passant.
+            view.ep = None;
+        }
+        let mut moves = Vec::with_capacity(48);
+        view.pseudo_legal_moves(&mut moves);
+        for mv in moves {
+            seen[mv.to] = true;
+        }
+        let ahead = match color {
+            Color::White => 1,
+            Color::Black => -1,
+        };
+        for (sq, man) in self.pieces(color) {
+            seen[sq.index()] = true;
+            if man.ty == Type::Pawn {
+                for to in [-1, 1].into_iter().filter_map(|dx| sq.offset(dx, ahead)) {
+                    seen[to.index()] = true;
+                }
+            }
+        }
+        seen
+    }
+
+    // Like [`Self::outcome`](), for a game of fog of war.
+    pub fn fog_outcome(&self) -> Option<Outcome> {
+        for color in [Color::White, Color::Black] {
+            if self.king(color).is_none() {
+                let winner = color.opposite();
+                return Some(Outcome::KingCaptured { winner });
+            }
+        }
+        if self.fog_moves().is_empty() {
+            return Some(Outcome::Stalemate);
+        }
+        if self.halfmoves >= 100 {
+            return Some(Outcome::FiftyMoves);
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constants::*;
+
+    #[test]
+    fn sees_what_the_pieces_reach() {
+        let pos = Position::startpos();
+        let seen = pos.visible_squares(Color::White);
+        let count = seen.iter().filter(|&&s| s).count();
+        // Both home ranks, the third rank and the fourth rank in front of the pawns.
+        assert_eq!(count, 16 + 8 + 8);
+        assert!(seen[E4] && !seen[E5] && !seen[E7]);
+
+        let pos = Position::from_fen("4k3/8/8/3p4/8/8/8/R3K3 w Q - 0 1").unwrap();
+        let seen = pos.visible_squares(Color::White);
+        assert!(seen[A8] && seen[D1] && !seen[D5]);
+        let seen = pos.visible_squares(Color::Black);
+        assert!(seen[C4] && seen[E4] && seen[D4] && !seen[A1]);
+    }
+
+    #[test]
+    fn king_may_be_taken() {
+        // The white king walks into the rook, which takes it.
+        let mut pos = Position::from_fen("3rk3/8/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+        let blunder = ChessMove::new(E1, D1);
+        assert!(!pos.legal_moves().contains(&blunder));
+        assert!(pos.fog_moves().contains(&blunder));
+        pos.make_move(&blunder);
+        pos.make_move(&ChessMove::new(D8, D1));
+        assert_eq!(
+            pos.fog_outcome(),
+            Some(Outcome::KingCaptured {
+                winner: Color::Black
+            })
+        );
+
+        // Castling through an attack is allowed too.
+        let pos = Position::from_fen("4k3/8/8/8/8/8/5r2/4K2R w K - 0 1").unwrap();
+        assert!(pos.fog_moves().contains(&ChessMove::new(E1, H1)));
+        assert!(!pos.legal_moves().contains(&ChessMove::new(E1, H1)));
+    }
+}
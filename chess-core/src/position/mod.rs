@@ -0,0 +1,916 @@
+//! chess_core::position
+//!
+//! A compact, copyable snapshot of a chess position that the engine can
+//! search with. Unlike [`crate::game::GameState`], nothing in here is
+//! reference counted: every square simply holds an optional [`Man`], so
+//! making a move is a matter of copying the position and editing a few bytes.
+//!
+//! Squares use the same [`TileId`] indexing as [`crate::constants`], i.e.
+//! `A1 == 0`, `H1 == 7` and `H8 == 63`; [`Square`] gives them names.
+
+pub mod attacks;
+pub mod chess960;
+pub mod crazyhouse;
+pub mod fen;
+pub mod fog;
+pub mod movegen;
+pub mod san;
+
+use self::crazyhouse::Reserves;
+use crate::constants::TILECOUNT;
+use crate::msg::TileId;
+use crate::square::Square;
+use crate::types::{Color, Type};
+use anyhow::{bail, Result};
+use std::fmt;
+
+pub const STARTING_FEN: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+
+// [`Man`]() is a piece as far as the rules are concerned: a color and a kind,
+// without any of the identity bookkeeping that [`crate::types::Piece`]() carries.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Man {
+    pub color: Color,
+    pub ty: Type,
+}
+
+impl Man {
+    pub const fn new(color: Color, ty: Type) -> Self {
+        Self { color, ty }
+    }
+    // FEN letter for the piece: uppercase for white, lowercase for black.
+    pub fn to_char(self) -> char {
+        let c = match self.ty {
+            Type::Pawn => 'p',
+            Type::Knight => 'n',
+            Type::Bishop => 'b',
+            Type::Rook => 'r',
+            Type::Queen => 'q',
+            Type::King => 'k',
+            Type::Fairy(c) => c.to_ascii_lowercase(),
+        };
+        match self.color {
+            Color::White => c.to_ascii_uppercase(),
+            Color::Black => c,
+        }
+    }
+    pub fn from_char(c: char) -> Option<Self> {
+        let color = if c.is_ascii_uppercase() {
+            Color::White
+        } else {
+            Color::Black
+        };
+        let ty = type_from_char(c)?;
+        Some(Self { color, ty })
+    }
+}
+
+pub fn type_from_char(c: char) -> Option<Type> {
+    match c.to_ascii_lowercase() {
+        'p' => Some(Type::Pawn),
+        'n' => Some(Type::Knight),
+        'b' => Some(Type::Bishop),
+        'r' => Some(Type::Rook),
+        'q' => Some(Type::Queen),
+        'k' => Some(Type::King),
+        _ => None,
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CastleSide {
+    King,
+    Queen,
+}
+
+impl CastleSide {
+    pub(crate) fn index(self) -> usize {
+        match self {
+            CastleSide::King => 0,
+            CastleSide::Queen => 1,
+        }
+    }
+    // The files the king and rook end up on, no matter where they started.
+    pub fn king_dest_file(self) -> usize {
+        match self {
+            CastleSide::King => 6,
+            CastleSide::Queen => 2,
+        }
+    }
+    pub fn rook_dest_file(self) -> usize {
+        match self {
+            CastleSide::King => 5,
+            CastleSide::Queen => 3,
+        }
+    }
+}
+
+// [`Castling`]() remembers the square of every rook which may still castle.
+// Keeping the rook square (rather than a plain flag) is what lets the same rules
+// serve any back rank arrangement.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct Castling {
+    rooks: [[Option<TileId>; 2]; 2],
+}
+
+impl Castling {
+    pub fn rook(&self, color: Color, side: CastleSide) -> Option<TileId> {
+        self.rooks[color.index()][side.index()]
+    }
+    pub fn set(&mut self, color: Color, side: CastleSide, rook: Option<TileId>) {
+        self.rooks[color.index()][side.index()] = rook;
+    }
+    pub fn clear_color(&mut self, color: Color) {
+        self.rooks[color.index()] = [None, None];
+    }
+    // Drops any right that depends on a rook standing on `sq`.
+    pub fn discard_rook(&mut self, sq: TileId) {
+        for side in self.rooks.iter_mut().flatten() {
+            if *side == Some(sq) {
+                *side = None;
+            }
+        }
+    }
+    pub fn is_empty(&self) -> bool {
+        self.rooks.iter().flatten().all(Option::is_none)
+    }
+}
+
+// [`ChessMove`]() is a single ply. Castling is encoded as the king "capturing"
+// its own rook, which keeps the encoding unambiguous for every starting setup;
+// use [`Position::move_to_uci`]() to print the conventional `e1g1` form.
+// A Crazyhouse drop has `from == to` and names the dropped piece in `promotion`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ChessMove {
+    pub from: TileId,
+    pub to: TileId,
+    pub promotion: Option<Type>,
+}
+
+impl ChessMove {
+    pub const fn new(from: TileId, to: TileId) -> Self {
+        Self {
+            from,
+            to,
+            promotion: None,
+        }
+    }
+    pub const fn promoting(from: TileId, to: TileId, ty: Type) -> Self {
+        Self {
+            from,
+            to,
+            promotion: Some(ty),
+        }
+    }
+    pub const fn drop(ty: Type, to: TileId) -> Self {
+        Self::promoting(to, to, ty)
+    }
+    pub fn is_drop(&self) -> bool {
+        self.from == self.to && self.promotion.is_some()
+    }
+    pub fn from_square(&self) -> Square {
+        Square::from_index(self.from).expect("moves stay on the board")
+    }
+    pub fn to_square(&self) -> Square {
+        Square::from_index(self.to).expect("moves stay on the board")
+    }
+}
+
+impl fmt::Display for ChessMove {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let (true, Some(ty)) = (self.is_drop(), self.promotion) {
+            let piece = Man::new(Color::White, ty).to_char();
+            return write!(f, "{piece}@{}", self.to_square());
+        }
+        write!(f, "{}{}", self.from_square(), self.to_square())?;
+        if let Some(ty) = self.promotion {
+            write!(f, "{}", Man::new(Color::Black, ty).to_char())?;
+        }
+        Ok(())
+    }
+}
+
+// Shorthands for [`Square`]() on the bare indices the rules work with. They
+// stay inside the crate; callers outside it use [`Square`]() itself.
+#[inline]
+pub(crate) fn file_of(sq: TileId) -> usize {
+    sq % 8
+}
+
+#[inline]
+pub(crate) fn rank_of(sq: TileId) -> usize {
+    sq / 8
+}
+
+#[inline]
+pub(crate) fn square_at(file: usize, rank: usize) -> TileId {
+    rank * 8 + file
+}
+
+// Steps `dx` files and `dy` ranks away from `sq`, or `None` past the board edge.
+#[inline]
+pub(crate) fn offset(sq: TileId, dx: isize, dy: isize) -> Option<TileId> {
+    Square::from_index(sq)?.offset(dx, dy).map(Square::index)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    Checkmate { winner: Color },
+    Stalemate,
+    FiftyMoves,
+    InsufficientMaterial,
+    Repetition,
+    // The loser's flag fell while the winner could still mate.
+    TimeForfeit { winner: Color },
+    // A flag fell, but the opponent has no way to mate.
+    TimeoutVsInsufficientMaterial,
+    // The players agreed to a draw.
+    Agreement,
+    // In variants without check, taking the king wins.
+    KingCaptured { winner: Color },
+    // Three-check: the winner gave their third check.
+    ThreeChecks { winner: Color },
+    // King of the Hill: the winner's king reached the center.
+    KingOfTheHill { winner: Color },
+    // Atomic: a capture next to the loser's king blew it up.
+    KingExploded { winner: Color },
+    // Antichess: the winner has no pieces left or no move to make.
+    NoMovesLeft { winner: Color },
+    // Racing Kings: the winner's king reached the eighth rank first.
+    RaceWon { winner: Color },
+    // Racing Kings: Black's king followed White's onto the eighth rank.
+    RaceTied,
+    // Horde: the winner took every one of the loser's pieces.
+    AllPiecesCaptured { winner: Color },
+}
+
+impl Outcome {
+    pub fn winner(&self) -> Option<Color> {
+        match self {
+            Outcome::Checkmate { winner }
+            | Outcome::TimeForfeit { winner }
+            | Outcome::KingCaptured { winner }
+            | Outcome::ThreeChecks { winner }
+            | Outcome::KingOfTheHill { winner }
+            | Outcome::KingExploded { winner }
+            | Outcome::NoMovesLeft { winner }
+            | Outcome::RaceWon { winner }
+            | Outcome::AllPiecesCaptured { winner } => Some(*winner),
+            _ => None,
+        }
+    }
+    // The PGN result token, e.g. `1-0`.
+    pub fn result(&self) -> &'static str {
+        match self.winner() {
+            Some(Color::White) => "1-0",
+            Some(Color::Black) => "0-1",
+            None => "1/2-1/2",
+        }
+    }
+    // A short human readable explanation, as used in PGN comments.
+    pub fn reason(&self) -> &'static str {
+        match self {
+            Outcome::Checkmate {
+                winner: Color::White,
+            } => "White mates",
+            Outcome::Checkmate {
+                winner: Color::Black,
+            } => "Black mates",
+            Outcome::Stalemate => "Stalemate",
+            Outcome::FiftyMoves => "Draw by 50 move rule",
+            Outcome::InsufficientMaterial => "Insufficient material",
+            Outcome::Repetition => "Draw by repetition",
+            Outcome::TimeForfeit {
+                winner: Color::White,
+            } => "Black loses on time",
+            Outcome::TimeForfeit {
+                winner: Color::Black,
+            } => "White loses on time",
+            Outcome::TimeoutVsInsufficientMaterial => "Draw by timeout vs insufficient material",
+            Outcome::Agreement => "Draw by agreement",
+            Outcome::KingCaptured {
+                winner: Color::White,
+            } => "White captures the king",
+            Outcome::KingCaptured {
+                winner: Color::Black,
+            } => "Black captures the king",
+            Outcome::ThreeChecks {
+                winner: Color::White,
+            } => "White gives a third check",
+            Outcome::ThreeChecks {
+                winner: Color::Black,
+            } => "Black gives a third check",
+            Outcome::KingOfTheHill {
+                winner: Color::White,
+            } => "White king reaches the hill",
+            Outcome::KingOfTheHill {
+                winner: Color::Black,
+            } => "Black king reaches the hill",
+            Outcome::KingExploded {
+                winner: Color::White,
+            } => "Black king explodes",
+            Outcome::KingExploded {
+                winner: Color::Black,
+            } => "White king explodes",
+            Outcome::NoMovesLeft {
+                winner: Color::White,
+            } => "White runs out of moves",
+            Outcome::NoMovesLeft {
+                winner: Color::Black,
+            } => "Black runs out of moves",
+            Outcome::RaceWon {
+                winner: Color::White,
+            } => "White king reaches the eighth rank",
+            Outcome::RaceWon {
+                winner: Color::Black,
+            } => "Black king reaches the eighth rank",
+            Outcome::RaceTied => "Both kings reach the eighth rank",
+            Outcome::AllPiecesCaptured {
+                winner: Color::White,
+            } => "White captures every Black piece",
+            Outcome::AllPiecesCaptured {
+                winner: Color::Black,
+            } => "Black captures every White piece",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Position {
+    pub board: [Option<Man>; TILECOUNT],
+    pub turn: Color,
+    pub castling: Castling,
+    pub ep: Option<Square>,
+    pub halfmoves: u32,
+    pub fullmoves: u32,
+    // The pieces in hand in Crazyhouse; None in every other game.
+    pub reserves: Option<Reserves>,
+    // How many more checks White and Black need to win in Three-check.
+    pub checks: Option<[u8; 2]>,
+}
+
+impl Default for Position {
+    fn default() -> Self {
+        Self::startpos()
+    }
+}
+
+impl Position {
+    pub fn empty() -> Self {
+        Self {
+            board: [None; TILECOUNT],
+            turn: Color::White,
+            castling: Castling::default(),
+            ep: None,
+            halfmoves: 0,
+            fullmoves: 1,
+            reserves: None,
+            checks: None,
+        }
+    }
+
+    pub fn startpos() -> Self {
+        Self::from_fen(STARTING_FEN).expect("the starting FEN is valid")
+    }
+
+    #[inline]
+    pub fn at(&self, sq: Square) -> Option<Man> {
+        self.board[sq.index()]
+    }
+
+    pub fn king(&self, color: Color) -> Option<Square> {
+        Square::all().find(|&sq| self.at(sq) == Some(Man::new(color, Type::King)))
+    }
+
+    pub fn pieces(&self, color: Color) -> impl Iterator<Item = (Square, Man)> + '_ {
+        Square::all().filter_map(move |sq| match self.at(sq) {
+            Some(man) if man.color == color => Some((sq, man)),
+            _ => None,
+        })
+    }
+
+    pub fn in_check(&self) -> bool {
+        match self.king(self.turn) {
+            Some(sq) => self.is_attacked(sq, self.turn.opposite()),
+            None => false,
+        }
+    }
+
+    // Is `sq` attacked by any piece of color `by`? The square itself may be empty.
+    pub fn is_attacked(&self, sq: Square, by: Color) -> bool {
+        self.attackers(sq, by).next().is_some()
+    }
+
+    // Every square holding a piece of color `by` that attacks `sq`.
+    pub fn attackers(&self, sq: Square, by: Color) -> impl Iterator<Item = Square> + '_ {
+        // A pawn of color `by` attacks `sq` if it stands one rank "behind" it.
+        let back: isize = match by {
+            Color::White => -1,
+            Color::Black => 1,
+        };
+        let pawns = [-1, 1]
+            .into_iter()
+            .filter_map(move |dx| sq.offset(dx, back))
+            .filter(move |&s| self.at(s) == Some(Man::new(by, Type::Pawn)));
+        let leapers = movegen::KNIGHT_STEPS
+            .iter()
+            .filter_map(move |&(dx, dy)| sq.offset(dx, dy))
+            .filter(move |&s| self.at(s) == Some(Man::new(by, Type::Knight)))
+            .chain(
+                movegen::KING_STEPS
+                    .iter()
+                    .filter_map(move |&(dx, dy)| sq.offset(dx, dy))
+                    .filter(move |&s| self.at(s) == Some(Man::new(by, Type::King))),
+            );
+        let sliders = movegen::ROOK_RAYS
+            .iter()
+            .map(|&d| (d, Type::Rook))
+            .chain(movegen::BISHOP_RAYS.iter().map(|&d| (d, Type::Bishop)))
+            .filter_map(move |((dx, dy), ty)| {
+                let hit = self.first_on_ray(sq.index(), dx, dy)?;
+                match self.board[hit] {
+                    Some(man) if man.color == by && (man.ty == ty || man.ty == Type::Queen) => {
+                        Square::from_index(hit)
+                    }
+                    _ => None,
+                }
+            });
+        pawns.chain(leapers).chain(sliders)
+    }
+
+    // The first occupied square walking from `sq` in direction `(dx, dy)`.
+    pub(crate) fn first_on_ray(&self, sq: TileId, dx: isize, dy: isize) -> Option<TileId> {
+        let mut cur = sq;
+        while let Some(next) = offset(cur, dx, dy) {
+            if self.board[next].is_some() {
+                return Some(next);
+            }
+            cur = next;
+        }
+        None
+    }
+
+    pub fn is_castling(&self, mv: &ChessMove) -> bool {
+        match (self.board[mv.from], self.board[mv.to]) {
+            (Some(k), Some(r)) => k.ty == Type::King && r.ty == Type::Rook && k.color == r.color,
+            _ => false,
+        }
+    }
+
+    pub fn is_capture(&self, mv: &ChessMove) -> bool {
+        if self.is_castling(mv) {
+            return false;
+        }
+        self.board[mv.to].is_some() || self.is_en_passant(mv)
+    }
+
+    pub fn is_en_passant(&self, mv: &ChessMove) -> bool {
+        Some(mv.to) == self.ep.map(Square::index)
+            && file_of(mv.from) != file_of(mv.to)
+            && matches!(self.board[mv.from], Some(Man { ty: Type::Pawn, .. }))
+    }
+
+    // The piece removed from the board by `mv`, if any.
+    pub fn captured(&self, mv: &ChessMove) -> Option<Man> {
+        if self.is_castling(mv) {
+            None
+        } else if self.is_en_passant(mv) {
+            Some(Man::new(self.turn.opposite(), Type::Pawn))
+        } else {
+            self.board[mv.to]
+        }
+    }
+
+    // Applies `mv` without checking that it is legal. Use [`Self::play`]() for
+    // input that has not come out of the move generator.
+    pub fn make_move(&mut self, mv: &ChessMove) {
+        if mv.is_drop() {
+            self.make_drop(mv);
+            return;
+        }
+        self.update_reserves(mv);
+        let us = self.turn;
+        let Some(man) = self.board[mv.from] else {
+            return;
+        };
+        let mut reset_clock = man.ty == Type::Pawn;
+        let ep = self.ep.take().map(Square::index);
+
+        if self.is_castling(mv) {
+            let side = if mv.to > mv.from {
+                CastleSide::King
+            } else {
+                CastleSide::Queen
+            };
+            let rank = rank_of(mv.from);
+            self.board[mv.from] = None;
+            self.board[mv.to] = None;
+            self.board[square_at(side.king_dest_file(), rank)] = Some(man);
+            self.board[square_at(side.rook_dest_file(), rank)] = Some(Man::new(us, Type::Rook));
+            self.castling.clear_color(us);
+        } else {
+            if self.board[mv.to].is_some() {
+                reset_clock = true;
+            }
+            if man.ty == Type::Pawn && Some(mv.to) == ep && file_of(mv.from) != file_of(mv.to) {
+                self.board[square_at(file_of(mv.to), rank_of(mv.from))] = None;
+            }
+            // A Horde pawn's double step from the first rank can't be taken
+            // en passant.
+            if man.ty == Type::Pawn
+                && rank_of(mv.from).abs_diff(rank_of(mv.to)) == 2
+                && matches!(rank_of(mv.from), 1 | 6)
+            {
+                self.ep = Square::from_index((mv.from + mv.to) / 2);
+            }
+            self.board[mv.from] = None;
+            self.board[mv.to] = Some(match mv.promotion {
+                Some(ty) => Man::new(us, ty),
+                None => man,
+            });
+            if man.ty == Type::King {
+                self.castling.clear_color(us);
+            }
+            self.castling.discard_rook(mv.from);
+            self.castling.discard_rook(mv.to);
+        }
+
+        self.halfmoves = if reset_clock { 0 } else { self.halfmoves + 1 };
+        if us == Color::Black {
+            self.fullmoves += 1;
+        }
+        self.turn = us.opposite();
+    }
+
+    // Applies `mv` if it is one of the legal moves in this position.
+    pub fn play(&mut self, mv: &ChessMove) -> Result<()> {
+        if !self.legal_moves().contains(mv) {
+            bail!("Illegal move {mv} in {}", self.to_fen());
+        }
+        self.make_move(mv);
+        Ok(())
+    }
+
+    // Parses a move in coordinate notation (`e2e4`, `e7e8q`). Castling is
+    // accepted both as the king's destination (`e1g1`) and as king-takes-rook (`e1h1`).
+    pub fn parse_uci(&self, s: &str) -> Result<ChessMove> {
+        let s = s.trim();
+        if !s.is_ascii() || !(4..=5).contains(&s.len()) {
+            bail!("Malformed move: {s:?}");
+        }
+        if let Some(("P" | "N" | "B" | "R" | "Q", to)) = s.split_once('@') {
+            let ty = type_from_char(s.as_bytes()[0] as char).expect("matched a piece letter");
+            let wanted = ChessMove::drop(ty, to.parse::<Square>()?.index());
+            if !self.legal_moves().contains(&wanted) {
+                bail!("Illegal move: {s}");
+            }
+            return Ok(wanted);
+        }
+        let from = s[0..2].parse::<Square>()?.index();
+        let to = s[2..4].parse::<Square>()?.index();
+        let promotion = match s[4..].chars().next() {
+            Some(c) => {
+                Some(type_from_char(c).ok_or_else(|| anyhow::anyhow!("Malformed move: {s:?}"))?)
+            }
+            None => None,
+        };
+        let legal = self.legal_moves();
+        let wanted = ChessMove {
+            from,
+            to,
+            promotion,
+        };
+        if legal.contains(&wanted) {
+            return Ok(wanted);
+        }
+        // King-to-destination castling notation
+        if let Some(mv) = legal
+            .iter()
+            .find(|mv| self.is_castling(mv) && mv.from == from && self.castle_king_dest(mv) == to)
+        {
+            return Ok(*mv);
+        }
+        bail!("Illegal move: {s}")
+    }
+
+    // Prints `mv` in coordinate notation, writing castling from the classical
+    // squares as the king's destination. Any other castling move keeps the
+    // king-takes-rook form, since e.g. `f1g1` would also be a king step.
+    pub fn move_to_uci(&self, mv: &ChessMove) -> String {
+        if self.is_castling(mv) && file_of(mv.from) == 4 && [0, 7].contains(&file_of(mv.to)) {
+            ChessMove::new(mv.from, self.castle_king_dest(mv)).to_string()
+        } else {
+            mv.to_string()
+        }
+    }
+
+    pub(crate) fn castle_king_dest(&self, mv: &ChessMove) -> TileId {
+        let side = if mv.to > mv.from {
+            CastleSide::King
+        } else {
+            CastleSide::Queen
+        };
+        square_at(side.king_dest_file(), rank_of(mv.from))
+    }
+
+    pub fn is_checkmate(&self) -> bool {
+        self.in_check() && self.legal_moves().is_empty()
+    }
+
+    pub fn is_stalemate(&self) -> bool {
+        !self.in_check() && self.legal_moves().is_empty()
+    }
+
+    // Neither side can possibly deliver mate: bare kings, a single minor piece,
+    // or bishops which all stand on the same shade.
+    pub fn is_insufficient_material(&self) -> bool {
+        // Pieces in hand can always be dropped back in.
+        if self.reserves.is_some() {
+            return false;
+        }
+        let mut minors = 0;
+        let mut bishop_shades = [false; 2];
+        for (sq, man) in (0..TILECOUNT).filter_map(|sq| self.board[sq].map(|m| (sq, m))) {
+            match man.ty {
+                Type::King => {}
+                Type::Knight => minors += 1,
+                Type::Bishop => {
+                    minors += 1;
+                    bishop_shades[(file_of(sq) + rank_of(sq)) % 2] = true;
+                }
+                _ => return false,
+            }
+        }
+        let bishops_only = (0..TILECOUNT).all(|sq| {
+            !matches!(
+                self.board[sq],
+                Some(Man {
+                    ty: Type::Knight,
+                    ..
+                })
+            )
+        });
+        minors <= 1 || (bishops_only && !(bishop_shades[0] && bishop_shades[1]))
+    }
+
+    // Whether `color` could mate with any series of legal moves, which decides
+    // if running out of time loses or draws. Lone knights and bishops only
+    // mate when the other side has men to block their own king with.
+    pub fn has_mating_material(&self, color: Color) -> bool {
+        if self.reserves.is_some() {
+            return true;
+        }
+        let mut knights = 0;
+        let mut bishops = 0;
+        for (_, man) in self.pieces(color) {
+            match man.ty {
+                Type::King => {}
+                Type::Knight => knights += 1,
+                Type::Bishop => bishops += 1,
+                _ => return true,
+            }
+        }
+        let opponent_blocks = self
+            .pieces(color.opposite())
+            .any(|(_, man)| !matches!(man.ty, Type::King | Type::Queen));
+        match (knights, bishops) {
+            (0, 0) => false,
+            (1, 0) => opponent_blocks,
+            (0, _) => {
+                // Bishops all on one shade mate only with a knight or pawn in the way.
+                let mut shades = [false; 2];
+                let mut helpers = false;
+                for (sq, man) in (0..TILECOUNT).filter_map(|sq| self.board[sq].map(|m| (sq, m))) {
+                    match man.ty {
+                        Type::Bishop => shades[(file_of(sq) + rank_of(sq)) % 2] = true,
+                        Type::Knight | Type::Pawn => helpers = true,
+                        _ => {}
+                    }
+                }
+                (shades[0] && shades[1]) || helpers
+            }
+            _ => true,
+        }
+    }
+
+    // Game end conditions that can be read off the position alone. Repetitions
+    // need the game's history and are left to the caller.
+    pub fn outcome(&self) -> Option<Outcome> {
+        if self.legal_moves().is_empty() {
+            return Some(if self.in_check() {
+                Outcome::Checkmate {
+                    winner: self.turn.opposite(),
+                }
+            } else {
+                Outcome::Stalemate
+            });
+        }
+        if self.is_insufficient_material() {
+            return Some(Outcome::InsufficientMaterial);
+        }
+        if self.halfmoves >= 100 {
+            return Some(Outcome::FiftyMoves);
+        }
+        None
+    }
+
+    // A 64 bit hash of everything that decides whether two positions repeat:
+    // the pieces, the side to move, castling rights, the en passant square and
+    // any variant state such as reserves or checks.
+    pub fn hash(&self) -> u64 {
+        let mut h: u64 = 0;
+        for sq in 0..TILECOUNT {
+            if let Some(man) = self.board[sq] {
+                h ^= zobrist(man_index(man) * TILECOUNT + sq);
+            }
+        }
+        if self.turn == Color::Black {
+            h ^= zobrist(12 * TILECOUNT);
+        }
+        for color in [Color::White, Color::Black] {
+            for side in [CastleSide::King, CastleSide::Queen] {
+                if let Some(rook) = self.castling.rook(color, side) {
+                    h ^= zobrist(12 * TILECOUNT + 1 + rook);
+                }
+            }
+        }
+        if let Some(ep) = self.ep.filter(|&ep| self.ep_capturable(ep)) {
+            h ^= zobrist(13 * TILECOUNT + 1 + ep.index());
+        }
+        if let Some(reserves) = self.reserves {
+            // Up to 16 of each kind in hand, and which pieces were promoted.
+            for (c, color) in [Color::White, Color::Black].into_iter().enumerate() {
+                for (t, ty) in crazyhouse::DROPPABLE.into_iter().enumerate() {
+                    let n = reserves.count(color, ty) as usize;
+                    h ^= zobrist(15 * TILECOUNT + 1 + (c * 5 + t) * 17 + n);
+                }
+            }
+            for sq in Square::all().filter(|&sq| reserves.is_promoted(sq)) {
+                h ^= zobrist(14 * TILECOUNT + 1 + sq.index());
+            }
+        }
+        if let Some(checks) = self.checks {
+            for (c, left) in checks.into_iter().enumerate() {
+                h ^= zobrist(18 * TILECOUNT + c * 256 + left as usize);
+            }
+        }
+        h
+    }
+
+    // An en passant square only matters if a pawn stands ready to capture onto it.
+    pub(crate) fn ep_capturable(&self, ep: Square) -> bool {
+        [-1, 1]
+            .into_iter()
+            .filter_map(|dx| ep.forward(self.turn, -1)?.offset(dx, 0))
+            .any(|sq| self.at(sq) == Some(Man::new(self.turn, Type::Pawn)))
+    }
+}
+
+pub(crate) fn man_index(man: Man) -> usize {
+    let ty = match man.ty {
+        Type::Pawn => 0,
+        Type::Knight => 1,
+        Type::Bishop => 2,
+        Type::Rook => 3,
+        Type::Queen => 4,
+        Type::King => 5,
+        Type::Fairy(_) => unreachable!("a position only holds orthodox pieces"),
+    };
+    ty * 2 + man.color.index()
+}
+
+// splitmix64 over the key index gives well spread, reproducible keys without a table.
+#[inline]
+fn zobrist(n: usize) -> u64 {
+    let mut z = (n as u64)
+        .wrapping_add(1)
+        .wrapping_mul(0x9E37_79B9_7F4A_7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+impl fmt::Display for Position {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for rank in (0..8).rev() {
+            for file in 0..8 {
+                let c = self.board[square_at(file, rank)].map_or('.', Man::to_char);
+                write!(f, "{c}")?;
+                if file < 7 {
+                    write!(f, " ")?;
+                }
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+// The number of move sequences `depth` plies long, by the standard rules
+// plus whatever the position carries, such as Crazyhouse reserves.
+#[cfg(test)]
+pub(crate) fn perft(pos: &Position, depth: u32) -> u64 {
+    if depth == 0 {
+        return 1;
+    }
+    let moves = pos.legal_moves();
+    if depth == 1 {
+        return moves.len() as u64;
+    }
+    moves
+        .iter()
+        .map(|mv| {
+            let mut next = *pos;
+            next.make_move(mv);
+            perft(&next, depth - 1)
+        })
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constants::*;
+
+    #[test]
+    fn perft_start_position() {
+        let pos = Position::startpos();
+        assert_eq!(perft(&pos, 1), 20);
+        assert_eq!(perft(&pos, 2), 400);
+        assert_eq!(perft(&pos, 3), 8902);
+    }
+
+    #[test]
+    fn perft_kiwipete() {
+        let pos = Position::from_fen(
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        )
+        .unwrap();
+        assert_eq!(perft(&pos, 1), 48);
+        assert_eq!(perft(&pos, 2), 2039);
+        assert_eq!(perft(&pos, 3), 97862);
+    }
+
+    #[test]
+    fn perft_promotions_and_pins() {
+        let pos = Position::from_fen("8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1").unwrap();
+        assert_eq!(perft(&pos, 3), 2812);
+        let pos =
+            Position::from_fen("r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1")
+                .unwrap();
+        assert_eq!(perft(&pos, 3), 9467);
+    }
+
+    #[test]
+    fn castling_moves_king_and_rook() {
+        let mut pos = Position::from_fen("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1").unwrap();
+        let mv = pos.parse_uci("e1g1").unwrap();
+        assert_eq!(mv, ChessMove::new(E1, H1));
+        pos.play(&mv).unwrap();
+        assert_eq!(pos.at(Square::G1), Some(Man::new(Color::White, Type::King)));
+        assert_eq!(pos.at(Square::F1), Some(Man::new(Color::White, Type::Rook)));
+        assert_eq!(pos.castling.rook(Color::White, CastleSide::Queen), None);
+        assert_eq!(pos.castling.rook(Color::Black, CastleSide::Queen), Some(A8));
+    }
+
+    #[test]
+    fn fools_mate_is_checkmate() {
+        let mut pos = Position::startpos();
+        for mv in ["f2f3", "e7e5", "g2g4", "d8h4"] {
+            let mv = pos.parse_uci(mv).unwrap();
+            pos.play(&mv).unwrap();
+        }
+        assert_eq!(
+            pos.outcome(),
+            Some(Outcome::Checkmate {
+                winner: Color::Black
+            })
+        );
+    }
+
+    #[test]
+    fn bare_kings_and_same_shade_bishops_are_dead() {
+        let kk = Position::from_fen("8/8/4k3/8/8/3K4/8/8 w - - 0 1").unwrap();
+        assert!(kk.is_insufficient_material());
+        let same_shade = Position::from_fen("8/3b4/4k3/8/8/3K4/4B3/8 w - - 0 1").unwrap();
+        assert!(same_shade.is_insufficient_material());
+        let opposite_shade = Position::from_fen("8/2b5/4k3/8/8/3K4/4B3/8 w - - 0 1").unwrap();
+        assert!(!opposite_shade.is_insufficient_material());
+    }
+
+    #[test]
+    fn lone_minor_pieces_mate_only_with_help() {
+        let mates = |fen: &str, color| Position::from_fen(fen).unwrap().has_mating_material(color);
+        // A knight against a bare king, or against a queen that cannot block.
+        assert!(!mates("8/8/4k3/8/8/3K4/4N3/8 w - - 0 1", Color::White));
+        assert!(!mates("8/8/4k3/8/3q4/3K4/4N3/8 w - - 0 1", Color::White));
+        assert!(mates("8/8/4k3/4p3/8/3K4/4N3/8 w - - 0 1", Color::White));
+        assert!(mates("8/8/4k3/8/8/3K4/3NN3/8 w - - 0 1", Color::White));
+        // Bishops need the other shade or something to get in the way.
+        assert!(!mates("8/3b4/4k3/8/8/3K4/4B3/8 w - - 0 1", Color::White));
+        assert!(mates("8/2b5/4k3/8/8/3K4/4B3/8 w - - 0 1", Color::White));
+        assert!(!mates("8/8/4k3/8/8/3K4/4B3/8 w - - 0 1", Color::White));
+        assert!(mates("8/8/4k3/8/8/3K4/4P3/8 w - - 0 1", Color::White));
+        assert!(!mates("8/8/4k3/8/8/3K4/4P3/8 w - - 0 1", Color::Black));
+    }
+}
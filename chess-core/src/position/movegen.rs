@@ -0,0 +1,197 @@
+//! chess_core::position::movegen
+//!
+//! Legal move generation for [`Position`]. Pieces move by stepping through
+//! `(file, rank)` offsets, the same way [`crate::game::math::XyPair`] reasons
+//! about the board, so nothing here depends on a clever square encoding.
+
+use super::{file_of, offset, rank_of, square_at, CastleSide, ChessMove, Man, Position};
+use crate::msg::TileId;
+use crate::square::Square;
+use crate::types::{Color, Type};
+
+pub const KNIGHT_STEPS: [(isize, isize); 8] = [
+    (1, 2),
+    (2, 1),
+    (2, -1),
+    (1, -2),
+    (-1, -2),
+    (-2, -1),
+    (-2, 1),
+    (-1, 2),
+];
+
+pub const KING_STEPS: [(isize, isize); 8] = [
+    (0, 1),
+    (1, 1),
+    (1, 0),
+    (1, -1),
+    (0, -1),
+    (-1, -1),
+    (-1, 0),
+    (-1, 1),
+];
+
+pub const ROOK_RAYS: [(isize, isize); 4] = [(0, 1), (1, 0), (0, -1), (-1, 0)];
+pub const BISHOP_RAYS: [(isize, isize); 4] = [(1, 1), (1, -1), (-1, -1), (-1, 1)];
+
+pub const PROMOTIONS: [Type; 4] = [Type::Queen, Type::Rook, Type::Bishop, Type::Knight];
+
+impl Position {
+    pub fn legal_moves(&self) -> Vec<ChessMove> {
+        let mut moves = Vec::with_capacity(48);
+        self.pseudo_legal_moves(&mut moves);
+        moves.retain(|mv| self.is_safe(mv));
+        moves
+    }
+
+    // Does playing `mv` leave the mover's own king out of check?
+    fn is_safe(&self, mv: &ChessMove) -> bool {
+        let mut next = *self;
+        next.make_move(mv);
+        match next.king(self.turn) {
+            Some(k) => !next.is_attacked(k, self.turn.opposite()),
+            None => true,
+        }
+    }
+
+    // Moves that obey how each piece travels, ignoring whether the mover's king
+    // is left in check. Castling is only produced when it is fully legal.
+    pub fn pseudo_legal_moves(&self, out: &mut Vec<ChessMove>) {
+        let us = self.turn;
+        for from in 0..self.board.len() {
+            let Some(man) = self.board[from] else {
+                continue;
+            };
+            if man.color != us {
+                continue;
+            }
+            match man.ty {
+                Type::Pawn => self.pawn_moves(from, out),
+                Type::Knight => self.leaper_moves(from, &KNIGHT_STEPS, out),
+                Type::King => {
+                    self.leaper_moves(from, &KING_STEPS, out);
+                    self.castling_moves(from, true, out);
+                }
+                Type::Bishop => self.slider_moves(from, &BISHOP_RAYS, out),
+                Type::Rook => self.slider_moves(from, &ROOK_RAYS, out),
+                Type::Queen => {
+                    self.slider_moves(from, &ROOK_RAYS, out);
+                    self.slider_moves(from, &BISHOP_RAYS, out);
+                }
+                Type::Fairy(_) => unreachable!("a position only holds orthodox pieces"),
+            }
+        }
+        self.drop_moves(out);
+    }
+
+    fn leaper_moves(&self, from: TileId, steps: &[(isize, isize)], out: &mut Vec<ChessMove>) {
+        for &(dx, dy) in steps {
+            if let Some(to) = offset(from, dx, dy) {
+                match self.board[to] {
+                    Some(Man { color, .. }) if color == self.turn => {}
+                    _ => out.push(ChessMove::new(from, to)),
+                }
+            }
+        }
+    }
+
+    fn slider_moves(&self, from: TileId, rays: &[(isize, isize)], out: &mut Vec<ChessMove>) {
+        for &(dx, dy) in rays {
+            let mut cur = from;
+            while let Some(to) = offset(cur, dx, dy) {
+                match self.board[to] {
+                    None => out.push(ChessMove::new(from, to)),
+                    Some(Man { color, .. }) => {
+                        if color != self.turn {
+                            out.push(ChessMove::new(from, to));
+                        }
+                        break;
+                    }
+                }
+                cur = to;
+            }
+        }
+    }
+
+    fn pawn_moves(&self, from: TileId, out: &mut Vec<ChessMove>) {
+        let (dy, first_rank, start_rank, last_rank) = match self.turn {
+            Color::White => (1, 0, 1, 7),
+            Color::Black => (-1, 7, 6, 0),
+        };
+        let push = |to: TileId, out: &mut Vec<ChessMove>| {
+            if rank_of(to) == last_rank {
+                for ty in PROMOTIONS {
+                    out.push(ChessMove::promoting(from, to, ty));
+                }
+            } else {
+                out.push(ChessMove::new(from, to));
+            }
+        };
+        if let Some(one) = offset(from, 0, dy) {
+            if self.board[one].is_none() {
+                push(one, out);
+                // Horde pawns on the first rank may also advance two squares.
+                if rank_of(from) == start_rank || rank_of(from) == first_rank {
+                    if let Some(two) = offset(one, 0, dy) {
+                        if self.board[two].is_none() {
+                            out.push(ChessMove::new(from, two));
+                        }
+                    }
+                }
+            }
+        }
+        for dx in [-1, 1] {
+            if let Some(to) = offset(from, dx, dy) {
+                let enemy = matches!(self.board[to], Some(Man { color, .. }) if color != self.turn);
+                if enemy || Some(to) == self.ep.map(Square::index) {
+                    push(to, out);
+                }
+            }
+        }
+    }
+
+    // With `mind_attacks` off the king may castle out of, through or into an
+    // attack, as in variants that know no check.
+    pub(crate) fn castling_moves(
+        &self,
+        king: TileId,
+        mind_attacks: bool,
+        out: &mut Vec<ChessMove>,
+    ) {
+        let us = self.turn;
+        let them = us.opposite();
+        for side in [CastleSide::King, CastleSide::Queen] {
+            let Some(rook) = self.castling.rook(us, side) else {
+                continue;
+            };
+            if self.board[rook] != Some(Man::new(us, Type::Rook)) || rank_of(rook) != rank_of(king)
+            {
+                continue;
+            }
+            let rank = rank_of(king);
+            let king_to = square_at(side.king_dest_file(), rank);
+            let rook_to = square_at(side.rook_dest_file(), rank);
+            let span = |a: TileId, b: TileId| a.min(b)..=a.max(b);
+            let blocked = span(king, king_to)
+                .chain(span(rook, rook_to))
+                .any(|sq| sq != king && sq != rook && self.board[sq].is_some());
+            if blocked {
+                continue;
+            }
+            // The king may not start on, pass through, or land on an attacked square.
+            // The landing square is left to the ordinary legality filter since the
+            // rook may currently be shielding it.
+            let attacked = mind_attacks
+                && span(king, king_to)
+                    .filter(|&sq| sq != king_to)
+                    .filter_map(Square::from_index)
+                    .any(|sq| self.is_attacked(sq, them));
+            if attacked {
+                continue;
+            }
+            debug_assert_eq!(rank_of(king_to), rank_of(rook));
+            debug_assert!(file_of(king_to) < 8);
+            out.push(ChessMove::new(king, rook));
+        }
+    }
+}
@@ -0,0 +1,242 @@
+//! chess_core::position::san
+//!
+//! Standard Algebraic Notation, the move format of PGN files: `Nf3`, `exd5`,
+//! `O-O`, `e8=Q+`, and Crazyhouse drops such as `N@f3`.
+
+use super::{file_of, rank_of, type_from_char, ChessMove, Man, Position};
+use crate::square::Square;
+use crate::types::{Color, Type};
+use anyhow::{anyhow, bail, Result};
+
+impl Position {
+    // Prints `mv`, which must be legal here, in SAN including the check or mate suffix.
+    pub fn move_to_san(&self, mv: &ChessMove) -> String {
+        let mut san = self.san_without_suffix(mv);
+        let mut next = *self;
+        next.make_move(mv);
+        if next.in_check() {
+            san.push(if next.legal_moves().is_empty() {
+                '#'
+            } else {
+                '+'
+            });
+        }
+        san
+    }
+
+    fn san_without_suffix(&self, mv: &ChessMove) -> String {
+        if self.is_castling(mv) {
+            return if mv.to > mv.from { "O-O" } else { "O-O-O" }.to_string();
+        }
+        if mv.is_drop() {
+            return mv.to_string();
+        }
+        let Some(man) = self.at(mv.from_square()) else {
+            return mv.to_string();
+        };
+        let capture = self.is_capture(mv);
+        let mut san = String::new();
+        if man.ty == Type::Pawn {
+            if capture {
+                san.push((b'a' + file_of(mv.from) as u8) as char);
+            }
+        } else {
+            san.push(Man::new(Color::White, man.ty).to_char());
+            // Other pieces of the same kind that could also go to `mv.to`.
+            let rivals: Vec<_> = self
+                .legal_moves()
+                .into_iter()
+                .filter(|other| {
+                    other.to == mv.to
+                        && other.from != mv.from
+                        && !self.is_castling(other)
+                        && self.at(other.from_square()) == Some(man)
+                })
+                .collect();
+            if !rivals.is_empty() {
+                let file_unique = rivals.iter().all(|o| file_of(o.from) != file_of(mv.from));
+                let rank_unique = rivals.iter().all(|o| rank_of(o.from) != rank_of(mv.from));
+                let square = mv.from_square();
+                if file_unique {
+                    san.push(square.file().to_char());
+                } else if rank_unique {
+                    san.push(square.rank().to_char());
+                } else {
+                    san.push_str(&square.to_string());
+                }
+            }
+        }
+        if capture {
+            san.push('x');
+        }
+        san.push_str(&mv.to_square().to_string());
+        if let Some(ty) = mv.promotion {
+            san.push('=');
+            san.push(Man::new(Color::White, ty).to_char());
+        }
+        san
+    }
+
+    // Parses a SAN move. Check marks and annotations (`+`, `#`, `!`, `?`) are
+    // ignored, and so is superfluous disambiguation such as `Ngf3`.
+    pub fn parse_san(&self, san: &str) -> Result<ChessMove> {
+        let text = san.trim().trim_end_matches(['+', '#', '!', '?']);
+        let legal = self.legal_moves();
+        let castle = match text {
+            "O-O" | "0-0" => Some(true),
+            "O-O-O" | "0-0-0" => Some(false),
+            _ => None,
+        };
+        if let Some(king_side) = castle {
+            return legal
+                .into_iter()
+                .find(|mv| self.is_castling(mv) && (mv.to > mv.from) == king_side)
+                .ok_or_else(|| anyhow!("Illegal move: {san}"));
+        }
+
+        let malformed = || anyhow!("Malformed move: {san:?}");
+        if !text.is_ascii() || text.len() < 2 {
+            return Err(malformed());
+        }
+        // Pawn drops may leave out the `P`.
+        if let Some((piece, to)) = text.split_once('@') {
+            let ty = match piece {
+                "" => Type::Pawn,
+                _ => type_from_char(piece.chars().next().ok_or_else(malformed)?)
+                    .filter(|_| piece.len() == 1)
+                    .ok_or_else(malformed)?,
+            };
+            let mv = ChessMove::drop(ty, to.parse::<Square>()?.index());
+            return match legal.contains(&mv) {
+                true => Ok(mv),
+                false => bail!("Illegal move: {san}"),
+            };
+        }
+        let (ty, rest) = match text.chars().next() {
+            Some(c @ ('N' | 'B' | 'R' | 'Q' | 'K')) => {
+                (type_from_char(c).ok_or_else(malformed)?, &text[1..])
+            }
+            _ => (Type::Pawn, text),
+        };
+        let (rest, promotion) = match rest.split_once('=') {
+            Some((rest, promo)) => {
+                let c = promo.chars().next().ok_or_else(malformed)?;
+                (rest, Some(type_from_char(c).ok_or_else(malformed)?))
+            }
+            // Some writers leave out the `=`, as in `e8Q`.
+            None => match rest.chars().last() {
+                Some(c @ ('N' | 'B' | 'R' | 'Q')) if ty == Type::Pawn => {
+                    (&rest[..rest.len() - 1], type_from_char(c))
+                }
+                _ => (rest, None),
+            },
+        };
+        if rest.len() < 2 {
+            return Err(malformed());
+        }
+        let to = rest[rest.len() - 2..].parse::<Square>()?.index();
+        let hint = rest[..rest.len() - 2].trim_end_matches(['x', ':', '-']);
+        let mut from_file = None;
+        let mut from_rank = None;
+        for c in hint.chars() {
+            match c {
+                'a'..='h' => from_file = Some(c as usize - 'a' as usize),
+                '1'..='8' => from_rank = Some(c as usize - '1' as usize),
+                _ => return Err(malformed()),
+            }
+        }
+
+        let mut candidates = legal.into_iter().filter(|mv| {
+            mv.to == to
+                && mv.promotion == promotion
+                && !self.is_castling(mv)
+                && self.at(mv.from_square()).is_some_and(|m| m.ty == ty)
+                && from_file.is_none_or(|f| file_of(mv.from) == f)
+                && from_rank.is_none_or(|r| rank_of(mv.from) == r)
+        });
+        match (candidates.next(), candidates.next()) {
+            (Some(mv), None) => Ok(mv),
+            (Some(_), Some(_)) => bail!("Ambiguous move: {san}"),
+            (None, _) => bail!("Illegal move: {san}"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn san_line(fen: &str, uci: &[&str]) -> Vec<String> {
+        let mut pos = Position::from_fen(fen).unwrap();
+        uci.iter()
+            .map(|text| {
+                let mv = pos.parse_uci(text).unwrap();
+                let san = pos.move_to_san(&mv);
+                assert_eq!(pos.parse_san(&san).unwrap(), mv, "{san}");
+                pos.make_move(&mv);
+                san
+            })
+            .collect()
+    }
+
+    #[test]
+    fn writes_and_reads_back() {
+        let start = crate::position::STARTING_FEN;
+        assert_eq!(
+            san_line(start, &["e2e4", "e7e5", "g1f3", "b8c6", "f1b5", "a7a6"]),
+            ["e4", "e5", "Nf3", "Nc6", "Bb5", "a6"]
+        );
+        assert_eq!(
+            san_line(start, &["f2f3", "e7e5", "g2g4", "d8h4"]),
+            ["f3", "e5", "g4", "Qh4#"]
+        );
+        assert_eq!(
+            san_line(
+                "r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1",
+                &["e1g1", "e8c8", "a1a8"]
+            ),
+            ["O-O", "O-O-O", "Ra8+"]
+        );
+        assert_eq!(
+            san_line(
+                "4k3/1P6/8/3pP3/8/8/8/4K3 w - d6 0 1",
+                &["e5d6", "e8d7", "b7b8n"]
+            ),
+            ["exd6", "Kd7", "b8=N+"]
+        );
+        assert_eq!(
+            san_line("4k3/8/8/8/8/8/8/4K3[N] w - - 0 1", &["N@d6"]),
+            ["N@d6+"]
+        );
+    }
+
+    #[test]
+    fn disambiguates() {
+        // Knights on b1 and f1 both reach d2; rooks on a1 and a5 both reach a3.
+        let fen = "4k3/8/8/R7/8/8/8/RN2KN2 w - - 0 1";
+        assert_eq!(san_line(fen, &["b1d2"]), ["Nbd2"]);
+        assert_eq!(san_line(fen, &["a1a3"]), ["R1a3"]);
+        // Three queens that all see b3 need the full square.
+        let fen = "7k/8/8/8/Q7/8/Q1Q5/4K3 w - - 0 1";
+        assert_eq!(san_line(fen, &["a2b3"]), ["Qa2b3"]);
+    }
+
+    #[test]
+    fn parsing_is_lenient_but_not_sloppy() {
+        let pos = Position::startpos();
+        assert_eq!(
+            pos.parse_san("Ngf3").unwrap(),
+            pos.parse_uci("g1f3").unwrap()
+        );
+        assert_eq!(
+            pos.parse_san("e4!?").unwrap(),
+            pos.parse_uci("e2e4").unwrap()
+        );
+        assert!(pos.parse_san("Nc3").is_ok());
+        assert!(pos.parse_san("Nd2").is_err());
+        assert!(pos.parse_san("O-O").is_err());
+        assert!(pos.parse_san("Zz9").is_err());
+        let pos = Position::from_fen("4k3/8/8/8/8/8/8/RN2KN2 w - - 0 1").unwrap();
+        assert!(pos.parse_san("Nd2").is_err());
+    }
+}
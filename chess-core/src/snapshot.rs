@@ -0,0 +1,458 @@
+//! chess_core::snapshot
+//!
+//! What stands on the board at one moment, owned outright. A
+//! [`BoardSnapshot`] copies the id, color, kind and tile of every piece out
+//! of a [`GameState`], so it borrows nothing from the [`crate::GameMaster`]
+//! and holds no reference-counted cells: it is `Send + Sync`, clones by
+//! bumping a reference count, and can go to a render thread or over the
+//! network. A player's snapshot of a fog of war game leaves out what they
+//! cannot see and lists those tiles as hidden.
+//!
+//! [`BoardSnapshot::diff`] compares a snapshot with a later one and
+//! [`BoardSnapshot::changes`] works out what a move will do to it, both as a
+//! short list of [`Change`]s, so that a front end can animate just the pieces
+//! that moved and a server can send only those.
+//!
+//! ```
+//! use chess_core::snapshot::BoardSnapshot;
+//! use chess_core::square::Square;
+//! use chess_core::types::Type;
+//!
+//! let mut gm = chess_core::spawn_game_master();
+//! let game_id = gm.create_game().unwrap();
+//! let snapshot = gm.request_snapshot(game_id).unwrap();
+//! let queen = snapshot.at(Square::D1.index()).unwrap();
+//! assert_eq!((queen.ty, queen.id), (Type::Queen, 4));
+//!
+//! let render = std::thread::spawn(move || snapshot.pieces().count());
+//! assert_eq!(render.join().unwrap(), 32);
+//! ```
+
+use crate::game::math::XyPair;
+use crate::game::GameState;
+use crate::geometry::Geometry;
+use crate::msg::{PieceId, TileId};
+use crate::position::{rank_of, square_at, CastleSide, ChessMove, Man, Position};
+use crate::types::{Background, Color, Piece, Type};
+use crate::variant::Variant;
+use std::sync::Arc;
+
+// One thing that happened to the pieces between two snapshots.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Change {
+    Moved {
+        id: PieceId,
+        from: TileId,
+        to: TileId,
+    },
+    // The rook that castled, which moved alongside its king.
+    CastlingRook {
+        id: PieceId,
+        from: TileId,
+        to: TileId,
+    },
+    // The piece was taken off the board from `at`.
+    Captured {
+        id: PieceId,
+        at: TileId,
+    },
+    // The piece on `at` became a `to`.
+    Promoted {
+        id: PieceId,
+        at: TileId,
+        to: Type,
+    },
+    // A piece came onto the board from a reserve. Its id is only known once
+    // it stands there, so [`BoardSnapshot::changes`]() gives None.
+    Dropped {
+        id: Option<PieceId>,
+        color: Color,
+        ty: Type,
+        at: TileId,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BoardSnapshot {
+    // The shape of the board, which has a tile for each of its squares.
+    pub geometry: Geometry,
+    // The piece on each tile in board order, if any can be seen.
+    tiles: Arc<[Option<Piece>]>,
+    // Which tiles the viewer cannot see, in board order.
+    hidden: Arc<[bool]>,
+}
+
+impl BoardSnapshot {
+    // The whole board, as spectators see it.
+    pub fn of(game: &GameState) -> Self {
+        let tiles: Vec<Option<Piece>> = game
+            .board
+            .iter()
+            .map(|tile| {
+                let pz = tile.pz.as_ref()?.upgrade()?;
+                let piece = pz.borrow().clone();
+                Some(piece)
+            })
+            .collect();
+        Self {
+            geometry: game.geometry,
+            hidden: vec![false; tiles.len()].into(),
+            tiles: tiles.into(),
+        }
+    }
+
+    // What `color` sees of a fog of war game.
+    pub fn seen_by(game: &GameState, color: Color) -> Self {
+        let seen = game.hist.position().visible_squares(color);
+        let mut tiles = Self::of(game).tiles.to_vec();
+        let mut hidden = vec![false; tiles.len()];
+        for (idx, _) in seen.iter().enumerate().filter(|(_, &seen)| !seen) {
+            tiles[idx] = None;
+            hidden[idx] = true;
+        }
+        Self {
+            geometry: game.geometry,
+            tiles: tiles.into(),
+            hidden: hidden.into(),
+        }
+    }
+
+    pub fn tile_count(&self) -> usize {
+        self.tiles.len()
+    }
+
+    // The piece on tile `loc`; None if it is empty, hidden or off the board.
+    pub fn at(&self, loc: TileId) -> Option<&Piece> {
+        self.tiles.get(loc)?.as_ref()
+    }
+
+    pub fn piece(&self, id: PieceId) -> Option<&Piece> {
+        self.pieces().find(|piece| piece.id == id)
+    }
+
+    // Every piece that can be seen, in board order.
+    pub fn pieces(&self) -> impl Iterator<Item = &Piece> + '_ {
+        self.tiles.iter().flatten()
+    }
+
+    pub fn is_hidden(&self, loc: TileId) -> bool {
+        self.hidden.get(loc).copied().unwrap_or(false)
+    }
+
+    pub fn hidden(&self) -> impl Iterator<Item = TileId> + '_ {
+        self.hidden
+            .iter()
+            .enumerate()
+            .filter(|(_, &hidden)| hidden)
+            .map(|(idx, _)| idx)
+    }
+
+    // Each tile with its coordinates, shade and piece, for drawing the board.
+    pub fn tiles(&self) -> impl Iterator<Item = (TileId, XyPair, Background, Option<&Piece>)> + '_ {
+        self.tiles.iter().enumerate().map(|(idx, piece)| {
+            let geometry = &self.geometry;
+            (
+                idx,
+                geometry.index_to_xy(idx),
+                geometry.background(idx),
+                piece.as_ref(),
+            )
+        })
+    }
+
+    // What happened between this snapshot and a later one of the same game,
+    // with pieces told apart by id: captures first, then moves and
+    // promotions, then drops. A king and a rook of one side moving along the
+    // same rank castled, and so did a rook jumping over its king, which in
+    // Chess960 may already stand where castling puts it. In fog of war a
+    // piece that goes out of sight looks captured.
+    pub fn diff(&self, after: &BoardSnapshot) -> Vec<Change> {
+        let rank = |loc: TileId| self.geometry.index_to_xy(loc).y;
+        let mut changes = Vec::new();
+        let mut moves = Vec::new();
+        let mut promotions = Vec::new();
+        for piece in self.pieces() {
+            let Some(now) = after.piece(piece.id) else {
+                changes.push(Change::Captured {
+                    id: piece.id,
+                    at: piece.loc,
+                });
+                continue;
+            };
+            if now.loc != piece.loc {
+                moves.push((piece, now.loc));
+            }
+            if now.ty != piece.ty {
+                promotions.push(Change::Promoted {
+                    id: piece.id,
+                    at: now.loc,
+                    to: now.ty,
+                });
+            }
+        }
+        let castled = |color: Color, from: TileId, to: TileId| {
+            let king_moved = moves.iter().any(|&(king, king_to)| {
+                king.ty == Type::King
+                    && king.color == color
+                    && rank(king.loc) == rank(from)
+                    && rank(king_to) == rank(from)
+            });
+            // No other rook move passes its own king.
+            let jumped_king = self.pieces().any(|king| {
+                king.ty == Type::King
+                    && king.color == color
+                    && (from.min(to)..from.max(to)).contains(&king.loc)
+            });
+            rank(from) == rank(to) && (king_moved || jumped_king)
+        };
+        for &(piece, to) in &moves {
+            let (id, from) = (piece.id, piece.loc);
+            changes.push(
+                match piece.ty == Type::Rook && castled(piece.color, from, to) {
+                    true => Change::CastlingRook { id, from, to },
+                    false => Change::Moved { id, from, to },
+                },
+            );
+        }
+        changes.extend(promotions);
+        for piece in after
+            .pieces()
+            .filter(|piece| self.piece(piece.id).is_none())
+        {
+            changes.push(Change::Dropped {
+                id: Some(piece.id),
+                color: piece.color,
+                ty: piece.ty,
+                at: piece.loc,
+            });
+        }
+        changes
+    }
+
+    // What playing `mv` from `pos` under the rules of `variant` does to this
+    // snapshot of `pos`, in the order of [`Self::diff`](). Unlike a diff it
+    // knows that a mover blown up in Atomic got to its square first.
+    pub fn changes(&self, variant: &dyn Variant, pos: &Position, mv: &ChessMove) -> Vec<Change> {
+        let mut after = *pos;
+        variant.make_move(&mut after, mv);
+        let mut changes = Vec::new();
+        if let (true, Some(ty)) = (mv.is_drop(), mv.promotion) {
+            changes.push(Change::Dropped {
+                id: None,
+                color: pos.turn,
+                ty,
+                at: mv.to,
+            });
+            return changes;
+        }
+        let Some(mover) = self.at(mv.from) else {
+            return changes;
+        };
+        let mut moves = Vec::new();
+        let to = match pos.is_castling(mv) {
+            true => {
+                let side = match mv.to > mv.from {
+                    true => CastleSide::King,
+                    false => CastleSide::Queen,
+                };
+                let rank = rank_of(mv.from);
+                if let Some(rook) = self.at(mv.to) {
+                    let rook_to = square_at(side.rook_dest_file(), rank);
+                    moves.push((rook.id, mv.to, rook_to));
+                }
+                square_at(side.king_dest_file(), rank)
+            }
+            false => mv.to,
+        };
+        moves.insert(0, (mover.id, mv.from, to));
+
+        let stays = |piece: &Piece| after.board[piece.loc] == Some(Man::new(piece.color, piece.ty));
+        for piece in self.pieces() {
+            if moves.iter().all(|&(id, ..)| id != piece.id) && !stays(piece) {
+                changes.push(Change::Captured {
+                    id: piece.id,
+                    at: piece.loc,
+                });
+            }
+        }
+        for (i, (id, from, to)) in moves
+            .into_iter()
+            .enumerate()
+            .filter(|(_, (_, from, to))| from != to)
+        {
+            changes.push(match i {
+                0 => Change::Moved { id, from, to },
+                _ => Change::CastlingRook { id, from, to },
+            });
+        }
+        if let Some(ty) = mv.promotion {
+            changes.push(Change::Promoted {
+                id: mover.id,
+                at: to,
+                to: ty,
+            });
+        }
+        if after.board[to].is_none() {
+            changes.push(Change::Captured {
+                id: mover.id,
+                at: to,
+            });
+        }
+        changes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constants::*;
+    use crate::types::Type;
+
+    fn assert_send_sync<T: Send + Sync + 'static>() {}
+
+    #[test]
+    fn snapshots_own_their_pieces() {
+        assert_send_sync::<BoardSnapshot>();
+        let mut game = crate::fen!("4k3/8/8/8/8/8/4P3/4K3 w - - 0 1");
+        let before = BoardSnapshot::of(&game);
+        let copy = before.clone();
+        assert_eq!(before.pieces().count(), 3);
+        assert_eq!(
+            before.at(E2).map(|pz| (pz.ty, pz.color)),
+            Some((Type::Pawn, Color::White))
+        );
+        assert_eq!(before.piece(-1).map(|pz| pz.loc), Some(E8));
+        assert_eq!(
+            before.tiles().nth(E2).map(|(_, xy, ..)| xy),
+            Some((4, 1).into())
+        );
+
+        // Later moves leave an earlier snapshot alone.
+        let pos = game.hist.position();
+        let mv = pos.parse_uci("e2e4").unwrap();
+        game.apply_move(&pos, &mv).unwrap();
+        let after = BoardSnapshot::of(&game);
+        assert_eq!(before, copy);
+        assert!(before.at(E4).is_none() && after.at(E2).is_none());
+        assert_eq!(after.at(E4).map(|pz| pz.id), before.at(E2).map(|pz| pz.id));
+    }
+
+    // Plays `uci`, checking that the changes foretold from the move are
+    // those found by comparing the board before and after it.
+    fn play(game: &mut GameState, uci: &str) -> Vec<Change> {
+        let pos = game.hist.position();
+        let mv = pos.parse_uci(uci).unwrap();
+        let before = BoardSnapshot::of(game);
+        let foretold = before.changes(game.hist.mode().variant(), &pos, &mv);
+        game.apply_move(&pos, &mv).unwrap();
+        game.hist.push(crate::game::Action::Move(mv));
+        assert_eq!(before.diff(&BoardSnapshot::of(game)), foretold, "{uci}");
+        foretold
+    }
+
+    #[test]
+    fn moves_change_only_the_pieces_they_touch() {
+        use Change::*;
+        let mut game = crate::fen!("r3k2r/1P6/8/3pP3/8/8/8/R3K2R w KQkq d6 0 1");
+        let id = |game: &GameState, loc| BoardSnapshot::of(game).at(loc).unwrap().id;
+        let (pawn, king, rook) = (id(&game, E5), id(&game, E1), id(&game, H1));
+        let (black_pawn, black_rook) = (id(&game, D5), id(&game, A8));
+
+        assert_eq!(
+            play(&mut game, "e5d6"),
+            [
+                Captured {
+                    id: black_pawn,
+                    at: D5
+                },
+                Moved {
+                    id: pawn,
+                    from: E5,
+                    to: D6
+                },
+            ]
+        );
+        assert!(play(&mut game, "h8h7").len() == 1);
+        assert_eq!(
+            play(&mut game, "e1h1"),
+            [
+                Moved {
+                    id: king,
+                    from: E1,
+                    to: G1
+                },
+                CastlingRook {
+                    id: rook,
+                    from: H1,
+                    to: F1
+                },
+            ]
+        );
+        assert!(play(&mut game, "h7h8").len() == 1);
+        let promoting = id(&game, B7);
+        assert_eq!(
+            play(&mut game, "b7a8q"),
+            [
+                Captured {
+                    id: black_rook,
+                    at: A8
+                },
+                Moved {
+                    id: promoting,
+                    from: B7,
+                    to: A8
+                },
+                Promoted {
+                    id: promoting,
+                    at: A8,
+                    to: Type::Queen
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn chess960_rooks_may_castle_past_a_king_that_stays() {
+        let mut game = crate::fen!("1r4kr/8/8/8/8/8/8/1R4KR w HBhb - 0 1");
+        let rook = BoardSnapshot::of(&game).at(H1).unwrap().id;
+        assert_eq!(
+            play(&mut game, "g1h1"),
+            [Change::CastlingRook {
+                id: rook,
+                from: H1,
+                to: F1
+            }]
+        );
+        // Moving up to the king is no castling.
+        let rook = BoardSnapshot::of(&game).at(B8).unwrap().id;
+        assert_eq!(
+            play(&mut game, "b8f8"),
+            [Change::Moved {
+                id: rook,
+                from: B8,
+                to: F8
+            }]
+        );
+    }
+
+    #[test]
+    fn drops_come_from_the_reserve() {
+        let start = Position::from_fen("4k3/8/8/8/8/8/8/4K3[N] w - - 0 1").unwrap();
+        let mut game = crate::ChessGame::from_position(0, start).unwrap().game;
+        let pos = game.hist.position();
+        let mv = pos.parse_uci("N@f3").unwrap();
+        let before = BoardSnapshot::of(&game);
+        let foretold = before.changes(game.hist.mode().variant(), &pos, &mv);
+        game.apply_move(&pos, &mv).unwrap();
+        let after = BoardSnapshot::of(&game);
+        let knight = after.at(F3).unwrap();
+        let drop = |id| Change::Dropped {
+            id,
+            color: Color::White,
+            ty: Type::Knight,
+            at: F3,
+        };
+        assert_eq!(foretold, [drop(None)]);
+        assert_eq!(before.diff(&after), [drop(Some(knight.id))]);
+    }
+}
@@ -0,0 +1,452 @@
+//! chess_core::square
+//!
+//! Squares of the standard 8x8 board by name rather than by number. A
+//! [`Square`] is a [`File`] and a [`Rank`]; it prints and parses as `e4`,
+//! steps across the board with [`Square::offset`], which gives `None` past
+//! the edge, and knows its shade and its distance to other squares. Black
+//! counts forward down the board: [`Square::forward`] and
+//! [`Square::relative_to`] take the side into account, while
+//! [`Square::flip_ranks`], [`Square::flip_files`] and [`Square::rotate`] turn
+//! the board over for either side. Squares
+//! convert to and from the [`TileId`] that boards are indexed with, so that
+//! `Square::E4.index() == constants::E4`.
+//!
+//! The 8x8 API speaks in squares: [`Position`]'s lookups, its
+//! [`AttackMap`]s and the squares of [`crate::events`]. Moves, snapshots
+//! and castling rights keep [`TileId`]s, since the same [`ChessMove`] and
+//! [`BoardSnapshot`] also serve fairy boards of any [`Geometry`], whose tiles
+//! run past h8; [`ChessMove::from_square`] and [`ChessMove::to_square`] name
+//! the squares of an 8x8 move.
+//!
+//! [`Position`]: crate::position::Position
+//! [`AttackMap`]: crate::position::attacks::AttackMap
+//! [`ChessMove`]: crate::position::ChessMove
+//! [`ChessMove::from_square`]: crate::position::ChessMove::from_square
+//! [`ChessMove::to_square`]: crate::position::ChessMove::to_square
+//! [`BoardSnapshot`]: crate::snapshot::BoardSnapshot
+//! [`Geometry`]: crate::geometry::Geometry
+//!
+//! ```
+//! use chess_core::square::{File, Rank, Square};
+//! use chess_core::types::Background;
+//!
+//! let e4: Square = "e4".parse().unwrap();
+//! assert_eq!(e4, Square::new(File::E, Rank::FOURTH));
+//! assert_eq!(e4.offset(1, 2), Some(Square::F6));
+//! assert_eq!(e4.offset(4, 0), None);
+//! assert_eq!(e4.color(), Background::Light);
+//! assert_eq!(e4.distance(Square::A1), 4);
+//! assert_eq!(Square::H8.to_string(), "h8");
+//! ```
+
+use crate::error::ChessError;
+use crate::game::math::XyPair;
+use crate::msg::TileId;
+use crate::types::{Background, Color};
+use std::fmt;
+use std::str::FromStr;
+
+// A column of the board, `a` to `h`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct File(u8);
+
+// A row of the board, `1` to `8`, counted from White's side.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Rank(u8);
+
+// One of the 64 squares, numbered like [`TileId`]() from a1 = 0 to h8 = 63.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Square(u8);
+
+// Moves `index` by `by` within 0..8.
+const fn step(index: u8, by: isize) -> Option<u8> {
+    let to = index as isize + by;
+    match to >= 0 && to < 8 {
+        true => Some(to as u8),
+        false => None,
+    }
+}
+
+impl File {
+    pub const A: File = File(0);
+    pub const B: File = File(1);
+    pub const C: File = File(2);
+    pub const D: File = File(3);
+    pub const E: File = File(4);
+    pub const F: File = File(5);
+    pub const G: File = File(6);
+    pub const H: File = File(7);
+    pub const ALL: [File; 8] = [
+        Self::A,
+        Self::B,
+        Self::C,
+        Self::D,
+        Self::E,
+        Self::F,
+        Self::G,
+        Self::H,
+    ];
+
+    // The file `index` places from the a file, or None past the h file.
+    pub const fn new(index: usize) -> Option<File> {
+        match index < 8 {
+            true => Some(File(index as u8)),
+            false => None,
+        }
+    }
+    pub const fn index(self) -> usize {
+        self.0 as usize
+    }
+    pub fn from_char(c: char) -> Option<File> {
+        match c.to_ascii_lowercase() {
+            c @ 'a'..='h' => Some(File(c as u8 - b'a')),
+            _ => None,
+        }
+    }
+    pub const fn to_char(self) -> char {
+        (b'a' + self.0) as char
+    }
+    // The file `by` files towards the h file, or None past the edge.
+    pub const fn offset(self, by: isize) -> Option<File> {
+        match step(self.0, by) {
+            Some(index) => Some(File(index)),
+            None => None,
+        }
+    }
+    pub const fn distance(self, other: File) -> usize {
+        self.0.abs_diff(other.0) as usize
+    }
+}
+
+impl Rank {
+    pub const FIRST: Rank = Rank(0);
+    pub const SECOND: Rank = Rank(1);
+    pub const THIRD: Rank = Rank(2);
+    pub const FOURTH: Rank = Rank(3);
+    pub const FIFTH: Rank = Rank(4);
+    pub const SIXTH: Rank = Rank(5);
+    pub const SEVENTH: Rank = Rank(6);
+    pub const EIGHTH: Rank = Rank(7);
+    pub const ALL: [Rank; 8] = [
+        Self::FIRST,
+        Self::SECOND,
+        Self::THIRD,
+        Self::FOURTH,
+        Self::FIFTH,
+        Self::SIXTH,
+        Self::SEVENTH,
+        Self::EIGHTH,
+    ];
+
+    // The rank `index` places above the first, or None past the eighth.
+    pub const fn new(index: usize) -> Option<Rank> {
+        match index < 8 {
+            true => Some(Rank(index as u8)),
+            false => None,
+        }
+    }
+    pub const fn index(self) -> usize {
+        self.0 as usize
+    }
+    pub fn from_char(c: char) -> Option<Rank> {
+        match c {
+            '1'..='8' => Some(Rank(c as u8 - b'1')),
+            _ => None,
+        }
+    }
+    pub const fn to_char(self) -> char {
+        (b'1' + self.0) as char
+    }
+    // The rank `by` ranks towards Black's side, or None past the edge.
+    pub const fn offset(self, by: isize) -> Option<Rank> {
+        match step(self.0, by) {
+            Some(index) => Some(Rank(index)),
+            None => None,
+        }
+    }
+    pub const fn distance(self, other: Rank) -> usize {
+        self.0.abs_diff(other.0) as usize
+    }
+    // The rank as `color` counts them from its own side: White's seventh
+    // rank is Black's second.
+    pub const fn relative_to(self, color: Color) -> Rank {
+        match color {
+            Color::White => self,
+            Color::Black => Rank(7 - self.0),
+        }
+    }
+}
+
+impl Square {
+    pub const A1: Square = Square(0);
+    pub const B1: Square = Square(1);
+    pub const C1: Square = Square(2);
+    pub const D1: Square = Square(3);
+    pub const E1: Square = Square(4);
+    pub const F1: Square = Square(5);
+    pub const G1: Square = Square(6);
+    pub const H1: Square = Square(7);
+    pub const A2: Square = Square(8);
+    pub const B2: Square = Square(9);
+    pub const C2: Square = Square(10);
+    pub const D2: Square = Square(11);
+    pub const E2: Square = Square(12);
+    pub const F2: Square = Square(13);
+    pub const G2: Square = Square(14);
+    pub const H2: Square = Square(15);
+    pub const A3: Square = Square(16);
+    pub const B3: Square = Square(17);
+    pub const C3: Square = Square(18);
+    pub const D3: Square = Square(19);
+    pub const E3: Square = Square(20);
+    pub const F3: Square = Square(21);
+    pub const G3: Square = Square(22);
+    pub const H3: Square = Square(23);
+    pub const A4: Square = Square(24);
+    pub const B4: Square = Square(25);
+    pub const C4: Square = Square(26);
+    pub const D4: Square = Square(27);
+    pub const E4: Square = Square(28);
+    pub const F4: Square = Square(29);
+    pub const G4: Square = Square(30);
+    pub const H4: Square = Square(31);
+    pub const A5: Square = Square(32);
+    pub const B5: Square = Square(33);
+    pub const C5: Square = Square(34);
+    pub const D5: Square = Square(35);
+    pub const E5: Square = Square(36);
+    pub const F5: Square = Square(37);
+    pub const G5: Square = Square(38);
+    pub const H5: Square = Square(39);
+    pub const A6: Square = Square(40);
+    pub const B6: Square = Square(41);
+    pub const C6: Square = Square(42);
+    pub const D6: Square = Square(43);
+    pub const E6: Square = Square(44);
+    pub const F6: Square = Square(45);
+    pub const G6: Square = Square(46);
+    pub const H6: Square = Square(47);
+    pub const A7: Square = Square(48);
+    pub const B7: Square = Square(49);
+    pub const C7: Square = Square(50);
+    pub const D7: Square = Square(51);
+    pub const E7: Square = Square(52);
+    pub const F7: Square = Square(53);
+    pub const G7: Square = Square(54);
+    pub const H7: Square = Square(55);
+    pub const A8: Square = Square(56);
+    pub const B8: Square = Square(57);
+    pub const C8: Square = Square(58);
+    pub const D8: Square = Square(59);
+    pub const E8: Square = Square(60);
+    pub const F8: Square = Square(61);
+    pub const G8: Square = Square(62);
+    pub const H8: Square = Square(63);
+
+    pub const fn new(file: File, rank: Rank) -> Square {
+        Square(rank.0 * 8 + file.0)
+    }
+    // The square numbered `index`, or None past h8.
+    pub const fn from_index(index: TileId) -> Option<Square> {
+        match index < 64 {
+            true => Some(Square(index as u8)),
+            false => None,
+        }
+    }
+    pub const fn index(self) -> TileId {
+        self.0 as TileId
+    }
+    pub const fn file(self) -> File {
+        File(self.0 % 8)
+    }
+    pub const fn rank(self) -> Rank {
+        Rank(self.0 / 8)
+    }
+    // Every square from a1 to h8, rank by rank.
+    pub fn all() -> impl Iterator<Item = Square> {
+        (0..64).map(Square)
+    }
+
+    // The square `files` files to the right and `ranks` ranks up, as White
+    // sees the board, or None past its edge.
+    pub const fn offset(self, files: isize, ranks: isize) -> Option<Square> {
+        match (self.file().offset(files), self.rank().offset(ranks)) {
+            (Some(file), Some(rank)) => Some(Square::new(file, rank)),
+            _ => None,
+        }
+    }
+
+    // a1 is dark, and the shades alternate from there.
+    pub const fn color(self) -> Background {
+        match (self.file().0 + self.rank().0) % 2 {
+            0 => Background::Dark,
+            _ => Background::Light,
+        }
+    }
+
+    // The number of king moves between the two squares.
+    pub const fn distance(self, other: Square) -> usize {
+        let files = self.file().distance(other.file());
+        let ranks = self.rank().distance(other.rank());
+        match files > ranks {
+            true => files,
+            false => ranks,
+        }
+    }
+
+    // The number of rook moves, one square at a time, between the two squares.
+    pub const fn manhattan_distance(self, other: Square) -> usize {
+        self.file().distance(other.file()) + self.rank().distance(other.rank())
+    }
+
+    // The square on the same file with the ranks counted from the other
+    // side, e7 for e2.
+    pub const fn flip_ranks(self) -> Square {
+        Square(self.0 ^ 56)
+    }
+
+    // The square on the same rank with the files in the other order, h4 for a4.
+    pub const fn flip_files(self) -> Square {
+        Square(self.0 ^ 7)
+    }
+
+    // Half a turn about the middle of the board, h8 for a1: where a square is
+    // drawn when the board is shown from Black's side.
+    pub const fn rotate(self) -> Square {
+        Square(63 - self.0)
+    }
+
+    // The square as `color` names it counting ranks from its own side, so
+    // that Black's pawns start on its second rank. Files keep their letters.
+    pub const fn relative_to(self, color: Color) -> Square {
+        match color {
+            Color::White => self,
+            Color::Black => self.flip_ranks(),
+        }
+    }
+
+    // `steps` squares towards the opponent of `color`, or None past the edge.
+    pub const fn forward(self, color: Color, steps: isize) -> Option<Square> {
+        self.offset(0, steps * color.forward())
+    }
+
+    pub fn xy(self) -> XyPair {
+        (self.file().index(), self.rank().index()).into()
+    }
+
+    // The square at `xy`, with a1 at (0, 0), or None off the board.
+    pub fn from_xy(xy: XyPair) -> Option<Square> {
+        Square::A1.offset(xy.x, xy.y)
+    }
+}
+
+impl From<Square> for TileId {
+    fn from(sq: Square) -> TileId {
+        sq.index()
+    }
+}
+
+impl TryFrom<TileId> for Square {
+    type Error = ChessError;
+    fn try_from(index: TileId) -> Result<Square, ChessError> {
+        Square::from_index(index).ok_or_else(|| ChessError::InvalidSquare(index.to_string()))
+    }
+}
+
+impl fmt::Display for File {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.to_char())
+    }
+}
+
+impl fmt::Display for Rank {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.to_char())
+    }
+}
+
+impl fmt::Display for Square {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}", self.file(), self.rank())
+    }
+}
+
+// A file letter and a rank digit; the letter may be uppercase.
+impl FromStr for Square {
+    type Err = ChessError;
+    fn from_str(s: &str) -> Result<Square, ChessError> {
+        let mut chars = s.chars();
+        match (
+            chars.next().and_then(File::from_char),
+            chars.next().and_then(Rank::from_char),
+            chars.next(),
+        ) {
+            (Some(file), Some(rank), None) => Ok(Square::new(file, rank)),
+            _ => Err(ChessError::InvalidSquare(s.to_string())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn squares_print_and_parse_by_name() {
+        for sq in Square::all() {
+            assert_eq!(sq.to_string().parse::<Square>(), Ok(sq));
+        }
+        assert_eq!("E4".parse::<Square>(), Ok(Square::E4));
+        for bad in ["", "e", "e9", "i1", "e44", "4e"] {
+            assert_eq!(
+                bad.parse::<Square>(),
+                Err(ChessError::InvalidSquare(bad.to_string()))
+            );
+        }
+        assert_eq!(
+            Square::try_from(64),
+            Err(ChessError::InvalidSquare("64".into()))
+        );
+    }
+
+    #[test]
+    fn offsets_stop_at_the_edge() {
+        assert_eq!(Square::A1.offset(7, 7), Some(Square::H8));
+        assert_eq!(Square::A1.offset(-1, 0), None);
+        assert_eq!(Square::H4.offset(1, 0), None);
+        assert_eq!(Square::E8.offset(0, 1), None);
+        assert_eq!(Square::G1.offset(-1, 2), Some(Square::F3));
+        assert_eq!(Square::from_xy((4, 3).into()), Some(Square::E4));
+        assert_eq!(Square::from_xy(XyPair { x: 8, y: 0 }), None);
+    }
+
+    #[test]
+    fn shades_and_distances() {
+        assert_eq!(Square::A1.color(), Background::Dark);
+        assert_eq!(Square::H1.color(), Background::Light);
+        assert_eq!(Square::D1.color(), Background::Light);
+        assert_eq!(Square::D8.color(), Background::Dark);
+        assert_eq!(Square::A1.distance(Square::H8), 7);
+        assert_eq!(Square::B1.distance(Square::C3), 2);
+        assert_eq!(Square::A1.manhattan_distance(Square::H8), 14);
+        assert_eq!(File::A.distance(File::H), 7);
+        assert_eq!(Rank::FIRST.distance(Rank::FOURTH), 3);
+    }
+
+    #[test]
+    fn each_side_looks_up_the_board() {
+        assert_eq!(Square::E2.forward(Color::White, 2), Some(Square::E4));
+        assert_eq!(Square::E7.forward(Color::Black, 2), Some(Square::E5));
+        assert_eq!(Square::E1.forward(Color::Black, 1), None);
+        assert_eq!(Square::E7.relative_to(Color::Black), Square::E2);
+        assert_eq!(Square::C3.relative_to(Color::White), Square::C3);
+        assert_eq!(Rank::SEVENTH.relative_to(Color::Black), Rank::SECOND);
+        assert_eq!(Square::B1.flip_ranks(), Square::B8);
+        assert_eq!(Square::B1.flip_files(), Square::G1);
+        assert_eq!(Square::B1.rotate(), Square::G8);
+        for sq in Square::all() {
+            assert_eq!(sq.rotate(), sq.flip_ranks().flip_files());
+            assert_eq!(sq.rotate().rotate(), sq);
+        }
+    }
+}
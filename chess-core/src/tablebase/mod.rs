@@ -0,0 +1,527 @@
+//! chess_core::tablebase
+//!
+//! Perfect play for endings with few pieces. [`Tablebase`] holds
+//! distance-to-mate tables, one per [`Material`] set, that it either
+//! generates itself by retrograde analysis (see [`retro`]) or loads from
+//! local files written by an earlier run.
+//!
+//! Tables know nothing about castling or the fifty-move rule: a position
+//! that may still castle is not probed, and a win is a win no matter how
+//! many moves the mate takes.
+
+pub mod retro;
+pub mod syzygy;
+
+use crate::position::{ChessMove, Man, Position};
+use crate::square::Square;
+use crate::types::{Color, Type};
+use anyhow::{anyhow, bail, Context, Result};
+use retro::{Table, MAX_PIECES};
+use std::collections::HashMap;
+use std::fmt;
+use std::path::Path;
+
+// Win, draw or loss for the side to move.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Wdl {
+    Loss,
+    Draw,
+    Win,
+}
+
+// Distance to mate for the side to move, counted in plies: `Win(1)` mates
+// with the next move, `Loss(0)` is already checkmated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Dtm {
+    Win(u32),
+    Draw,
+    Loss(u32),
+}
+
+impl Dtm {
+    pub fn wdl(self) -> Wdl {
+        match self {
+            Dtm::Win(_) => Wdl::Win,
+            Dtm::Draw => Wdl::Draw,
+            Dtm::Loss(_) => Wdl::Loss,
+        }
+    }
+
+    // The value one ply earlier, for the side that moved into this position.
+    pub fn before(self) -> Dtm {
+        match self {
+            Dtm::Win(d) => Dtm::Loss(d + 1),
+            Dtm::Draw => Dtm::Draw,
+            Dtm::Loss(d) => Dtm::Win(d + 1),
+        }
+    }
+
+    // Orders values from the side to move's point of view: quick wins first,
+    // slow losses last.
+    fn goodness(self) -> i64 {
+        match self {
+            Dtm::Win(d) => i64::from(u32::MAX) - i64::from(d),
+            Dtm::Draw => 0,
+            Dtm::Loss(d) => i64::from(d) - i64::from(u32::MAX),
+        }
+    }
+}
+
+impl fmt::Display for Dtm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Dtm::Win(d) => write!(f, "win, mate in {}", d.div_ceil(2)),
+            Dtm::Draw => write!(f, "draw"),
+            Dtm::Loss(0) => write!(f, "checkmated"),
+            Dtm::Loss(d) => write!(f, "loss, mated in {}", d / 2),
+        }
+    }
+}
+
+// Which men each side has, kings included, strongest first. Written the
+// usual way, e.g. `KBNvK` or `KRvKP`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Material {
+    pub white: Vec<Type>,
+    pub black: Vec<Type>,
+}
+
+fn strength(ty: Type) -> u8 {
+    match ty {
+        Type::King => 6,
+        Type::Queen => 5,
+        Type::Rook => 4,
+        Type::Bishop => 3,
+        Type::Knight => 2,
+        Type::Pawn => 1,
+        Type::Fairy(_) => 0,
+    }
+}
+
+fn letter(ty: Type) -> char {
+    Man::new(Color::White, ty).to_char()
+}
+
+impl Material {
+    pub fn new(mut white: Vec<Type>, mut black: Vec<Type>) -> Self {
+        white.sort_by_key(|&ty| std::cmp::Reverse(strength(ty)));
+        black.sort_by_key(|&ty| std::cmp::Reverse(strength(ty)));
+        Self { white, black }
+    }
+
+    pub fn from_position(pos: &Position) -> Self {
+        let side = |color| pos.pieces(color).map(|(_, man)| man.ty).collect();
+        Self::new(side(Color::White), side(Color::Black))
+    }
+
+    // Reads `KBNvK`; the `v` may be left out (`KBNK`).
+    pub fn parse(text: &str) -> Result<Self> {
+        let (white, black) = match text.split_once(['v', 'V']) {
+            Some(sides) => sides,
+            None => {
+                let second_king = text
+                    .char_indices()
+                    .skip(1)
+                    .find(|&(_, c)| c.eq_ignore_ascii_case(&'k'))
+                    .map(|(i, _)| i)
+                    .ok_or_else(|| anyhow!("Material {text:?} needs two kings"))?;
+                text.split_at(second_king)
+            }
+        };
+        let side = |letters: &str| {
+            letters
+                .chars()
+                .map(|c| {
+                    crate::position::type_from_char(c)
+                        .ok_or_else(|| anyhow!("Unknown piece {c:?} in {text:?}"))
+                })
+                .collect::<Result<Vec<_>>>()
+        };
+        let material = Self::new(side(white)?, side(black)?);
+        for pieces in [&material.white, &material.black] {
+            if pieces.iter().filter(|&&ty| ty == Type::King).count() != 1 {
+                bail!("Material {text:?} needs one king a side");
+            }
+        }
+        Ok(material)
+    }
+
+    pub fn count(&self) -> usize {
+        self.white.len() + self.black.len()
+    }
+
+    pub fn has_pawns(&self) -> bool {
+        self.white.contains(&Type::Pawn) || self.black.contains(&Type::Pawn)
+    }
+
+    // The same material with the colors swapped.
+    pub fn mirrored(&self) -> Self {
+        Self::new(self.black.clone(), self.white.clone())
+    }
+
+    // Tables are stored with the stronger side as white.
+    pub fn is_canonical(&self) -> bool {
+        let key = |pieces: &[Type]| {
+            (
+                pieces.len(),
+                pieces.iter().map(|&ty| strength(ty)).collect::<Vec<_>>(),
+            )
+        };
+        key(&self.white) >= key(&self.black)
+    }
+
+    pub fn canonical(&self) -> Self {
+        if self.is_canonical() {
+            self.clone()
+        } else {
+            self.mirrored()
+        }
+    }
+
+    // Every material set one capture or promotion away, in canonical form.
+    pub fn subsets(&self) -> Vec<Material> {
+        let mut out = Vec::new();
+        let mut push = |white: Vec<Type>, black: Vec<Type>| {
+            let material = Self::new(white, black).canonical();
+            if !out.contains(&material) {
+                out.push(material);
+            }
+        };
+        for (us, them, flip) in [
+            (&self.white, &self.black, false),
+            (&self.black, &self.white, true),
+        ] {
+            let side = |us: Vec<Type>, them: Vec<Type>| if flip { (them, us) } else { (us, them) };
+            let promotions = |pieces: &[Type]| -> Vec<Vec<Type>> {
+                let Some(pawn) = pieces.iter().position(|&ty| ty == Type::Pawn) else {
+                    return Vec::new();
+                };
+                [Type::Queen, Type::Rook, Type::Bishop, Type::Knight]
+                    .into_iter()
+                    .map(|ty| {
+                        let mut promoted = pieces.to_vec();
+                        promoted[pawn] = ty;
+                        promoted
+                    })
+                    .collect()
+            };
+            for victim in (0..them.len()).filter(|&i| them[i] != Type::King) {
+                let mut rest = them.clone();
+                rest.remove(victim);
+                let (w, b) = side(us.clone(), rest.clone());
+                push(w, b);
+                for promoted in promotions(us) {
+                    let (w, b) = side(promoted, rest.clone());
+                    push(w, b);
+                }
+            }
+            for promoted in promotions(us) {
+                let (w, b) = side(promoted, them.clone());
+                push(w, b);
+            }
+        }
+        out
+    }
+}
+
+impl fmt::Display for Material {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let white: String = self.white.iter().map(|&ty| letter(ty)).collect();
+        let black: String = self.black.iter().map(|&ty| letter(ty)).collect();
+        write!(f, "{white}v{black}")
+    }
+}
+
+// A set of distance-to-mate tables.
+#[derive(Debug, Clone, Default)]
+pub struct Tablebase {
+    tables: HashMap<Material, Table>,
+}
+
+impl Tablebase {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Loads every table file in `dir`.
+    pub fn open(dir: impl AsRef<Path>) -> Result<Self> {
+        let dir = dir.as_ref();
+        let mut tb = Self::new();
+        let entries =
+            std::fs::read_dir(dir).with_context(|| format!("Failed to read {}", dir.display()))?;
+        for entry in entries {
+            let path = entry?.path();
+            if path.extension().is_some_and(|ext| ext == retro::EXTENSION) {
+                tb.insert(Table::open(&path)?);
+            }
+        }
+        Ok(tb)
+    }
+
+    // Writes every table to `dir`, one file per material set.
+    pub fn save(&self, dir: impl AsRef<Path>) -> Result<()> {
+        let dir = dir.as_ref();
+        std::fs::create_dir_all(dir)
+            .with_context(|| format!("Failed to create {}", dir.display()))?;
+        for table in self.tables.values() {
+            table.save(dir.join(table.file_name()))?;
+        }
+        Ok(())
+    }
+
+    pub fn insert(&mut self, table: Table) {
+        self.tables.insert(table.material().clone(), table);
+    }
+
+    pub fn table(&self, material: &Material) -> Option<&Table> {
+        self.tables.get(&material.canonical())
+    }
+
+    pub fn materials(&self) -> impl Iterator<Item = &Material> {
+        self.tables.keys()
+    }
+
+    // Builds the table for `material`, and first every smaller table it
+    // depends on, unless they are already there.
+    pub fn generate(&mut self, material: &Material) -> Result<()> {
+        let material = material.canonical();
+        if material.count() > MAX_PIECES {
+            bail!("{material} has more than {MAX_PIECES} pieces");
+        }
+        if material.count() <= 2 || self.tables.contains_key(&material) {
+            return Ok(());
+        }
+        for subset in material.subsets() {
+            self.generate(&subset)?;
+        }
+        let table = retro::generate(&material, self);
+        self.insert(table);
+        Ok(())
+    }
+
+    // The distance to mate of `pos`, if there is a table for it.
+    pub fn probe(&self, pos: &Position) -> Option<Dtm> {
+        if !pos.castling.is_empty() {
+            return None;
+        }
+        // Tables hold positions without an en passant square; when the capture
+        // is actually on, look one ply ahead instead.
+        if pos.ep.is_some() {
+            let moves = pos.legal_moves();
+            if moves.iter().any(|mv| pos.is_en_passant(mv)) {
+                return self.probe_moves(pos, &moves).map(|(_, dtm)| dtm);
+            }
+        }
+        let pieces: Vec<(Man, Square)> = Square::all()
+            .filter_map(|sq| Some((pos.at(sq)?, sq)))
+            .collect();
+        self.probe_pieces(&pieces, pos.turn)
+    }
+
+    pub fn probe_wdl(&self, pos: &Position) -> Option<Wdl> {
+        self.probe(pos).map(Dtm::wdl)
+    }
+
+    // The move that wins fastest, or draws, or loses slowest, and the value
+    // of `pos` that goes with it.
+    pub fn best_move(&self, pos: &Position) -> Option<(ChessMove, Dtm)> {
+        if !pos.castling.is_empty() {
+            return None;
+        }
+        self.probe_moves(pos, &pos.legal_moves())
+            .and_then(|(mv, dtm)| Some((mv?, dtm)))
+    }
+
+    fn probe_moves(&self, pos: &Position, moves: &[ChessMove]) -> Option<(Option<ChessMove>, Dtm)> {
+        if moves.is_empty() {
+            let dtm = if pos.in_check() {
+                Dtm::Loss(0)
+            } else {
+                Dtm::Draw
+            };
+            return Some((None, dtm));
+        }
+        let mut best: Option<(ChessMove, Dtm)> = None;
+        for mv in moves {
+            let mut next = *pos;
+            next.make_move(mv);
+            let dtm = self.probe(&next)?.before();
+            if best.is_none_or(|(_, b)| dtm.goodness() > b.goodness()) {
+                best = Some((*mv, dtm));
+            }
+        }
+        best.map(|(mv, dtm)| (Some(mv), dtm))
+    }
+
+    // Looks up the men on `pieces` with `turn` to move, in whichever table
+    // matches their material.
+    pub fn probe_pieces(&self, pieces: &[(Man, Square)], turn: Color) -> Option<Dtm> {
+        if pieces.len() > MAX_PIECES {
+            return None;
+        }
+        if pieces.len() == 2 {
+            return Some(Dtm::Draw);
+        }
+        let side = |color| {
+            pieces
+                .iter()
+                .filter(|(man, _)| man.color == color)
+                .map(|(man, _)| man.ty)
+                .collect()
+        };
+        let material = Material::new(side(Color::White), side(Color::Black));
+        let table = self.tables.get(&material.canonical())?;
+        let mut pieces = pieces.to_vec();
+        let mut turn = turn;
+        if !material.is_canonical() {
+            for (man, sq) in pieces.iter_mut() {
+                man.color = man.color.opposite();
+                *sq = sq.flip_ranks();
+            }
+            turn = turn.opposite();
+        }
+        Some(table.probe(&mut pieces, turn))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::OnceLock;
+
+    fn generated(material: &str) -> Tablebase {
+        let mut tb = Tablebase::new();
+        tb.generate(&Material::parse(material).unwrap()).unwrap();
+        tb
+    }
+
+    fn probe(tb: &Tablebase, fen: &str) -> Option<Dtm> {
+        tb.probe(&Position::from_fen(fen).unwrap())
+    }
+
+    #[test]
+    fn names_material() {
+        let m = Material::parse("KPKR").unwrap();
+        assert_eq!(m.to_string(), "KPvKR");
+        assert_eq!(m.canonical().to_string(), "KRvKP");
+        assert_eq!(Material::parse("knbvk").unwrap().to_string(), "KBNvK");
+        assert!(Material::parse("KQQ").is_err());
+        let subsets: Vec<String> = Material::parse("KRvKP")
+            .unwrap()
+            .subsets()
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        for name in ["KRvK", "KPvK", "KQvKR", "KRvKR", "KRvKB", "KRvKN", "KQvK"] {
+            assert!(subsets.contains(&name.to_string()), "{name} in {subsets:?}");
+        }
+    }
+
+    #[test]
+    fn knows_the_basic_mates() {
+        let tb = generated("KQvK");
+        assert_eq!(
+            probe(&tb, "7k/8/6K1/8/8/8/8/1Q6 w - - 0 1"),
+            Some(Dtm::Win(1))
+        );
+        assert_eq!(
+            probe(&tb, "1Q5k/8/6K1/8/8/8/8/8 b - - 0 1"),
+            Some(Dtm::Loss(0))
+        );
+        // Stalemate, and the queen left hanging.
+        assert_eq!(
+            probe(&tb, "7k/5Q2/6K1/8/8/8/8/8 b - - 0 1"),
+            Some(Dtm::Draw)
+        );
+        assert_eq!(probe(&tb, "8/8/8/8/8/8/6Qk/4K3 b - - 0 1"), Some(Dtm::Draw));
+        // No table for this one.
+        assert_eq!(probe(&tb, "8/8/8/8/8/8/1k6/Qq1K4 w - - 0 1"), None);
+        // Black's queen is the same table seen from the other side.
+        assert_eq!(
+            probe(&tb, "1q6/8/8/8/8/6k1/8/7K b - - 0 1"),
+            Some(Dtm::Win(1))
+        );
+        // The longest KQK win is ten moves.
+        assert_eq!(
+            tb.table(&Material::parse("KQK").unwrap())
+                .unwrap()
+                .longest(),
+            19
+        );
+        assert_eq!(
+            generated("KRK")
+                .table(&Material::parse("KRK").unwrap())
+                .unwrap()
+                .longest(),
+            31
+        );
+    }
+
+    #[test]
+    fn knows_king_and_pawn_endings() {
+        let tb = generated("KPvK");
+        assert_eq!(
+            probe(&tb, "4k3/8/4K3/4P3/8/8/8/8 w - - 0 1").map(Dtm::wdl),
+            Some(Wdl::Win)
+        );
+        assert_eq!(
+            probe(&tb, "4k3/8/4P3/4K3/8/8/8/8 w - - 0 1"),
+            Some(Dtm::Draw)
+        );
+        // The rook pawn cannot be forced through.
+        assert_eq!(probe(&tb, "k7/8/K7/P7/8/8/8/8 w - - 0 1"), Some(Dtm::Draw));
+        // With the king on the sixth in front of the pawn, the move does not matter.
+        let pos = Position::from_fen("4k3/8/4K3/4P3/8/8/8/8 b - - 0 1").unwrap();
+        let (mv, dtm) = tb.best_move(&pos).unwrap();
+        assert_eq!(dtm.wdl(), Wdl::Loss);
+        assert_eq!(tb.probe(&pos), Some(dtm));
+        assert!(["e8d8", "e8f8"].contains(&mv.to_string().as_str()), "{mv}");
+    }
+
+    fn kbnk() -> &'static Tablebase {
+        static TB: OnceLock<Tablebase> = OnceLock::new();
+        TB.get_or_init(|| generated("KBNK"))
+    }
+
+    #[test]
+    fn bishop_and_knight_mate() {
+        let tb = kbnk();
+        let table = tb.table(&Material::parse("KBNvK").unwrap()).unwrap();
+        // 33 moves from the worst start.
+        assert_eq!(table.longest(), 65);
+        let mut pos = Position::from_fen("8/8/8/4k3/8/8/8/KBN5 w - - 0 1").unwrap();
+        let Some(Dtm::Win(plies)) = tb.probe(&pos) else {
+            panic!("KBNK is a win");
+        };
+        // Playing the table's moves for both sides mates on time.
+        for ply in 0..plies {
+            let (mv, dtm) = tb.best_move(&pos).unwrap();
+            let left = plies - ply;
+            assert_eq!(
+                dtm,
+                if ply % 2 == 0 {
+                    Dtm::Win(left)
+                } else {
+                    Dtm::Loss(left)
+                }
+            );
+            pos.make_move(&mv);
+        }
+        assert!(pos.is_checkmate());
+    }
+
+    #[test]
+    fn reads_back_saved_tables() {
+        let tb = generated("KRvK");
+        let dir = std::env::temp_dir().join(format!("chess-core-tb-{}", std::process::id()));
+        tb.save(&dir).unwrap();
+        let loaded = Tablebase::open(&dir).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+        let material = Material::parse("KRvK").unwrap();
+        assert_eq!(loaded.table(&material), tb.table(&material));
+        assert_eq!(
+            loaded.probe(&Position::from_fen("8/8/8/8/8/1K6/7R/k7 w - - 0 1").unwrap()),
+            Some(Dtm::Win(1))
+        );
+    }
+}
@@ -0,0 +1,749 @@
+//! chess_core::tablebase::retro
+//!
+//! Distance-to-mate tables built by retrograde analysis. Starting from the
+//! checkmates, every round walks one ply back: positions that can move into
+//! a loss are wins, and positions whose every move runs into a win are
+//! losses. Captures and promotions leave the table, so their values come
+//! from the smaller tables generated beforehand.
+//!
+//! A table stores one byte per position: `0` for a draw, `d + 1` for a mate
+//! `d` plies away, a win when `d` is odd and a loss when it is even. Only
+//! the white king's squares up to symmetry are stored: the a1-d1-d4 triangle
+//! without pawns, the queen side with them.
+
+use super::{Dtm, Material, Tablebase};
+use crate::msg::TileId;
+use crate::position::movegen::{BISHOP_RAYS, KING_STEPS, KNIGHT_STEPS, PROMOTIONS, ROOK_RAYS};
+use crate::position::{file_of, offset, rank_of, square_at, Man};
+use crate::square::Square;
+use crate::types::{Color, Type};
+use anyhow::{bail, Context, Result};
+use std::path::Path;
+
+pub const MAX_PIECES: usize = 4;
+pub const EXTENSION: &str = "cctb";
+const MAGIC: &[u8; 4] = b"CCTB";
+const VERSION: u8 = 1;
+
+const UNKNOWN: u8 = 0;
+const INVALID: u8 = u8::MAX;
+const UNCOUNTED: u8 = u8::MAX;
+// The longest mate a byte can hold, in plies.
+const MAX_PLIES: usize = INVALID as usize - 2;
+
+// The white king's squares in a pawnless table: a1-d1-d4.
+const TRIANGLE: [TileId; 10] = [0, 1, 2, 3, 9, 10, 11, 18, 19, 27];
+
+const TRIANGLE_INDEX: [u8; 64] = {
+    let mut index = [u8::MAX; 64];
+    let mut i = 0;
+    while i < TRIANGLE.len() {
+        index[TRIANGLE[i]] = i as u8;
+        i += 1;
+    }
+    index
+};
+
+// The eight symmetries of the board: bit 0 mirrors the files, bit 1 the
+// ranks, and bit 2 then swaps files and ranks.
+const fn mirror(sq: TileId, symmetry: usize) -> TileId {
+    let (mut file, mut rank) = (sq % 8, sq / 8);
+    if symmetry & 1 != 0 {
+        file = 7 - file;
+    }
+    if symmetry & 2 != 0 {
+        rank = 7 - rank;
+    }
+    if symmetry & 4 != 0 {
+        (file, rank) = (rank, file);
+    }
+    rank * 8 + file
+}
+
+const MIRRORS: [[u8; 64]; 8] = {
+    let mut table = [[0; 64]; 8];
+    let mut symmetry = 0;
+    while symmetry < 8 {
+        let mut sq = 0;
+        while sq < 64 {
+            table[symmetry][sq] = mirror(sq, symmetry) as u8;
+            sq += 1;
+        }
+        symmetry += 1;
+    }
+    table
+};
+
+// For every white king square, the symmetry that takes it into the triangle.
+const SYMMETRY: [u8; 64] = {
+    let mut table = [0; 64];
+    let mut sq = 0;
+    while sq < 64 {
+        let mut symmetry = 0;
+        if sq % 8 > 3 {
+            symmetry |= 1;
+        }
+        if sq / 8 > 3 {
+            symmetry |= 2;
+        }
+        let flipped = mirror(sq, symmetry);
+        if flipped / 8 > flipped % 8 {
+            symmetry |= 4;
+        }
+        table[sq] = symmetry as u8;
+        sq += 1;
+    }
+    table
+};
+
+// With pawns only the files can be mirrored.
+const SYMMETRY_WITH_PAWNS: [u8; 64] = {
+    let mut table = [0; 64];
+    let mut sq = 0;
+    while sq < 64 {
+        if sq % 8 > 3 {
+            table[sq] = 1;
+        }
+        sq += 1;
+    }
+    table
+};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Table {
+    material: Material,
+    // White's men, then black's, as in `material`; the white king comes first.
+    men: Vec<Man>,
+    values: Vec<u8>,
+}
+
+impl Table {
+    fn empty(material: &Material) -> Self {
+        let men = material
+            .white
+            .iter()
+            .map(|&ty| Man::new(Color::White, ty))
+            .chain(material.black.iter().map(|&ty| Man::new(Color::Black, ty)))
+            .collect();
+        let mut table = Self {
+            material: material.clone(),
+            men,
+            values: Vec::new(),
+        };
+        table.values = vec![UNKNOWN; table.size()];
+        table
+    }
+
+    pub fn material(&self) -> &Material {
+        &self.material
+    }
+
+    pub fn file_name(&self) -> String {
+        format!("{}.{EXTENSION}", self.material)
+    }
+
+    fn king_squares(&self) -> usize {
+        if self.material.has_pawns() {
+            32
+        } else {
+            TRIANGLE.len()
+        }
+    }
+
+    pub fn size(&self) -> usize {
+        2 * self.king_squares() * 64usize.pow(self.men.len() as u32 - 1)
+    }
+
+    // The longest win in the table, in plies.
+    pub fn longest(&self) -> u32 {
+        self.values
+            .iter()
+            .filter(|&&v| v != UNKNOWN && (v - 1) % 2 == 1)
+            .map(|&v| u32::from(v - 1))
+            .max()
+            .unwrap_or(0)
+    }
+
+    // Mirrors `squares` so that the white king lands on a stored square.
+    fn normalise(&self, squares: &mut [TileId]) {
+        let symmetry = match self.material.has_pawns() {
+            true => SYMMETRY_WITH_PAWNS[squares[0]],
+            false => SYMMETRY[squares[0]],
+        };
+        for sq in squares.iter_mut() {
+            *sq = MIRRORS[symmetry as usize][*sq] as TileId;
+        }
+    }
+
+    fn index(&self, squares: &[TileId], turn: Color) -> usize {
+        self.entries(squares, turn).0
+    }
+
+    // The index of a position and, when the white king stands on the a1-d4
+    // diagonal, of its mirror image along that diagonal, which is stored too.
+    fn entries(&self, at: &[TileId], turn: Color) -> (usize, Option<usize>) {
+        let mut buf = [0; MAX_PIECES];
+        let squares = &mut buf[..at.len()];
+        squares.copy_from_slice(at);
+        self.normalise(squares);
+        let index = self.stored_index(squares, turn);
+        if self.material.has_pawns() || rank_of(squares[0]) != file_of(squares[0]) {
+            return (index, None);
+        }
+        for sq in squares.iter_mut() {
+            *sq = MIRRORS[4][*sq] as TileId;
+        }
+        let twin = self.stored_index(squares, turn);
+        (index, (twin != index).then_some(twin))
+    }
+
+    // The one index out of [`Table::entries`]() that stands for both.
+    fn class(&self, board: &Board) -> usize {
+        match self.entries(&board.squares[..board.len], board.turn) {
+            (index, Some(twin)) => index.min(twin),
+            (index, None) => index,
+        }
+    }
+
+    // The class of `next`, which is `board` (stored at `class`) after `man`
+    // moved and the turn passed. Unless the white king moved or stands on
+    // the diagonal, the symmetry stays put and only one square changes.
+    fn class_after(&self, class: usize, board: &Board, next: &Board, man: usize) -> usize {
+        let king = board.squares[0];
+        if man == 0 || !self.material.has_pawns() && rank_of(king) == file_of(king) {
+            return self.class(next);
+        }
+        let weight = 64usize.pow((board.len - 1 - man) as u32);
+        let turn = self.king_squares() * 64usize.pow(board.len as u32 - 1);
+        let index = class - board.squares[man] * weight + next.squares[man] * weight;
+        match board.turn {
+            Color::White => index + turn,
+            Color::Black => index - turn,
+        }
+    }
+
+    // The index of squares that are already normalised.
+    fn stored_index(&self, squares: &[TileId], turn: Color) -> usize {
+        let king = if self.material.has_pawns() {
+            rank_of(squares[0]) * 4 + file_of(squares[0])
+        } else {
+            TRIANGLE_INDEX[squares[0]] as usize
+        };
+        let mut index = turn.index() * self.king_squares() + king;
+        for &sq in &squares[1..] {
+            index = index * 64 + sq;
+        }
+        index
+    }
+
+    fn decode(&self, mut index: usize) -> Board {
+        let mut board = Board {
+            men: [Man::new(Color::White, Type::King); MAX_PIECES],
+            squares: [0; MAX_PIECES],
+            len: self.men.len(),
+            occupied: 0,
+            turn: Color::White,
+        };
+        board.men[..self.men.len()].copy_from_slice(&self.men);
+        for i in (1..self.men.len()).rev() {
+            board.squares[i] = index % 64;
+            index /= 64;
+        }
+        let king = index % self.king_squares();
+        board.squares[0] = if self.material.has_pawns() {
+            square_at(king % 4, king / 4)
+        } else {
+            TRIANGLE[king]
+        };
+        if index / self.king_squares() == 1 {
+            board.turn = Color::Black;
+        }
+        board
+    }
+
+    // Looks up `pieces`, which have to match this table's material, in any order.
+    pub fn probe(&self, pieces: &mut [(Man, Square)], turn: Color) -> Dtm {
+        pieces.sort_by_key(|(man, _)| self.men.iter().position(|m| m == man));
+        let mut squares = [0; MAX_PIECES];
+        for (sq, &(_, at)) in squares.iter_mut().zip(pieces.iter()) {
+            *sq = at.index();
+        }
+        dtm(self.values[self.index(&squares[..pieces.len()], turn)])
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let name = self.material.to_string();
+        let mut bytes = Vec::with_capacity(self.values.len() + 6 + name.len());
+        bytes.extend_from_slice(MAGIC);
+        bytes.push(VERSION);
+        bytes.push(name.len() as u8);
+        bytes.extend_from_slice(name.as_bytes());
+        bytes.extend_from_slice(&self.values);
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        let Some((header, rest)) = bytes.split_at_checked(6) else {
+            bail!("Truncated table");
+        };
+        if &header[..4] != MAGIC || header[4] != VERSION {
+            bail!("Not a version {VERSION} table");
+        }
+        let Some((name, values)) = rest.split_at_checked(header[5] as usize) else {
+            bail!("Truncated table");
+        };
+        let material = Material::parse(std::str::from_utf8(name)?)?;
+        if !material.is_canonical() || material.count() > MAX_PIECES {
+            bail!("Unexpected material {material}");
+        }
+        let mut table = Self::empty(&material);
+        if values.len() != table.size() {
+            bail!(
+                "{material} table has {} positions, expected {}",
+                values.len(),
+                table.size()
+            );
+        }
+        table.values.copy_from_slice(values);
+        Ok(table)
+    }
+
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let bytes = std::fs::read(path)
+            .with_context(|| format!("Failed to read table {}", path.display()))?;
+        Self::from_bytes(&bytes).with_context(|| format!("In table {}", path.display()))
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        let path = path.as_ref();
+        std::fs::write(path, self.to_bytes())
+            .with_context(|| format!("Failed to write table {}", path.display()))
+    }
+}
+
+fn dtm(value: u8) -> Dtm {
+    match value {
+        UNKNOWN | INVALID => Dtm::Draw,
+        v if (v - 1) % 2 == 1 => Dtm::Win(u32::from(v - 1)),
+        v => Dtm::Loss(u32::from(v - 1)),
+    }
+}
+
+// One move of a [`Board`](): which man goes where, whom it takes and what
+// it promotes to.
+#[derive(Debug, Clone, Copy)]
+struct Step {
+    man: usize,
+    to: TileId,
+    capture: Option<usize>,
+    promotion: Option<Type>,
+}
+
+impl Step {
+    // Captures and promotions lead out of the table.
+    fn converts(&self) -> bool {
+        self.capture.is_some() || self.promotion.is_some()
+    }
+}
+
+// The few men of a table position, with just enough rules to move them.
+#[derive(Debug, Clone, Copy)]
+struct Board {
+    men: [Man; MAX_PIECES],
+    squares: [TileId; MAX_PIECES],
+    len: usize,
+    // One bit per occupied square.
+    occupied: u64,
+    turn: Color,
+}
+
+fn rays(ty: Type) -> (&'static [(isize, isize)], bool) {
+    match ty {
+        Type::Knight => (&KNIGHT_STEPS, false),
+        Type::King => (&KING_STEPS, false),
+        Type::Bishop => (&BISHOP_RAYS, true),
+        Type::Rook => (&ROOK_RAYS, true),
+        Type::Queen => (&KING_STEPS, true),
+        Type::Pawn => (&[], false),
+        Type::Fairy(_) => unreachable!("a position only holds orthodox pieces"),
+    }
+}
+
+fn forward(color: Color) -> isize {
+    match color {
+        Color::White => 1,
+        Color::Black => -1,
+    }
+}
+
+// Rank counted from `color`'s side of the board.
+fn relative_rank(sq: TileId, color: Color) -> usize {
+    match color {
+        Color::White => rank_of(sq),
+        Color::Black => 7 - rank_of(sq),
+    }
+}
+
+impl Board {
+    // Fills in the occupancy; `false` if two men share a square.
+    fn place(&mut self) -> bool {
+        self.occupied = 0;
+        for &sq in &self.squares[..self.len] {
+            if self.occupied & 1 << sq != 0 {
+                return false;
+            }
+            self.occupied |= 1 << sq;
+        }
+        true
+    }
+
+    fn is_empty(&self, sq: TileId) -> bool {
+        self.occupied & 1 << sq == 0
+    }
+
+    fn man_on(&self, sq: TileId) -> Option<usize> {
+        (0..self.len).find(|&i| self.squares[i] == sq)
+    }
+
+    fn pieces(&self) -> Vec<(Man, Square)> {
+        (0..self.len)
+            .map(|i| {
+                (
+                    self.men[i],
+                    Square::from_index(self.squares[i]).expect("men stand on the board"),
+                )
+            })
+            .collect()
+    }
+
+    fn king(&self, color: Color) -> TileId {
+        let i = (0..self.len)
+            .find(|&i| self.men[i] == Man::new(color, Type::King))
+            .expect("both kings are on the board");
+        self.squares[i]
+    }
+
+    fn attacks(&self, i: usize, target: TileId) -> bool {
+        let (man, from) = (self.men[i], self.squares[i]);
+        let dx = file_of(target) as isize - file_of(from) as isize;
+        let dy = rank_of(target) as isize - rank_of(from) as isize;
+        let straight = dx == 0 || dy == 0;
+        let diagonal = dx.abs() == dy.abs();
+        let aligned = match man.ty {
+            Type::Pawn => return dy == forward(man.color) && dx.abs() == 1,
+            Type::Knight => return dx.abs() * dy.abs() == 2,
+            Type::King => return dx.abs().max(dy.abs()) == 1,
+            Type::Rook => straight,
+            Type::Bishop => diagonal,
+            Type::Queen => straight || diagonal,
+            Type::Fairy(_) => unreachable!("a position only holds orthodox pieces"),
+        };
+        if !aligned || from == target {
+            return false;
+        }
+        let step = dy.signum() * 8 + dx.signum();
+        let mut sq = from as isize + step;
+        while sq != target as isize {
+            if !self.is_empty(sq as TileId) {
+                return false;
+            }
+            sq += step;
+        }
+        true
+    }
+
+    // Is `color`'s king attacked? A `captured` man no longer counts.
+    fn in_check(&self, color: Color, captured: Option<usize>) -> bool {
+        let king = self.king(color);
+        (0..self.len)
+            .any(|i| Some(i) != captured && self.men[i].color != color && self.attacks(i, king))
+    }
+
+    // Pawns never stand on the back ranks, and the side that just moved is
+    // not in check.
+    fn is_valid(&self) -> bool {
+        let pawn_on_edge = (0..self.len)
+            .any(|i| self.men[i].ty == Type::Pawn && matches!(rank_of(self.squares[i]), 0 | 7));
+        !pawn_on_edge && !self.in_check(self.turn.opposite(), None)
+    }
+
+    // Does `step` keep the mover's king out of check?
+    fn is_legal(&mut self, step: &Step) -> bool {
+        let from = self.squares[step.man];
+        let occupied = self.occupied;
+        self.occupied = occupied & !(1 << from) | 1 << step.to;
+        self.squares[step.man] = step.to;
+        let safe = !self.in_check(self.turn, step.capture);
+        self.squares[step.man] = from;
+        self.occupied = occupied;
+        safe
+    }
+
+    fn after(&self, step: &Step) -> Board {
+        let mut next = *self;
+        next.squares[step.man] = step.to;
+        if let Some(ty) = step.promotion {
+            next.men[step.man].ty = ty;
+        }
+        if let Some(victim) = step.capture {
+            next.men.copy_within(victim + 1..next.len, victim);
+            next.squares.copy_within(victim + 1..next.len, victim);
+            next.len -= 1;
+        }
+        next.place();
+        next.turn = self.turn.opposite();
+        next
+    }
+
+    // With `conversions_only`, just the captures and promotions.
+    fn pseudo_steps(&self, out: &mut Vec<Step>, conversions_only: bool) {
+        out.clear();
+        let us = self.turn;
+        for man in (0..self.len).filter(|&i| self.men[i].color == us) {
+            let from = self.squares[man];
+            let mut push = |to: TileId, capture: Option<usize>| {
+                let promotes = self.men[man].ty == Type::Pawn && relative_rank(to, us) == 7;
+                if conversions_only && capture.is_none() && !promotes {
+                    return;
+                }
+                if promotes {
+                    for ty in PROMOTIONS {
+                        out.push(Step {
+                            man,
+                            to,
+                            capture,
+                            promotion: Some(ty),
+                        });
+                    }
+                } else {
+                    out.push(Step {
+                        man,
+                        to,
+                        capture,
+                        promotion: None,
+                    });
+                }
+            };
+            if self.men[man].ty == Type::Pawn {
+                let dy = forward(us);
+                if let Some(one) = offset(from, 0, dy).filter(|&sq| self.is_empty(sq)) {
+                    push(one, None);
+                    if let Some(two) = offset(one, 0, dy)
+                        .filter(|&sq| relative_rank(from, us) == 1 && self.is_empty(sq))
+                    {
+                        push(two, None);
+                    }
+                }
+                for dx in [-1, 1] {
+                    let Some(to) = offset(from, dx, dy) else {
+                        continue;
+                    };
+                    if let Some(victim) = self.man_on(to).filter(|&v| self.men[v].color != us) {
+                        push(to, Some(victim));
+                    }
+                }
+                continue;
+            }
+            let (steps, slides) = rays(self.men[man].ty);
+            for &(dx, dy) in steps {
+                let mut sq = from;
+                while let Some(to) = offset(sq, dx, dy) {
+                    if let Some(victim) = self.man_on(to) {
+                        if self.men[victim].color != us {
+                            push(to, Some(victim));
+                        }
+                        break;
+                    }
+                    push(to, None);
+                    if !slides {
+                        break;
+                    }
+                    sq = to;
+                }
+            }
+        }
+    }
+
+    fn legal_steps(&self, out: &mut Vec<Step>, conversions_only: bool) {
+        self.pseudo_steps(out, conversions_only);
+        let mut board = *self;
+        out.retain(|step| board.is_legal(step));
+    }
+
+    // The number of different positions the legal moves lead to, counting
+    // every capture and promotion on its own. The board is stored at `class`.
+    fn successors(&self, table: &Table, class: usize, steps: &mut Vec<Step>) -> u8 {
+        self.legal_steps(steps, false);
+        let mut classes: Vec<usize> = steps
+            .iter()
+            .filter(|step| !step.converts())
+            .map(|step| table.class_after(class, self, &self.after(step), step.man))
+            .collect();
+        classes.sort_unstable();
+        classes.dedup();
+        let exits = steps.iter().filter(|step| step.converts()).count();
+        (classes.len() + exits) as u8
+    }
+
+    // Every position one ply earlier from which the side that just moved
+    // could have got here without capturing or promoting.
+    fn unmoves(&self, mut found: impl FnMut(&Board, usize)) {
+        let them = self.turn.opposite();
+        for man in (0..self.len).filter(|&i| self.men[i].color == them) {
+            let at = self.squares[man];
+            let mut visit = |from: TileId| {
+                let mut prev = *self;
+                prev.squares[man] = from;
+                prev.occupied = self.occupied & !(1 << at) | 1 << from;
+                prev.turn = them;
+                found(&prev, man);
+            };
+            if self.men[man].ty == Type::Pawn {
+                let back = -forward(them);
+                let Some(one) = offset(at, 0, back).filter(|&sq| self.is_empty(sq)) else {
+                    continue;
+                };
+                if relative_rank(one, them) >= 1 {
+                    visit(one);
+                }
+                if relative_rank(at, them) == 3 {
+                    if let Some(two) = offset(one, 0, back).filter(|&sq| self.is_empty(sq)) {
+                        visit(two);
+                    }
+                }
+                continue;
+            }
+            let (steps, slides) = rays(self.men[man].ty);
+            for &(dx, dy) in steps {
+                let mut sq = at;
+                while let Some(from) = offset(sq, dx, dy) {
+                    if !self.is_empty(from) {
+                        break;
+                    }
+                    visit(from);
+                    if !slides {
+                        break;
+                    }
+                    sq = from;
+                }
+            }
+        }
+    }
+}
+
+// Builds the table for `material`; `tb` has to hold every table a capture or
+// promotion can lead to.
+pub(crate) fn generate(material: &Material, tb: &Tablebase) -> Table {
+    let mut table = Table::empty(material);
+    let size = table.size();
+    // Positions with a capture or promotion into a mate this many plies away.
+    let mut exits: Vec<Vec<u32>> = vec![Vec::new(); MAX_PLIES + 2];
+    // Positions resolved in the last round.
+    let mut frontier = Vec::new();
+    let mut steps = Vec::new();
+
+    for index in 0..size {
+        let mut board = table.decode(index);
+        if !board.place() {
+            table.values[index] = INVALID;
+            continue;
+        }
+        // Twins share a value, worked out on the lower index.
+        let (_, twin) = table.entries(&board.squares[..board.len], board.turn);
+        if twin.is_some_and(|twin| twin < index) {
+            continue;
+        }
+        let resolve = |table: &mut Table, value: u8| {
+            table.values[index] = value;
+            if let Some(twin) = twin {
+                table.values[twin] = value;
+            }
+        };
+        if !board.is_valid() {
+            resolve(&mut table, INVALID);
+            continue;
+        }
+        // Quiet moves only matter for telling mate from check.
+        let check = board.in_check(board.turn, None);
+        board.legal_steps(&mut steps, !check);
+        if check && steps.is_empty() {
+            resolve(&mut table, 1);
+            frontier.push(index as u32);
+            continue;
+        }
+        for step in steps.iter().filter(|step| step.converts()) {
+            let next = board.after(step);
+            let plies = match tb.probe_pieces(&next.pieces(), next.turn) {
+                Some(Dtm::Win(d) | Dtm::Loss(d)) => d as usize + 1,
+                _ => continue,
+            };
+            if plies <= MAX_PLIES {
+                exits[plies].push(index as u32);
+            }
+        }
+    }
+
+    // How many different positions a position can still move to without
+    // running into a win for the opponent. Filled in when first needed.
+    let mut open = vec![UNCOUNTED; size];
+    let mut earlier = Vec::new();
+    for plies in 1..=MAX_PLIES {
+        let wins = plies % 2 == 1;
+        let mut reached = Vec::new();
+        // `class` has one more move into a position lost (when looking for
+        // wins) or won (when looking for losses) for the opponent.
+        let mut step_into = |table: &mut Table, class: usize, steps: &mut Vec<Step>| {
+            if table.values[class] != UNKNOWN {
+                return;
+            }
+            if !wins {
+                if open[class] == UNCOUNTED {
+                    let mut board = table.decode(class);
+                    board.place();
+                    open[class] = board.successors(table, class, steps);
+                }
+                open[class] -= 1;
+                if open[class] > 0 {
+                    return;
+                }
+            }
+            let mut board = table.decode(class);
+            board.place();
+            for index in [
+                Some(class),
+                table.entries(&board.squares[..board.len], board.turn).1,
+            ]
+            .into_iter()
+            .flatten()
+            {
+                table.values[index] = plies as u8 + 1;
+            }
+            reached.push(class as u32);
+        };
+        for &index in &std::mem::take(&mut exits[plies]) {
+            step_into(&mut table, index as usize, &mut steps);
+        }
+        for &child in &frontier {
+            let mut board = table.decode(child as usize);
+            board.place();
+            board.unmoves(|prev, man| {
+                earlier.push(table.class_after(child as usize, &board, prev, man))
+            });
+            earlier.sort_unstable();
+            earlier.dedup();
+            for class in earlier.drain(..) {
+                step_into(&mut table, class, &mut steps);
+            }
+        }
+        frontier = reached;
+        if frontier.is_empty() && exits[plies..].iter().all(Vec::is_empty) {
+            break;
+        }
+    }
+
+    for value in table.values.iter_mut().filter(|v| **v == INVALID) {
+        *value = UNKNOWN;
+    }
+    table
+}
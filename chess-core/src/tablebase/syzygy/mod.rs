@@ -0,0 +1,496 @@
+//! chess_core::tablebase::syzygy
+//!
+//! Probing of the published Syzygy tablebases for endings with up to seven
+//! men. [`Syzygy::open`] maps every `.rtbw` (win/draw/loss) and `.rtbz`
+//! (distance to zeroing) file of a directory; [`Syzygy::probe_wdl`],
+//! [`Syzygy::probe_dtz`] and [`Syzygy::best_move`] answer from them.
+//!
+//! Syzygy values respect the fifty-move rule: a cursed win is a mate that
+//! takes too long to count, a blessed loss one that is too slow for the
+//! opponent. DTZ counts the plies to the next capture or pawn move on the
+//! way to the result.
+
+mod table;
+
+use crate::game::GameState;
+use crate::position::{ChessMove, Position};
+use crate::types::{Color, Type};
+use anyhow::{Context, Result};
+use std::collections::HashMap;
+use std::path::Path;
+use table::{Lookup, Table};
+
+pub use table::MAX_MEN;
+
+pub const WDL_EXTENSION: &str = "rtbw";
+pub const DTZ_EXTENSION: &str = "rtbz";
+
+// Win, draw or loss for the side to move, with the fifty-move rule in mind.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Wdl {
+    Loss,
+    BlessedLoss,
+    Draw,
+    CursedWin,
+    Win,
+}
+
+impl Wdl {
+    fn from_value(value: i32) -> Self {
+        match value {
+            ..=-2 => Wdl::Loss,
+            -1 => Wdl::BlessedLoss,
+            0 => Wdl::Draw,
+            1 => Wdl::CursedWin,
+            _ => Wdl::Win,
+        }
+    }
+
+    fn signum(self) -> i32 {
+        match self {
+            Wdl::Loss | Wdl::BlessedLoss => -1,
+            Wdl::Draw => 0,
+            Wdl::CursedWin | Wdl::Win => 1,
+        }
+    }
+
+    // The result as it stands on the board: cursed wins and blessed losses
+    // are draws.
+    pub fn result(self) -> super::Wdl {
+        match self {
+            Wdl::Win => super::Wdl::Win,
+            Wdl::Loss => super::Wdl::Loss,
+            _ => super::Wdl::Draw,
+        }
+    }
+}
+
+impl std::ops::Neg for Wdl {
+    type Output = Wdl;
+
+    fn neg(self) -> Wdl {
+        match self {
+            Wdl::Loss => Wdl::Win,
+            Wdl::BlessedLoss => Wdl::CursedWin,
+            Wdl::Draw => Wdl::Draw,
+            Wdl::CursedWin => Wdl::BlessedLoss,
+            Wdl::Win => Wdl::Loss,
+        }
+    }
+}
+
+// What the tables say about a position or a move. `dtz` is in plies,
+// positive when winning; it is missing when only the WDL table is there.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Probe {
+    pub wdl: Wdl,
+    pub dtz: Option<i32>,
+}
+
+// The DTZ of the move before a capture or pawn move that reaches `wdl`.
+fn dtz_before_zeroing(wdl: Wdl) -> i32 {
+    match wdl {
+        Wdl::Win => 1,
+        Wdl::CursedWin => 101,
+        Wdl::Draw => 0,
+        Wdl::BlessedLoss => -101,
+        Wdl::Loss => -1,
+    }
+}
+
+// Splits a table name such as `KRvKP` into both sides' men.
+fn parse_name(name: &str) -> Option<(Vec<Type>, Vec<Type>)> {
+    let side = |men: &str| -> Option<Vec<Type>> {
+        let men: Vec<Type> = men
+            .chars()
+            .map(|c| match c {
+                'K' => Some(Type::King),
+                'Q' => Some(Type::Queen),
+                'R' => Some(Type::Rook),
+                'B' => Some(Type::Bishop),
+                'N' => Some(Type::Knight),
+                'P' => Some(Type::Pawn),
+                _ => None,
+            })
+            .collect::<Option<_>>()?;
+        (men.iter().filter(|&&ty| ty == Type::King).count() == 1).then_some(men)
+    };
+    let (white, black) = name.split_once('v')?;
+    Some((side(white)?, side(black)?))
+}
+
+// One side's half of a table name: `K` first, then `QRBNP`.
+fn side_name(pos: &Position, color: Color) -> String {
+    [
+        (Type::King, 'K'),
+        (Type::Queen, 'Q'),
+        (Type::Rook, 'R'),
+        (Type::Bishop, 'B'),
+        (Type::Knight, 'N'),
+        (Type::Pawn, 'P'),
+    ]
+    .iter()
+    .flat_map(|&(ty, c)| {
+        let count = pos.pieces(color).filter(|(_, man)| man.ty == ty).count();
+        std::iter::repeat_n(c, count)
+    })
+    .collect()
+}
+
+// The mapped table files of one or more directories.
+#[derive(Debug, Default)]
+pub struct Syzygy {
+    // Keyed by name, e.g. `KRvK`.
+    wdl: HashMap<String, Table>,
+    dtz: HashMap<String, Table>,
+    max_men: usize,
+}
+
+impl Syzygy {
+    // Maps the tables in `path`, which may list several directories the way
+    // `PATH` does.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        let mut tb = Self::default();
+        for dir in std::env::split_paths(path.as_ref()) {
+            let entries = std::fs::read_dir(&dir)
+                .with_context(|| format!("Failed to read {}", dir.display()))?;
+            for entry in entries {
+                let path = entry?.path();
+                let (Some(name), Some(ext)) = (
+                    path.file_stem().and_then(|s| s.to_str()),
+                    path.extension().and_then(|s| s.to_str()),
+                ) else {
+                    continue;
+                };
+                let Some((white, black)) = parse_name(name) else {
+                    continue;
+                };
+                let (tables, dtz) = match ext {
+                    WDL_EXTENSION => (&mut tb.wdl, false),
+                    DTZ_EXTENSION => (&mut tb.dtz, true),
+                    _ => continue,
+                };
+                if !tables.contains_key(name) {
+                    tables.insert(name.to_string(), Table::open(&path, &white, &black, dtz)?);
+                    tb.max_men = tb.max_men.max(white.len() + black.len());
+                }
+            }
+        }
+        Ok(tb)
+    }
+
+    // The most men of any WDL or DTZ table found.
+    pub fn max_men(&self) -> usize {
+        self.max_men
+    }
+
+    // The names of the WDL tables, e.g. `KRvK`.
+    pub fn tables(&self) -> impl Iterator<Item = &str> {
+        self.wdl.keys().map(String::as_str)
+    }
+
+    // Whether `pos` could be in the tables at all.
+    fn covers(&self, pos: &Position) -> bool {
+        pos.castling.is_empty() && pos.board.iter().flatten().count() <= self.max_men.max(2)
+    }
+
+    // The table for `pos`'s material, and whether its colors are swapped
+    // relative to the file.
+    fn table<'a>(
+        &self,
+        tables: &'a HashMap<String, Table>,
+        pos: &Position,
+    ) -> Option<(&'a Table, bool)> {
+        let (white, black) = (side_name(pos, Color::White), side_name(pos, Color::Black));
+        if let Some(table) = tables.get(&format!("{white}v{black}")) {
+            // Both sides alike: only white to move is stored.
+            return Some((table, white == black && pos.turn == Color::Black));
+        }
+        tables
+            .get(&format!("{black}v{white}"))
+            .map(|table| (table, true))
+    }
+
+    fn probe_wdl_table(&self, pos: &Position) -> Option<Wdl> {
+        if pos.board.iter().flatten().count() == 2 {
+            return Some(Wdl::Draw);
+        }
+        let (table, flip) = self.table(&self.wdl, pos)?;
+        match table.probe(pos, flip, Wdl::Draw)? {
+            Lookup::Value(value) => Some(Wdl::from_value(value)),
+            Lookup::OtherSide => None,
+        }
+    }
+
+    // The tables may hold any value for positions where a capture (or, with
+    // `zeroing`, also a pawn move) is at least as good, so those are tried
+    // first. The flag is set when such a move is the best one.
+    fn search(&self, pos: &Position, zeroing: bool) -> Option<(Wdl, bool)> {
+        let moves = pos.legal_moves();
+        let mut best = Wdl::Loss;
+        let mut tried = 0;
+        for mv in &moves {
+            let pawn = pos
+                .at(mv.from_square())
+                .is_some_and(|man| man.ty == Type::Pawn);
+            if pos.captured(mv).is_none() && !(zeroing && pawn) {
+                continue;
+            }
+            tried += 1;
+            let mut next = *pos;
+            next.make_move(mv);
+            let (value, _) = self.search(&next, false)?;
+            let value = -value;
+            if value > best {
+                best = value;
+                if value == Wdl::Win {
+                    return Some((value, true));
+                }
+            }
+        }
+        // With every move tried the table is not needed, and it could be
+        // wrong: tables know nothing about en passant.
+        let all_tried = tried > 0 && tried == moves.len();
+        let value = match all_tried {
+            true => best,
+            false => self.probe_wdl_table(pos)?,
+        };
+        if best >= value {
+            return Some((best, best > Wdl::Draw || all_tried));
+        }
+        Some((value, false))
+    }
+
+    pub fn probe_wdl(&self, pos: &Position) -> Option<Wdl> {
+        if !self.covers(pos) {
+            return None;
+        }
+        self.search(pos, false).map(|(wdl, _)| wdl)
+    }
+
+    // Plies to the next capture or pawn move that keeps the result, positive
+    // when winning and 0 for draws.
+    pub fn probe_dtz(&self, pos: &Position) -> Option<i32> {
+        if !self.covers(pos) {
+            return None;
+        }
+        let (wdl, zeroing) = self.search(pos, true)?;
+        if wdl == Wdl::Draw {
+            return Some(0);
+        }
+        if zeroing {
+            return Some(dtz_before_zeroing(wdl));
+        }
+        let (table, flip) = self.table(&self.dtz, pos)?;
+        match table.probe(pos, flip, wdl)? {
+            Lookup::Value(dtz) => {
+                let cursed = matches!(wdl, Wdl::CursedWin | Wdl::BlessedLoss);
+                Some((dtz + if cursed { 100 } else { 0 }) * wdl.signum())
+            }
+            // Only the other side to move is stored: look one ply ahead.
+            Lookup::OtherSide => {
+                let mut min = i32::MAX;
+                for mv in pos.legal_moves() {
+                    let zeroing = pos.captured(&mv).is_some()
+                        || pos
+                            .at(mv.from_square())
+                            .is_some_and(|man| man.ty == Type::Pawn);
+                    let mut next = *pos;
+                    next.make_move(&mv);
+                    let mut dtz = match zeroing {
+                        true => -dtz_before_zeroing(self.search(&next, false)?.0),
+                        false => -self.probe_dtz(&next)?,
+                    };
+                    if dtz == 1 && next.is_checkmate() {
+                        min = 1;
+                    }
+                    if !zeroing {
+                        dtz += dtz.signum();
+                    }
+                    if dtz < min && dtz.signum() == wdl.signum() {
+                        min = dtz;
+                    }
+                }
+                Some(if min == i32::MAX { -1 } else { min })
+            }
+        }
+    }
+
+    pub fn probe(&self, pos: &Position) -> Option<Probe> {
+        let wdl = self.probe_wdl(pos)?;
+        Some(Probe {
+            wdl,
+            dtz: self.probe_dtz(pos),
+        })
+    }
+
+    // Probes the current position of `game`.
+    pub fn probe_game(&self, game: &GameState) -> Option<Probe> {
+        self.probe(&game.hist.position())
+    }
+
+    // The move that wins soonest, or holds the draw, or loses slowest,
+    // counting the fifty-move rule from `pos`'s halfmove clock. The probe is
+    // `pos`'s value when that move is played.
+    pub fn best_move(&self, pos: &Position) -> Option<(ChessMove, Probe)> {
+        const CERTAIN: i32 = 1000;
+        let clock = pos.halfmoves as i32;
+        let mut best: Option<(i32, i32, ChessMove)> = None;
+        for mv in pos.legal_moves() {
+            let mut next = *pos;
+            next.make_move(&mv);
+            // Plies from `pos` to the next zeroing move on the way to the result.
+            let mut dtz = if next.halfmoves == 0 {
+                dtz_before_zeroing(-self.probe_wdl(&next)?)
+            } else {
+                let dtz = -self.probe_dtz(&next)?;
+                dtz + dtz.signum()
+            };
+            if dtz == 2 && next.is_checkmate() {
+                dtz = 1;
+            }
+            // Wins the fifty-move rule does not spoil are all as good, and so
+            // are losses it does not save.
+            let rank = match dtz {
+                1.. if dtz + clock <= 99 => CERTAIN,
+                1.. => CERTAIN - (dtz + clock),
+                0 => 0,
+                _ if -dtz * 2 + clock < 100 => -CERTAIN,
+                _ => -CERTAIN + (-dtz + clock),
+            };
+            if best.is_none_or(|(r, d, _)| (rank, -dtz) > (r, -d)) {
+                best = Some((rank, dtz, mv));
+            }
+        }
+        let (rank, dtz, mv) = best?;
+        let wdl = match rank {
+            CERTAIN => Wdl::Win,
+            1.. => Wdl::CursedWin,
+            0 => Wdl::Draw,
+            r if r > -CERTAIN => Wdl::BlessedLoss,
+            _ => Wdl::Loss,
+        };
+        Some((
+            mv,
+            Probe {
+                wdl,
+                dtz: Some(dtz),
+            },
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::position::Man;
+    use crate::tablebase::{Material, Tablebase};
+
+    #[test]
+    fn names_tables_after_the_material() {
+        let pos = Position::from_fen("8/8/4k3/3r4/8/8/2PQ4/4K3 w - - 0 1").unwrap();
+        assert_eq!(side_name(&pos, Color::White), "KQP");
+        assert_eq!(side_name(&pos, Color::Black), "KR");
+        assert_eq!(
+            parse_name("KRPvKB"),
+            Some((
+                vec![Type::King, Type::Rook, Type::Pawn],
+                vec![Type::King, Type::Bishop]
+            ))
+        );
+        assert_eq!(parse_name("KRvR"), None);
+        assert_eq!(parse_name("KRK"), None);
+        assert_eq!(-Wdl::CursedWin, Wdl::BlessedLoss);
+        assert_eq!(Wdl::CursedWin.result(), crate::tablebase::Wdl::Draw);
+    }
+
+    #[test]
+    fn maps_table_files() {
+        let dir = std::env::temp_dir().join(format!("chess-core-syzygy-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("KQvK.rtbw"), b"not a table").unwrap();
+        let err = Syzygy::open(&dir).unwrap_err();
+        assert!(
+            format!("{err:#}").contains("not a Syzygy WDL table"),
+            "{err:#}"
+        );
+
+        std::fs::write(
+            dir.join("KQvK.rtbw"),
+            [&table::WDL_MAGIC[..], &[0; 8]].concat(),
+        )
+        .unwrap();
+        let err = Syzygy::open(&dir).unwrap_err();
+        assert!(format!("{err:#}").contains("damaged"), "{err:#}");
+
+        // Every position of the table is a win with white to move, and a
+        // loss with black to move.
+        std::fs::write(dir.join("KQvK.rtbw"), table::tests::kqvk_file(Some([4, 0]))).unwrap();
+        let tb = Syzygy::open(&dir).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(tb.max_men(), 3);
+        let wdl = |fen: &str| tb.probe_wdl(&Position::from_fen(fen).unwrap());
+        assert_eq!(wdl("8/8/8/8/8/2k5/8/KQ6 w - - 0 1"), Some(Wdl::Win));
+        assert_eq!(wdl("8/8/8/4k3/8/8/8/KQ6 b - - 0 1"), Some(Wdl::Loss));
+        assert_eq!(wdl("kq6/8/8/8/8/8/8/4K3 b - - 0 1"), Some(Wdl::Win));
+        // Taking the queen draws, whatever the table says.
+        assert_eq!(wdl("8/8/8/8/8/8/kQ6/7K b - - 0 1"), Some(Wdl::Draw));
+        assert_eq!(wdl("8/8/8/8/8/2k5/8/KR6 w - - 0 1"), None);
+        // No DTZ table to go with it.
+        assert_eq!(
+            tb.probe_dtz(&Position::from_fen("8/8/8/8/8/2k5/8/KQ6 w - - 0 1").unwrap()),
+            None
+        );
+
+        let empty = Syzygy::default();
+        assert_eq!(
+            empty.probe_wdl(&Position::from_fen("8/8/4k3/8/8/8/8/4K3 w - - 0 1").unwrap()),
+            Some(Wdl::Draw)
+        );
+        assert_eq!(empty.probe_wdl(&Position::startpos()), None);
+    }
+
+    // Checks the published 3-man tables in `tests/syzygy` against our own
+    // retrograde analysis: with so few men the fifty-move rule never
+    // matters, so the results agree.
+    #[test]
+    fn agrees_with_retrograde_analysis() {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/syzygy");
+        let syzygy = Syzygy::open(path).unwrap();
+        assert_eq!(syzygy.max_men(), 3);
+        let mut tb = Tablebase::new();
+        for name in ["KQvK", "KRvK", "KPvK"] {
+            tb.generate(&Material::parse(name).unwrap()).unwrap();
+        }
+        for (white, margin) in [(Type::Queen, 0), (Type::Rook, 0), (Type::Pawn, 8)] {
+            for (wk, bk, sq) in
+                (0..64).flat_map(|a| (0..64).flat_map(move |b| (0..64).map(move |c| (a, b, c))))
+            {
+                if wk == bk || sq == wk || sq == bk || !(margin..64 - margin).contains(&sq) {
+                    continue;
+                }
+                let mut pos = Position::empty();
+                pos.board[wk] = Some(Man::new(Color::White, Type::King));
+                pos.board[bk] = Some(Man::new(Color::Black, Type::King));
+                pos.board[sq] = Some(Man::new(Color::White, white));
+                for turn in [Color::White, Color::Black] {
+                    pos.turn = turn;
+                    let them = turn.opposite();
+                    if pos.is_attacked(pos.king(them).unwrap(), turn) {
+                        continue;
+                    }
+                    let wdl = syzygy.probe_wdl(&pos).unwrap();
+                    assert_eq!(wdl.result(), tb.probe_wdl(&pos).unwrap(), "{pos:?}");
+                    let dtz = syzygy.probe_dtz(&pos).unwrap();
+                    assert_eq!(dtz.signum(), wdl.signum(), "{pos:?}");
+                }
+            }
+        }
+        let pos = Position::from_fen("8/8/8/3k4/8/8/8/R3K3 w - - 0 1").unwrap();
+        let (mv, probe) = syzygy.best_move(&pos).unwrap();
+        assert_eq!(probe.wdl, Wdl::Win);
+        let mut next = pos;
+        next.make_move(&mv);
+        assert_eq!(syzygy.probe_wdl(&next), Some(Wdl::Loss));
+    }
+}
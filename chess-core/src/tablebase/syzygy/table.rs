@@ -0,0 +1,865 @@
+//! chess_core::tablebase::syzygy::table
+//!
+//! A single memory-mapped `.rtbw` or `.rtbz` file: its header, the position
+//! index that the generator used and the decompression of the stored values.
+//! The layout follows the reference prober that ships with the tables.
+
+use super::Wdl;
+use crate::msg::TileId;
+use crate::position::{file_of, rank_of, Man, Position};
+use crate::types::{Color, Type};
+use anyhow::{bail, ensure, Context, Result};
+use memmap2::Mmap;
+use std::fs::File;
+use std::path::Path;
+use std::sync::OnceLock;
+
+pub const WDL_MAGIC: [u8; 4] = [0x71, 0xe8, 0x23, 0x5d];
+pub const DTZ_MAGIC: [u8; 4] = [0xd7, 0x66, 0x0c, 0xa5];
+
+// The most men any published table has.
+pub const MAX_MEN: usize = 7;
+
+// Flags of each [`Pairs`]() block.
+const STM: u8 = 1;
+const MAPPED: u8 = 2;
+const WIN_PLIES: u8 = 4;
+const LOSS_PLIES: u8 = 8;
+const WIDE: u8 = 16;
+const SINGLE_VALUE: u8 = 128;
+
+// Index tables shared by every file, built on first use.
+struct Indices {
+    // `binomial[k][n]` ways to choose `k` of `n` squares.
+    binomial: [[u64; 64]; MAX_MEN],
+    // a2-h7 numbered so that the pawn nearest the edge, then lowest, has the
+    // highest number; it leads the encoding.
+    map_pawns: [usize; 64],
+    lead_pawn_index: [[u64; 64]; MAX_MEN],
+    lead_pawns_size: [[u64; 4]; MAX_MEN],
+    // The 28 squares below the a1-h8 diagonal.
+    map_b1h1h7: [u64; 64],
+    // The a1-d1-d4 triangle, diagonal squares last.
+    map_a1d1d4: [usize; 64],
+    // The 462 ways to place two kings with the first one in the triangle.
+    map_kk: [[u64; 64]; 10],
+}
+
+// Rank minus file: negative below the a1-h8 diagonal.
+fn off_diagonal(sq: TileId) -> isize {
+    rank_of(sq) as isize - file_of(sq) as isize
+}
+
+fn indices() -> &'static Indices {
+    static INDICES: OnceLock<Indices> = OnceLock::new();
+    INDICES.get_or_init(|| {
+        let mut ix = Indices {
+            binomial: [[0; 64]; MAX_MEN],
+            map_pawns: [0; 64],
+            lead_pawn_index: [[0; 64]; MAX_MEN],
+            lead_pawns_size: [[0; 4]; MAX_MEN],
+            map_b1h1h7: [0; 64],
+            map_a1d1d4: [0; 64],
+            map_kk: [[0; 64]; 10],
+        };
+        ix.binomial[0][0] = 1;
+        for n in 1..64 {
+            for k in 0..MAX_MEN.min(n + 1) {
+                let with = if k > 0 { ix.binomial[k - 1][n - 1] } else { 0 };
+                let without = if k < n { ix.binomial[k][n - 1] } else { 0 };
+                ix.binomial[k][n] = with + without;
+            }
+        }
+
+        let mut available = 48;
+        for leads in 1..MAX_MEN - 1 {
+            for file in 0..4 {
+                let mut index = 0;
+                for rank in 1..7 {
+                    let sq = rank * 8 + file;
+                    if leads == 1 {
+                        available -= 1;
+                        ix.map_pawns[sq] = available;
+                        available -= 1;
+                        ix.map_pawns[sq ^ 7] = available;
+                    }
+                    ix.lead_pawn_index[leads][sq] = index;
+                    index += ix.binomial[leads - 1][ix.map_pawns[sq]];
+                }
+                ix.lead_pawns_size[leads][file] = index;
+            }
+        }
+
+        for (code, sq) in (0..64).filter(|&sq| off_diagonal(sq) < 0).enumerate() {
+            ix.map_b1h1h7[sq] = code as u64;
+        }
+
+        let mut code = 0;
+        let triangle = (0..28).filter(|&sq| file_of(sq) <= 3);
+        for sq in triangle.clone().filter(|&sq| off_diagonal(sq) < 0) {
+            ix.map_a1d1d4[sq] = code;
+            code += 1;
+        }
+        for sq in triangle.filter(|&sq| off_diagonal(sq) == 0) {
+            ix.map_a1d1d4[sq] = code;
+            code += 1;
+        }
+
+        let mut code = 0;
+        let mut both_on_diagonal = Vec::new();
+        for index in 0..10 {
+            // Only b1 really maps to 0; the other zeroes are unused squares.
+            let Some(first) =
+                (0..28).find(|&sq| ix.map_a1d1d4[sq] == index && (index > 0 || sq == 1))
+            else {
+                continue;
+            };
+            for second in 0..64 {
+                let apart = file_of(first)
+                    .abs_diff(file_of(second))
+                    .max(rank_of(first).abs_diff(rank_of(second)));
+                if apart <= 1 || off_diagonal(first) == 0 && off_diagonal(second) > 0 {
+                    continue;
+                }
+                if off_diagonal(first) == 0 && off_diagonal(second) == 0 {
+                    both_on_diagonal.push((index, second));
+                } else {
+                    ix.map_kk[index][second] = code;
+                    code += 1;
+                }
+            }
+        }
+        for (index, second) in both_on_diagonal {
+            ix.map_kk[index][second] = code;
+            code += 1;
+        }
+        ix
+    })
+}
+
+// The pieces as the files number them: white 1-6, black 9-14.
+fn piece_code(man: Man) -> u8 {
+    let ty = match man.ty {
+        Type::Pawn => 1,
+        Type::Knight => 2,
+        Type::Bishop => 3,
+        Type::Rook => 4,
+        Type::Queen => 5,
+        Type::King => 6,
+        Type::Fairy(_) => unreachable!("a position only holds orthodox pieces"),
+    };
+    match man.color {
+        Color::White => ty,
+        Color::Black => ty | 8,
+    }
+}
+
+// Mirrors the men of a pawnless position so that the first one stands in the
+// a1-d1-d4 triangle and, where that leaves a choice, the first men of the
+// leading group below the diagonal. Then numbers the leading group: the two
+// kings, or three unique men when the material has any.
+fn lead_index(squares: &mut [TileId], lead: usize, unique: bool) -> u64 {
+    let ix = indices();
+    if file_of(squares[0]) > 3 {
+        squares.iter_mut().for_each(|sq| *sq ^= 7);
+    }
+    if rank_of(squares[0]) > 3 {
+        squares.iter_mut().for_each(|sq| *sq ^= 56);
+    }
+    if let Some(i) = (0..lead).find(|&i| off_diagonal(squares[i]) != 0) {
+        if off_diagonal(squares[i]) > 0 {
+            for sq in &mut squares[i..] {
+                *sq = (*sq >> 3 | *sq << 3) & 63;
+            }
+        }
+    }
+    if !unique {
+        return ix.map_kk[ix.map_a1d1d4[squares[0]]][squares[1]];
+    }
+    let (s0, s1, s2) = (squares[0], squares[1], squares[2]);
+    let adjust1 = (s1 > s0) as u64;
+    let adjust2 = (s2 > s0) as u64 + (s2 > s1) as u64;
+    let (r0, r1, r2) = (rank_of(s0) as u64, rank_of(s1) as u64, rank_of(s2) as u64);
+    if off_diagonal(s0) != 0 {
+        (ix.map_a1d1d4[s0] as u64 * 63 + s1 as u64 - adjust1) * 62 + s2 as u64 - adjust2
+    } else if off_diagonal(s1) != 0 {
+        (6 * 63 + r0 * 28 + ix.map_b1h1h7[s1]) * 62 + s2 as u64 - adjust2
+    } else if off_diagonal(s2) != 0 {
+        6 * 63 * 62 + 4 * 28 * 62 + r0 * 7 * 28 + (r1 - adjust1) * 28 + ix.map_b1h1h7[s2]
+    } else {
+        6 * 63 * 62 + 4 * 28 * 62 + 4 * 7 * 28 + r0 * 7 * 6 + (r1 - adjust1) * 6 + r2 - adjust2
+    }
+}
+
+// What came out of a lookup.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Lookup {
+    Value(i32),
+    // DTZ files only store one side to move; this is the other one.
+    OtherSide,
+}
+
+// One compressed sub-table: a side to move, and with pawns the file of the
+// leading pawn.
+#[derive(Debug, Clone, Default)]
+struct Pairs {
+    flags: u8,
+    max_sym_len: u8,
+    min_sym_len: u8,
+    num_blocks: usize,
+    block_size: usize,
+    span: usize,
+    // Offsets into the file.
+    lowest_sym: usize,
+    btree: usize,
+    block_length: usize,
+    block_length_size: usize,
+    sparse_index: usize,
+    sparse_index_size: usize,
+    data: usize,
+    base64: Vec<u64>,
+    // How many values, minus one, each symbol expands to.
+    symlen: Vec<u8>,
+    // The men in the order they are encoded.
+    pieces: [u8; MAX_MEN],
+    group_index: [u64; MAX_MEN + 1],
+    group_len: [usize; MAX_MEN + 1],
+    // Where the DTZ value maps for win, loss, cursed win and blessed loss start.
+    map: [usize; 4],
+}
+
+// Little helpers for reading the mapped bytes.
+fn byte(data: &[u8], at: usize) -> Option<u8> {
+    data.get(at).copied()
+}
+
+fn u16_le(data: &[u8], at: usize) -> Option<u16> {
+    Some(u16::from_le_bytes(data.get(at..at + 2)?.try_into().ok()?))
+}
+
+fn u32_le(data: &[u8], at: usize) -> Option<u32> {
+    Some(u32::from_le_bytes(data.get(at..at + 4)?.try_into().ok()?))
+}
+
+fn u32_be(data: &[u8], at: usize) -> Option<u32> {
+    Some(u32::from_be_bytes(data.get(at..at + 4)?.try_into().ok()?))
+}
+
+fn u64_be(data: &[u8], at: usize) -> Option<u64> {
+    Some(u64::from_be_bytes(data.get(at..at + 8)?.try_into().ok()?))
+}
+
+impl Pairs {
+    // The left and right halves of a symbol; the left one of a leaf is the value.
+    fn children(&self, data: &[u8], sym: usize) -> Option<(usize, usize)> {
+        let at = self.btree + 3 * sym;
+        let bytes = data.get(at..at + 3)?;
+        let left = (usize::from(bytes[1]) & 0xf) << 8 | usize::from(bytes[0]);
+        let right = usize::from(bytes[2]) << 4 | usize::from(bytes[1]) >> 4;
+        Some((left, right))
+    }
+
+    // Splits the men into groups that are numbered one after another, and
+    // works out what each group's number is multiplied by.
+    fn set_groups(&mut self, table: &Table, order: [u8; 2], file: usize) {
+        let ix = indices();
+        let mut n = 0;
+        let mut first_len: isize = match (table.has_pawns, table.unique) {
+            (true, _) => 0,
+            (false, true) => 3,
+            (false, false) => 2,
+        };
+        self.group_len[0] = 1;
+        for i in 1..table.men {
+            first_len -= 1;
+            if first_len > 0 || self.pieces[i] == self.pieces[i - 1] {
+                self.group_len[n] += 1;
+            } else {
+                n += 1;
+                self.group_len[n] = 1;
+            }
+        }
+        n += 1;
+        self.group_len[n] = 0;
+
+        let both_pawns = table.has_pawns && table.pawns[1] > 0;
+        let mut next = if both_pawns { 2 } else { 1 };
+        let mut free = 64 - self.group_len[0] - if both_pawns { self.group_len[1] } else { 0 };
+        let mut index = 1;
+        let mut k = 0;
+        while next < n || k == order[0] as usize || k == order[1] as usize {
+            if k == order[0] as usize {
+                self.group_index[0] = index;
+                index *= match (table.has_pawns, table.unique) {
+                    (true, _) => ix.lead_pawns_size[self.group_len[0]][file],
+                    (false, true) => 31332,
+                    (false, false) => 462,
+                };
+            } else if k == order[1] as usize {
+                self.group_index[1] = index;
+                index *= ix.binomial[self.group_len[1]][48 - self.group_len[0]];
+            } else {
+                self.group_index[next] = index;
+                index *= ix.binomial[self.group_len[next]][free];
+                free -= self.group_len[next];
+                next += 1;
+            }
+            k += 1;
+        }
+        self.group_index[n] = index;
+    }
+
+    // Reads the block sizes and the Huffman code; returns where they end.
+    fn set_sizes(&mut self, data: &[u8], mut at: usize) -> Option<usize> {
+        self.flags = byte(data, at)?;
+        at += 1;
+        if self.flags & SINGLE_VALUE != 0 {
+            // The one value every position has.
+            self.min_sym_len = byte(data, at)?;
+            return Some(at + 1);
+        }
+        let groups = self.group_len.iter().position(|&len| len == 0)?;
+        let size = self.group_index[groups] as usize;
+        self.block_size = 1usize.checked_shl(byte(data, at)?.into())?;
+        self.span = 1usize.checked_shl(byte(data, at + 1)?.into())?;
+        self.sparse_index_size = size.div_ceil(self.span);
+        let padding = usize::from(byte(data, at + 2)?);
+        self.num_blocks = u32_le(data, at + 3)? as usize;
+        self.block_length_size = self.num_blocks + padding;
+        self.max_sym_len = byte(data, at + 7)?;
+        self.min_sym_len = byte(data, at + 8)?;
+        at += 9;
+        if self.min_sym_len == 0 || self.max_sym_len < self.min_sym_len || self.max_sym_len > 32 {
+            return None;
+        }
+        self.lowest_sym = at;
+
+        // Canonical Huffman code: longer codes have lower values. `base64[l]`
+        // is the lowest code of length `min_sym_len + l`, padded to 64 bits.
+        let lengths = usize::from(self.max_sym_len - self.min_sym_len) + 1;
+        let lowest = |i: usize| u16_le(data, self.lowest_sym + 2 * i).map(u64::from);
+        self.base64 = vec![0; lengths];
+        for i in (0..lengths - 1).rev() {
+            let sum = self.base64[i + 1]
+                .wrapping_add(lowest(i)?)
+                .wrapping_sub(lowest(i + 1)?);
+            self.base64[i] = sum / 2;
+        }
+        for (i, base) in self.base64.iter_mut().enumerate() {
+            *base <<= 64 - i - usize::from(self.min_sym_len);
+        }
+        at += 2 * lengths;
+
+        let symbols = usize::from(u16_le(data, at)?);
+        at += 2;
+        self.btree = at;
+        self.symlen = vec![0; symbols];
+        let mut visited = vec![false; symbols];
+        for sym in 0..symbols {
+            if !visited[sym] {
+                self.symlen[sym] = self.expand(data, sym, &mut visited)?;
+            }
+        }
+        Some(at + 3 * symbols + (symbols & 1))
+    }
+
+    // The number of values, minus one, a symbol stands for.
+    fn expand(&mut self, data: &[u8], sym: usize, visited: &mut [bool]) -> Option<u8> {
+        visited[sym] = true;
+        let (left, right) = self.children(data, sym)?;
+        if right == 0xfff {
+            return Some(0);
+        }
+        for child in [left, right] {
+            if !*visited.get(child)? {
+                self.symlen[child] = self.expand(data, child, visited)?;
+            }
+        }
+        Some(
+            self.symlen[left]
+                .wrapping_add(self.symlen[right])
+                .wrapping_add(1),
+        )
+    }
+
+    // The stored value of position `index`.
+    fn decompress(&self, data: &[u8], index: u64) -> Option<u16> {
+        if self.flags & SINGLE_VALUE != 0 {
+            return Some(u16::from(self.min_sym_len));
+        }
+        let index = index as usize;
+        // Every `span` positions a sparse entry says which block holds the
+        // position in the middle of the span, and where in the block it is.
+        let k = index / self.span;
+        let entry = self.sparse_index + 6 * k;
+        let mut block = u32_le(data, entry)? as usize;
+        let mut offset = i64::from(u16_le(data, entry + 4)?);
+        offset += (index % self.span) as i64 - (self.span / 2) as i64;
+        let length = |block: usize| {
+            (block < self.block_length_size)
+                .then(|| u16_le(data, self.block_length + 2 * block))
+                .flatten()
+                .map(i64::from)
+        };
+        while offset < 0 {
+            block = block.checked_sub(1)?;
+            offset += length(block)? + 1;
+        }
+        while offset > length(block)? {
+            offset -= length(block)? + 1;
+            block += 1;
+        }
+
+        // Walk the block's Huffman symbols until one covers the offset.
+        let mut at = self.data + block * self.block_size;
+        let mut buf = u64_be(data, at)?;
+        at += 8;
+        let mut buf_size = 64;
+        let min = usize::from(self.min_sym_len);
+        let mut sym;
+        loop {
+            let mut len = 0;
+            while buf < self.base64[len] {
+                len += 1;
+                if len == self.base64.len() {
+                    return None;
+                }
+            }
+            sym = ((buf - self.base64[len]) >> (64 - len - min)) as usize;
+            sym += usize::from(u16_le(data, self.lowest_sym + 2 * len)?);
+            let count = i64::from(*self.symlen.get(sym)?) + 1;
+            if offset < count {
+                break;
+            }
+            offset -= count;
+            let bits = len + min;
+            buf <<= bits;
+            buf_size -= bits;
+            if buf_size <= 32 {
+                buf_size += 32;
+                buf |= u64::from(u32_be(data, at)?) << (64 - buf_size);
+                at += 4;
+            }
+        }
+
+        // Symbols stand for pairs of symbols; descend to the one value.
+        while self.symlen[sym] != 0 {
+            let (left, right) = self.children(data, sym)?;
+            let count = i64::from(*self.symlen.get(left)?) + 1;
+            if offset < count {
+                sym = left;
+            } else {
+                offset -= count;
+                sym = right;
+            }
+        }
+        self.children(data, sym).map(|(value, _)| value as u16)
+    }
+}
+
+#[derive(Debug)]
+pub struct Table {
+    map: Mmap,
+    dtz: bool,
+    // Both sides have the same men; only white to move is stored.
+    symmetric: bool,
+    has_pawns: bool,
+    // Some side has exactly one of a kind besides its king.
+    unique: bool,
+    men: usize,
+    // Pawns of the leading color, then of the other one.
+    pawns: [usize; 2],
+    // By side to move, then by file of the leading pawn.
+    pairs: Vec<Vec<Pairs>>,
+}
+
+impl Table {
+    // Maps the table for the men named by `white` and `black`, the two halves
+    // of the file name.
+    pub fn open(path: &Path, white: &[Type], black: &[Type], dtz: bool) -> Result<Self> {
+        let file =
+            File::open(path).with_context(|| format!("Failed to open {}", path.display()))?;
+        // SAFETY: table files are never written to while they are in use, so
+        // the mapped bytes stay put for as long as the map lives.
+        let map = unsafe { Mmap::map(&file) }
+            .with_context(|| format!("Failed to map {}", path.display()))?;
+        let count = |men: &[Type]| men.iter().filter(|&&ty| ty == Type::Pawn).count();
+        let (white_pawns, black_pawns) = (count(white), count(black));
+        // The side with fewer pawns leads, as long as it has any.
+        let white_leads = black_pawns == 0 || white_pawns > 0 && black_pawns >= white_pawns;
+        let unique = [white, black].iter().any(|men| {
+            men.iter()
+                .any(|&ty| ty != Type::King && men.iter().filter(|&&t| t == ty).count() == 1)
+        });
+        let mut table = Self {
+            map,
+            dtz,
+            symmetric: white == black,
+            has_pawns: white_pawns + black_pawns > 0,
+            unique,
+            men: white.len() + black.len(),
+            pawns: match white_leads {
+                true => [white_pawns, black_pawns],
+                false => [black_pawns, white_pawns],
+            },
+            pairs: Vec::new(),
+        };
+        ensure!(
+            table.men <= MAX_MEN,
+            "{} has more than {MAX_MEN} men",
+            path.display()
+        );
+        let magic = if dtz { DTZ_MAGIC } else { WDL_MAGIC };
+        ensure!(
+            table.map.get(..4) == Some(&magic[..]),
+            "{} is not a Syzygy {} table",
+            path.display(),
+            if dtz { "DTZ" } else { "WDL" }
+        );
+        match table.read_header() {
+            Some(pairs) => table.pairs = pairs,
+            None => bail!("{} is damaged or does not match its name", path.display()),
+        }
+        Ok(table)
+    }
+
+    fn read_header(&self) -> Option<Vec<Vec<Pairs>>> {
+        let data = &self.map[..];
+        let flags = byte(data, 4)?;
+        if (flags & 2 != 0) != self.has_pawns || (flags & 1 != 0) == self.symmetric {
+            return None;
+        }
+        let mut at = 5;
+        let sides = if self.dtz || self.symmetric { 1 } else { 2 };
+        let files = if self.has_pawns { 4 } else { 1 };
+        let both_pawns = self.has_pawns && self.pawns[1] > 0;
+        let mut pairs = vec![vec![Pairs::default(); files]; sides];
+
+        for file in 0..files {
+            let first = byte(data, at)?;
+            let second = if both_pawns {
+                byte(data, at + 1)?
+            } else {
+                0xff
+            };
+            let order = [[first & 0xf, second & 0xf], [first >> 4, second >> 4]];
+            at += 1 + both_pawns as usize;
+            for k in 0..self.men {
+                let code = byte(data, at + k)?;
+                for (side, pairs) in pairs.iter_mut().enumerate() {
+                    pairs[file].pieces[k] = if side == 1 { code >> 4 } else { code & 0xf };
+                }
+            }
+            at += self.men;
+            for (side, pairs) in pairs.iter_mut().enumerate() {
+                pairs[file].set_groups(self, order[side], file);
+            }
+        }
+        at += at & 1;
+
+        for file in 0..files {
+            for pairs in pairs.iter_mut() {
+                at = pairs[file].set_sizes(data, at)?;
+            }
+        }
+
+        if self.dtz {
+            for pairs in pairs[0].iter_mut().filter(|p| p.flags & MAPPED != 0) {
+                if pairs.flags & WIDE != 0 {
+                    at += at & 1;
+                    for start in pairs.map.iter_mut() {
+                        *start = at + 2;
+                        at += 2 * usize::from(u16_le(data, at)?) + 2;
+                    }
+                } else {
+                    for start in pairs.map.iter_mut() {
+                        *start = at + 1;
+                        at += usize::from(byte(data, at)?) + 1;
+                    }
+                }
+            }
+            at += at & 1;
+        }
+
+        for file in 0..files {
+            for pairs in pairs.iter_mut() {
+                pairs[file].sparse_index = at;
+                at += 6 * pairs[file].sparse_index_size;
+            }
+        }
+        for file in 0..files {
+            for pairs in pairs.iter_mut() {
+                pairs[file].block_length = at;
+                at += 2 * pairs[file].block_length_size;
+            }
+        }
+        for file in 0..files {
+            for pairs in pairs.iter_mut() {
+                at = at.next_multiple_of(64);
+                pairs[file].data = at;
+                at += pairs[file].num_blocks * pairs[file].block_size;
+            }
+        }
+        (at <= data.len()).then_some(pairs)
+    }
+
+    // Looks up `pos`. With `flip` the colors of `pos` are swapped to match
+    // the file. DTZ tables need the position's `wdl` to read their values.
+    // `None` if the file turns out to be damaged.
+    pub fn probe(&self, pos: &Position, flip: bool, wdl: Wdl) -> Option<Lookup> {
+        let ix = indices();
+        let flip_color = if flip { 8 } else { 0 };
+        let flip_squares = if flip { 56 } else { 0 };
+        let stm = usize::from(flip) ^ usize::from(pos.turn == Color::Black);
+        let mut squares = [0; MAX_MEN];
+        let mut pieces = [0; MAX_MEN];
+        let mut size = 0;
+        let mut leads = 0;
+        let mut lead_pawns = 0u64;
+        let mut file = 0;
+
+        if self.has_pawns {
+            let lead = self.pairs[0][0].pieces[0] ^ flip_color;
+            let color = if lead & 8 == 0 {
+                Color::White
+            } else {
+                Color::Black
+            };
+            for (sq, _) in pos.pieces(color).filter(|(_, man)| man.ty == Type::Pawn) {
+                squares[size] = sq.index() ^ flip_squares;
+                lead_pawns |= 1 << sq.index();
+                size += 1;
+            }
+            leads = size;
+            let first = (0..leads).max_by_key(|&i| ix.map_pawns[squares[i]])?;
+            squares.swap(0, first);
+            file = file_of(squares[0]).min(7 - file_of(squares[0]));
+        }
+
+        let side = if self.dtz { 0 } else { stm % self.pairs.len() };
+        let d = &self.pairs[side][file];
+        // Symmetric pawnless tables serve both sides to move.
+        let one_sided = self.has_pawns || !self.symmetric;
+        if self.dtz && one_sided && usize::from(d.flags & STM) != stm {
+            return Some(Lookup::OtherSide);
+        }
+
+        for sq in (0..64).filter(|&sq| lead_pawns & 1 << sq == 0) {
+            if let Some(man) = pos.board[sq] {
+                if size == MAX_MEN {
+                    return None;
+                }
+                squares[size] = sq ^ flip_squares;
+                pieces[size] = piece_code(man) ^ flip_color;
+                size += 1;
+            }
+        }
+        if size != self.men {
+            return None;
+        }
+        // Line the men up in the order of the file.
+        for i in leads..size.saturating_sub(1) {
+            if let Some(j) = (i + 1..size).find(|&j| pieces[j] == d.pieces[i]) {
+                pieces.swap(i, j);
+                squares.swap(i, j);
+            }
+        }
+
+        let squares = &mut squares[..size];
+        let mut index = if self.has_pawns {
+            if file_of(squares[0]) > 3 {
+                squares.iter_mut().for_each(|sq| *sq ^= 7);
+            }
+            let mut index = ix.lead_pawn_index[leads][squares[0]];
+            squares[1..leads].sort_by_key(|&sq| ix.map_pawns[sq]);
+            for (i, &sq) in squares.iter().enumerate().take(leads).skip(1) {
+                index += ix.binomial[i][ix.map_pawns[sq]];
+            }
+            index
+        } else {
+            lead_index(squares, d.group_len[0], self.unique)
+        };
+
+        // The other groups, each sorted, counting only the squares the earlier
+        // groups left free.
+        index *= d.group_index[0];
+        let mut start = d.group_len[0];
+        let mut pawns_left = self.has_pawns && self.pawns[1] > 0;
+        let mut next = 1;
+        while d.group_len[next] != 0 {
+            let len = d.group_len[next];
+            squares[start..start + len].sort_unstable();
+            let mut n = 0;
+            for i in 0..len {
+                let sq = squares[start + i];
+                let adjust = squares[..start].iter().filter(|&&s| sq > s).count();
+                n += ix.binomial[i + 1][sq - adjust - if pawns_left { 8 } else { 0 }];
+            }
+            pawns_left = false;
+            index += n * d.group_index[next];
+            start += len;
+            next += 1;
+        }
+
+        let value = i32::from(d.decompress(&self.map, index)?);
+        if !self.dtz {
+            return Some(Lookup::Value(value - 2));
+        }
+        Some(Lookup::Value(self.dtz_value(d, value, wdl)?))
+    }
+
+    // Turns a stored DTZ value into plies.
+    fn dtz_value(&self, d: &Pairs, mut value: i32, wdl: Wdl) -> Option<i32> {
+        if d.flags & MAPPED != 0 {
+            let map = match wdl {
+                Wdl::Win | Wdl::Draw => d.map[0],
+                Wdl::Loss => d.map[1],
+                Wdl::CursedWin => d.map[2],
+                Wdl::BlessedLoss => d.map[3],
+            };
+            value = match d.flags & WIDE != 0 {
+                true => i32::from(u16_le(&self.map, map + 2 * value as usize)?),
+                false => i32::from(byte(&self.map, map + value as usize)?),
+            };
+        }
+        let in_moves = match wdl {
+            Wdl::Win => d.flags & WIN_PLIES == 0,
+            Wdl::Loss => d.flags & LOSS_PLIES == 0,
+            Wdl::CursedWin | Wdl::BlessedLoss => true,
+            Wdl::Draw => false,
+        };
+        if in_moves {
+            value *= 2;
+        }
+        Some(value + 1)
+    }
+}
+
+#[cfg(test)]
+pub(super) mod tests {
+    use super::*;
+
+    const KQVK_SIZE: usize = 31332;
+
+    // Which positions the Huffman-coded table of [`kqvk_file`]() has as wins.
+    fn pattern(side: usize, index: usize) -> bool {
+        index.is_multiple_of(3 + side)
+    }
+
+    // A KQvK WDL file laid out like the real ones. With `single` every
+    // position of a side to move has the same value; otherwise each block
+    // stores 256 positions as one-bit symbols for draw and win.
+    pub(in crate::tablebase::syzygy) fn kqvk_file(single: Option<[u8; 2]>) -> Vec<u8> {
+        let mut file = WDL_MAGIC.to_vec();
+        // White and black to move apart; the leading group first for both;
+        // king, queen and black king; padding.
+        file.extend([1, 0, 0x66, 0x55, 0xee, 0]);
+        let blocks = KQVK_SIZE.div_ceil(256);
+        for side in 0..2 {
+            match single {
+                Some(values) => file.extend([SINGLE_VALUE, values[side]]),
+                None => {
+                    // 64-byte blocks, a sparse entry every 256 positions.
+                    file.extend([0, 6, 8, 0]);
+                    file.extend((blocks as u32).to_le_bytes());
+                    // Symbols of one bit only: 0 is a draw, 1 a win.
+                    file.extend([1, 1, 0, 0, 2, 0]);
+                    file.extend([2, 0xf0, 0xff, 4, 0xf0, 0xff]);
+                }
+            }
+        }
+        if single.is_some() {
+            // Real files pad to where the data would start.
+            file.resize(file.len().next_multiple_of(64), 0);
+            return file;
+        }
+        for _ in 0..2 {
+            for block in 0..blocks {
+                file.extend((block as u32).to_le_bytes());
+                file.extend(128u16.to_le_bytes());
+            }
+        }
+        for _ in 0..2 {
+            for block in 0..blocks {
+                let len = (KQVK_SIZE - 256 * block).min(256) - 1;
+                file.extend((len as u16).to_le_bytes());
+            }
+        }
+        for side in 0..2 {
+            file.resize(file.len().next_multiple_of(64), 0);
+            let start = file.len();
+            file.resize(start + 64 * blocks, 0);
+            for index in (0..KQVK_SIZE).filter(|&i| pattern(side, i)) {
+                let bit = index % 256;
+                file[start + 64 * (index / 256) + bit / 8] |= 0x80 >> (bit % 8);
+            }
+        }
+        file
+    }
+
+    #[test]
+    fn decompresses_blocks() {
+        let dir = std::env::temp_dir().join(format!("chess-core-pairs-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("KQvK.rtbw");
+        std::fs::write(&path, kqvk_file(None)).unwrap();
+        let table = Table::open(&path, &[Type::King, Type::Queen], &[Type::King], false).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(table.pairs.len(), 2);
+        for (side, pairs) in table.pairs.iter().enumerate() {
+            assert_eq!(pairs[0].group_len[..2], [3, 0]);
+            assert_eq!(pairs[0].group_index[1], KQVK_SIZE as u64);
+            for index in 0..KQVK_SIZE {
+                let value = if pattern(side, index) { 4 } else { 2 };
+                assert_eq!(pairs[0].decompress(&table.map, index as u64), Some(value));
+            }
+        }
+    }
+
+    // The eight ways to mirror and rotate the board.
+    fn symmetries(sq: TileId) -> [TileId; 8] {
+        let t = (sq >> 3 | sq << 3) & 63;
+        [sq, sq ^ 7, sq ^ 56, sq ^ 63, t, t ^ 7, t ^ 56, t ^ 63]
+    }
+
+    #[test]
+    fn numbers_the_leading_group() {
+        let ix = indices();
+        assert_eq!(ix.lead_pawns_size[1], [6; 4]);
+        assert_eq!(ix.lead_pawns_size[2], [252, 180, 108, 36]);
+
+        for (a, b, c) in
+            (0..64).flat_map(|a| (0..64).flat_map(move |b| (0..64).map(move |c| (a, b, c))))
+        {
+            if a == b || b == c || a == c {
+                continue;
+            }
+            let first = lead_index(&mut [a, b, c], 3, true);
+            assert!(first < 31332);
+            for s in 1..8 {
+                let image = [symmetries(a)[s], symmetries(b)[s], symmetries(c)[s]];
+                assert_eq!(lead_index(&mut image.clone(), 3, true), first, "{image:?}");
+            }
+        }
+
+        let mut kings = Vec::new();
+        for (a, b) in (0..64).flat_map(|a| (0..64).map(move |b| (a, b))) {
+            if file_of(a)
+                .abs_diff(file_of(b))
+                .max(rank_of(a).abs_diff(rank_of(b)))
+                <= 1
+            {
+                continue;
+            }
+            let first = lead_index(&mut [a, b], 2, false);
+            for s in 1..8 {
+                let image = [symmetries(a)[s], symmetries(b)[s]];
+                assert_eq!(lead_index(&mut image.clone(), 2, false), first);
+            }
+            kings.push(first);
+        }
+        kings.sort_unstable();
+        kings.dedup();
+        assert_eq!(kings, (0..462).collect::<Vec<_>>());
+    }
+}
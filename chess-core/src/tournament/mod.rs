@@ -0,0 +1,598 @@
+//! chess_core::tournament
+//!
+//! Engine-vs-engine matches, for telling whether a change made the engine
+//! stronger. Two [`EngineConfig`]s, either the built-in [`Engine`] or an
+//! external UCI program, play every opening of a suite twice with colors
+//! reversed. Each game is kept in a [`GameMaster`] whose [`ChessClock`] runs
+//! on the [`Level`] time control, and several games can be played side by side.
+//!
+//! With [`Syzygy`] tables attached, games that reach an ending they cover
+//! are adjudicated from them.
+//!
+//! [`run_match`] returns every game as PGN together with the score of the
+//! first engine; see [`stats`] for the Elo estimate and the SPRT.
+
+pub mod openings;
+pub mod stats;
+
+use crate::clock::ChessClock;
+use crate::engine::xboard::Level;
+use crate::engine::{Engine, SearchLimits};
+use crate::game::History;
+use crate::msg::GameId;
+use crate::pgn::PgnGame;
+use crate::position::{ChessMove, Outcome, Position};
+use crate::tablebase::syzygy::{Syzygy, Wdl};
+use crate::types::Color;
+use crate::uci::UciEngine;
+use crate::GameMaster;
+use anyhow::{bail, Result};
+use openings::Opening;
+use stats::{Sprt, SprtStatus, Tally};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{mpsc, Arc};
+use std::thread;
+use std::time::Duration;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EngineKind {
+    Internal,
+    Uci {
+        command: PathBuf,
+        args: Vec<String>,
+        // `setoption` name/value pairs sent after the handshake.
+        options: Vec<(String, String)>,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EngineConfig {
+    pub name: String,
+    pub kind: EngineKind,
+}
+
+impl EngineConfig {
+    pub fn internal(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            kind: EngineKind::Internal,
+        }
+    }
+
+    pub fn uci(name: impl Into<String>, command: impl Into<PathBuf>) -> Self {
+        Self {
+            name: name.into(),
+            kind: EngineKind::Uci {
+                command: command.into(),
+                args: Vec::new(),
+                options: Vec::new(),
+            },
+        }
+    }
+
+    pub fn with_arg(mut self, arg: impl Into<String>) -> Self {
+        if let EngineKind::Uci { args, .. } = &mut self.kind {
+            args.push(arg.into());
+        }
+        self
+    }
+
+    pub fn with_option(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        if let EngineKind::Uci { options, .. } = &mut self.kind {
+            options.push((name.into(), value.into()));
+        }
+        self
+    }
+
+    fn start(&self) -> Result<Contestant> {
+        match &self.kind {
+            EngineKind::Internal => Ok(Contestant::Internal(Box::default())),
+            EngineKind::Uci {
+                command,
+                args,
+                options,
+            } => {
+                let args: Vec<&std::ffi::OsStr> = args.iter().map(|a| a.as_ref()).collect();
+                let mut engine = UciEngine::spawn(command.as_os_str(), &args)?;
+                for (name, value) in options {
+                    engine.set_option(name, value)?;
+                }
+                Ok(Contestant::Uci(engine))
+            }
+        }
+    }
+}
+
+// A running engine, ready to play one side of a game.
+enum Contestant {
+    Internal(Box<Engine>),
+    Uci(UciEngine),
+}
+
+impl Contestant {
+    fn new_game(&mut self) -> Result<()> {
+        match self {
+            Contestant::Internal(_) => Ok(()),
+            Contestant::Uci(engine) => engine.new_game(),
+        }
+    }
+
+    fn think(&mut self, hist: &History, limits: &SearchLimits) -> Result<Option<ChessMove>> {
+        match self {
+            Contestant::Internal(engine) => {
+                let (_, earlier) = hist.hashes().split_last().expect("there is a start");
+                let pos = hist.position();
+                Ok(engine.search(&pos, earlier, limits, |_| {}).best)
+            }
+            Contestant::Uci(engine) => Ok(engine.go(hist, limits)?.best),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct MatchConfig {
+    pub first: EngineConfig,
+    pub second: EngineConfig,
+    // Start positions; an empty suite plays from the standard position.
+    pub openings: Vec<Opening>,
+    // How many times the whole suite is played. Every round plays each opening
+    // twice, once with each engine as white.
+    pub rounds: usize,
+    pub concurrency: usize,
+    // The clock, if any. Without one `limits` alone decides how long engines think.
+    pub level: Option<Level>,
+    // Per-move limits such as a fixed depth; combined with the clock.
+    pub limits: SearchLimits,
+    // Games still going after this many plies are adjudicated as draws.
+    pub max_plies: Option<usize>,
+    // Games reaching an ending these tables cover are adjudicated from them.
+    pub syzygy: Option<Arc<Syzygy>>,
+    // Stops the match early once the test has an answer.
+    pub sprt: Option<Sprt>,
+    pub event: String,
+}
+
+impl Default for MatchConfig {
+    fn default() -> Self {
+        Self {
+            first: EngineConfig::internal("chess-core"),
+            second: EngineConfig::internal("chess-core"),
+            openings: Vec::new(),
+            rounds: 1,
+            concurrency: 1,
+            level: None,
+            limits: SearchLimits::default(),
+            max_plies: Some(400),
+            syzygy: None,
+            sprt: None,
+            event: "Engine match".to_string(),
+        }
+    }
+}
+
+impl MatchConfig {
+    pub fn total_games(&self) -> usize {
+        2 * self.rounds * self.openings.len().max(1)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Termination {
+    Rules(Outcome),
+    TimeForfeit { loser: Color },
+    // Crashed, hung, or answered with something that is not a legal move.
+    Forfeit { loser: Color, reason: String },
+    MoveLimit,
+    // Decided by the Syzygy tables.
+    Tablebase { winner: Option<Color> },
+}
+
+impl Termination {
+    pub fn winner(&self) -> Option<Color> {
+        match self {
+            Termination::Rules(outcome) => outcome.winner(),
+            Termination::TimeForfeit { loser } | Termination::Forfeit { loser, .. } => {
+                Some(loser.opposite())
+            }
+            Termination::MoveLimit => None,
+            Termination::Tablebase { winner } => *winner,
+        }
+    }
+
+    pub fn result(&self) -> &'static str {
+        match self.winner() {
+            Some(Color::White) => "1-0",
+            Some(Color::Black) => "0-1",
+            None => "1/2-1/2",
+        }
+    }
+
+    // The value of the PGN `Termination` tag.
+    pub fn tag(&self) -> &'static str {
+        match self {
+            Termination::Rules(_) => "normal",
+            Termination::TimeForfeit { .. } => "time forfeit",
+            Termination::Forfeit { .. } => "rules infraction",
+            Termination::MoveLimit | Termination::Tablebase { .. } => "adjudication",
+        }
+    }
+
+    pub fn comment(&self) -> String {
+        match self {
+            Termination::Rules(outcome) => outcome.reason().to_string(),
+            Termination::TimeForfeit { loser } => format!("{loser:?} loses on time"),
+            Termination::Forfeit { loser, reason } => format!("{loser:?} forfeits: {reason}"),
+            Termination::MoveLimit => "Draw by adjudication".to_string(),
+            Termination::Tablebase {
+                winner: Some(winner),
+            } => {
+                format!("{winner:?} wins by tablebase adjudication")
+            }
+            Termination::Tablebase { winner: None } => "Draw by tablebase adjudication".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GameRecord {
+    // Position in the match schedule, starting at 0.
+    pub index: usize,
+    // Index into [`MatchConfig::openings`]().
+    pub opening: usize,
+    pub first_is_white: bool,
+    pub termination: Termination,
+    pub pgn: PgnGame,
+}
+
+impl GameRecord {
+    // Points scored by [`MatchConfig::first`]().
+    pub fn first_score(&self) -> f64 {
+        match self.termination.winner() {
+            None => 0.5,
+            Some(Color::White) if self.first_is_white => 1.0,
+            Some(Color::Black) if !self.first_is_white => 1.0,
+            Some(_) => 0.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct MatchReport {
+    // Every finished game in schedule order.
+    pub games: Vec<GameRecord>,
+    // From [`MatchConfig::first`]()'s point of view.
+    pub tally: Tally,
+    pub sprt: Option<SprtStatus>,
+}
+
+// Plays the match described by `config`. `progress` sees every game as it
+// finishes along with the score so far.
+pub fn run_match(
+    config: &MatchConfig,
+    mut progress: impl FnMut(&GameRecord, &Tally),
+) -> Result<MatchReport> {
+    if config.rounds == 0 {
+        bail!("A match needs at least one round");
+    }
+    let total = config.total_games();
+    let next = AtomicUsize::new(0);
+    let stop = AtomicBool::new(false);
+    let (tx, rx) = mpsc::channel();
+
+    let mut games = Vec::with_capacity(total);
+    let mut tally = Tally::default();
+    let mut sprt = config.sprt.map(|_| SprtStatus::Continue);
+    let mut failure = None;
+    thread::scope(|scope| {
+        for _ in 0..config.concurrency.clamp(1, total) {
+            let tx = tx.clone();
+            let (next, stop) = (&next, &stop);
+            scope.spawn(move || {
+                let result = play_games(config, next, stop, |record| {
+                    let _ = tx.send(Ok(record));
+                });
+                if let Err(err) = result {
+                    stop.store(true, Ordering::Relaxed);
+                    let _ = tx.send(Err(err));
+                }
+            });
+        }
+        drop(tx);
+
+        for result in rx {
+            let record = match result {
+                Ok(record) => record,
+                Err(err) => {
+                    failure.get_or_insert(err);
+                    continue;
+                }
+            };
+            tally.record(record.first_score());
+            progress(&record, &tally);
+            games.push(record);
+            if let Some(test) = &config.sprt {
+                let status = test.status(&tally);
+                sprt = Some(status);
+                if status != SprtStatus::Continue {
+                    stop.store(true, Ordering::Relaxed);
+                }
+            }
+        }
+    });
+    if let Some(err) = failure {
+        return Err(err);
+    }
+    games.sort_by_key(|g| g.index);
+    Ok(MatchReport { games, tally, sprt })
+}
+
+// One worker: takes games off the schedule until it runs dry or the match is stopped.
+fn play_games(
+    config: &MatchConfig,
+    next: &AtomicUsize,
+    stop: &AtomicBool,
+    mut done: impl FnMut(GameRecord),
+) -> Result<()> {
+    let mut gm = GameMaster::new();
+    let mut first = None;
+    let mut second = None;
+    let total = config.total_games();
+    let suite = config.openings.len().max(1);
+    loop {
+        if stop.load(Ordering::Relaxed) {
+            return Ok(());
+        }
+        let index = next.fetch_add(1, Ordering::Relaxed);
+        if index >= total {
+            return Ok(());
+        }
+        let opening = (index / 2) % suite;
+        let first_is_white = index.is_multiple_of(2);
+        let start = config
+            .openings
+            .get(opening)
+            .map_or_else(Position::startpos, |o| o.position);
+
+        // Engines that crashed in an earlier game are started afresh.
+        if first.is_none() {
+            first = Some(config.first.start()?);
+        }
+        if second.is_none() {
+            second = Some(config.second.start()?);
+        }
+        let (white, black) = if first_is_white {
+            (&mut first, &mut second)
+        } else {
+            (&mut second, &mut first)
+        };
+        let mut players = [white, black];
+        let (game_id, termination) = play_game(config, &mut gm, start, &mut players, index)?;
+
+        let (white_name, black_name) = if first_is_white {
+            (&config.first.name, &config.second.name)
+        } else {
+            (&config.second.name, &config.first.name)
+        };
+        let hist = gm.remove_game(game_id)?.game.hist;
+        let mut pgn = PgnGame::from_history(&hist);
+        pgn.set_tag("Event", &config.event);
+        pgn.set_tag("Site", "chess-core");
+        pgn.set_tag("Round", (index + 1).to_string());
+        pgn.set_tag("White", white_name);
+        pgn.set_tag("Black", black_name);
+        if let Some(level) = config.level {
+            pgn.set_tag("TimeControl", time_control_tag(&level));
+        }
+        if let Some(name) = config.openings.get(opening).and_then(|o| o.name.as_ref()) {
+            pgn.set_tag("Opening", name);
+        }
+        pgn.set_tag("Termination", termination.tag());
+        pgn.result = termination.result().to_string();
+        pgn.comment = Some(termination.comment());
+        done(GameRecord {
+            index,
+            opening,
+            first_is_white,
+            termination,
+            pgn,
+        });
+    }
+}
+
+// How `pos` ends with best play, if `syzygy` covers it. Wins are only
+// called when the fifty-move rule cannot get in the way any more.
+fn tablebase_verdict(syzygy: &Syzygy, pos: &Position) -> Option<Termination> {
+    let probe = syzygy.probe(pos)?;
+    let clock = pos.halfmoves as i32;
+    let winner = match probe.wdl {
+        Wdl::Win => pos.turn,
+        Wdl::Loss => pos.turn.opposite(),
+        _ => return Some(Termination::Tablebase { winner: None }),
+    };
+    let in_time = probe.dtz.map_or(clock == 0, |dtz| dtz.abs() + clock <= 100);
+    in_time.then_some(Termination::Tablebase {
+        winner: Some(winner),
+    })
+}
+
+// Plays one game from `start`; `players` are white and black. Returns the
+// id the game had in `gm` and how it ended.
+fn play_game(
+    config: &MatchConfig,
+    gm: &mut GameMaster,
+    start: Position,
+    players: &mut [&mut Option<Contestant>; 2],
+    index: usize,
+) -> Result<(GameId, Termination)> {
+    let game_id = gm.create_game_from(start)?;
+    for player in players.iter_mut() {
+        let failed = player.as_mut().map(|p| p.new_game().is_err());
+        if failed == Some(true) {
+            **player = None;
+        }
+    }
+    if let Some(level) = config.level {
+        let mut clock = ChessClock::new(level.into());
+        clock.start(start.turn);
+        gm.set_clock(game_id, Some(clock))?;
+    }
+
+    let termination = loop {
+        let chess = gm.request_game_state(game_id)?;
+        let hist = &chess.game.hist;
+        if let Some(outcome) = hist.outcome() {
+            break Termination::Rules(outcome);
+        }
+        if config
+            .max_plies
+            .is_some_and(|max| hist.moves().count() >= max)
+        {
+            break Termination::MoveLimit;
+        }
+        let pos = hist.position();
+        if let Some(verdict) = config
+            .syzygy
+            .as_ref()
+            .and_then(|tb| tablebase_verdict(tb, &pos))
+        {
+            break verdict;
+        }
+        let side = pos.turn;
+        let mut limits = config.limits.clone();
+        if let Some(clock) = &chess.game.clock {
+            limits.wtime = Some(clock.remaining(Color::White));
+            limits.btime = Some(clock.remaining(Color::Black));
+            limits.winc = Some(clock.increment(Color::White));
+            limits.binc = Some(clock.increment(Color::Black));
+            limits.movestogo = clock.moves_to_go(side);
+        }
+
+        let Some(player) = players[side.index()].as_mut() else {
+            break Termination::Forfeit {
+                loser: side,
+                reason: "engine is not running".to_string(),
+            };
+        };
+        let reply = player.think(hist, &limits);
+        let mv = match reply {
+            Ok(Some(mv)) => mv,
+            Ok(None) => {
+                break Termination::Forfeit {
+                    loser: side,
+                    reason: "no move in a position with legal moves".to_string(),
+                }
+            }
+            Err(err) => {
+                // Whatever went wrong, the process is not to be trusted with another game.
+                *players[side.index()] = None;
+                break Termination::Forfeit {
+                    loser: side,
+                    reason: format!("{err:#}"),
+                };
+            }
+        };
+
+        // The game refuses moves made after the flag fell.
+        if let Err(err) = gm.make_move(game_id, mv) {
+            match gm.request_game_state(game_id)?.game.time_outcome() {
+                Some(Outcome::TimeForfeit { winner }) => {
+                    break Termination::TimeForfeit {
+                        loser: winner.opposite(),
+                    }
+                }
+                Some(outcome) => break Termination::Rules(outcome),
+                None => bail!("Game {} of the match: {err}", index + 1),
+            }
+        }
+    };
+    Ok((game_id, termination))
+}
+
+// The PGN `TimeControl` tag: `40/5400+30` or `300+2`, in seconds.
+pub fn time_control_tag(level: &Level) -> String {
+    let mut tag = String::new();
+    if level.moves_per_session > 0 {
+        tag.push_str(&format!("{}/", level.moves_per_session));
+    }
+    tag.push_str(&level.base.as_secs_f64().to_string());
+    if !level.increment.is_zero() {
+        tag.push_str(&format!("+{}", level.increment.as_secs_f64()));
+    }
+    tag
+}
+
+// Reads a time control written like [`time_control_tag`]() does.
+pub fn parse_time_control(tag: &str) -> Option<Level> {
+    let (moves_per_session, rest) = match tag.split_once('/') {
+        Some((moves, rest)) => (moves.parse().ok()?, rest),
+        None => (0, tag),
+    };
+    let (base, increment) = rest.split_once('+').unwrap_or((rest, "0"));
+    let seconds = |s: &str| {
+        let s: f64 = s.parse().ok()?;
+        (s.is_finite() && s >= 0.0).then(|| Duration::from_secs_f64(s))
+    };
+    Some(Level {
+        moves_per_session,
+        base: seconds(base)?,
+        increment: seconds(increment)?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn time_control_tags_round_trip() {
+        for tag in ["40/5400+30", "300+2", "60", "10+0.1", "40/7200"] {
+            let level = parse_time_control(tag).unwrap();
+            assert_eq!(time_control_tag(&level), tag);
+        }
+        let level = parse_time_control("40/5400+30").unwrap();
+        assert_eq!(level.moves_per_session, 40);
+        assert_eq!(level.base, Duration::from_secs(5400));
+        assert_eq!(level.increment, Duration::from_secs(30));
+        assert_eq!(parse_time_control("5 minutes"), None);
+        assert_eq!(parse_time_control("-3+1"), None);
+    }
+
+    #[test]
+    fn scores_from_the_first_engines_side() {
+        let record = |first_is_white, termination| GameRecord {
+            index: 0,
+            opening: 0,
+            first_is_white,
+            termination,
+            pgn: PgnGame::default(),
+        };
+        let white_mates = Termination::Rules(Outcome::Checkmate {
+            winner: Color::White,
+        });
+        assert_eq!(record(true, white_mates.clone()).first_score(), 1.0);
+        assert_eq!(record(false, white_mates).first_score(), 0.0);
+        let flagged = Termination::TimeForfeit {
+            loser: Color::White,
+        };
+        assert_eq!(flagged.result(), "0-1");
+        assert_eq!(record(false, flagged).first_score(), 1.0);
+        assert_eq!(record(true, Termination::MoveLimit).first_score(), 0.5);
+    }
+
+    #[test]
+    fn adjudicates_from_syzygy_tables() {
+        let tb = Syzygy::default();
+        let bare_kings = Position::from_fen("8/8/4k3/8/8/8/8/4K3 w - - 0 1").unwrap();
+        let draw = tablebase_verdict(&tb, &bare_kings).unwrap();
+        assert_eq!(draw, Termination::Tablebase { winner: None });
+        assert_eq!(draw.result(), "1/2-1/2");
+        assert_eq!(draw.comment(), "Draw by tablebase adjudication");
+        assert_eq!(tablebase_verdict(&tb, &Position::startpos()), None);
+        let won = Termination::Tablebase {
+            winner: Some(Color::Black),
+        };
+        assert_eq!(won.tag(), "adjudication");
+        assert_eq!(won.comment(), "Black wins by tablebase adjudication");
+    }
+}
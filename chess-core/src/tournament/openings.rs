@@ -0,0 +1,124 @@
+//! chess_core::tournament::openings
+//!
+//! Opening suites: one start position per line, either as a full FEN or as
+//! an EPD record (the first four FEN fields followed by `opcode operand;`
+//! operations). Blank lines and lines starting with `#` are skipped.
+
+use crate::position::Position;
+use anyhow::{Context, Result};
+use std::path::Path;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Opening {
+    // The EPD `id` operation, if there was one.
+    pub name: Option<String>,
+    pub position: Position,
+}
+
+pub fn load_suite(path: impl AsRef<Path>) -> Result<Vec<Opening>> {
+    let path = path.as_ref();
+    let text = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read opening suite {}", path.display()))?;
+    parse_suite(&text).with_context(|| format!("In opening suite {}", path.display()))
+}
+
+pub fn parse_suite(text: &str) -> Result<Vec<Opening>> {
+    text.lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty() && !line.trim_start().starts_with('#'))
+        .map(|(n, line)| parse_line(line).with_context(|| format!("Line {}", n + 1)))
+        .collect()
+}
+
+fn parse_line(line: &str) -> Result<Opening> {
+    let fields: Vec<&str> = line.split_whitespace().collect();
+    let is_counter = |i: usize| fields.get(i).is_some_and(|f| f.parse::<u32>().is_ok());
+    if fields.len() == 6 && is_counter(4) && is_counter(5) {
+        return Ok(Opening {
+            name: None,
+            position: Position::from_fen(line)?,
+        });
+    }
+
+    // EPD: four FEN fields, then operations; `hmvc` and `fmvn` stand in for the
+    // two move counters FEN would have.
+    let mut rest = line.trim_start();
+    for _ in 0..4 {
+        let end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+        rest = rest[end..].trim_start();
+    }
+    let head = &line[..line.len() - rest.len()];
+    let mut name = None;
+    let (mut hmvc, mut fmvn) = ("0", "1");
+    for op in operations(rest) {
+        match op {
+            ("id", operand) => name = Some(operand.to_string()),
+            ("hmvc", operand) => hmvc = operand,
+            ("fmvn", operand) => fmvn = operand,
+            _ => {}
+        }
+    }
+    let position = Position::from_fen(&format!("{} {hmvc} {fmvn}", head.trim_end()))?;
+    Ok(Opening { name, position })
+}
+
+// Splits `opcode operand; opcode "quoted operand";` into pairs.
+fn operations(mut text: &str) -> Vec<(&str, &str)> {
+    let mut ops = Vec::new();
+    loop {
+        text = text.trim_start();
+        if text.is_empty() {
+            return ops;
+        }
+        let end = text.find(char::is_whitespace).unwrap_or(text.len());
+        let opcode = text[..end].trim_end_matches(';');
+        text = text[end..].trim_start();
+        let operand = if let Some(quoted) = text.strip_prefix('"') {
+            let close = quoted.find('"').unwrap_or(quoted.len());
+            text = quoted[close..].trim_start_matches('"');
+            &quoted[..close]
+        } else {
+            let close = text.find(';').unwrap_or(text.len());
+            let operand = text[..close].trim();
+            text = &text[close..];
+            operand
+        };
+        text = text.trim_start().trim_start_matches(';');
+        ops.push((opcode, operand));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_fen_and_epd_lines() {
+        let suite = parse_suite(
+            "# two openings\n\
+             rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1\n\
+             \n\
+             rnbqkbnr/pp1ppppp/8/2p5/4P3/8/PPPP1PPP/RNBQKBNR w KQkq c6 id \"Sicilian Defence\"; hmvc 0; fmvn 2;\n\
+             r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq -\n",
+        )
+        .unwrap();
+        assert_eq!(suite.len(), 3);
+        assert_eq!(suite[0].name, None);
+        assert_eq!(suite[0].position.fullmoves, 1);
+        assert_eq!(suite[1].name.as_deref(), Some("Sicilian Defence"));
+        assert_eq!(
+            suite[1].position.to_fen(),
+            "rnbqkbnr/pp1ppppp/8/2p5/4P3/8/PPPP1PPP/RNBQKBNR w KQkq c6 0 2"
+        );
+        assert_eq!(
+            suite[2].position.to_fen(),
+            "r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 0 1"
+        );
+    }
+
+    #[test]
+    fn points_at_the_broken_line() {
+        let err = parse_suite("4k3/8/8/8/8/8/8/4K3 w - - 0 1\nnot a position\n").unwrap_err();
+        assert_eq!(err.to_string(), "Line 2");
+    }
+}
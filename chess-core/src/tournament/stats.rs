@@ -0,0 +1,254 @@
+//! chess_core::tournament::stats
+//!
+//! Turning match results into numbers: the Elo difference with a 95%
+//! confidence interval, and the sequential probability ratio test (SPRT)
+//! that decides whether one engine build is stronger than the other.
+//!
+//! Both use the usual normal approximation over per-game scores (1, ½ or 0).
+
+use std::fmt;
+
+// Two-sided 95% quantile of the standard normal distribution.
+const Z_95: f64 = 1.959_963_984_540_054;
+
+// The score a player `elo` points stronger is expected to make per game.
+pub fn expected_score(elo: f64) -> f64 {
+    1.0 / (1.0 + 10f64.powf(-elo / 400.0))
+}
+
+// The Elo difference that explains a per-game `score` strictly between 0 and 1.
+pub fn elo_from_score(score: f64) -> f64 {
+    -400.0 * (1.0 / score - 1.0).log10()
+}
+
+// Wins, draws and losses from one player's point of view.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Tally {
+    pub wins: u32,
+    pub draws: u32,
+    pub losses: u32,
+}
+
+impl Tally {
+    pub fn new(wins: u32, draws: u32, losses: u32) -> Self {
+        Self {
+            wins,
+            draws,
+            losses,
+        }
+    }
+
+    // Counts one game worth `score` points: 1 for a win, ½ for a draw, 0 for a loss.
+    pub fn record(&mut self, score: f64) {
+        if score > 0.5 {
+            self.wins += 1;
+        } else if score < 0.5 {
+            self.losses += 1;
+        } else {
+            self.draws += 1;
+        }
+    }
+
+    pub fn games(&self) -> u32 {
+        self.wins + self.draws + self.losses
+    }
+
+    pub fn points(&self) -> f64 {
+        self.wins as f64 + self.draws as f64 / 2.0
+    }
+
+    // Average points per game.
+    pub fn score(&self) -> Option<f64> {
+        (self.games() > 0).then(|| self.points() / self.games() as f64)
+    }
+
+    // The same games seen from the opponent's side.
+    pub fn reversed(&self) -> Self {
+        Self::new(self.losses, self.draws, self.wins)
+    }
+
+    // Variance of a single game's score.
+    fn variance(&self) -> Option<f64> {
+        let score = self.score()?;
+        let n = self.games() as f64;
+        let var = (self.wins as f64 * (1.0 - score).powi(2)
+            + self.draws as f64 * (0.5 - score).powi(2)
+            + self.losses as f64 * score.powi(2))
+            / n;
+        Some(var)
+    }
+
+    // The Elo difference and the half-width of its 95% confidence interval.
+    // Undefined for a perfect or a zero score.
+    pub fn elo(&self) -> Option<Elo> {
+        let score = self.score()?;
+        if score <= 0.0 || score >= 1.0 {
+            return None;
+        }
+        let error = (self.variance()? / self.games() as f64).sqrt();
+        let low = (score - Z_95 * error).max(f64::MIN_POSITIVE);
+        let high = score + Z_95 * error;
+        let margin = if high >= 1.0 {
+            f64::INFINITY
+        } else {
+            (elo_from_score(high) - elo_from_score(low)) / 2.0
+        };
+        Some(Elo {
+            diff: elo_from_score(score),
+            margin,
+        })
+    }
+}
+
+impl fmt::Display for Tally {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "+{} ={} -{}", self.wins, self.draws, self.losses)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Elo {
+    pub diff: f64,
+    pub margin: f64,
+}
+
+impl fmt::Display for Elo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:+.1} +/- {:.1}", self.diff, self.margin)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SprtStatus {
+    // Not enough evidence yet.
+    Continue,
+    // The difference is at most `elo0`: the test failed.
+    AcceptH0,
+    // The difference is at least `elo1`: the test passed.
+    AcceptH1,
+}
+
+// Tests H0 "the first player is `elo0` stronger" against H1 "... `elo1` stronger"
+// with false positive rate `alpha` and false negative rate `beta`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Sprt {
+    pub elo0: f64,
+    pub elo1: f64,
+    pub alpha: f64,
+    pub beta: f64,
+}
+
+impl Default for Sprt {
+    fn default() -> Self {
+        Self::new(0.0, 5.0)
+    }
+}
+
+impl Sprt {
+    pub fn new(elo0: f64, elo1: f64) -> Self {
+        Self {
+            elo0,
+            elo1,
+            alpha: 0.05,
+            beta: 0.05,
+        }
+    }
+
+    // The log-likelihood ratio below which H0 and above which H1 is accepted.
+    pub fn bounds(&self) -> (f64, f64) {
+        let lower = (self.beta / (1.0 - self.alpha)).ln();
+        let upper = ((1.0 - self.beta) / self.alpha).ln();
+        (lower, upper)
+    }
+
+    // Log-likelihood ratio of H1 over H0 given `tally`.
+    pub fn llr(&self, tally: &Tally) -> f64 {
+        let (Some(score), Some(var)) = (tally.score(), tally.variance()) else {
+            return 0.0;
+        };
+        // Nothing but wins (or losses, or draws) has no spread to speak of. Half a
+        // made-up win and half a made-up loss keep the variance, and the LLR, finite.
+        let var = if var > 0.0 {
+            var
+        } else {
+            let (w, d, l) = (tally.wins, tally.draws, tally.losses);
+            Tally::new(2 * w + 1, 2 * d, 2 * l + 1)
+                .variance()
+                .unwrap_or(0.25)
+        };
+        let (s0, s1) = (expected_score(self.elo0), expected_score(self.elo1));
+        tally.games() as f64 * (s1 - s0) * (2.0 * score - s0 - s1) / (2.0 * var)
+    }
+
+    pub fn status(&self, tally: &Tally) -> SprtStatus {
+        let llr = self.llr(tally);
+        let (lower, upper) = self.bounds();
+        if llr >= upper {
+            SprtStatus::AcceptH1
+        } else if llr <= lower {
+            SprtStatus::AcceptH0
+        } else {
+            SprtStatus::Continue
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn close(a: f64, b: f64, eps: f64) -> bool {
+        (a - b).abs() < eps
+    }
+
+    #[test]
+    fn elo_and_score_are_inverse() {
+        assert!(close(expected_score(0.0), 0.5, 1e-12));
+        assert!(close(expected_score(400.0), 10.0 / 11.0, 1e-12));
+        for elo in [-300.0, -35.5, 0.0, 12.0, 250.0] {
+            assert!(close(elo_from_score(expected_score(elo)), elo, 1e-9));
+        }
+    }
+
+    #[test]
+    fn estimates_elo_with_error_bars() {
+        // 60% from 100 games with 20 draws: a per-game variance of 0.19 puts the
+        // score between 51.5% and 68.5%, i.e. between +10.1 and +135.3 Elo.
+        let tally = Tally::new(50, 20, 30);
+        let elo = tally.elo().unwrap();
+        assert!(close(elo.diff, 70.44, 0.01), "{elo}");
+        assert!(close(elo.margin, 62.6, 0.1), "{elo}");
+        assert!(close(tally.reversed().elo().unwrap().diff, -elo.diff, 1e-9));
+        assert_eq!(Tally::new(3, 0, 0).elo(), None);
+        assert_eq!(Tally::default().elo(), None);
+        assert_eq!(tally.to_string(), "+50 =20 -30");
+    }
+
+    #[test]
+    fn sprt_decides() {
+        let sprt = Sprt::new(0.0, 10.0);
+        let (lower, upper) = sprt.bounds();
+        assert!(close(lower, -2.944, 1e-3));
+        assert!(close(upper, 2.944, 1e-3));
+
+        assert_eq!(sprt.status(&Tally::new(10, 10, 10)), SprtStatus::Continue);
+        assert_eq!(
+            sprt.status(&Tally::new(300, 400, 200)),
+            SprtStatus::AcceptH1
+        );
+        assert_eq!(
+            sprt.status(&Tally::new(200, 400, 300)),
+            SprtStatus::AcceptH0
+        );
+        // An even match over many games is evidence for H0.
+        assert_eq!(
+            sprt.status(&Tally::new(1500, 2000, 1500)),
+            SprtStatus::AcceptH0
+        );
+        assert!(sprt.llr(&Tally::new(120, 100, 100)) > 0.0);
+        // Runs of identical results move the LLR, but not by leaps.
+        assert!(sprt.llr(&Tally::new(0, 2, 0)).abs() < 0.01);
+        assert_eq!(sprt.status(&Tally::new(5, 0, 0)), SprtStatus::Continue);
+        assert_eq!(sprt.status(&Tally::new(30, 0, 0)), SprtStatus::AcceptH1);
+    }
+}
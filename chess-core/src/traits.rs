@@ -1,3 +1,56 @@
+//! chess_core::traits
+//!
+//! Factories for the pieces of a game. [`ChessFactory`] builds single pieces,
+//! [`StandardChess`] the sixteen a side of a standard game and [`FairyChess`]
+//! the pieces of a fairy variant's start; chess-derive derives all three.
+//! `#[derive(StandardChess)]` gives the classical setup, or the one a
+//! `#[chess(...)]` attribute asks for: the placement of a FEN record, a
+//! `layout` listing each piece with its square, or one of the 960 Fischer
+//! random setups picked by a seed.
+//!
+//! ```
+//! use chess_core::traits::{ChessFactory, StandardChess};
+//! use chess_core::types::Type;
+//! use chess_derive::{ChessFactory, StandardChess};
+//!
+//! #[derive(StandardChess, ChessFactory)]
+//! #[chess(variant = "chess960", seed = 42)]
+//! struct Fischer;
+//!
+//! let white = Fischer::gen_std_white();
+//! assert_eq!(white.iter().filter(|p| p.ty == Type::Pawn).count(), 8);
+//! ```
+//!
+//! The setup is checked while the derive expands, so a position that does
+//! not give each side sixteen pieces and one king, that names a square
+//! twice, or that puts a pawn on a back rank does not compile:
+//!
+//! ```compile_fail
+//! # use chess_core::traits::{ChessFactory, StandardChess};
+//! # use chess_derive::{ChessFactory, StandardChess};
+//! // White is a pawn short.
+//! #[derive(StandardChess, ChessFactory)]
+//! #[chess(fen = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPP1/RNBQKBNR")]
+//! struct Board;
+//! ```
+//!
+//! ```compile_fail
+//! # use chess_core::traits::{ChessFactory, StandardChess};
+//! # use chess_derive::{ChessFactory, StandardChess};
+//! #[derive(StandardChess, ChessFactory)]
+//! #[chess(fen = "rnbqkbnP/ppppppp1/8/8/8/8/PPPPPPP1/RNBQKBNR")]
+//! struct Board;
+//! ```
+//!
+//! ```compile_fail
+//! # use chess_core::traits::{ChessFactory, StandardChess};
+//! # use chess_derive::{ChessFactory, StandardChess};
+//! #[derive(StandardChess, ChessFactory)]
+//! #[chess(layout = "Ke1 Qe1")]
+//! struct Board;
+//! ```
+
+use crate::fairy::{FairyPosition, PieceDef};
 use crate::msg::PieceId;
 use crate::msg::TileId;
 use crate::types::*;
@@ -42,8 +95,16 @@ pub trait ChessFactory {
     fn queen_black(loc: TileId, id: PieceId) -> Piece {
         <Self as ChessFactory>::init(Type::Queen, Color::Black, loc, id)
     }
+    fn fairy_white(def: &PieceDef, loc: TileId, id: PieceId) -> Piece {
+        <Self as ChessFactory>::init(def.ty, Color::White, loc, id)
+    }
+    fn fairy_black(def: &PieceDef, loc: TileId, id: PieceId) -> Piece {
+        <Self as ChessFactory>::init(def.ty, Color::Black, loc, id)
+    }
 }
 
+// The sixteen pieces each side starts with, numbered in board order from
+// a1: White's from 1 and Black's from -16.
 pub trait StandardChess: ChessFactory {
     fn gen_std_white() -> [Piece; 16] {
         use crate::constants::*;
@@ -88,3 +149,35 @@ pub trait StandardChess: ChessFactory {
         ]
     }
 }
+
+// The pieces of a [`FairyPosition`](), numbered the way
+// [`crate::ChessGame::from_position`]() numbers them: white's from 1 upwards
+// and black's from -1 downwards, in board order.
+pub trait FairyChess: ChessFactory {
+    fn gen_fairy_white(start: &FairyPosition) -> Vec<Piece> {
+        let mut id = 0;
+        start
+            .men(Color::White)
+            .map(|(loc, man)| {
+                id += 1;
+                match start.pieces.get(man.ty) {
+                    Some(def) => <Self as ChessFactory>::fairy_white(def, loc, id),
+                    None => <Self as ChessFactory>::init(man.ty, Color::White, loc, id),
+                }
+            })
+            .collect()
+    }
+    fn gen_fairy_black(start: &FairyPosition) -> Vec<Piece> {
+        let mut id = 0;
+        start
+            .men(Color::Black)
+            .map(|(loc, man)| {
+                id -= 1;
+                match start.pieces.get(man.ty) {
+                    Some(def) => <Self as ChessFactory>::fairy_black(def, loc, id),
+                    None => <Self as ChessFactory>::init(man.ty, Color::Black, loc, id),
+                }
+            })
+            .collect()
+    }
+}
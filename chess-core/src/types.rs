@@ -1,21 +1,21 @@
 #![allow(dead_code)]
-use crate::game::math::{index_to_xy, xy_to_index};
-use crate::msg::{PieceId, TileId};
-use crate::{constants::TILECOUNT, game::math::XyPair};
+use crate::error::ChessError;
+use crate::game::math::XyPair;
+use crate::msg::{PieceId, PlayerId, TileId};
+use crate::square::Square;
 // use const_typed_builder::Builder;
 // use serde::{Deserialize, Serialize};
-use anyhow::{bail, Result};
-use std::borrow::Borrow;
 use std::fmt::Debug;
 use std::{
     cell::RefCell,
     rc::{Rc, Weak},
 };
 
-// [`RawBoard`] is a flat array of 64 [`Tile`s]().
+// [`RawBoard`] is the flat list of [`Tile`s](), rank by rank from a1; there are
+// as many as its [`crate::geometry::Geometry`]() has squares, 64 on a standard board.
 // There is a useful collection of chess board tile codes
-// for referring to given slots to this via [`crate::helper`]().
-pub type RawBoard = [Tile; TILECOUNT];
+// for referring to given slots to this via [`crate::constants`]().
+pub type RawBoard = Vec<Tile>;
 
 // [`Piece`]() is a shared data structure. [`PlayerData`]()
 // instances are considered their owning source. That is, they have the
@@ -25,7 +25,7 @@ pub type RawBoard = [Tile; TILECOUNT];
 // This detail is important because [`PlayerData`]() shares write-access (via
 // interior mutability) with [`crate::core::types::Tile`s](), provided one calls
 // [`Rc::upgrade`]() on the [`std::rc::Weak`]() refernce counted pointer to the piece.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct Piece {
     pub id: PieceId,
     pub color: Color,
@@ -34,7 +34,7 @@ pub struct Piece {
 }
 
 // [`update_loc`]() should be called whenever its position on the board changes.
-// If a [`loc`] on a [`Piece`] reaches 64 or higher, its visibility should be updated
+// If a [`loc`] on a [`Piece`] lies past the last tile, its visibility should be updated
 // so that it cannot be seen on the board.
 impl Piece {
     pub fn update_loc(&mut self, new_loc: TileId) {
@@ -43,6 +43,11 @@ impl Piece {
     pub fn set_id(&mut self, new_id: PieceId) {
         self.id = new_id;
     }
+    // Where the piece stands on a standard board; None in a reserve or
+    // beyond the 64 squares of a larger board.
+    pub fn square(&self) -> Option<Square> {
+        Square::from_index(self.loc)
+    }
 }
 
 pub struct Move<'a> {
@@ -71,23 +76,23 @@ impl<'a> Debug for Move<'a> {
 }
 
 impl<'a> Move<'a> {
+    // Where the move ends, in White's coordinates whichever side moves. It may
+    // lie off the board.
     pub fn dest(&self) -> XyPair {
-        if ((*self.on.clone()).borrow()).color == Color::Black {
-            todo!("Perform the rotation transformation to interpolate values")
-        }
-        match self.dir {
-            Direction::Nil => index_to_xy(((*self.on.clone()).borrow()).loc),
-            Direction::Forward(delta_y) => {
-                let XyPair { x, y } = index_to_xy(((*self.on.clone()).borrow()).loc);
-                let new_y = y as usize + delta_y;
-                XyPair {
-                    x,
-                    y: new_y as isize,
-                }
-            }
-            _ => todo!("Handle remaining directions of movement"),
+        let color = (*self.on).borrow().color;
+        let (dx, dy) = self.dir.delta(color);
+        let XyPair { x, y } = self.origin().xy();
+        XyPair {
+            x: x + dx,
+            y: y + dy,
         }
     }
+    fn origin(&self) -> Square {
+        (*self.on)
+            .borrow()
+            .square()
+            .expect("a moving piece stands on the board")
+    }
     pub fn new_nil(on: &Rc<RefCell<Piece>>) -> Self {
         let on = Rc::clone(on);
         Self {
@@ -97,6 +102,14 @@ impl<'a> Move<'a> {
             on_complete: None,
         }
     }
+    // What the move shows of the board, without the piece it holds on to.
+    pub fn sight(&self) -> Sight {
+        Sight {
+            from: self.origin().xy(),
+            to: self.dest(),
+            capture: self.cap,
+        }
+    }
     pub fn forward(on: &Rc<RefCell<Piece>>, len: usize) -> Self {
         let on = Rc::clone(on);
         Self {
@@ -130,10 +143,44 @@ pub enum Direction {
     Nil,
 }
 
+// Directions are as the moving side sees the board: forward is towards the
+// opponent and right is the hand that side's player would point with, so for
+// Black both turn round.
+impl Direction {
+    // The files and ranks to step, as White counts them, for a piece of `color`.
+    pub fn delta(&self, color: Color) -> (isize, isize) {
+        use Direction::*;
+        let (right, forward) = match *self {
+            Forward(n) => (0, n as isize),
+            Backward(n) => (0, -(n as isize)),
+            Right(n) => (n as isize, 0),
+            Left(n) => (-(n as isize), 0),
+            ForwardRight(n) => (n as isize, n as isize),
+            BackwardRight(n) => (n as isize, -(n as isize)),
+            ForwardLeft(n) => (-(n as isize), n as isize),
+            BackwardLeft(n) => (-(n as isize), -(n as isize)),
+            ForwardTwoRightOne => (1, 2),
+            ForwardOneRightTwo => (2, 1),
+            BackwardTwoRightOne => (1, -2),
+            BackwardOneRightTwo => (2, -1),
+            ForwardTwoLeftOne => (-1, 2),
+            ForwardOneLeftTwo => (-2, 1),
+            BackwardTwoLeftOne => (-1, -2),
+            BacwardOneLeftTwo => (-2, -1),
+            Nil => (0, 0),
+        };
+        (right * color.forward(), forward * color.forward())
+    }
+}
+
 // Each of the playable kinds of chess [`Piece`]() has a
 // particular [`Type`] that distinguishes its possible movement
 // options, special properties, movement constraints, and subjective
 // power-level in comparison to others.
+//
+// Pieces beyond the orthodox six are [`Type::Fairy`]() with their
+// uppercase FEN letter; how they move is up to the
+// [`crate::fairy::PieceSet`]() of the game they are in.
 #[derive(Clone, Debug, Eq, PartialEq, Hash, Copy)]
 pub enum Type {
     Pawn,
@@ -142,6 +189,18 @@ pub enum Type {
     Knight,
     Queen,
     King,
+    Fairy(char),
+}
+
+impl Type {
+    pub const ORTHODOX: [Type; 6] = [
+        Type::Pawn,
+        Type::Knight,
+        Type::Bishop,
+        Type::Rook,
+        Type::Queen,
+        Type::King,
+    ];
 }
 
 // [`Color`]() is the enum which associates owned player's pieces
@@ -154,6 +213,37 @@ pub enum Color {
     Black,
 }
 
+impl Color {
+    pub fn opposite(self) -> Color {
+        match self {
+            Color::White => Color::Black,
+            Color::Black => Color::White,
+        }
+    }
+    // One rank in the direction this color's pawns advance: up the board,
+    // towards the eighth rank, for White.
+    pub const fn forward(self) -> isize {
+        match self {
+            Color::White => 1,
+            Color::Black => -1,
+        }
+    }
+    // Player 1 plays white and player 2 black.
+    pub fn of_player(player: PlayerId) -> Color {
+        match player {
+            false => Color::White,
+            true => Color::Black,
+        }
+    }
+    #[inline]
+    pub(crate) fn index(self) -> usize {
+        match self {
+            Color::White => 0,
+            Color::Black => 1,
+        }
+    }
+}
+
 // The shading of the tile beneath any given chess piece is this
 // this module's [`Background`]().
 #[derive(Default, Debug, Clone, Eq, PartialEq, Hash, Copy)]
@@ -232,13 +322,9 @@ impl Tile {
         &mut self,
         new_piece: Option<Rc<RefCell<Piece>>>,
         replace: bool,
-    ) -> Result<()> {
+    ) -> Result<(), ChessError> {
         if self.pz.as_ref().is_some() && replace {
-            bail!(
-                "Unexpected deallocation attempt on {:?} from {:?}!",
-                &self.pz,
-                new_piece.unwrap()
-            );
+            return Err(ChessError::TileOccupied(self.index));
         }
         let pz: Option<Weak<RefCell<Piece>>> = if let Some(owned) = new_piece {
             Some(Rc::downgrade(&owned))
@@ -250,30 +336,37 @@ impl Tile {
     }
 }
 
-#[derive(Default)]
-pub struct VisionPiece<'a> {
+// [`Sight`]() is one square a piece sees: where it stands, where it looks,
+// which may lie off the board, and whether it would capture there.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Sight {
+    pub from: XyPair,
+    pub to: XyPair,
+    pub capture: bool,
+}
+
+// [`VisionPiece`]() holds every [`Sight`]() a piece has, however many that
+// is; a queen alone may have 27. For who attacks which square across the
+// whole board, see [`crate::position::attacks::AttackMap`]().
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct VisionPiece {
     pub piece_id: PieceId,
-    pub moves: [Option<Move<'a>>; 25],
+    pub moves: Vec<Sight>,
 }
 
-impl<'a> VisionPiece<'a> {
+impl VisionPiece {
     #[inline]
     pub fn new_empty(piece_id: PieceId) -> Self {
-        let moves: [Option<Move<'a>>; 25] = Default::default();
-        Self { piece_id, moves }
+        Self {
+            piece_id,
+            moves: Vec::new(),
+        }
     }
     #[inline]
-    pub fn new_with_moves<const N: usize>(piece_id: PieceId, moves: [Move<'a>; N]) -> Self {
-        let mut buffer: [Option<Move>; 25] = Default::default();
-        for (i, m) in moves.into_iter().enumerate() {
-            if i >= 25 {
-                break;
-            }
-            buffer[i] = Some(m);
-        }
+    pub fn new_with_moves(piece_id: PieceId, moves: impl IntoIterator<Item = Sight>) -> Self {
         Self {
             piece_id,
-            moves: buffer,
+            moves: moves.into_iter().collect(),
         }
     }
 }
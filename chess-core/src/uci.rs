@@ -0,0 +1,535 @@
+//! chess_core::uci
+//!
+//! Runs an external engine that speaks the Universal Chess Interface and
+//! lets it play or analyse games kept in a [`crate::GameMaster`]. The
+//! engine lives in a child process; a reader thread forwards its stdout
+//! line by line so that every wait can be bounded by a timeout.
+
+use crate::engine::SearchLimits;
+use crate::game::History;
+use crate::position::{ChessMove, Position};
+use anyhow::{anyhow, bail, Context, Result};
+use std::ffi::OsStr;
+use std::io::{BufRead, BufReader, Write};
+use std::process::{Child, ChildStdin, Command, Stdio};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::thread;
+use std::time::{Duration, Instant};
+
+// How long the engine may take to answer `uci` and `isready`.
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Score {
+    Centipawns(i32),
+    // Moves until mate; negative when the engine is getting mated.
+    Mate(i32),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Bound {
+    Exact,
+    Lower,
+    Upper,
+}
+
+// A parsed `info` line. Only the fields the engine sent are set.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Info {
+    pub depth: Option<u32>,
+    pub seldepth: Option<u32>,
+    pub multipv: Option<u32>,
+    pub score: Option<Score>,
+    pub bound: Option<Bound>,
+    pub nodes: Option<u64>,
+    pub nps: Option<u64>,
+    pub time: Option<Duration>,
+    pub pv: Vec<ChessMove>,
+    pub string: Option<String>,
+}
+
+impl Info {
+    // Parses the part after `info`. PV moves are resolved against `pos`;
+    // the PV stops at the first move that is not legal.
+    pub fn parse(line: &str, pos: &Position) -> Self {
+        let mut info = Info::default();
+        let mut tokens = line.split_whitespace().peekable();
+        while let Some(token) = tokens.next() {
+            match token {
+                "depth" => info.depth = tokens.next().and_then(|t| t.parse().ok()),
+                "seldepth" => info.seldepth = tokens.next().and_then(|t| t.parse().ok()),
+                "multipv" => info.multipv = tokens.next().and_then(|t| t.parse().ok()),
+                "nodes" => info.nodes = tokens.next().and_then(|t| t.parse().ok()),
+                "nps" => info.nps = tokens.next().and_then(|t| t.parse().ok()),
+                "time" => {
+                    info.time = tokens
+                        .next()
+                        .and_then(|t| t.parse().ok())
+                        .map(Duration::from_millis)
+                }
+                "score" => {
+                    let kind = tokens.next();
+                    let value = tokens.next().and_then(|t| t.parse().ok());
+                    info.score = match (kind, value) {
+                        (Some("cp"), Some(v)) => Some(Score::Centipawns(v)),
+                        (Some("mate"), Some(v)) => Some(Score::Mate(v)),
+                        _ => None,
+                    };
+                    info.bound = Some(match tokens.peek() {
+                        Some(&"lowerbound") => Bound::Lower,
+                        Some(&"upperbound") => Bound::Upper,
+                        _ => Bound::Exact,
+                    });
+                    if info.bound != Some(Bound::Exact) {
+                        tokens.next();
+                    }
+                }
+                "pv" => {
+                    let mut cur = *pos;
+                    for text in tokens.by_ref() {
+                        match cur.parse_uci(text) {
+                            Ok(mv) => {
+                                cur.make_move(&mv);
+                                info.pv.push(mv);
+                            }
+                            Err(_) => break,
+                        }
+                    }
+                }
+                "string" => {
+                    info.string = Some(tokens.by_ref().collect::<Vec<_>>().join(" "));
+                }
+                _ => {}
+            }
+        }
+        info
+    }
+}
+
+// The answer to a `go`: the chosen move and everything reported on the way.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BestMove {
+    pub best: Option<ChessMove>,
+    pub ponder: Option<ChessMove>,
+    pub infos: Vec<Info>,
+}
+
+impl BestMove {
+    // The deepest report for each `multipv` line, best line first. Lines
+    // the engine never reported are left out, and so is a `multipv 0`.
+    pub fn lines(&self) -> Vec<&Info> {
+        let mut lines: Vec<Option<&Info>> = Vec::new();
+        for info in self.infos.iter().filter(|i| !i.pv.is_empty()) {
+            let slot = match info.multipv {
+                Some(0) => continue,
+                Some(n) => n as usize,
+                None => 1,
+            };
+            if lines.len() < slot {
+                lines.resize(slot, None);
+            }
+            lines[slot - 1] = Some(info);
+        }
+        lines.into_iter().flatten().collect()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UciOption {
+    pub name: String,
+    // Everything after the name, e.g. `type spin default 16 min 1 max 1024`.
+    pub spec: String,
+}
+
+pub struct UciEngine {
+    child: Child,
+    stdin: ChildStdin,
+    lines: Receiver<String>,
+    pub name: Option<String>,
+    pub author: Option<String>,
+    pub options: Vec<UciOption>,
+    timeout: Duration,
+    // Set through the `UCI_Chess960` option; castling is then sent as king-takes-rook.
+    chess960: bool,
+}
+
+impl UciEngine {
+    // Starts `program` and runs the `uci`/`isready` handshake.
+    pub fn spawn<S: AsRef<OsStr>>(program: S, args: &[S]) -> Result<Self> {
+        let program = program.as_ref();
+        let mut child = Command::new(program)
+            .args(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .with_context(|| format!("Failed to start UCI engine {program:?}"))?;
+        let stdin = child.stdin.take().expect("stdin is piped");
+        let stdout = child.stdout.take().expect("stdout is piped");
+        let (tx, lines) = mpsc::channel();
+        thread::spawn(move || {
+            for line in BufReader::new(stdout).lines() {
+                let Ok(line) = line else { break };
+                if tx.send(line).is_err() {
+                    break;
+                }
+            }
+        });
+        let mut engine = Self {
+            child,
+            stdin,
+            lines,
+            name: None,
+            author: None,
+            options: Vec::new(),
+            timeout: DEFAULT_TIMEOUT,
+            chess960: false,
+        };
+        engine.handshake()?;
+        Ok(engine)
+    }
+
+    pub fn set_timeout(&mut self, timeout: Duration) {
+        self.timeout = timeout;
+    }
+
+    fn handshake(&mut self) -> Result<()> {
+        self.send("uci")?;
+        let deadline = Instant::now() + self.timeout;
+        loop {
+            let line = self.recv(deadline)?;
+            let (cmd, rest) = line.trim().split_once(' ').unwrap_or((line.trim(), ""));
+            match cmd {
+                "uciok" => break,
+                "id" => match rest.split_once(' ') {
+                    Some(("name", name)) => self.name = Some(name.to_string()),
+                    Some(("author", author)) => self.author = Some(author.to_string()),
+                    _ => {}
+                },
+                "option" => {
+                    if let Some(spec) = rest.strip_prefix("name ") {
+                        let (name, spec) = match spec.find(" type ") {
+                            Some(i) => (&spec[..i], spec[i + 1..].to_string()),
+                            None => (spec, String::new()),
+                        };
+                        self.options.push(UciOption {
+                            name: name.to_string(),
+                            spec,
+                        });
+                    }
+                }
+                _ => {}
+            }
+        }
+        self.sync()
+    }
+
+    // Sends `isready` and waits for `readyok`.
+    pub fn sync(&mut self) -> Result<()> {
+        self.send("isready")?;
+        let deadline = Instant::now() + self.timeout;
+        while self.recv(deadline)?.trim() != "readyok" {}
+        Ok(())
+    }
+
+    pub fn set_option(&mut self, name: &str, value: &str) -> Result<()> {
+        if !self
+            .options
+            .iter()
+            .any(|o| o.name.eq_ignore_ascii_case(name))
+        {
+            bail!("UCI engine has no option named {name:?}");
+        }
+        self.send(&format!("setoption name {name} value {value}"))?;
+        if name.eq_ignore_ascii_case("UCI_Chess960") {
+            self.chess960 = value == "true";
+        }
+        self.sync()
+    }
+
+    pub fn new_game(&mut self) -> Result<()> {
+        self.send("ucinewgame")?;
+        self.sync()
+    }
+
+    pub fn set_position(&mut self, history: &History) -> Result<()> {
+        self.send(&position_command(history, self.chess960))
+    }
+
+    // Searches the current position of `history` and waits for `bestmove`.
+    // The engine gets `limits.movetime`, or else its whole clock, plus the
+    // configured timeout as grace; if it overstays it is told to `stop`, and
+    // killed if even that fails.
+    pub fn go(&mut self, history: &History, limits: &SearchLimits) -> Result<BestMove> {
+        self.set_position(history)?;
+        let mut go = String::from("go");
+        if let Some(depth) = limits.depth {
+            go.push_str(&format!(" depth {depth}"));
+        }
+        if let Some(nodes) = limits.nodes {
+            go.push_str(&format!(" nodes {nodes}"));
+        }
+        if let Some(movetime) = limits.movetime {
+            go.push_str(&format!(" movetime {}", movetime.as_millis()));
+        }
+        let clock = [
+            ("wtime", limits.wtime),
+            ("btime", limits.btime),
+            ("winc", limits.winc),
+            ("binc", limits.binc),
+        ];
+        for (name, time) in clock {
+            if let Some(time) = time {
+                go.push_str(&format!(" {name} {}", time.as_millis()));
+            }
+        }
+        if let Some(movestogo) = limits.movestogo {
+            go.push_str(&format!(" movestogo {movestogo}"));
+        }
+        if go == "go" {
+            bail!("Refusing to start a UCI search without a depth, node or time limit");
+        }
+        self.send(&go)?;
+
+        let pos = history.position();
+        // With a clock the engine may think for as long as it has left.
+        let allowed = limits
+            .movetime
+            .or(limits.clock(pos.turn))
+            .unwrap_or_default();
+        let deadline = Instant::now() + allowed + self.timeout;
+        let mut infos = Vec::new();
+        loop {
+            let line = match self.recv(deadline) {
+                Ok(line) => line,
+                Err(err) if self.is_alive() => {
+                    // One last chance before we give up on the process.
+                    self.send("stop")?;
+                    match self.wait_bestmove(&pos, Instant::now() + Duration::from_secs(1)) {
+                        Ok(best) => return Ok(best),
+                        Err(_) => {
+                            let _ = self.child.kill();
+                            let _ = self.child.wait();
+                            return Err(err);
+                        }
+                    }
+                }
+                Err(err) => return Err(err),
+            };
+            let (cmd, rest) = line.trim().split_once(' ').unwrap_or((line.trim(), ""));
+            match cmd {
+                "info" => infos.push(Info::parse(rest, &pos)),
+                "bestmove" => {
+                    let mut best = parse_bestmove(rest, &pos)?;
+                    best.infos = infos;
+                    return Ok(best);
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn wait_bestmove(&mut self, pos: &Position, deadline: Instant) -> Result<BestMove> {
+        loop {
+            let line = self.recv(deadline)?;
+            if let Some(rest) = line.trim().strip_prefix("bestmove") {
+                return parse_bestmove(rest, pos);
+            }
+        }
+    }
+
+    pub fn is_alive(&mut self) -> bool {
+        matches!(self.child.try_wait(), Ok(None))
+    }
+
+    fn send(&mut self, cmd: &str) -> Result<()> {
+        writeln!(self.stdin, "{cmd}")
+            .and_then(|_| self.stdin.flush())
+            .map_err(|e| anyhow!("UCI engine {} is not listening: {e}", self.label()))
+    }
+
+    fn recv(&mut self, deadline: Instant) -> Result<String> {
+        let wait = deadline.saturating_duration_since(Instant::now());
+        match self.lines.recv_timeout(wait) {
+            Ok(line) => Ok(line),
+            Err(RecvTimeoutError::Timeout) => {
+                bail!("UCI engine {} timed out", self.label())
+            }
+            Err(RecvTimeoutError::Disconnected) => {
+                let status = self
+                    .child
+                    .wait()
+                    .map(|s| s.to_string())
+                    .unwrap_or_else(|e| e.to_string());
+                bail!("UCI engine {} exited unexpectedly ({status})", self.label())
+            }
+        }
+    }
+
+    fn label(&self) -> String {
+        self.name.clone().unwrap_or_else(|| "<unnamed>".to_string())
+    }
+
+    pub fn quit(mut self) -> Result<()> {
+        let _ = self.send("quit");
+        let deadline = Instant::now() + self.timeout;
+        while Instant::now() < deadline {
+            if self.child.try_wait()?.is_some() {
+                return Ok(());
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+        self.child.kill()?;
+        Ok(())
+    }
+}
+
+impl Drop for UciEngine {
+    fn drop(&mut self) {
+        if self.is_alive() {
+            let _ = self.send("quit");
+            thread::sleep(Duration::from_millis(20));
+            let _ = self.child.kill();
+        }
+        let _ = self.child.wait();
+    }
+}
+
+// `position startpos moves ...` or `position fen ... moves ...` for `history`.
+// In `chess960` mode every castling move is written as the king taking its rook.
+pub fn position_command(history: &History, chess960: bool) -> String {
+    let mut cmd = if history.start() == Position::startpos() {
+        String::from("position startpos")
+    } else {
+        format!("position fen {}", history.start().to_fen())
+    };
+    let mut pos = history.start();
+    let mut first = true;
+    for mv in history.moves() {
+        if first {
+            cmd.push_str(" moves");
+            first = false;
+        }
+        cmd.push(' ');
+        match chess960 {
+            true => cmd.push_str(&mv.to_string()),
+            false => cmd.push_str(&pos.move_to_uci(mv)),
+        }
+        pos.make_move(mv);
+    }
+    cmd
+}
+
+fn parse_bestmove(rest: &str, pos: &Position) -> Result<BestMove> {
+    let mut tokens = rest.split_whitespace();
+    let best = match tokens.next() {
+        None | Some("(none)") | Some("0000") => None,
+        Some(text) => Some(
+            pos.parse_uci(text)
+                .with_context(|| format!("UCI engine sent an illegal bestmove {text}"))?,
+        ),
+    };
+    let ponder = match (best, tokens.next(), tokens.next()) {
+        (Some(best), Some("ponder"), Some(text)) => {
+            let mut next = *pos;
+            next.make_move(&best);
+            next.parse_uci(text).ok()
+        }
+        _ => None,
+    };
+    Ok(BestMove {
+        best,
+        ponder,
+        infos: Vec::new(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::Action;
+
+    #[test]
+    fn parses_info_lines() {
+        let pos = Position::startpos();
+        let info = Info::parse(
+            "depth 12 seldepth 18 multipv 2 score cp -31 upperbound nodes 40210 nps 801000 \
+             time 50 pv e2e4 e7e5 g1f3 e1e1 d2d4",
+            &pos,
+        );
+        assert_eq!(info.depth, Some(12));
+        assert_eq!(info.seldepth, Some(18));
+        assert_eq!(info.multipv, Some(2));
+        assert_eq!(info.score, Some(Score::Centipawns(-31)));
+        assert_eq!(info.bound, Some(Bound::Upper));
+        assert_eq!(info.nodes, Some(40210));
+        assert_eq!(info.time, Some(Duration::from_millis(50)));
+        // The PV is cut at the first move that is not legal.
+        assert_eq!(info.pv.len(), 3);
+
+        let info = Info::parse("score mate -3 string hello there", &pos);
+        assert_eq!(info.score, Some(Score::Mate(-3)));
+        assert_eq!(info.string.as_deref(), Some("hello there"));
+    }
+
+    #[test]
+    fn orders_lines_by_multipv() {
+        let pos = Position::startpos();
+        let infos = [
+            "multipv 2 pv d2d4",
+            "multipv 1 pv e2e4",
+            "multipv 0 pv c2c4",
+        ]
+        .map(|text| Info::parse(&format!("depth 1 {text}"), &pos))
+        .to_vec();
+        let best = BestMove {
+            best: None,
+            ponder: None,
+            infos,
+        };
+        let firsts: Vec<String> = best
+            .lines()
+            .iter()
+            .map(|info| pos.move_to_uci(&info.pv[0]))
+            .collect();
+        assert_eq!(firsts, ["e2e4", "d2d4"]);
+    }
+
+    #[test]
+    fn parses_bestmove_with_castling_and_ponder() {
+        let pos = Position::from_fen("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1").unwrap();
+        let best = parse_bestmove("e1g1 ponder e8c8", &pos).unwrap();
+        assert_eq!(pos.move_to_uci(&best.best.unwrap()), "e1g1");
+        assert!(best.ponder.is_some());
+        assert!(parse_bestmove("(none)", &pos).unwrap().best.is_none());
+        assert!(parse_bestmove("e1e5", &pos).is_err());
+    }
+
+    #[test]
+    fn describes_history_as_a_position_command() {
+        let mut hist = History::init("uci");
+        assert_eq!(position_command(&hist, false), "position startpos");
+        let mut pos = hist.start();
+        for text in ["e2e4", "e7e5"] {
+            let mv = pos.parse_uci(text).unwrap();
+            pos.make_move(&mv);
+            hist.push(Action::Move(mv));
+        }
+        assert_eq!(
+            position_command(&hist, false),
+            "position startpos moves e2e4 e7e5"
+        );
+
+        let start = Position::from_fen("4k3/8/8/8/8/8/8/4K2R w K - 0 1").unwrap();
+        let mut hist = History::init_from("uci", start);
+        hist.push(Action::Move(start.parse_uci("e1g1").unwrap()));
+        assert_eq!(
+            position_command(&hist, false),
+            "position fen 4k3/8/8/8/8/8/8/4K2R w K - 0 1 moves e1g1"
+        );
+        assert_eq!(
+            position_command(&hist, true),
+            "position fen 4k3/8/8/8/8/8/8/4K2R w K - 0 1 moves e1h1"
+        );
+    }
+}
@@ -0,0 +1,126 @@
+//! chess_core::variant::antichess
+//!
+//! Antichess, also known as losing chess: whoever gets rid of all their
+//! pieces, or has no move left, wins. Capturing is compulsory whenever any
+//! capture is possible. The king is an ordinary piece that can be taken and
+//! that pawns may promote to; there is no check and no castling.
+
+use super::Variant;
+use crate::error::ChessError;
+use crate::position::{ChessMove, Outcome, Position};
+use crate::types::{Background, Color, Type};
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Antichess;
+
+impl Variant for Antichess {
+    fn name(&self) -> &'static str {
+        "Antichess"
+    }
+
+    fn setup(&self, mut start: Position) -> Position {
+        start.castling = Default::default();
+        start
+    }
+
+    // Any number of kings will do, none included.
+    fn validate(&self, _pos: &Position) -> Result<(), ChessError> {
+        Ok(())
+    }
+
+    fn special_outcome(&self, pos: &Position) -> Option<Outcome> {
+        self.legal_moves(pos)
+            .is_empty()
+            .then_some(Outcome::NoMovesLeft { winner: pos.turn })
+    }
+
+    fn legal_moves(&self, pos: &Position) -> Vec<ChessMove> {
+        let mut moves = Vec::with_capacity(48);
+        pos.pseudo_legal_moves(&mut moves);
+        moves.retain(|mv| !pos.is_castling(mv));
+        let kings: Vec<_> = moves
+            .iter()
+            .filter(|mv| mv.promotion == Some(Type::Queen))
+            .map(|mv| ChessMove::promoting(mv.from, mv.to, Type::King))
+            .collect();
+        moves.extend(kings);
+        if moves.iter().any(|mv| pos.is_capture(mv)) {
+            moves.retain(|mv| pos.is_capture(mv));
+        }
+        moves
+    }
+
+    fn in_check(&self, _pos: &Position) -> bool {
+        false
+    }
+
+    // Two lone bishops on squares of opposite shades can never meet.
+    fn is_insufficient_material(&self, pos: &Position) -> bool {
+        let lone_bishop = |color| -> Option<Background> {
+            let mut pieces = pos.pieces(color);
+            match (pieces.next(), pieces.next()) {
+                (Some((sq, man)), None) if man.ty == Type::Bishop => Some(sq.color()),
+                _ => None,
+            }
+        };
+        match (lone_bishop(Color::White), lone_bishop(Color::Black)) {
+            (Some(white), Some(black)) => white != black,
+            _ => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constants::*;
+
+    #[test]
+    fn captures_are_compulsory_and_losing_everything_wins() {
+        let mut pos = Antichess.setup(Position::startpos());
+        for mv in ["e2e3", "b7b5"] {
+            let mv = pos.parse_uci(mv).unwrap();
+            Antichess.make_move(&mut pos, &mv);
+        }
+        // The bishop has to take b5.
+        assert_eq!(Antichess.legal_moves(&pos), [ChessMove::new(F1, B5)]);
+
+        let pos = Antichess.parse_fen("8/8/8/8/8/8/1p6/8 b - - 0 1").unwrap();
+        let promotions = Antichess.legal_moves(&pos);
+        assert_eq!(promotions.len(), 5);
+        assert!(promotions.contains(&ChessMove::promoting(B2, B1, Type::King)));
+        // Having no pieces, or none that can move, wins.
+        for fen in ["8/8/8/8/8/8/1p6/8 w - - 0 1", "8/8/8/8/8/p7/P7/8 w - - 0 1"] {
+            assert_eq!(
+                Antichess.outcome(&Antichess.parse_fen(fen).unwrap()),
+                Some(Outcome::NoMovesLeft {
+                    winner: Color::White
+                })
+            );
+        }
+        let bishops = Antichess.parse_fen("8/8/8/8/4b3/8/8/B7 w - - 0 1").unwrap();
+        assert_eq!(
+            Antichess.outcome(&bishops),
+            Some(Outcome::InsufficientMaterial)
+        );
+        assert!(Position::from_fen("8/8/8/8/8/8/1p6/8 b - - 0 1").is_err());
+    }
+
+    // The `antichess-start` and `a-pawn-vs-b-pawn` entries of python-chess's
+    // `tests/antichess.perft`.
+    #[test]
+    fn perft() {
+        let start = Antichess.setup(Position::startpos());
+        assert_eq!(Antichess.perft(&start, 1), 20);
+        assert_eq!(Antichess.perft(&start, 2), 400);
+        assert_eq!(Antichess.perft(&start, 3), 8067);
+        assert_eq!(Antichess.perft(&start, 4), 153299);
+        let pawns = Antichess.parse_fen("8/1p6/8/8/8/8/P7/8 w - - 0 1").unwrap();
+        assert_eq!(
+            (1..=6)
+                .map(|d| Antichess.perft(&pawns, d))
+                .collect::<Vec<_>>(),
+            [2, 4, 4, 3, 1, 0]
+        );
+    }
+}
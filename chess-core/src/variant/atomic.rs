@@ -0,0 +1,231 @@
+//! chess_core::variant::atomic
+//!
+//! Atomic chess: every capture is an explosion that removes the capturing
+//! piece, the captured one and every piece other than a pawn on the eight
+//! squares around the target. Blowing up the enemy king wins, so kings never
+//! capture, a move that would blow up one's own king is illegal, and kings
+//! standing next to each other cannot be in check.
+
+use super::Variant;
+use crate::position::attacks::AttackMap;
+use crate::position::movegen::KING_STEPS;
+use crate::position::{offset, ChessMove, Man, Outcome, Position};
+use crate::square::Square;
+use crate::types::{Color, Type};
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Atomic;
+
+impl Atomic {
+    fn kings_touch(pos: &Position) -> bool {
+        match (pos.king(Color::White), pos.king(Color::Black)) {
+            (Some(white), Some(black)) => white.distance(black) == 1,
+            _ => false,
+        }
+    }
+
+    // Is `color`'s king attacked by anything that could capture it? Only
+    // other pieces can, and not while the two kings touch.
+    fn king_attacked(pos: &Position, color: Color) -> bool {
+        let Some(king) = pos.king(color) else {
+            return false;
+        };
+        !Self::kings_touch(pos) && pos.is_attacked(king, color.opposite())
+    }
+
+    // Could the side to move be captured on `sq`, e.g. while castling?
+    fn square_attacked(pos: &Position, sq: Square) -> bool {
+        let them = pos.turn.opposite();
+        let beside_their_king = pos.king(them).is_some_and(|k| k.distance(sq) == 1);
+        !beside_their_king && pos.is_attacked(sq, them)
+    }
+
+    fn is_legal(&self, pos: &Position, mv: &ChessMove) -> bool {
+        let us = pos.turn;
+        if pos.is_castling(mv) {
+            let king_to = pos.castle_king_dest(mv);
+            let path = mv.from.min(king_to)..=mv.from.max(king_to);
+            let mut through = path
+                .filter(|&sq| sq != king_to)
+                .filter_map(Square::from_index);
+            if through.any(|sq| Self::square_attacked(pos, sq)) {
+                return false;
+            }
+        } else if pos.at(mv.from_square()).is_some_and(|m| m.ty == Type::King) && pos.is_capture(mv)
+        {
+            return false;
+        }
+        let mut next = *pos;
+        self.make_move(&mut next, mv);
+        match (next.king(us), next.king(us.opposite())) {
+            (None, _) => false,
+            (Some(_), None) => true,
+            (Some(_), Some(_)) => !Self::king_attacked(&next, us),
+        }
+    }
+}
+
+impl Variant for Atomic {
+    fn name(&self) -> &'static str {
+        "Atomic"
+    }
+
+    fn special_outcome(&self, pos: &Position) -> Option<Outcome> {
+        [Color::White, Color::Black]
+            .into_iter()
+            .find(|&color| pos.king(color).is_none())
+            .map(|loser| Outcome::KingExploded {
+                winner: loser.opposite(),
+            })
+    }
+
+    fn legal_moves(&self, pos: &Position) -> Vec<ChessMove> {
+        if self.special_outcome(pos).is_some() {
+            return Vec::new();
+        }
+        let mut moves = Vec::with_capacity(48);
+        pos.pseudo_legal_moves(&mut moves);
+        // Castling is checked for attacks the atomic way below.
+        moves.retain(|mv| !pos.is_castling(mv));
+        if let Some(king) = pos.king(pos.turn) {
+            pos.castling_moves(king.index(), false, &mut moves);
+        }
+        moves.retain(|mv| self.is_legal(pos, mv));
+        moves
+    }
+
+    fn make_move(&self, pos: &mut Position, mv: &ChessMove) {
+        if !pos.is_capture(mv) {
+            pos.make_move(mv);
+            return;
+        }
+        pos.make_move(mv);
+        pos.board[mv.to] = None;
+        for sq in KING_STEPS
+            .iter()
+            .filter_map(|&(dx, dy)| offset(mv.to, dx, dy))
+        {
+            match pos.board[sq] {
+                Some(Man { ty: Type::Pawn, .. }) | None => {}
+                Some(Man { color, ty }) => {
+                    if ty == Type::King {
+                        pos.castling.clear_color(color);
+                    }
+                    pos.castling.discard_rook(sq);
+                    pos.board[sq] = None;
+                }
+            }
+        }
+    }
+
+    fn in_check(&self, pos: &Position) -> bool {
+        Self::king_attacked(pos, pos.turn)
+    }
+
+    // Kings never capture, so they threaten nothing.
+    fn attack_map(&self, pos: &Position, by: Color) -> AttackMap {
+        let mut map = pos.attack_map(by);
+        if let Some(king) = pos.king(by) {
+            map.remove_attacker(king);
+        }
+        map
+    }
+
+    // A lone king can never explode anything, and a lone minor piece cannot
+    // get at a lone king.
+    fn is_insufficient_material(&self, pos: &Position) -> bool {
+        let army = |color| -> Vec<Type> {
+            pos.pieces(color)
+                .map(|(_, man)| man.ty)
+                .filter(|&ty| ty != Type::King)
+                .collect()
+        };
+        let (white, black) = (army(Color::White), army(Color::Black));
+        let harmless = |army: &[Type], other: &[Type]| {
+            army.is_empty()
+                || (other.is_empty()
+                    && army.len() == 1
+                    && matches!(army[0], Type::Knight | Type::Bishop))
+        };
+        harmless(&white, &black) && harmless(&black, &white)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constants::*;
+
+    #[test]
+    fn captures_explode_the_neighbourhood() {
+        let mut pos =
+            Position::from_fen("rnbqkbnr/ppp2ppp/8/3pp3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 0 3")
+                .unwrap();
+        let mv = pos.parse_uci("f3e5").unwrap();
+        Atomic.make_move(&mut pos, &mv);
+        // The knight and the pawn it took are gone, the pawns next door stay.
+        assert_eq!(pos.at(Square::E5), None);
+        assert_eq!(pos.at(Square::F3), None);
+        assert_eq!(pos.at(Square::D5), Some(Man::new(Color::Black, Type::Pawn)));
+        for mv in ["d8h4", "a2a3"] {
+            let mv = pos.parse_uci(mv).unwrap();
+            Atomic.make_move(&mut pos, &mv);
+        }
+        // Qxf2 blows up the bishop and king next to f2, but not the pawn.
+        let mut boom = pos;
+        Atomic.make_move(&mut boom, &pos.parse_uci("h4f2").unwrap());
+        assert_eq!(boom.king(Color::White), None);
+        assert_eq!(boom.at(Square::F1), None);
+        assert_eq!(
+            boom.at(Square::G2),
+            Some(Man::new(Color::White, Type::Pawn))
+        );
+        assert_eq!(
+            Atomic.outcome(&boom),
+            Some(Outcome::KingExploded {
+                winner: Color::Black
+            })
+        );
+        // A king may not take the queen next to it, so this is mate.
+        let mate = Position::from_fen("8/8/8/8/4k3/8/3q4/3K4 w - - 0 1").unwrap();
+        assert!(mate.legal_moves().contains(&ChessMove::new(D1, D2)));
+        assert_eq!(
+            Atomic.outcome(&mate),
+            Some(Outcome::Checkmate {
+                winner: Color::Black
+            })
+        );
+        // Nor does it threaten the queen.
+        assert!(mate.attack_map(Color::White).is_attacked(Square::D2));
+        assert_eq!(Atomic.attack_map(&mate, Color::White).attacked().count(), 0);
+        // Touching kings cannot give check.
+        let kings = Position::from_fen("8/8/8/3kq3/8/3K4/8/8 w - - 0 1").unwrap();
+        let moves = Atomic.legal_moves(&kings);
+        assert!(moves.contains(&ChessMove::new(D3, D4)));
+        assert!(!Atomic.in_check(&Position::from_fen("8/8/8/3k4/3K4/8/8/7q w - - 0 1").unwrap()));
+    }
+
+    // The `atomic-start`, `programfox-1` and `programfox-2` entries of
+    // python-chess's `tests/atomic.perft`.
+    #[test]
+    fn perft() {
+        let start = Position::startpos();
+        assert_eq!(Atomic.perft(&start, 3), 8902);
+        // Captures are possible from the third ply, but an explosion only
+        // changes the moves after it, so the counts first differ from chess's
+        // on the fourth.
+        assert_eq!(Atomic.perft(&start, 4), 197326);
+        let pos = Position::from_fen(
+            "rn2kb1r/1pp1p2p/p2q1pp1/3P4/2P3b1/4PN2/PP3PPP/R2QKB1R b KQkq - 0 1",
+        )
+        .unwrap();
+        assert_eq!(Atomic.perft(&pos, 1), 40);
+        assert_eq!(Atomic.perft(&pos, 2), 1238);
+        assert_eq!(Atomic.perft(&pos, 3), 45237);
+        let pos =
+            Position::from_fen("rn1qkb1r/p5pp/2p5/3p4/N3P3/5P2/PPP4P/R1BQK3 w Qkq - 0 1").unwrap();
+        assert_eq!(Atomic.perft(&pos, 1), 28);
+        assert_eq!(Atomic.perft(&pos, 2), 833);
+        assert_eq!(Atomic.perft(&pos, 3), 23353);
+    }
+}
@@ -0,0 +1,75 @@
+//! chess_core::variant::bughouse
+//!
+//! One board of a Bughouse match. Two teams of two play two games side by
+//! side, partners taking opposite colors, and whatever a player captures goes
+//! to their partner's reserve on the other board, to be dropped as in
+//! Crazyhouse. On its own board a capture leaves the reserves alone; the
+//! pieces arrive through [`Action::Receive`], and linking the boards is up to
+//! whoever runs the match.
+//!
+//! A player left without a legal move is mated or stalemated on the spot,
+//! even if a piece their partner is about to send could have saved them.
+//!
+//! [`Action::Receive`]: crate::game::Action::Receive
+
+use super::Variant;
+use crate::position::{ChessMove, Position};
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Bughouse;
+
+impl Variant for Bughouse {
+    fn name(&self) -> &'static str {
+        "Bughouse"
+    }
+
+    fn setup(&self, start: Position) -> Position {
+        start.with_reserves()
+    }
+
+    fn make_move(&self, pos: &mut Position, mv: &ChessMove) {
+        let (mover, gain) = (pos.turn, pos.reserve_gain(mv));
+        pos.make_move(mv);
+        if let (Some(ty), Some(reserves)) = (gain, pos.reserves.as_mut()) {
+            reserves.take(mover, ty);
+        }
+    }
+
+    // More pieces can always come from the other board.
+    fn is_insufficient_material(&self, _pos: &Position) -> bool {
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constants::*;
+    use crate::types::{Color, Type};
+
+    #[test]
+    fn captures_leave_the_board_and_its_reserves() {
+        let mut pos = Bughouse.setup(Position::startpos());
+        for mv in ["e2e4", "d7d5", "e4d5"] {
+            let mv = pos.parse_uci(mv).unwrap();
+            Bughouse.make_move(&mut pos, &mv);
+        }
+        assert_eq!(pos.reserve_gain(&ChessMove::new(D8, D5)), Some(Type::Pawn));
+        assert!(pos.reserves.unwrap().is_empty(Color::White));
+        assert_eq!(
+            pos.to_fen(),
+            "rnbqkbnr/ppp1pppp/8/3P4/8/8/PPPP1PPP/RNBQKBNR[] b KQkq - 0 2"
+        );
+        // A promoted piece still counts as a pawn for the partner.
+        let pos = Bughouse
+            .parse_fen("3qk3/2P5/8/8/8/8/8/4K3[] w - - 0 1")
+            .unwrap();
+        let mut promoted = pos;
+        Bughouse.make_move(&mut promoted, &ChessMove::promoting(C7, D8, Type::Queen));
+        assert_eq!(
+            promoted.reserve_gain(&ChessMove::new(E8, D8)),
+            Some(Type::Pawn)
+        );
+        assert!(!Bughouse.is_insufficient_material(&promoted));
+    }
+}
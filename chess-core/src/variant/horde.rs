@@ -0,0 +1,114 @@
+//! chess_core::variant::horde
+//!
+//! Horde: Black's usual army faces a horde of 36 white pawns and no white
+//! king. Black wins by capturing every white piece, White by mating the black
+//! king. White pawns on the first rank may advance two squares, just like
+//! those on the second, but cannot be taken en passant when they do.
+
+use super::Variant;
+use crate::error::{ChessError, FenField};
+use crate::position::{Outcome, Position};
+use crate::types::{Color, Type};
+
+pub const HORDE_FEN: &str =
+    "rnbqkbnr/pppppppp/8/1PP2PP1/PPPPPPPP/PPPPPPPP/PPPPPPPP/PPPPPPPP w kq - 0 1";
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Horde;
+
+impl Variant for Horde {
+    fn name(&self) -> &'static str {
+        "Horde"
+    }
+
+    fn setup(&self, _start: Position) -> Position {
+        self.parse_fen(HORDE_FEN).expect("the Horde FEN is valid")
+    }
+
+    // Black has exactly one king and White none.
+    fn validate(&self, pos: &Position) -> Result<(), ChessError> {
+        let kings = |color| {
+            pos.pieces(color)
+                .filter(|(_, man)| man.ty == Type::King)
+                .count()
+        };
+        match (kings(Color::White), kings(Color::Black)) {
+            (0, 1) => Ok(()),
+            _ => Err(ChessError::fen(
+                FenField::Placement,
+                "a horde has no king and Black exactly one",
+            )),
+        }
+    }
+
+    fn special_outcome(&self, pos: &Position) -> Option<Outcome> {
+        pos.pieces(Color::White)
+            .next()
+            .is_none()
+            .then_some(Outcome::AllPiecesCaptured {
+                winner: Color::Black,
+            })
+    }
+
+    // Black can always go after what is left of the horde, and the horde may
+    // still promote.
+    fn is_insufficient_material(&self, _pos: &Position) -> bool {
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constants::*;
+    use crate::position::ChessMove;
+
+    #[test]
+    fn first_rank_pawns_double_push_and_the_horde_can_be_wiped_out() {
+        let start = Horde.setup(Position::startpos());
+        assert_eq!(start.pieces(Color::White).count(), 36);
+        assert_eq!(start.to_fen(), HORDE_FEN);
+        assert!(Position::from_fen(HORDE_FEN).is_err());
+
+        let pos = Horde.parse_fen("4k3/8/8/8/8/8/8/P7 w - - 0 1").unwrap();
+        let moves = Horde.legal_moves(&pos);
+        assert_eq!(moves, [ChessMove::new(A1, A2), ChessMove::new(A1, A3)]);
+        let mut pushed = pos;
+        Horde.make_move(&mut pushed, &moves[1]);
+        assert_eq!(pushed.ep, None);
+
+        let mut pos = Horde.parse_fen("4k3/8/8/8/8/8/1q6/P7 b - - 0 1").unwrap();
+        Horde.make_move(&mut pos, &ChessMove::new(B2, A1));
+        assert_eq!(
+            Horde.outcome(&pos),
+            Some(Outcome::AllPiecesCaptured {
+                winner: Color::Black
+            })
+        );
+        // A White king has no place in the horde.
+        assert!(Horde.parse_fen("4k3/8/8/8/8/8/8/K7 w - - 0 1").is_err());
+    }
+
+    // The `horde-start`, `horde-open-flank` and `horde-en-passant` entries of
+    // python-chess's `tests/horde.perft`.
+    #[test]
+    fn perft() {
+        let start = Horde.setup(Position::startpos());
+        assert_eq!(Horde.perft(&start, 1), 8);
+        assert_eq!(Horde.perft(&start, 2), 128);
+        assert_eq!(Horde.perft(&start, 3), 1274);
+        assert_eq!(Horde.perft(&start, 4), 23310);
+        let flank = Horde
+            .parse_fen("4k3/pp4q1/3P2p1/8/P3PP2/PPP2r2/PPP5/PPPP4 b - - 0 1")
+            .unwrap();
+        assert_eq!(Horde.perft(&flank, 1), 30);
+        assert_eq!(Horde.perft(&flank, 2), 241);
+        assert_eq!(Horde.perft(&flank, 3), 6633);
+        let ep = Horde
+            .parse_fen("k7/5p2/4p2P/3p2P1/2p2P2/1p2P2P/p2P2P1/2P2P2 w - - 0 1")
+            .unwrap();
+        assert_eq!(Horde.perft(&ep, 1), 13);
+        assert_eq!(Horde.perft(&ep, 2), 172);
+        assert_eq!(Horde.perft(&ep, 3), 2205);
+    }
+}
@@ -0,0 +1,84 @@
+//! chess_core::variant::king_of_the_hill
+//!
+//! King of the Hill: standard chess, except that a king which reaches one of
+//! the four center squares wins. Since a king can always walk there, no
+//! material is ever insufficient.
+
+use super::Variant;
+use crate::position::{Outcome, Position};
+use crate::square::Square;
+use crate::types::Color;
+
+pub const HILL: [Square; 4] = [Square::D4, Square::E4, Square::D5, Square::E5];
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct KingOfTheHill;
+
+impl Variant for KingOfTheHill {
+    fn name(&self) -> &'static str {
+        "King of the Hill"
+    }
+
+    fn special_outcome(&self, pos: &Position) -> Option<Outcome> {
+        [Color::White, Color::Black]
+            .into_iter()
+            .find(|&color| pos.king(color).is_some_and(|k| HILL.contains(&k)))
+            .map(|winner| Outcome::KingOfTheHill { winner })
+    }
+
+    fn is_insufficient_material(&self, _pos: &Position) -> bool {
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::variant::Standard;
+
+    #[test]
+    fn a_king_on_the_hill_wins() {
+        let mut pos = Position::from_fen("8/8/8/8/8/3k4/8/3K4 b - - 0 1").unwrap();
+        assert_eq!(KingOfTheHill.outcome(&pos), None);
+        pos.play(&pos.parse_uci("d3e4").unwrap()).unwrap();
+        assert_eq!(
+            KingOfTheHill.outcome(&pos),
+            Some(Outcome::KingOfTheHill {
+                winner: Color::Black
+            })
+        );
+        assert!(KingOfTheHill.legal_moves(&pos).is_empty());
+    }
+
+    // Nobody reaches the centre in three plies from either position, so the
+    // counts are chess's own: 8902 from the start, and kiwipete's from the
+    // chessprogramming wiki, `pos-2` in python-chess's `tests/tricky.perft`.
+    #[test]
+    fn perft() {
+        let start = Position::startpos();
+        assert_eq!(KingOfTheHill.perft(&start, 3), 8902);
+        let kiwipete = Position::from_fen(
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        )
+        .unwrap();
+        assert_eq!(KingOfTheHill.perft(&kiwipete, 3), 97862);
+        // White's king already stands on e4.
+        let won = Position::from_fen("rnbq1bnr/ppp2ppp/3k4/4p2Q/3PK3/8/PPP2PPP/RNB2BNR b - - 0 1")
+            .unwrap();
+        assert_eq!(KingOfTheHill.perft(&won, 4), 0);
+    }
+
+    // Each king is a step away from e4 or e5, so the tree is cut from the
+    // first ply on. No published suite reaches the hill, so these are the
+    // King of the Hill counts of shakmaty 0.27, which follows python-chess.
+    #[test]
+    fn perft_with_hill_wins() {
+        let pos = Position::from_fen("r1bq1bnr/ppp2ppp/2nk4/8/8/2NK4/PPP2PPP/R1BQ1BNR w - - 0 1")
+            .unwrap();
+        assert_eq!(KingOfTheHill.perft(&pos, 1), 37);
+        assert_eq!(KingOfTheHill.perft(&pos, 2), 1121);
+        assert_eq!(KingOfTheHill.perft(&pos, 3), 34904);
+        assert_eq!(KingOfTheHill.perft(&pos, 4), 1082424);
+        assert_ne!(Standard.perft(&pos, 3), 34904);
+    }
+}
@@ -0,0 +1,172 @@
+//! chess_core::variant
+//!
+//! Rule sets other than standard chess. A [`Variant`] decides which moves are
+//! legal, what a move does to the board beyond the usual, and when the game is
+//! over; [`crate::game::GameMode`] picks one for a game and its [`History`]
+//! replays every move through it. Anything a variant has to remember between
+//! moves, such as Crazyhouse reserves or Three-check counters, lives in the
+//! [`Position`] so that it survives copying, hashing and FEN.
+//!
+//! [`History`]: crate::game::History
+
+pub mod antichess;
+pub mod atomic;
+pub mod bughouse;
+pub mod horde;
+pub mod king_of_the_hill;
+pub mod racing_kings;
+pub mod three_check;
+
+pub use self::antichess::Antichess;
+pub use self::atomic::Atomic;
+pub use self::bughouse::Bughouse;
+pub use self::horde::Horde;
+pub use self::king_of_the_hill::KingOfTheHill;
+pub use self::racing_kings::RacingKings;
+pub use self::three_check::ThreeCheck;
+
+use crate::error::ChessError;
+use crate::position::attacks::AttackMap;
+use crate::position::{ChessMove, Outcome, Position};
+use crate::types::Color;
+use std::fmt;
+
+pub trait Variant: fmt::Debug + Send + Sync {
+    // The name used in PGN `Variant` tags and UCI `UCI_Variant` options.
+    fn name(&self) -> &'static str;
+
+    // Turns a standard starting position into one of this variant, e.g. by
+    // giving it reserves or check counters.
+    fn setup(&self, start: Position) -> Position {
+        start
+    }
+
+    // Checks that `pos` can be played under this variant. Unless the variant
+    // says otherwise, each side needs exactly one king.
+    fn validate(&self, pos: &Position) -> Result<(), ChessError> {
+        pos.check_kings()
+    }
+
+    // Reads a position of this variant from FEN.
+    fn parse_fen(&self, fen: &str) -> Result<Position, ChessError> {
+        let pos = Position::from_fen_unchecked(fen)?;
+        self.validate(&pos)?;
+        Ok(pos)
+    }
+
+    // A win or loss by this variant's own rules, checked before checkmate and
+    // the draws of standard chess.
+    fn special_outcome(&self, _pos: &Position) -> Option<Outcome> {
+        None
+    }
+
+    // The moves the side to move may play; none once the game is decided.
+    fn legal_moves(&self, pos: &Position) -> Vec<ChessMove> {
+        match self.special_outcome(pos) {
+            Some(_) => Vec::new(),
+            None => pos.legal_moves(),
+        }
+    }
+
+    // Plays `mv`, which must be legal, along with whatever else it sets off.
+    fn make_move(&self, pos: &mut Position, mv: &ChessMove) {
+        pos.make_move(mv);
+    }
+
+    fn in_check(&self, pos: &Position) -> bool {
+        pos.in_check()
+    }
+
+    // Which pieces of `by` attack each square under this variant's rules.
+    fn attack_map(&self, pos: &Position, by: Color) -> AttackMap {
+        pos.attack_map(by)
+    }
+
+    fn is_insufficient_material(&self, pos: &Position) -> bool {
+        pos.is_insufficient_material()
+    }
+
+    // Like [`Position::outcome`](), under this variant's rules.
+    fn outcome(&self, pos: &Position) -> Option<Outcome> {
+        if let Some(outcome) = self.special_outcome(pos) {
+            return Some(outcome);
+        }
+        if self.legal_moves(pos).is_empty() {
+            return Some(match self.in_check(pos) {
+                true => Outcome::Checkmate {
+                    winner: pos.turn.opposite(),
+                },
+                false => Outcome::Stalemate,
+            });
+        }
+        if self.is_insufficient_material(pos) {
+            return Some(Outcome::InsufficientMaterial);
+        }
+        if pos.halfmoves >= 100 {
+            return Some(Outcome::FiftyMoves);
+        }
+        None
+    }
+
+    // The number of move sequences `depth` plies long, for checking the move
+    // generator against published counts.
+    fn perft(&self, pos: &Position, depth: u32) -> u64 {
+        if depth == 0 {
+            return 1;
+        }
+        let moves = self.legal_moves(pos);
+        if depth == 1 {
+            return moves.len() as u64;
+        }
+        moves
+            .iter()
+            .map(|mv| {
+                let mut next = *pos;
+                self.make_move(&mut next, mv);
+                self.perft(&next, depth - 1)
+            })
+            .sum()
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Standard;
+
+impl Variant for Standard {
+    fn name(&self) -> &'static str {
+        "Standard"
+    }
+}
+
+// See [`crate::position::fog`]().
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FogOfWar;
+
+impl Variant for FogOfWar {
+    fn name(&self) -> &'static str {
+        "Fog of War"
+    }
+    fn legal_moves(&self, pos: &Position) -> Vec<ChessMove> {
+        pos.fog_moves()
+    }
+    fn in_check(&self, _pos: &Position) -> bool {
+        false
+    }
+    fn outcome(&self, pos: &Position) -> Option<Outcome> {
+        pos.fog_outcome()
+    }
+}
+
+// See [`crate::position::crazyhouse`](). The reserves in the position do all
+// the work.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Crazyhouse;
+
+impl Variant for Crazyhouse {
+    fn name(&self) -> &'static str {
+        "Crazyhouse"
+    }
+    fn setup(&self, start: Position) -> Position {
+        start.with_reserves()
+    }
+}
@@ -0,0 +1,131 @@
+//! chess_core::variant::racing_kings
+//!
+//! Racing Kings: both armies start side by side on the first two ranks with
+//! no pawns, and the first king to reach the eighth rank wins. Nobody may
+//! give check, or walk into one. White moves first, so if White's king gets
+//! there and Black's can follow on the very next move, the race is a tie.
+
+use super::Variant;
+use crate::position::{rank_of, ChessMove, Outcome, Position};
+use crate::square::Rank;
+use crate::types::Color;
+
+pub const RACING_KINGS_FEN: &str = "8/8/8/8/8/8/krbnNBRK/qrbnNBRQ w - - 0 1";
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RacingKings;
+
+impl RacingKings {
+    // The legal moves of standard chess that do not give check, whether or
+    // not the race is already over.
+    fn race_moves(pos: &Position) -> Vec<ChessMove> {
+        let mut moves = pos.legal_moves();
+        moves.retain(|mv| {
+            let mut next = *pos;
+            next.make_move(mv);
+            !next.in_check()
+        });
+        moves
+    }
+
+    fn home(pos: &Position, color: Color) -> bool {
+        pos.king(color).is_some_and(|k| k.rank() == Rank::EIGHTH)
+    }
+}
+
+impl Variant for RacingKings {
+    fn name(&self) -> &'static str {
+        "Racing Kings"
+    }
+
+    // The race always starts from the same position.
+    fn setup(&self, _start: Position) -> Position {
+        Position::from_fen(RACING_KINGS_FEN).expect("the Racing Kings FEN is valid")
+    }
+
+    fn special_outcome(&self, pos: &Position) -> Option<Outcome> {
+        match (Self::home(pos, Color::White), Self::home(pos, Color::Black)) {
+            (true, true) => Some(Outcome::RaceTied),
+            (false, true) => Some(Outcome::RaceWon {
+                winner: Color::Black,
+            }),
+            (true, false) => {
+                // Black still gets the move that could draw level.
+                let king = pos.king(Color::Black);
+                let catch_up = pos.turn == Color::Black
+                    && Self::race_moves(pos)
+                        .iter()
+                        .any(|mv| Some(mv.from_square()) == king && rank_of(mv.to) == 7);
+                (!catch_up).then_some(Outcome::RaceWon {
+                    winner: Color::White,
+                })
+            }
+            (false, false) => None,
+        }
+    }
+
+    fn legal_moves(&self, pos: &Position) -> Vec<ChessMove> {
+        match self.special_outcome(pos) {
+            Some(_) => Vec::new(),
+            None => Self::race_moves(pos),
+        }
+    }
+
+    // Kings alone can always race.
+    fn is_insufficient_material(&self, _pos: &Position) -> bool {
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constants::*;
+
+    #[test]
+    fn checks_are_illegal_and_the_race_can_tie() {
+        let start = RacingKings.setup(Position::startpos());
+        assert_eq!(start.to_fen(), RACING_KINGS_FEN);
+        // Nc3 would check the king on a2.
+        assert!(start.legal_moves().contains(&ChessMove::new(E2, C3)));
+        assert!(!RacingKings
+            .legal_moves(&start)
+            .contains(&ChessMove::new(E2, C3)));
+
+        let won = Color::White;
+        let mut pos = Position::from_fen("8/6K1/1k6/8/8/8/8/8 w - - 0 1").unwrap();
+        RacingKings.make_move(&mut pos, &ChessMove::new(G7, G8));
+        assert_eq!(
+            RacingKings.outcome(&pos),
+            Some(Outcome::RaceWon { winner: won })
+        );
+        // One step behind, Black may still draw level, but only right away.
+        let behind = Position::from_fen("8/1k4K1/8/8/8/8/8/8 w - - 0 1").unwrap();
+        let mut pos = behind;
+        RacingKings.make_move(&mut pos, &ChessMove::new(G7, G8));
+        assert_eq!(RacingKings.outcome(&pos), None);
+        let mut tied = pos;
+        RacingKings.make_move(&mut tied, &ChessMove::new(B7, B8));
+        assert_eq!(RacingKings.outcome(&tied), Some(Outcome::RaceTied));
+        RacingKings.make_move(&mut pos, &ChessMove::new(B7, B6));
+        assert_eq!(
+            RacingKings.outcome(&pos),
+            Some(Outcome::RaceWon { winner: won })
+        );
+    }
+
+    // The `racingkings-start` and `occupied-goal` entries of python-chess's
+    // `tests/racingkings.perft`.
+    #[test]
+    fn perft() {
+        let start = RacingKings.setup(Position::startpos());
+        assert_eq!(RacingKings.perft(&start, 1), 21);
+        assert_eq!(RacingKings.perft(&start, 2), 421);
+        assert_eq!(RacingKings.perft(&start, 3), 11264);
+        assert_eq!(RacingKings.perft(&start, 4), 296242);
+        let goal = Position::from_fen("4brn1/2K2k2/8/8/8/8/8/8 w - - 0 1").unwrap();
+        assert_eq!(RacingKings.perft(&goal, 1), 6);
+        assert_eq!(RacingKings.perft(&goal, 2), 33);
+        assert_eq!(RacingKings.perft(&goal, 3), 178);
+    }
+}
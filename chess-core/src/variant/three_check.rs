@@ -0,0 +1,97 @@
+//! chess_core::variant::three_check
+//!
+//! Three-check: standard chess, except that whoever gives check for the third
+//! time wins on the spot. [`Position::checks`] counts down the checks each
+//! side still needs.
+
+use super::Variant;
+use crate::position::{ChessMove, Outcome, Position};
+use crate::types::{Color, Type};
+
+pub const CHECKS_TO_WIN: u8 = 3;
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ThreeCheck;
+
+impl Variant for ThreeCheck {
+    fn name(&self) -> &'static str {
+        "Three-check"
+    }
+
+    fn setup(&self, mut start: Position) -> Position {
+        start.checks.get_or_insert([CHECKS_TO_WIN; 2]);
+        start
+    }
+
+    fn special_outcome(&self, pos: &Position) -> Option<Outcome> {
+        let checks = pos.checks?;
+        [Color::White, Color::Black]
+            .into_iter()
+            .find(|color| checks[color.index()] == 0)
+            .map(|winner| Outcome::ThreeChecks { winner })
+    }
+
+    fn make_move(&self, pos: &mut Position, mv: &ChessMove) {
+        let us = pos.turn;
+        pos.make_move(mv);
+        if pos.in_check() {
+            let checks = pos.checks.get_or_insert([CHECKS_TO_WIN; 2]);
+            checks[us.index()] = checks[us.index()].saturating_sub(1);
+        }
+    }
+
+    // Any piece at all can give check.
+    fn is_insufficient_material(&self, pos: &Position) -> bool {
+        pos.board.iter().flatten().all(|man| man.ty == Type::King)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_third_check_wins() {
+        let mut pos = ThreeCheck.setup(Position::startpos());
+        assert_eq!(
+            pos.to_fen(),
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 3+3 0 1"
+        );
+        for mv in [
+            "e2e4", "e7e5", "f1c4", "g8f6", "c4f7", "e8f7", "d1h5", "f7e7",
+        ] {
+            let mv = pos.parse_uci(mv).unwrap();
+            ThreeCheck.make_move(&mut pos, &mv);
+        }
+        assert_eq!(pos.checks, Some([1, 3]));
+        assert_eq!(ThreeCheck.outcome(&pos), None);
+        let mv = pos.parse_uci("h5e5").unwrap();
+        ThreeCheck.make_move(&mut pos, &mv);
+        assert_eq!(
+            ThreeCheck.outcome(&pos),
+            Some(Outcome::ThreeChecks {
+                winner: Color::White
+            })
+        );
+        assert!(ThreeCheck.legal_moves(&pos).is_empty());
+        assert_eq!(Position::from_fen(&pos.to_fen()).unwrap(), pos);
+        assert!(Position::from_fen("4k3/8/8/8/8/8/8/4K3 w - - 3+x 0 1").is_err());
+    }
+
+    // The kiwipete counts are the `3check-kiwipete` entry of python-chess's
+    // `tests/3check.perft`; nobody can give check in the first three plies,
+    // so the start counts as in chess.
+    #[test]
+    fn perft() {
+        let start = ThreeCheck.setup(Position::startpos());
+        assert_eq!(ThreeCheck.perft(&start, 3), 8902);
+        // One check away from winning, every check ends the game.
+        let kiwipete = Position::from_fen(
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 1+1 0 1",
+        )
+        .unwrap();
+        assert_eq!(ThreeCheck.perft(&kiwipete, 1), 48);
+        assert_eq!(ThreeCheck.perft(&kiwipete, 2), 2039);
+        assert_eq!(ThreeCheck.perft(&kiwipete, 3), 97848);
+    }
+}
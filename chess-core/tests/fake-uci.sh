@@ -0,0 +1,56 @@
+#!/bin/sh
+# A scripted stand-in for a UCI engine, used by tests/uci.rs.
+#
+# It "plays" the Scholar's mate: whatever the position, it answers with the
+# next move of the script, counted by the moves in the last `position`.
+# The first argument selects a misbehaviour:
+#   normal  answer every `go` right away
+#   slow    only answer `go` once told to `stop`
+#   hang    never answer `go`, not even after `stop`
+#   crash   exit as soon as a search is requested
+
+mode="${1:-normal}"
+script="e2e4 e7e5 f1c4 b8c6 d1h5 g8f6 h5f7"
+played=0
+
+bestmove() {
+    set -- $script
+    shift "$played"
+    echo "info depth 1 seldepth 1 score cp 25 nodes 20 nps 20000 time 1 pv $1"
+    echo "info string scripted reply"
+    echo "bestmove $1"
+}
+
+while IFS= read -r line; do
+    case "$line" in
+        uci)
+            echo "id name Fake UCI"
+            echo "id author chess-core tests"
+            echo "option name Hash type spin default 16 min 1 max 1024"
+            echo "option name Skill Level type spin default 20 min 0 max 20"
+            echo "uciok"
+            ;;
+        isready)
+            echo "readyok"
+            ;;
+        position*)
+            set -- ${line#*moves}
+            case "$line" in
+                *moves*) played=$# ;;
+                *) played=0 ;;
+            esac
+            ;;
+        go*)
+            case "$mode" in
+                crash) exit 3 ;;
+                normal) bestmove ;;
+            esac
+            ;;
+        stop)
+            [ "$mode" = slow ] && bestmove
+            ;;
+        quit)
+            exit 0
+            ;;
+    esac
+done
@@ -0,0 +1,173 @@
+// Short matches between the built-in engine and scripted UCI engines.
+
+use chess_core::engine::xboard::Level;
+use chess_core::engine::SearchLimits;
+use chess_core::position::Position;
+use chess_core::tournament::openings::parse_suite;
+use chess_core::tournament::stats::{Sprt, SprtStatus};
+use chess_core::tournament::{run_match, EngineConfig, MatchConfig, Termination};
+use chess_core::types::Color;
+use std::time::Duration;
+
+const FAKE_ENGINE: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fake-uci.sh");
+
+fn quick_match() -> MatchConfig {
+    MatchConfig {
+        first: EngineConfig::internal("first"),
+        second: EngineConfig::internal("second"),
+        limits: SearchLimits::depth(1),
+        max_plies: Some(40),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn plays_each_opening_with_both_colors() {
+    let config = MatchConfig {
+        openings: parse_suite(
+            "rnbqkbnr/pppp1ppp/8/4p3/4P3/8/PPPP1PPP/RNBQKBNR w KQkq - id \"Open game\";\n\
+             rnbqkbnr/ppp1pppp/8/3p4/3P4/8/PPP1PPPP/RNBQKBNR w KQkq - 0 2\n",
+        )
+        .unwrap(),
+        concurrency: 2,
+        ..quick_match()
+    };
+    let mut seen = 0;
+    let report = run_match(&config, |_, tally| {
+        seen += 1;
+        assert_eq!(tally.games(), seen);
+    })
+    .unwrap();
+    assert_eq!(report.games.len(), 4);
+    assert_eq!(report.tally.games(), 4);
+    assert_eq!(report.sprt, None);
+
+    for (i, game) in report.games.iter().enumerate() {
+        assert_eq!(game.index, i);
+        assert_eq!(game.opening, i / 2);
+        assert_eq!(game.first_is_white, i % 2 == 0);
+        let (white, black) = if game.first_is_white {
+            ("first", "second")
+        } else {
+            ("second", "first")
+        };
+        assert_eq!(game.pgn.tag("White"), Some(white));
+        assert_eq!(game.pgn.tag("Black"), Some(black));
+        assert_eq!(game.pgn.result, game.termination.result());
+        assert_eq!(game.pgn.start, config.openings[game.opening].position);
+        assert!(game.pgn.moves.len() <= 40);
+
+        // The movetext replays from the FEN tag.
+        let text = game.pgn.to_string();
+        let fen = text
+            .lines()
+            .find_map(|l| l.strip_prefix("[FEN \""))
+            .unwrap()
+            .trim_end_matches("\"]");
+        let mut pos = Position::from_fen(fen).unwrap();
+        let movetext = text.split("\n\n").nth(1).unwrap().replace('\n', " ");
+        let mut replayed = 0;
+        for token in movetext.split_whitespace() {
+            if token.ends_with('.') || token.starts_with('{') || token.ends_with('}') {
+                continue;
+            }
+            if let Ok(mv) = pos.parse_san(token) {
+                pos.make_move(&mv);
+                replayed += 1;
+            }
+        }
+        assert_eq!(replayed, game.pgn.moves.len());
+    }
+    assert_eq!(report.games[0].pgn.tag("Opening"), Some("Open game"));
+}
+
+#[test]
+fn flags_fall_on_an_empty_clock() {
+    let config = MatchConfig {
+        level: Some(Level {
+            moves_per_session: 0,
+            base: Duration::from_millis(1),
+            increment: Duration::ZERO,
+        }),
+        limits: SearchLimits::default(),
+        ..quick_match()
+    };
+    let report = run_match(&config, |_, _| {}).unwrap();
+    for game in &report.games {
+        assert_eq!(
+            game.termination,
+            Termination::TimeForfeit {
+                loser: Color::White
+            }
+        );
+        assert_eq!(game.pgn.tag("Termination"), Some("time forfeit"));
+        assert_eq!(game.pgn.tag("TimeControl"), Some("0.001"));
+    }
+    assert_eq!((report.tally.wins, report.tally.losses), (1, 1));
+}
+
+#[test]
+fn clocks_tick_with_increments() {
+    let config = MatchConfig {
+        level: Some(Level {
+            moves_per_session: 0,
+            base: Duration::from_secs(1),
+            increment: Duration::from_millis(50),
+        }),
+        limits: SearchLimits::default(),
+        max_plies: Some(16),
+        ..quick_match()
+    };
+    let report = run_match(&config, |_, _| {}).unwrap();
+    for game in &report.games {
+        assert_eq!(game.termination, Termination::MoveLimit);
+        assert_eq!(game.pgn.moves.len(), 16);
+        assert_eq!(game.pgn.tag("TimeControl"), Some("1+0.05"));
+    }
+}
+
+#[test]
+fn crashing_engines_forfeit_and_are_restarted() {
+    let config = MatchConfig {
+        second: EngineConfig::uci("crashy", FAKE_ENGINE).with_arg("crash"),
+        rounds: 2,
+        ..quick_match()
+    };
+    let report = run_match(&config, |_, _| {}).unwrap();
+    assert_eq!(report.games.len(), 4);
+    assert_eq!(report.tally.wins, 4);
+    for game in &report.games {
+        let Termination::Forfeit { loser, reason } = &game.termination else {
+            panic!("unexpected {:?}", game.termination);
+        };
+        let crashy = if game.first_is_white {
+            Color::Black
+        } else {
+            Color::White
+        };
+        assert_eq!(*loser, crashy);
+        assert!(reason.contains("exited unexpectedly"), "{reason}");
+    }
+}
+
+#[test]
+fn uci_options_are_checked_before_the_first_game() {
+    let config = MatchConfig {
+        second: EngineConfig::uci("fake", FAKE_ENGINE).with_option("Threads", "4"),
+        ..quick_match()
+    };
+    assert!(run_match(&config, |_, _| {}).is_err());
+}
+
+#[test]
+fn sprt_ends_the_match_early() {
+    let config = MatchConfig {
+        second: EngineConfig::uci("crashy", FAKE_ENGINE).with_arg("crash"),
+        rounds: 50,
+        sprt: Some(Sprt::new(0.0, 10.0)),
+        ..quick_match()
+    };
+    let report = run_match(&config, |_, _| {}).unwrap();
+    assert_eq!(report.sprt, Some(SprtStatus::AcceptH1));
+    assert!(report.games.len() < config.total_games());
+}
@@ -0,0 +1,148 @@
+// Drives `UciEngine` against tests/fake-uci.sh, a shell script that answers
+// like a UCI engine would and can be told to misbehave.
+
+use chess_core::engine::SearchLimits;
+use chess_core::position::Outcome;
+use chess_core::types::Color;
+use chess_core::uci::{Score, UciEngine};
+use chess_core::GameMaster;
+use std::time::Duration;
+
+const FAKE_ENGINE: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fake-uci.sh");
+
+fn fake_engine(mode: &str) -> UciEngine {
+    UciEngine::spawn(FAKE_ENGINE, &[mode]).expect("failed to start fake engine")
+}
+
+#[test]
+fn handshake_reads_id_and_options() {
+    let mut engine = fake_engine("normal");
+    assert_eq!(engine.name.as_deref(), Some("Fake UCI"));
+    assert_eq!(engine.author.as_deref(), Some("chess-core tests"));
+    let names: Vec<_> = engine.options.iter().map(|o| o.name.as_str()).collect();
+    assert_eq!(names, ["Hash", "Skill Level"]);
+    assert!(engine.options[0].spec.starts_with("type spin"));
+
+    engine.set_option("Skill Level", "3").unwrap();
+    assert!(engine.set_option("Contempt", "10").is_err());
+    engine.new_game().unwrap();
+    engine.quit().unwrap();
+}
+
+#[test]
+fn engine_plays_a_whole_game_in_the_game_master() {
+    let mut gm = GameMaster::new();
+    let game_id = gm.create_game().unwrap();
+    let mut engine = fake_engine("normal");
+    engine.new_game().unwrap();
+
+    let limits = SearchLimits::depth(1);
+    let mut played = Vec::new();
+    while !gm.request_game_state(game_id).unwrap().game.finished {
+        let mv = gm.play_engine_move(game_id, &mut engine, &limits).unwrap();
+        played.push(mv.to_string());
+    }
+    assert_eq!(
+        played,
+        ["e2e4", "e7e5", "f1c4", "b8c6", "d1h5", "g8f6", "h5f7"]
+    );
+
+    let chess = gm.request_game_state(game_id).unwrap();
+    assert_eq!(
+        chess.game.hist.outcome(),
+        Some(Outcome::Checkmate {
+            winner: Color::White
+        })
+    );
+    // Finished games refuse further moves, whoever suggests them.
+    assert!(gm.play_engine_move(game_id, &mut engine, &limits).is_err());
+}
+
+#[test]
+fn analyse_collects_info_lines() {
+    let mut gm = GameMaster::new();
+    let game_id = gm.create_game().unwrap();
+    let mut engine = fake_engine("normal");
+
+    let reply = gm
+        .analyse(game_id, &mut engine, &SearchLimits::depth(1))
+        .unwrap();
+    let pos = gm.request_position(game_id).unwrap();
+    assert_eq!(reply.best, Some(pos.parse_uci("e2e4").unwrap()));
+    assert_eq!(reply.infos.len(), 2);
+    assert_eq!(reply.infos[1].string.as_deref(), Some("scripted reply"));
+
+    let lines = reply.lines();
+    assert_eq!(lines.len(), 1);
+    assert_eq!(lines[0].depth, Some(1));
+    assert_eq!(lines[0].score, Some(Score::Centipawns(25)));
+    assert_eq!(lines[0].time, Some(Duration::from_millis(1)));
+    assert_eq!(lines[0].pv, reply.best.into_iter().collect::<Vec<_>>());
+    // Analysis leaves the game alone.
+    assert_eq!(gm.request_position(game_id).unwrap(), pos);
+}
+
+#[test]
+fn searches_without_a_limit_are_refused() {
+    let mut gm = GameMaster::new();
+    let game_id = gm.create_game().unwrap();
+    let mut engine = fake_engine("normal");
+    assert!(gm
+        .analyse(game_id, &mut engine, &SearchLimits::default())
+        .is_err());
+    assert!(engine.is_alive());
+}
+
+#[test]
+fn slow_engine_is_stopped_after_the_timeout() {
+    let mut gm = GameMaster::new();
+    let game_id = gm.create_game().unwrap();
+    let mut engine = fake_engine("slow");
+    engine.set_timeout(Duration::from_millis(200));
+
+    let limits = SearchLimits::movetime(Duration::from_millis(50));
+    let mv = gm.play_engine_move(game_id, &mut engine, &limits).unwrap();
+    assert_eq!(mv.to_string(), "e2e4");
+    assert!(engine.is_alive());
+}
+
+#[test]
+fn hanging_engine_is_killed() {
+    let mut gm = GameMaster::new();
+    let game_id = gm.create_game().unwrap();
+    let mut engine = fake_engine("hang");
+    engine.set_timeout(Duration::from_millis(200));
+
+    let err = gm
+        .play_engine_move(game_id, &mut engine, &SearchLimits::depth(1))
+        .unwrap_err();
+    assert!(err.to_string().contains("timed out"), "{err}");
+    assert!(!engine.is_alive());
+    // Nothing was played.
+    assert!(gm
+        .request_game_state(game_id)
+        .unwrap()
+        .game
+        .hist
+        .actions()
+        .is_empty());
+}
+
+#[test]
+fn crashing_engine_is_reported() {
+    let mut gm = GameMaster::new();
+    let game_id = gm.create_game().unwrap();
+    let mut engine = fake_engine("crash");
+
+    let err = gm
+        .play_engine_move(game_id, &mut engine, &SearchLimits::depth(1))
+        .unwrap_err();
+    assert!(err.to_string().contains("exited unexpectedly"), "{err}");
+    assert!(!engine.is_alive());
+}
+
+#[test]
+fn missing_engine_fails_to_spawn() {
+    let missing = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/no-such-engine");
+    assert!(UciEngine::spawn(missing, &[]).is_err());
+}
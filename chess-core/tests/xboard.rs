@@ -0,0 +1,163 @@
+// Drives the `chess-engine` binary through its stdin/stdout the way an
+// xboard-compatible interface would.
+
+use chess_core::position::Position;
+use std::io::{BufRead, BufReader, Write};
+use std::process::{Child, ChildStdin, Command, Stdio};
+use std::sync::mpsc::{self, Receiver};
+use std::thread;
+use std::time::Duration;
+
+struct EngineProcess {
+    child: Child,
+    stdin: ChildStdin,
+    lines: Receiver<String>,
+}
+
+impl EngineProcess {
+    fn spawn() -> Self {
+        let mut child = Command::new(env!("CARGO_BIN_EXE_chess-engine"))
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .expect("failed to start chess-engine");
+        let stdin = child.stdin.take().unwrap();
+        let stdout = child.stdout.take().unwrap();
+        let (tx, lines) = mpsc::channel();
+        thread::spawn(move || {
+            for line in BufReader::new(stdout).lines() {
+                let Ok(line) = line else { break };
+                if tx.send(line).is_err() {
+                    break;
+                }
+            }
+        });
+        Self {
+            child,
+            stdin,
+            lines,
+        }
+    }
+
+    fn send(&mut self, cmd: &str) {
+        writeln!(self.stdin, "{cmd}").unwrap();
+        self.stdin.flush().unwrap();
+    }
+
+    fn recv(&self) -> String {
+        self.lines
+            .recv_timeout(Duration::from_secs(30))
+            .expect("engine went silent")
+    }
+
+    // Reads lines until one satisfies `pred`, returning it.
+    fn expect(&self, pred: impl Fn(&str) -> bool) -> String {
+        loop {
+            let line = self.recv();
+            if pred(&line) {
+                return line;
+            }
+        }
+    }
+}
+
+impl Drop for EngineProcess {
+    fn drop(&mut self) {
+        let _ = writeln!(self.stdin, "quit");
+        let _ = self.stdin.flush();
+        let _ = self.child.wait();
+    }
+}
+
+fn is_result(line: &str) -> bool {
+    ["1-0", "0-1", "1/2-1/2"]
+        .iter()
+        .any(|r| line.starts_with(&format!("{r} {{")))
+}
+
+#[test]
+fn reports_features_and_answers_ping() {
+    let mut e = EngineProcess::spawn();
+    e.send("xboard");
+    e.send("protover 2");
+    let features = e.expect(|l| l.contains("myname="));
+    for flag in ["usermove=1", "setboard=1", "ping=1", "time=1", "sigint=0"] {
+        assert!(features.contains(flag), "missing {flag} in {features}");
+    }
+    e.expect(|l| l == "feature done=1");
+    e.send("ping 7");
+    assert_eq!(e.recv(), "pong 7");
+}
+
+#[test]
+fn plays_a_full_game_against_a_scripted_opponent() {
+    let mut e = EngineProcess::spawn();
+    e.send("xboard");
+    e.send("protover 2");
+    e.expect(|l| l == "feature done=1");
+    e.send("new");
+    e.send("sd 3");
+    e.send("post");
+    e.send("level 0 5 0");
+
+    // We play white by always picking the first legal move, which the
+    // engine (black) should punish.
+    let mut pos = Position::startpos();
+    let result = loop {
+        assert!(pos.fullmoves < 300, "game did not finish");
+        let ours = pos.legal_moves()[0];
+        e.send("time 30000");
+        e.send("otim 30000");
+        e.send(&format!("usermove {}", pos.move_to_uci(&ours)));
+        pos.play(&ours).unwrap();
+        let line = e.expect(|l| l.starts_with("move ") || is_result(l));
+        if is_result(&line) {
+            break line;
+        }
+        let theirs = pos.parse_uci(&line["move ".len()..]).unwrap();
+        pos.play(&theirs).unwrap();
+        if pos.outcome().is_some() {
+            break e.expect(is_result);
+        }
+    };
+    assert_eq!(result, "0-1 {Black mates}");
+    assert!(pos.is_checkmate());
+}
+
+#[test]
+fn force_mode_undo_setboard_and_go() {
+    let mut e = EngineProcess::spawn();
+    e.send("xboard");
+    e.send("protover 2");
+    e.expect(|l| l == "feature done=1");
+    e.send("new");
+    e.send("force");
+    for mv in ["f2f3", "e7e5", "g2g4"] {
+        e.send(&format!("usermove {mv}"));
+    }
+    e.send("usermove e1e3");
+    assert_eq!(e.recv(), "Illegal move: e1e3");
+    e.send("usermove d8h4");
+    assert_eq!(e.recv(), "0-1 {Black mates}");
+    e.send("result 0-1 {Black mates}");
+
+    // Back up two plies and let the engine find the mate itself.
+    e.send("setboard rnbqkbnr/pppp1ppp/8/4p3/6P1/5P2/PPPPP2P/RNBQKBNR b KQkq g3 0 2");
+    e.send("st 1");
+    e.send("go");
+    assert_eq!(e.recv(), "move d8h4");
+    assert_eq!(e.recv(), "0-1 {Black mates}");
+
+    e.send("new");
+    e.send("force");
+    e.send("usermove e2e4");
+    e.send("usermove e7e5");
+    e.send("remove");
+    e.send("sd 1");
+    e.send("go");
+    let reply = e.expect(|l| l.starts_with("move "));
+    let mut pos = Position::startpos();
+    assert!(pos.parse_uci(&reply["move ".len()..]).is_ok());
+    pos.play(&pos.parse_uci(&reply["move ".len()..]).unwrap())
+        .unwrap();
+}
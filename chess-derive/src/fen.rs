@@ -0,0 +1,254 @@
+// FEN records checked while a macro expands, with the same rules as
+// `Position::from_fen` and a few more: each side has one king, no pawn
+// stands on a back rank, and an en passant square lies behind a pawn that
+// could just have made its double step. An error says where it lies, so
+// that `board!` can point at the token responsible.
+
+use crate::setup::Men;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Blame {
+    // A rank of the placement, counted from the top (rank 8 is 0), at the
+    // given file; the file may lie past the edge of the board.
+    At { row: usize, file: usize },
+    // A rank of the placement as a whole, counted from the top.
+    Row(usize),
+    // A field after the placement, 1 for the side to move.
+    Field(usize),
+    Whole,
+}
+
+#[derive(Debug)]
+pub(crate) struct FenError {
+    pub blame: Blame,
+    pub reason: String,
+}
+
+fn fail<T>(blame: Blame, reason: impl Into<String>) -> Result<T, FenError> {
+    Err(FenError {
+        blame,
+        reason: reason.into(),
+    })
+}
+
+fn square_name(sq: usize) -> String {
+    format!("{}{}", (b'a' + (sq % 8) as u8) as char, sq / 8 + 1)
+}
+
+fn at(sq: usize) -> Blame {
+    Blame::At {
+        row: 7 - sq / 8,
+        file: sq % 8,
+    }
+}
+
+// The piece placement: eight ranks of eight files, top rank first.
+pub(crate) fn placement(text: &str) -> Result<Men, FenError> {
+    let rows: Vec<&str> = text.split('/').collect();
+    if rows.len() != 8 {
+        let blame = match rows.len() {
+            n if n > 8 => Blame::Row(8),
+            _ => Blame::Whole,
+        };
+        return fail(blame, format!("expected 8 ranks, found {}", rows.len()));
+    }
+    let mut men = Men::new();
+    for (row, text) in rows.iter().enumerate() {
+        let rank = 7 - row;
+        let mut file = 0;
+        for c in text.chars() {
+            match c {
+                '1'..='8' => file += c as usize - '0' as usize,
+                'p' | 'n' | 'b' | 'r' | 'q' | 'k' | 'P' | 'N' | 'B' | 'R' | 'Q' | 'K' => {
+                    if file < 8 {
+                        men.push((rank * 8 + file, c));
+                    }
+                    file += 1;
+                }
+                _ => return fail(Blame::At { row, file }, format!("unknown piece {c:?}")),
+            }
+            if file > 8 {
+                return fail(
+                    Blame::At { row, file: 8 },
+                    format!("rank {} runs past the h file", rank + 1),
+                );
+            }
+        }
+        if file != 8 {
+            return fail(
+                Blame::Row(row),
+                format!("rank {} has {file} files instead of 8", rank + 1),
+            );
+        }
+    }
+    men.sort();
+    Ok(men)
+}
+
+// The placement with digits for runs of empty squares, as FEN writes it.
+pub(crate) fn write_placement(men: &Men) -> String {
+    let mut out = String::new();
+    for rank in (0..8).rev() {
+        let mut empty = 0;
+        for file in 0..8 {
+            match men.iter().find(|&&(sq, _)| sq == rank * 8 + file) {
+                Some(&(_, c)) => {
+                    if empty > 0 {
+                        out.push_str(&empty.to_string());
+                        empty = 0;
+                    }
+                    out.push(c);
+                }
+                None => empty += 1,
+            }
+        }
+        if empty > 0 {
+            out.push_str(&empty.to_string());
+        }
+        if rank > 0 {
+            out.push('/');
+        }
+    }
+    out
+}
+
+fn piece_on(men: &Men, sq: usize) -> Option<char> {
+    men.iter().find(|&&(at, _)| at == sq).map(|&(_, c)| c)
+}
+
+fn check_pieces(men: &Men) -> Result<(), FenError> {
+    if let Some(&(sq, c)) = men
+        .iter()
+        .find(|&&(sq, c)| c.eq_ignore_ascii_case(&'p') && !(8..56).contains(&sq))
+    {
+        return fail(
+            at(sq),
+            format!("pawn {c} on {}, a back rank", square_name(sq)),
+        );
+    }
+    for (side, king) in [("White", 'K'), ("Black", 'k')] {
+        let kings: Vec<usize> = men
+            .iter()
+            .filter(|&&(_, c)| c == king)
+            .map(|&(sq, _)| sq)
+            .collect();
+        match kings.as_slice() {
+            [_] => {}
+            [] => return fail(Blame::Whole, format!("{side} has no king")),
+            [_, second, ..] => return fail(at(*second), format!("{side} has a second king")),
+        }
+    }
+    Ok(())
+}
+
+// A right such as `K`, `q` or the file of the castling rook, `H`.
+fn check_castling(men: &Men, rights: &str) -> Result<(), FenError> {
+    if rights == "-" {
+        return Ok(());
+    }
+    for c in rights.chars() {
+        let (king, rook, back) = match c.is_ascii_uppercase() {
+            true => ('K', 'R', 0),
+            false => ('k', 'r', 56),
+        };
+        let Some(king_sq) = (back..back + 8).find(|&sq| piece_on(men, sq) == Some(king)) else {
+            return fail(
+                Blame::Field(2),
+                format!("{c}: the king is not on its back rank"),
+            );
+        };
+        let king_file = king_sq - back;
+        let mut files: Box<dyn Iterator<Item = usize>> = match c.to_ascii_lowercase() {
+            'k' => Box::new((king_file + 1..8).rev()),
+            'q' => Box::new(0..king_file),
+            file @ 'a'..='h' => Box::new(std::iter::once((file as u8 - b'a') as usize)),
+            _ => return fail(Blame::Field(2), format!("unknown castling right {c:?}")),
+        };
+        if !files.any(|file| piece_on(men, back + file) == Some(rook)) {
+            return fail(
+                Blame::Field(2),
+                format!("{c}: there is no rook to castle with"),
+            );
+        }
+    }
+    Ok(())
+}
+
+// The square behind a pawn of the side not to move that stands on its
+// fourth rank.
+fn check_en_passant(men: &Men, white_to_move: bool, ep: &str) -> Result<(), FenError> {
+    if ep == "-" {
+        return Ok(());
+    }
+    let blame = Blame::Field(3);
+    let sq = match ep.as_bytes() {
+        &[file @ b'a'..=b'h', rank @ b'1'..=b'8'] => {
+            (rank - b'1') as usize * 8 + (file - b'a') as usize
+        }
+        _ => return fail(blame, format!("{ep:?} is not a square")),
+    };
+    let (rank, pawn, step): (usize, char, isize) = match white_to_move {
+        true => (5, 'p', -8),
+        false => (2, 'P', 8),
+    };
+    if sq / 8 != rank || piece_on(men, (sq as isize + step) as usize) != Some(pawn) {
+        return fail(blame, format!("no pawn can be taken en passant on {ep}"));
+    }
+    Ok(())
+}
+
+// Checks a whole record and writes it back the way FEN is usually written.
+pub(crate) fn check(fen: &str) -> Result<String, FenError> {
+    let fields: Vec<&str> = fen.split_whitespace().collect();
+    let Some(&placement_text) = fields.first() else {
+        return fail(Blame::Whole, "the FEN is empty");
+    };
+    let men = placement(placement_text)?;
+    check_pieces(&men)?;
+
+    let turn = fields.get(1).copied().unwrap_or("w");
+    if !matches!(turn, "w" | "b") {
+        return fail(Blame::Field(1), format!("{turn:?} is neither w nor b"));
+    }
+    let castling = fields.get(2).copied().unwrap_or("-");
+    check_castling(&men, castling)?;
+    let ep = fields.get(3).copied().unwrap_or("-");
+    check_en_passant(&men, turn == "w", ep)?;
+
+    let mut out = format!("{} {turn} {castling} {ep}", write_placement(&men));
+    let mut rest = fields.iter().enumerate().skip(4).peekable();
+    if let Some((i, checks)) = rest.next_if(|(_, field)| field.contains('+')) {
+        let counts = checks
+            .split_once('+')
+            .filter(|(w, b)| w.parse::<u8>().is_ok() && b.parse::<u8>().is_ok());
+        if counts.is_none() {
+            return fail(
+                Blame::Field(i),
+                format!("{checks:?} is not a count of checks like 3+3"),
+            );
+        }
+        out = format!("{out} {checks}");
+    }
+    for (name, default) in [("halfmove clock", "0"), ("fullmove number", "1")] {
+        let value = match rest.next() {
+            Some((i, field)) => {
+                if field.parse::<u32>().is_err() {
+                    return fail(
+                        Blame::Field(i),
+                        format!("the {name} {field:?} is not a number"),
+                    );
+                }
+                *field
+            }
+            None => default,
+        };
+        out = format!("{out} {value}");
+    }
+    if let Some((i, field)) = rest.next() {
+        return fail(
+            Blame::Field(i),
+            format!("unexpected {field:?} after the move number"),
+        );
+    }
+    Ok(out)
+}
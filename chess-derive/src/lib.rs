@@ -1,7 +1,10 @@
-use proc_macro::TokenStream;
+use proc_macro::{Span, TokenStream, TokenTree};
 use quote::quote;
 use syn;
 
+mod fen;
+mod setup;
+
 #[proc_macro_derive(ChessFactory)]
 pub fn chess_factory_derive(input: TokenStream) -> TokenStream {
     let it = syn::parse(input).unwrap();
@@ -16,16 +19,212 @@ fn impl_chess_factory(input: &syn::DeriveInput) -> TokenStream {
     gen.into()
 }
 
-#[proc_macro_derive(StandardChess)]
+// The classical setup unless a `#[chess(...)]` attribute gives another:
+//
+// - `fen = "..."` takes the piece placement of a FEN record;
+// - `layout = "Ke1 Qd1 ... ke8"` lists every piece with its square;
+// - `variant = "chess960", seed = 42` picks one of the 960 Fischer random
+//   setups, the same one for the same seed.
+//
+// The position is checked while the macro expands: each side needs sixteen
+// pieces with one king, no square may be given twice and no pawn may stand
+// on a back rank. Pieces are numbered in board order from a1, White's from 1
+// and Black's from -16, just like the classical ones.
+#[proc_macro_derive(StandardChess, attributes(chess))]
 pub fn standard_chess_derive(input: TokenStream) -> TokenStream {
     let it = syn::parse(input).unwrap();
     impl_standard_chess(&it)
 }
 
 fn impl_standard_chess(input: &syn::DeriveInput) -> TokenStream {
+    let name = &input.ident;
+    let men = match setup::parse(&input.attrs) {
+        Ok(Some(men)) => men,
+        Ok(None) => {
+            let gen = quote! {
+                impl StandardChess for #name {}
+            };
+            return gen.into();
+        }
+        Err(e) => return e.to_compile_error().into(),
+    };
+    let pieces = |white: bool| {
+        let side: Vec<_> = men
+            .iter()
+            .filter(|(_, c)| c.is_ascii_uppercase() == white)
+            .collect();
+        let count = side.len() as i64;
+        side.into_iter().enumerate().map(move |(i, &(loc, c))| {
+            let ty = match c.to_ascii_lowercase() {
+                'p' => "pawn",
+                'n' => "knight",
+                'b' => "bishop",
+                'r' => "rook",
+                'q' => "queen",
+                _ => "king",
+            };
+            let (color, id) = match white {
+                true => ("white", i as i64 + 1),
+                false => ("black", i as i64 - count),
+            };
+            let factory = syn::Ident::new(&format!("{ty}_{color}"), name.span());
+            let id = syn::LitInt::new(&id.to_string(), name.span());
+            quote! { <Self as ::chess_core::traits::ChessFactory>::#factory(#loc, #id) }
+        })
+    };
+    let (white, black) = (pieces(true), pieces(false));
+    let gen = quote! {
+        impl StandardChess for #name {
+            fn gen_std_white() -> [::chess_core::types::Piece; 16] {
+                [#(#white),*]
+            }
+            fn gen_std_black() -> [::chess_core::types::Piece; 16] {
+                [#(#black),*]
+            }
+        }
+    };
+    gen.into()
+}
+
+#[proc_macro_derive(FairyChess)]
+pub fn fairy_chess_derive(input: TokenStream) -> TokenStream {
+    let it = syn::parse(input).unwrap();
+    impl_fairy_chess(&it)
+}
+
+fn impl_fairy_chess(input: &syn::DeriveInput) -> TokenStream {
     let name = &input.ident;
     let gen = quote! {
-        impl StandardChess for #name {}
+        impl FairyChess for #name {}
     };
     gen.into()
 }
+
+// A game set up from a FEN record, checked while compiling:
+// `fen!("8/8/8/4k3/8/8/4P3/4K3 w - - 0 1")`. Fields after the placement may
+// be left out and default to `w - - 0 1`.
+#[proc_macro]
+pub fn fen(input: TokenStream) -> TokenStream {
+    let lit = syn::parse_macro_input!(input as syn::LitStr);
+    match fen::check(&lit.value()) {
+        Ok(fen) => expand_game(&fen),
+        Err(e) => syn::Error::new(lit.span(), format!("invalid FEN: {}", e.reason))
+            .to_compile_error()
+            .into(),
+    }
+}
+
+// A game set up from a diagram, White at the bottom:
+//
+// ```text
+// board! {
+//     r n b q k b n r /
+//     p p p p p p p p /
+//     . . . . . . . . /
+//     . . . . . . . . /
+//     . . . . P . . . /
+//     . . . . . . . . /
+//     P P P P . P P P /
+//     R N B Q K B N R ; b KQkq e3 0 1
+// }
+// ```
+//
+// Squares are FEN letters, digits for runs of empty squares, or `.` (or `_`)
+// for one; letters and digits may also be run together as in `rnbqkbnr` or
+// `4P3`. The fields after `;` are those of FEN, one token each. An error
+// points at the square or field it is about.
+#[proc_macro]
+pub fn board(input: TokenStream) -> TokenStream {
+    match read_board(input) {
+        Ok(fen) => expand_game(&fen),
+        Err(e) => e.to_compile_error().into(),
+    }
+}
+
+fn expand_game(fen: &str) -> TokenStream {
+    let gen = quote! {
+        ::chess_core::ChessGame::from_position(
+            0,
+            ::chess_core::position::Position::from_fen(#fen)
+                .expect("the FEN was checked while compiling"),
+        )
+        .expect("a checked FEN sets up a game")
+        .game
+    };
+    gen.into()
+}
+
+// Turns the diagram into FEN, remembering which token stands for which
+// squares so that errors can be reported there.
+fn read_board(input: TokenStream) -> syn::Result<String> {
+    let mut rows = vec![String::new()];
+    // For each rank, the first file of each of its tokens.
+    let mut tokens: Vec<Vec<(usize, Span)>> = vec![Vec::new()];
+    let mut fields: Vec<(String, Span)> = Vec::new();
+    let (mut in_fields, mut joined) = (false, false);
+    for tree in input {
+        let span = tree.span();
+        let text = match tree {
+            TokenTree::Group(_) => return Err(syn::Error::new(span.into(), "expected a square")),
+            TokenTree::Punct(punct) => punct.as_char().to_string(),
+            tree => tree.to_string(),
+        };
+        if in_fields {
+            // `3+2`, the checks of three-check, comes as three tokens.
+            match fields.last_mut() {
+                Some(last) if text == "+" || joined => {
+                    last.0.push_str(&text);
+                    joined = text == "+";
+                }
+                _ => fields.push((text, span)),
+            }
+            continue;
+        }
+        match text.as_str() {
+            "/" => {
+                rows.push(String::new());
+                tokens.push(Vec::new());
+            }
+            ";" => in_fields = true,
+            _ => {
+                let row = rows.last_mut().expect("there is always a rank");
+                let file = row
+                    .chars()
+                    .map(|c| c.to_digit(10).unwrap_or(1) as usize)
+                    .sum();
+                tokens
+                    .last_mut()
+                    .expect("there is always a rank")
+                    .push((file, span));
+                row.extend(text.chars().map(|c| match c {
+                    '.' | '_' => '1',
+                    c => c,
+                }));
+            }
+        }
+    }
+
+    let mut fen = rows.join("/");
+    for (field, _) in &fields {
+        fen.push(' ');
+        fen.push_str(field);
+    }
+    fen::check(&fen).map_err(|e| {
+        let first = |row: usize| tokens.get(row).and_then(|row| row.first());
+        let span = match e.blame {
+            fen::Blame::At { row, file } => tokens[row]
+                .iter()
+                .rev()
+                .find(|&&(start, _)| start <= file)
+                .or_else(|| first(row))
+                .map(|&(_, span)| span),
+            fen::Blame::Row(row) => first(row).map(|&(_, span)| span),
+            fen::Blame::Field(i) => fields.get(i - 1).map(|&(_, span)| span),
+            fen::Blame::Whole => None,
+        };
+        syn::Error::new(
+            span.unwrap_or_else(Span::call_site).into(),
+            format!("invalid position: {}", e.reason),
+        )
+    })
+}
@@ -0,0 +1,208 @@
+// Starting positions for `#[derive(StandardChess)]`, worked out while the
+// macro expands. A setup is a list of (square, FEN letter) pairs with squares
+// numbered from a1 = 0 to h8 = 63 and uppercase letters for White.
+
+use syn::{Attribute, LitInt, LitStr};
+
+pub(crate) type Men = Vec<(usize, char)>;
+
+const BACK_RANK: &str = "RNBQKBNR";
+
+// The classical setup with `back` as White's first rank, mirrored for Black.
+fn with_back_rank(back: &[char]) -> Men {
+    let mut men = Men::with_capacity(32);
+    for (file, &c) in back.iter().enumerate() {
+        men.push((file, c));
+        men.push((8 + file, 'P'));
+        men.push((48 + file, 'p'));
+        men.push((56 + file, c.to_ascii_lowercase()));
+    }
+    men.sort();
+    men
+}
+
+fn square(name: &str) -> Option<usize> {
+    match name.as_bytes() {
+        &[file @ b'a'..=b'h', rank @ b'1'..=b'8'] => {
+            Some((rank - b'1') as usize * 8 + (file - b'a') as usize)
+        }
+        _ => None,
+    }
+}
+
+fn square_name(sq: usize) -> String {
+    format!("{}{}", (b'a' + (sq % 8) as u8) as char, sq / 8 + 1)
+}
+
+// The piece placement of a FEN record; anything after it is ignored.
+pub(crate) fn from_fen(fen: &str) -> Result<Men, String> {
+    let placement = fen.split_whitespace().next().unwrap_or_default();
+    crate::fen::placement(placement).map_err(|e| e.reason)
+}
+
+// Pieces by square, such as `Ke1 Qd1 Pe2 ke8`: the letter's case gives the
+// color.
+pub(crate) fn from_layout(layout: &str) -> Result<Men, String> {
+    let mut men = Men::new();
+    for entry in layout.split_whitespace() {
+        let mut chars = entry.chars();
+        let piece = chars
+            .next()
+            .filter(|c| "pnbrqkPNBRQK".contains(*c))
+            .ok_or_else(|| format!("{entry:?} does not start with a piece letter"))?;
+        let sq = square(chars.as_str())
+            .ok_or_else(|| format!("{entry:?} does not name a square like Ke1"))?;
+        if let Some(&(_, other)) = men.iter().find(|&&(taken, _)| taken == sq) {
+            return Err(format!(
+                "{} is given twice, to {other} and {piece}",
+                square_name(sq)
+            ));
+        }
+        men.push((sq, piece));
+    }
+    men.sort();
+    Ok(men)
+}
+
+// Chess960 start number `n` in Scharnagl's numbering, where 518 is the
+// classical setup.
+pub(crate) fn chess960(n: u64) -> Men {
+    const KNIGHTS: [(usize, usize); 10] = [
+        (0, 1),
+        (0, 2),
+        (0, 3),
+        (0, 4),
+        (1, 2),
+        (1, 3),
+        (1, 4),
+        (2, 3),
+        (2, 4),
+        (3, 4),
+    ];
+    let n = (n % 960) as usize;
+    let mut back = [' '; 8];
+    back[(n % 4) * 2 + 1] = 'B';
+    back[(n / 4 % 4) * 2] = 'B';
+    let empty = |back: &[char; 8]| -> Vec<usize> { (0..8).filter(|&f| back[f] == ' ').collect() };
+    let queen = empty(&back)[n / 16 % 6];
+    back[queen] = 'Q';
+    let (first, second) = KNIGHTS[n / 96];
+    let free = empty(&back);
+    back[free[first]] = 'N';
+    back[free[second]] = 'N';
+    for (file, piece) in empty(&back).into_iter().zip(['R', 'K', 'R']) {
+        back[file] = piece;
+    }
+    with_back_rank(&back)
+}
+
+// splitmix64, so that nearby seeds give unrelated positions.
+fn mix(seed: u64) -> u64 {
+    let mut z = seed.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+// What `gen_std_white` and `gen_std_black` can hand out: sixteen pieces a
+// side with one king, and no pawn on the first or last rank.
+pub(crate) fn validate(men: &Men) -> Result<(), String> {
+    if let Some(&(sq, c)) = men
+        .iter()
+        .find(|&&(sq, c)| c.eq_ignore_ascii_case(&'p') && !(8..56).contains(&sq))
+    {
+        return Err(format!("pawn {c} on {}, a back rank", square_name(sq)));
+    }
+    for (side, is_side) in [
+        ("White", char::is_ascii_uppercase as fn(&char) -> bool),
+        ("Black", char::is_ascii_lowercase),
+    ] {
+        let pieces: Vec<char> = men.iter().map(|&(_, c)| c).filter(is_side).collect();
+        let count = |letter: char| {
+            pieces
+                .iter()
+                .filter(|c| c.eq_ignore_ascii_case(&letter))
+                .count()
+        };
+        if pieces.len() != 16 {
+            return Err(format!("{side} has {} pieces, not 16", pieces.len()));
+        }
+        if count('k') != 1 {
+            return Err(format!("{side} has {} kings, not 1", count('k')));
+        }
+        if count('p') > 8 {
+            return Err(format!("{side} has {} pawns, more than 8", count('p')));
+        }
+    }
+    Ok(())
+}
+
+// Reads the `#[chess(...)]` attributes. None means the classical setup.
+pub(crate) fn parse(attrs: &[Attribute]) -> syn::Result<Option<Men>> {
+    let mut fen: Option<LitStr> = None;
+    let mut layout: Option<LitStr> = None;
+    let mut variant: Option<LitStr> = None;
+    let mut seed: Option<LitInt> = None;
+    for attr in attrs.iter().filter(|attr| attr.path().is_ident("chess")) {
+        attr.parse_nested_meta(|meta| {
+            let key = meta
+                .path
+                .get_ident()
+                .map(ToString::to_string)
+                .unwrap_or_default();
+            let slot = match key.as_str() {
+                "fen" => &mut fen,
+                "layout" => &mut layout,
+                "variant" => &mut variant,
+                "seed" => {
+                    seed = Some(meta.value()?.parse()?);
+                    return Ok(());
+                }
+                _ => return Err(meta.error("expected `fen`, `layout`, `variant` or `seed`")),
+            };
+            *slot = Some(meta.value()?.parse()?);
+            Ok(())
+        })?;
+    }
+
+    let invalid = |lit: &LitStr, reason: String| {
+        syn::Error::new(lit.span(), format!("invalid starting position: {reason}"))
+    };
+    if let (Some(seed), false) = (&seed, variant.is_some()) {
+        return Err(syn::Error::new(
+            seed.span(),
+            "`seed` needs `variant = \"chess960\"`",
+        ));
+    }
+    let (men, lit) = match (fen, layout, variant) {
+        (None, None, None) => return Ok(None),
+        (Some(fen), None, None) => (from_fen(&fen.value()), fen),
+        (None, Some(layout), None) => (from_layout(&layout.value()), layout),
+        (None, None, Some(variant)) => {
+            let men = match (variant.value().as_str(), &seed) {
+                ("standard", _) => with_back_rank(&BACK_RANK.chars().collect::<Vec<_>>()),
+                ("chess960", Some(seed)) => chess960(mix(seed.base10_parse()?)),
+                ("chess960", None) => {
+                    return Err(syn::Error::new(variant.span(), "chess960 needs a `seed`"))
+                }
+                (other, _) => {
+                    return Err(syn::Error::new(
+                        variant.span(),
+                        format!("unknown variant {other:?}, expected \"standard\" or \"chess960\""),
+                    ))
+                }
+            };
+            (Ok(men), variant)
+        }
+        (fen, layout, variant) => {
+            let second = [fen, layout, variant].into_iter().flatten().nth(1);
+            return Err(syn::Error::new(
+                second.expect("two of them are set").span(),
+                "only one of `fen`, `layout` and `variant` may be given",
+            ));
+        }
+    };
+    let men = men.map_err(|e| invalid(&lit, e))?;
+    validate(&men).map_err(|e| invalid(&lit, e))?;
+    Ok(Some(men))
+}
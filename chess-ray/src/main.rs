@@ -5,15 +5,17 @@ use anyhow::Result;
 use chess_core::{
     self,
     constants::*,
+    fairy::FairyVariant,
     game::{
         self,
         math::{self, XyPair},
     },
+    geometry::{Geometry, Orientation},
     helper,
-    layout::{self, Layout},
+    snapshot::BoardSnapshot,
     msg::{self, PieceId, TileId},
     traits::*,
-    types::{self, Color as COLOR, Tile, Type as TYPE},
+    types::{self, Color as COLOR, Piece, Type as TYPE},
 };
 use raylib::texture::Texture2D;
 use std::{
@@ -29,7 +31,7 @@ use crossbeam_channel::unbounded;
 use crossbeam_utils::thread::scope;
 use image_data as img;
 use raylib::prelude::*;
-use utils::{get_y_from_col, xy_to_row_col, SQUARE_SIZE};
+use utils::square_size;
 
 fn main() -> Result<()> {
     const X_MARGIN: i32 = 312;
@@ -41,7 +43,25 @@ fn main() -> Result<()> {
         d.clear_background(Color::RAYWHITE);
     }
     let mut gm = chess_core::spawn_game_master();
-    let game_id: chess_core::msg::GameId = gm.create_game().unwrap();
+    // An optional board size such as `10x8` shows an empty board of that shape,
+    // and `capablanca` sets up Capablanca chess.
+    let arg = std::env::args().nth(1);
+    let game_id: chess_core::msg::GameId = match arg.as_deref() {
+        Some("capablanca") => gm.create_fairy_game(&FairyVariant::capablanca())?,
+        Some(size) => match size.parse::<Geometry>()? {
+            Geometry::STANDARD => gm.create_game().unwrap(),
+            geometry => gm.create_empty_game(geometry)?,
+        },
+        None => gm.create_game().unwrap(),
+    };
+
+    // At this point, need to query the game master for the current state of the game
+    // so we can build a relation between the XyPairs of tiles and the RayTiles.
+    // The snapshot owns its copy of every piece, so the tiles built from it do
+    // not borrow from the game master.
+    let snapshot: BoardSnapshot = gm.request_snapshot(game_id)?;
+    let geometry = snapshot.geometry;
+    let square = square_size(&geometry);
 
     let is_even = |pos: usize| pos % 2 == 0;
     let is_odd = |pos: usize| !is_even(pos);
@@ -53,23 +73,22 @@ fn main() -> Result<()> {
         }
     };
 
-    let tile_mapping: HashMap<XyPair, Vertices> = {
+    // Pressing F turns the board round, so that either side can sit at the bottom.
+    let mut orientation = Orientation::White;
+    let tile_mapping = |orientation: Orientation| -> HashMap<XyPair, Vertices> {
         let mut it = HashMap::new();
-        for row in 0..8 {
-            for col in 0..8 {
-                let y_lower_bound = Y_MARGIN + col * SQUARE_SIZE;
-                let y_upper_bound = Y_MARGIN + (col + 1) * SQUARE_SIZE;
-                let x_upper_bound = X_MARGIN + (row + 1) * SQUARE_SIZE;
-                let x_lower_bound = X_MARGIN + row * SQUARE_SIZE;
-                let x = row;
-                let y = get_y_from_col(col);
+        for column in 0..geometry.files as i32 {
+            for row in 0..geometry.ranks as i32 {
+                let y_lower_bound = Y_MARGIN + row * square;
+                let y_upper_bound = Y_MARGIN + (row + 1) * square;
+                let x_upper_bound = X_MARGIN + (column + 1) * square;
+                let x_lower_bound = X_MARGIN + column * square;
 
-                // prepare an xypair from the supplied data and make a set of mappings
-                // between the chess tiles' XyPair and the offset bounds
-                let norm_xy = XyPair {
-                    x: x.try_into().unwrap(),
-                    y: y.try_into().unwrap(),
-                };
+                // make a set of mappings between the chess tiles' XyPair and the
+                // offset bounds of wherever the orientation draws them
+                let norm_xy = orientation
+                    .from_screen(&geometry, column as usize, row as usize)
+                    .unwrap();
 
                 // There are four vertices on each of the tiles. Each of the four vertices
                 // is needed correlate the mouse's location with a wrapped tile border.
@@ -86,30 +105,35 @@ fn main() -> Result<()> {
         it
     };
 
-    // At this point, need to query the game master for the current state of the game
-    // so we can build a relation between the XyPairs of tiles and the RayTiles.
-    // It is worth noting that the data field on Layout is a std::collections::BTreeMap of
-    // XyPairs to &Tile data.
-    let layout: Layout = gm.request_game_layout(game_id)?;
-
     // Finally: we bridge the two worlds of raylib and chess_core.
     // This is the main loop.
     use chess_core::types::{Color as COLOR, Type as TYPE};
-    let mut raytiles: Vec<RayTile> = Vec::with_capacity(64);
-    for (xy, vertices) in tile_mapping.iter() {
-        let raw_tile: &Tile = layout.data.get(&xy).unwrap();
+    let mut raytiles: Vec<RayTile> = Vec::with_capacity(geometry.tile_count());
+    let mapping = tile_mapping(orientation);
+    for (tile_id, xy, background, piece) in snapshot.tiles() {
         let _color = tile_color(xy.x as usize, xy.y as usize);
         let _selected = false;
-        let _tile_id: TileId = raw_tile.clone().index;
         let _ = &mut raytiles.push(RayTile::init(
-            raw_tile,
-            xy.clone(),
-            vertices.clone(),
+            tile_id,
+            xy,
+            background,
+            piece,
+            mapping[&xy],
+            square,
             &mut rl,
             &thread,
         ));
     }
     while !&rl.window_should_close() {
+        if rl.is_key_pressed(KeyboardKey::KEY_F) {
+            orientation = orientation.flipped();
+            let mapping = tile_mapping(orientation);
+            for tile in raytiles.iter_mut() {
+                tile.vertices = mapping[&tile.xy];
+            }
+            let mut d = rl.begin_drawing(&thread);
+            d.clear_background(Color::RAYWHITE);
+        }
         {
             let mouse: Vector2 = rl.get_mouse_position();
             for tile in &mut raytiles.iter_mut() {
@@ -139,9 +163,9 @@ fn main() -> Result<()> {
 // This struct is the collection of related data specific to the raylib-specific
 // graphical rectangle.
 //
-// It is loosely coupled to a [`chess_core::types::Tile`]() and is only concerned
-// with dynamic UI interactions, and forwarding intent to the underpinning ChessGame.
-pub struct RayTile<'a> {
+// It is built from a tile of a [`chess_core::snapshot::BoardSnapshot`]() and is only
+// concerned with dynamic UI interactions, and forwarding intent to the underpinning ChessGame.
+pub struct RayTile {
     pub selected: bool,
     pub hovered: bool,
     pub vertices: Vertices,
@@ -150,14 +174,12 @@ pub struct RayTile<'a> {
     pub piece_id: Option<msg::PieceId>,
     pub texture_overlay: Option<Texture2D>,
     pub tile_id: TileId,
-    pub raw_tile: &'a Tile,
 }
 
-impl<'a> RayTile<'a> {
+impl RayTile {
     pub fn new(
         selected: bool,
         hovered: bool,
-        raw_tile: &'a Tile,
         xy: XyPair,
         tile_id: TileId,
         vertices: Vertices,
@@ -173,7 +195,6 @@ impl<'a> RayTile<'a> {
             background_color,
             texture_overlay,
             tile_id,
-            raw_tile,
             piece_id,
         }
     }
@@ -211,56 +232,32 @@ impl<'a> RayTile<'a> {
     }
 
     pub fn init(
-        raw_tile: &'a Tile,
+        tile_id: TileId,
         xy: XyPair,
+        background: types::Background,
+        piece: Option<&Piece>,
         vertices: Vertices,
+        square: i32,
         raylib_handle: &mut RaylibHandle,
         raylib_thread: &RaylibThread,
     ) -> Self {
-        let has_associated_piece = raw_tile.pz.is_some();
-        let color_bg = match raw_tile.color {
+        let color_bg = match background {
             types::Background::Light => Color::WHITE,
             types::Background::Dark => Color::LIGHTGRAY,
         };
-        let tile_id: TileId = raw_tile.index;
-        if has_associated_piece {
-            // Safety: already verified that the `pz` field is not `None`
-            let pz = unsafe { raw_tile.pz.as_ref().unwrap_unchecked() };
-            assert!(pz.weak_count() > 0, "PlayerData already dropped");
-            match (*(unsafe { pz.upgrade().unwrap_unchecked() }))
-                .borrow()
-                .clone()
-            {
-                types::Piece {
-                    id,
-                    color,
-                    ty,
-                    loc: _loc,
-                } => {
-                    let texture = get_piece(color, ty, raylib_handle, raylib_thread);
-                    return Self::new(
-                        /* selected: bool = */ false,
-                        /* hovered: bool = */ false,
-                        /* raw_tile: &'a Tile = */ raw_tile,
-                        /* xy: XyPair = */ xy,
-                        /* tile_id: TileId = */ tile_id,
-                        /* vertices: Vertices = */ vertices,
-                        /* texture_overlay: Option<Texture2D> = */ Some(texture),
-                        /* background_color: Color = */ color_bg,
-                        /* piece_id: Option<msg::PieceId> = */ Some(id),
-                    );
-                }
-            }
-        } else {
-            Self::new(
-                /* selected: bool = */ false, /* hovered: bool = */ false,
-                /* raw_tile: &'a Tile = */ raw_tile, /* xy: XyPair = */ xy,
-                /* tile_id: TileId = */ tile_id, /* vertices: Vertices = */ vertices,
-                /* texture_overlay: Option<Texture2D> = */ None,
-                /* background_color: Color = */ color_bg,
-                /* piece_id: Option<msg::PieceId> = */ None,
-            )
-        }
+        let texture = piece.map(|&Piece { color, ty, .. }| {
+            get_piece(color, ty, square, raylib_handle, raylib_thread)
+        });
+        Self::new(
+            /* selected: bool = */ false,
+            /* hovered: bool = */ false,
+            /* xy: XyPair = */ xy,
+            /* tile_id: TileId = */ tile_id,
+            /* vertices: Vertices = */ vertices,
+            /* texture_overlay: Option<Texture2D> = */ texture,
+            /* background_color: Color = */ color_bg,
+            /* piece_id: Option<msg::PieceId> = */ piece.map(|pz| pz.id),
+        )
     }
 
     pub fn select(&mut self) {
@@ -340,11 +337,9 @@ impl TryFrom<&Vertices> for Rectangle {
 #[test]
 #[allow(non_upper_case_globals)]
 fn click_raytile_toggle_state() {
-    use chess_core::types::Tile;
     const sel: bool = false;
     const tid: chess_core::msg::TileId = 9;
     const bg: Color = Color::WHITE;
-    const tile: Tile = chess_core::types::Tile::light(8, false, false);
     const t2d: Option<raylib::texture::Texture2D> = None;
     let xy: XyPair = (7, 0).into();
     const piece_id: Option<PieceId> = Some(8);
@@ -354,10 +349,10 @@ fn click_raytile_toggle_state() {
             vertices,
             xy,
             selected: sel,
+            hovered: false,
             background_color: bg,
             texture_overlay: t2d,
             tile_id: tid,
-            raw_tile: &tile,
             piece_id,
         };
         assert_eq!(rt.is_selected(), false, "Sanity check failed");
@@ -371,6 +366,7 @@ fn click_raytile_toggle_state() {
 fn get_piece<'a>(
     color: COLOR,
     piece_type: TYPE,
+    square: i32,
     raylib_handle: &mut RaylibHandle,
     raylib_thread: &RaylibThread,
 ) -> Texture2D {
@@ -378,8 +374,11 @@ fn get_piece<'a>(
     // distort the original. I believe this approach makes it possible to
     // maintain different views of an image, but always have the flexibility
     // to restore the original.
-    let mut image = images().get_mut(&(color, piece_type)).unwrap().clone();
-    image.resize(SQUARE_SIZE, SQUARE_SIZE);
+    let mut image = images()
+        .entry((color, piece_type))
+        .or_insert_with(|| fairy_image(color, piece_type))
+        .clone();
+    image.resize(square, square);
     raylib_handle
         .load_texture_from_image(raylib_thread, &image)
         .unwrap()
@@ -406,6 +405,41 @@ fn images() -> &'static mut HashMap<(COLOR, TYPE), raylib::prelude::Image> {
     // Safety: image_map is not null and points to a properly initialized value
     unsafe { &mut *image_map }
 }
+// Fairy pieces have no bundled art. A picture may be dropped in as
+// `assets/fairy-<letter>-<color>.png`; otherwise the piece shows as its
+// letter, in the colors of its side.
+fn fairy_image(color: COLOR, piece_type: TYPE) -> Image {
+    const SIZE: i32 = 240;
+    let TYPE::Fairy(letter) = piece_type else {
+        panic!("Every orthodox piece has an image: {piece_type:?}");
+    };
+    let side = match color {
+        COLOR::White => "white",
+        COLOR::Black => "black",
+    };
+    let path = format!("assets/fairy-{}-{side}.png", letter.to_ascii_lowercase());
+    if let Ok(image) = Image::load_image(&path) {
+        return image;
+    }
+    let (ink, paper) = match color {
+        COLOR::White => (Color::BLACK, Color::WHITE),
+        COLOR::Black => (Color::WHITE, Color::BLACK),
+    };
+    let mut image = Image::gen_image_color(SIZE, SIZE, Color::BLANK);
+    image.draw_circle(SIZE / 2, SIZE / 2, SIZE * 2 / 5, paper);
+    let text = letter.to_string();
+    let font_size = SIZE / 2;
+    let width = measure_text(&text, font_size);
+    image.draw_text(
+        &text,
+        (SIZE - width) / 2,
+        (SIZE - font_size) / 2,
+        font_size,
+        ink,
+    );
+    image
+}
+
 /// change this error type parameter.
 type LoadResult<E> = Result<HashMap<(COLOR, TYPE), Image>, E>;
 
@@ -426,17 +460,11 @@ fn generate_image_map() -> LoadResult<String> {
         (COLOR::Black, TYPE::Rook) => img::png_data_black_rook,
         (COLOR::Black, TYPE::Queen) => img::png_data_black_queen,
         (COLOR::Black, TYPE::King) => img::png_data_black_king,
+        (_, TYPE::Fairy(_)) => unreachable!("Fairy pieces are drawn by fairy_image"),
     };
 
     for color in [COLOR::White, COLOR::Black] {
-        for piece_type in [
-            TYPE::Pawn,
-            TYPE::Knight,
-            TYPE::Bishop,
-            TYPE::Rook,
-            TYPE::Queen,
-            TYPE::King,
-        ] {
+        for piece_type in TYPE::ORTHODOX {
             let key = (color.clone(), piece_type.clone());
             let data = lambda(color, piece_type);
             let image = Image::load_image_from_mem(".png", data.data)?;
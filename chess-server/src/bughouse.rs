@@ -0,0 +1,359 @@
+//! chess_server::bughouse
+//!
+//! A Bughouse match: four players, two teams, two boards. Each board is a
+//! [`GameMode::Bughouse`] game of the [`GameMaster`] with a clock of its own.
+//! Partners sit at different boards with opposite colors, and a
+//! [`BughouseSession`] passes every piece a player captures on to their
+//! partner's reserve. The match is over as soon as either board is; the
+//! other one is halted where it stands. [`BughouseSession::to_bpgn`] writes
+//! both boards as one game in Bughouse PGN.
+
+use chess_core::clock::ChessClock;
+use chess_core::error::ChessError;
+use chess_core::game::GameMode;
+use chess_core::msg::GameId;
+use chess_core::position::{ChessMove, Outcome};
+use chess_core::types::Color;
+use chess_core::GameMaster;
+use std::fmt::Write;
+
+const LINE_WIDTH: usize = 80;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Board {
+    A,
+    B,
+}
+
+impl Board {
+    pub fn other(self) -> Board {
+        match self {
+            Board::A => Board::B,
+            Board::B => Board::A,
+        }
+    }
+    fn index(self) -> usize {
+        match self {
+            Board::A => 0,
+            Board::B => 1,
+        }
+    }
+    // `A` or `B` for White's moves, `a` or `b` for Black's, as in BPGN move numbers.
+    fn letter(self, color: Color) -> char {
+        match (self, color) {
+            (Board::A, Color::White) => 'A',
+            (Board::A, Color::Black) => 'a',
+            (Board::B, Color::White) => 'B',
+            (Board::B, Color::Black) => 'b',
+        }
+    }
+}
+
+// Team A is White on board A and Black on board B; team B the other two.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Team {
+    A,
+    B,
+}
+
+// Where one of the four players sits.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Seat {
+    pub board: Board,
+    pub color: Color,
+}
+
+impl Seat {
+    pub const ALL: [Seat; 4] = [
+        Seat::new(Board::A, Color::White),
+        Seat::new(Board::A, Color::Black),
+        Seat::new(Board::B, Color::White),
+        Seat::new(Board::B, Color::Black),
+    ];
+
+    pub const fn new(board: Board, color: Color) -> Self {
+        Self { board, color }
+    }
+    // The teammate at the other board, who plays the other color.
+    pub fn partner(self) -> Seat {
+        Seat::new(self.board.other(), self.color.opposite())
+    }
+    pub fn team(self) -> Team {
+        match (self.board, self.color) {
+            (Board::A, Color::White) | (Board::B, Color::Black) => Team::A,
+            _ => Team::B,
+        }
+    }
+}
+
+// How the match ended: the board that decided it and how.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BughouseResult {
+    pub board: Board,
+    pub outcome: Outcome,
+}
+
+impl BughouseResult {
+    pub fn winner(&self) -> Option<Team> {
+        let color = self.outcome.winner()?;
+        Some(Seat::new(self.board, color).team())
+    }
+    // `1-0` when team A wins, as BPGN has it.
+    pub fn result(&self) -> &'static str {
+        match self.winner() {
+            Some(Team::A) => "1-0",
+            Some(Team::B) => "0-1",
+            None => "1/2-1/2",
+        }
+    }
+}
+
+// A move as it goes into the BPGN movetext.
+#[derive(Debug, Clone)]
+struct Played {
+    board: Board,
+    color: Color,
+    fullmoves: u32,
+    san: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct BughouseSession {
+    boards: [GameId; 2],
+    // By seat, in the order of [`Seat::ALL`]().
+    names: [String; 4],
+    // Both boards' moves in the order they were made.
+    played: Vec<Played>,
+    result: Option<BughouseResult>,
+}
+
+impl BughouseSession {
+    // Sets up both boards in `gm`, each with its own copy of `clock`. The
+    // players are named in the order of [`Seat::ALL`]().
+    pub fn create(
+        gm: &mut GameMaster,
+        names: [String; 4],
+        clock: Option<ChessClock>,
+    ) -> Result<Self, ChessError> {
+        let mut boards = [0; 2];
+        for game_id in boards.iter_mut() {
+            *game_id = gm.create_game()?;
+            gm.set_mode(*game_id, GameMode::Bughouse)?;
+            gm.set_clock(*game_id, clock.clone())?;
+        }
+        Ok(Self {
+            boards,
+            names,
+            played: Vec::new(),
+            result: None,
+        })
+    }
+
+    pub fn game_id(&self, board: Board) -> GameId {
+        self.boards[board.index()]
+    }
+
+    pub fn name(&self, seat: Seat) -> &str {
+        let idx = Seat::ALL.iter().position(|&s| s == seat).unwrap();
+        &self.names[idx]
+    }
+
+    pub fn result(&self) -> Option<&BughouseResult> {
+        self.result.as_ref()
+    }
+
+    // Plays `mv` for `seat`, whose turn it has to be on their board, and
+    // hands whatever it captures to their partner.
+    pub fn make_move(
+        &mut self,
+        gm: &mut GameMaster,
+        seat: Seat,
+        mv: ChessMove,
+    ) -> Result<(), ChessError> {
+        if self.result.is_some() {
+            return Err(ChessError::GameFinished);
+        }
+        let game_id = self.game_id(seat.board);
+        let before = gm.request_position(game_id)?;
+        if before.turn != seat.color {
+            return Err(ChessError::NotYourTurn);
+        }
+        gm.make_move(game_id, mv)?;
+        self.played.push(Played {
+            board: seat.board,
+            color: seat.color,
+            fullmoves: before.fullmoves,
+            san: before.move_to_san(&mv),
+        });
+        if self.update(gm).is_some() {
+            return Ok(());
+        }
+        match before.reserve_gain(&mv) {
+            Some(ty) => gm.receive(self.game_id(seat.board.other()), seat.partner().color, ty),
+            None => Ok(()),
+        }
+    }
+
+    // Ends the match once either board is over, halting the other one. Moves
+    // made through [`Self::make_move`]() do this by themselves; call it after
+    // [`GameMaster::tick`]() so that a fallen flag ends the match too.
+    pub fn update(&mut self, gm: &mut GameMaster) -> Option<&BughouseResult> {
+        if self.result.is_none() {
+            for board in [Board::A, Board::B] {
+                let game = &gm.request_game_state(self.game_id(board)).ok()?.game;
+                if let (true, Some(outcome)) = (game.finished, game.outcome()) {
+                    self.result = Some(BughouseResult { board, outcome });
+                    gm.halt_game(self.game_id(board.other())).ok()?;
+                    break;
+                }
+            }
+        }
+        self.result.as_ref()
+    }
+
+    // The match in Bughouse PGN: one tag section naming all four players and
+    // the moves of both boards in the order they were made, numbered like
+    // `1A.` and `1a.` for White and Black on board A.
+    pub fn to_bpgn(&self) -> String {
+        let result = self.result.map_or("*", |r| r.result());
+        let mut out = String::new();
+        for (tag, value) in [("Event", "?"), ("Site", "?"), ("Date", "?")] {
+            let _ = writeln!(out, "[{tag} \"{value}\"]");
+        }
+        for (tag, name) in ["WhiteA", "BlackA", "WhiteB", "BlackB"]
+            .iter()
+            .zip(&self.names)
+        {
+            let name = name.replace('\\', "\\\\").replace('"', "\\\"");
+            let _ = writeln!(out, "[{tag} \"{name}\"]");
+        }
+        let _ = writeln!(out, "[Result \"{result}\"]");
+        let _ = writeln!(out, "[Variant \"Bughouse\"]");
+        out.push('\n');
+
+        let mut tokens: Vec<String> = self
+            .played
+            .iter()
+            .map(|p| format!("{}{}. {}", p.fullmoves, p.board.letter(p.color), p.san))
+            .collect();
+        if let Some(r) = &self.result {
+            tokens.push(format!("{{Board {:?}: {}}}", r.board, r.outcome.reason()));
+        }
+        tokens.push(result.to_string());
+        let mut line = String::new();
+        for token in tokens {
+            if !line.is_empty() && line.len() + 1 + token.len() > LINE_WIDTH {
+                let _ = writeln!(out, "{line}");
+                line.clear();
+            }
+            if !line.is_empty() {
+                line.push(' ');
+            }
+            line.push_str(&token);
+        }
+        let _ = writeln!(out, "{line}");
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chess_core::clock::{ManualTime, TimeControl};
+    use chess_core::constants::{E5, E7};
+    use chess_core::spawn_game_master;
+    use chess_core::types::Type;
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    fn names() -> [String; 4] {
+        ["Ann", "Bob", "Cid", "Dee"].map(String::from)
+    }
+
+    fn play(session: &mut BughouseSession, gm: &mut GameMaster, board: Board, uci: &str) {
+        let pos = gm.request_position(session.game_id(board)).unwrap();
+        let mv = pos.parse_uci(uci).unwrap();
+        session
+            .make_move(gm, Seat::new(board, pos.turn), mv)
+            .unwrap();
+    }
+
+    #[test]
+    fn captures_go_to_the_partner_and_the_first_board_to_finish_ends_the_match() {
+        let mut gm = spawn_game_master();
+        let mut session = BughouseSession::create(&mut gm, names(), None).unwrap();
+        let (a, b) = (session.game_id(Board::A), session.game_id(Board::B));
+        for uci in ["e2e4", "d7d5", "e4d5"] {
+            play(&mut session, &mut gm, Board::B, uci);
+        }
+        // White took a black pawn on board B, so Black on board A gets one.
+        let reserves = gm.request_position(a).unwrap().reserves.unwrap();
+        assert_eq!(reserves.count(Color::Black, Type::Pawn), 1);
+        let reserves = gm.request_position(b).unwrap().reserves.unwrap();
+        assert!(reserves.is_empty(Color::White));
+        assert_eq!(gm.request_game_state(a).unwrap().game.p2.reserve.len(), 1);
+
+        let black_a = Seat::new(Board::A, Color::Black);
+        assert_eq!(black_a.partner().team(), Team::B);
+        let e5 = ChessMove::new(E7, E5);
+        assert_eq!(
+            session.make_move(&mut gm, black_a, e5),
+            Err(ChessError::NotYourTurn)
+        );
+        for uci in ["f2f3", "e7e5", "b2b3", "P@d3", "g2g4", "d8h4"] {
+            play(&mut session, &mut gm, Board::A, uci);
+        }
+        let result = *session.result().unwrap();
+        assert_eq!(result.board, Board::A);
+        assert_eq!(result.winner(), Some(Team::B));
+        let other = &gm.request_game_state(b).unwrap().game;
+        assert!(other.finished);
+        assert_eq!(other.outcome(), None);
+        assert_eq!(
+            session.make_move(&mut gm, Seat::new(Board::B, Color::Black), e5),
+            Err(ChessError::GameFinished)
+        );
+
+        assert_eq!(
+            session.to_bpgn(),
+            "[Event \"?\"]\n[Site \"?\"]\n[Date \"?\"]\n[WhiteA \"Ann\"]\n[BlackA \"Bob\"]\n\
+             [WhiteB \"Cid\"]\n[BlackB \"Dee\"]\n[Result \"0-1\"]\n[Variant \"Bughouse\"]\n\n\
+             1B. e4 1b. d5 2B. exd5 1A. f3 1a. e5 2A. b3 2a. P@d3 3A. g4 3a. Qh4#\n\
+             {Board A: Black mates} 0-1\n"
+        );
+    }
+
+    #[test]
+    fn each_board_has_its_own_clock() {
+        let time = ManualTime::default();
+        let clock = ChessClock::with_source(
+            TimeControl::sudden_death(Duration::from_secs(60)),
+            Arc::new(time.clone()),
+        );
+        let mut gm = spawn_game_master();
+        let mut session = BughouseSession::create(&mut gm, names(), Some(clock)).unwrap();
+        play(&mut session, &mut gm, Board::A, "e2e4");
+        play(&mut session, &mut gm, Board::B, "d2d4");
+        time.advance(Duration::from_secs(30));
+        play(&mut session, &mut gm, Board::A, "e7e5");
+        time.advance(Duration::from_secs(31));
+        gm.tick();
+        // Black on board B ran out; board A played on in between.
+        let result = *session.update(&mut gm).unwrap();
+        assert_eq!(result.board, Board::B);
+        assert_eq!(
+            result.outcome,
+            Outcome::TimeForfeit {
+                winner: Color::White
+            }
+        );
+        assert_eq!(result.winner(), Some(Team::B));
+        let a = &gm
+            .request_game_state(session.game_id(Board::A))
+            .unwrap()
+            .game;
+        let clock = a.clock.as_ref().unwrap();
+        assert_eq!(clock.running(), None);
+        assert_eq!(clock.remaining(Color::Black), Duration::from_secs(30));
+    }
+}
@@ -1 +1,6 @@
+//! chess_server
+//!
+//! Sessions that tie several games of a [`chess_core::GameMaster`] together,
+//! such as the two boards of a [`bughouse::BughouseSession`].
 
+pub mod bughouse;